@@ -0,0 +1,101 @@
+use rendering::testing::conformance::{backends, Report};
+use rendering::types::{Error, Result};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::{env, panic};
+
+const USAGE: &str = "\
+Usage: conformance [OPTIONS] [DIR]
+
+Runs the html5lib tree construction fixtures in DIR, or in
+tests/data/html5lib-tests/tree-construction if no directory is given, against every backend
+and reports the passes, failures and panics for each file.
+
+Options:
+  -o, --output <FORMAT>       markdown (default) or json
+  -h, --help                  print this message
+";
+
+#[derive(Clone, Copy)]
+enum Format {
+    Markdown,
+    Json,
+}
+
+struct Args {
+    format: Format,
+    dir: Option<PathBuf>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>> {
+    let mut parsed = Args {
+        format: Format::Markdown,
+        dir: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| Error::General(format!("missing value for {arg}")))
+        };
+
+        match arg.as_str() {
+            "-o" | "--output" => {
+                parsed.format = match value()?.as_str() {
+                    "markdown" => Format::Markdown,
+                    "json" => Format::Json,
+                    other => return Err(Error::General(format!("unknown format: {other}"))),
+                }
+            }
+            "-h" | "--help" => return Ok(None),
+            dir if !dir.starts_with('-') && parsed.dir.is_none() => {
+                parsed.dir = Some(PathBuf::from(dir))
+            }
+            other => return Err(Error::General(format!("unexpected argument: {other}"))),
+        }
+    }
+
+    Ok(Some(parsed))
+}
+
+fn run(args: &Args) -> Result<()> {
+    let report = match args.dir {
+        Some(ref dir) => Report::run_with(dir, &backends())?,
+        None => Report::run()?,
+    };
+
+    let output = match args.format {
+        Format::Markdown => report.to_markdown(),
+        Format::Json => serde_json::to_string_pretty(&report)?,
+    };
+
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{output}")?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("conformance: {err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    // Panics in a backend are caught and go in the report
+    panic::set_hook(Box::new(|_| {}));
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("conformance: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
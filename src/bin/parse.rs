@@ -1,3 +1,182 @@
-fn main() {
-    println!("Hello, world!");
+use rendering::html5::{encoding, html5ever, quick_xml, Document, Parser, Serializable};
+use rendering::testing::tree_construction::TestSerialization;
+use rendering::types::{Error, Result};
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+use std::{env, fs};
+
+const USAGE: &str = "\
+Usage: parse [OPTIONS] [FILE]
+
+Parses an HTML document from FILE, or from stdin if no file is given.
+
+Options:
+  -b, --backend <BACKEND>     html5ever (default) or quick-xml
+  -f, --fragment <CONTEXT>    parse a fragment in the given context element, e.g. \"td\" or \"svg path\"
+  -s, --scripting             parse with scripting enabled
+  -e, --encoding <LABEL>      transport layer character encoding
+  -o, --output <FORMAT>       tree (html5lib test format, default), html or json
+      --errors                print parse errors to stderr and exit with 1 if there were any
+  -h, --help                  print this message
+";
+
+#[derive(Clone, Copy)]
+enum Backend {
+    Html5ever,
+    QuickXml,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Tree,
+    Html,
+    Json,
+}
+
+struct Args {
+    backend: Backend,
+    fragment: Option<String>,
+    scripting_enabled: bool,
+    encoding: Option<String>,
+    format: Format,
+    errors: bool,
+    path: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>> {
+    let mut parsed = Args {
+        backend: Backend::Html5ever,
+        fragment: None,
+        scripting_enabled: false,
+        encoding: None,
+        format: Format::Tree,
+        errors: false,
+        path: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| Error::General(format!("missing value for {arg}")))
+        };
+
+        match arg.as_str() {
+            "-b" | "--backend" => {
+                parsed.backend = match value()?.as_str() {
+                    "html5ever" => Backend::Html5ever,
+                    "quick-xml" => Backend::QuickXml,
+                    other => return Err(Error::General(format!("unknown backend: {other}"))),
+                }
+            }
+            "-f" | "--fragment" => parsed.fragment = Some(value()?),
+            "-s" | "--scripting" => parsed.scripting_enabled = true,
+            "-e" | "--encoding" => parsed.encoding = Some(value()?),
+            "-o" | "--output" => {
+                parsed.format = match value()?.as_str() {
+                    "tree" => Format::Tree,
+                    "html" => Format::Html,
+                    "json" => Format::Json,
+                    other => return Err(Error::General(format!("unknown format: {other}"))),
+                }
+            }
+            "--errors" => parsed.errors = true,
+            "-h" | "--help" => return Ok(None),
+            "-" => parsed.path = None,
+            path if !path.starts_with('-') && parsed.path.is_none() => {
+                parsed.path = Some(path.to_owned())
+            }
+            other => return Err(Error::General(format!("unexpected argument: {other}"))),
+        }
+    }
+
+    Ok(Some(parsed))
+}
+
+fn read_input(path: Option<&str>) -> Result<Vec<u8>> {
+    match path {
+        Some(path) => Ok(fs::read(path)?),
+        None => {
+            let mut buf = vec![];
+            io::stdin().read_to_end(&mut buf)?;
+            Ok(buf)
+        }
+    }
+}
+
+fn parse<'i, T>(args: &Args, data: &[u8]) -> Result<T>
+where
+    T: Document<'i, T>,
+{
+    match args.fragment {
+        Some(ref context) => {
+            let (decoded, _) = encoding::decode(data, args.encoding.as_deref());
+            let mut parser = T::fragment_parser(args.scripting_enabled, context)?;
+            parser.feed(decoded.as_bytes())?;
+            parser.finish()
+        }
+        None => {
+            let (dom, _) =
+                T::parse_document_bytes(data, args.encoding.as_deref(), args.scripting_enabled)?;
+            Ok(dom)
+        }
+    }
+}
+
+// Returns whether any parse errors were reported
+fn run<'i, T>(args: &Args, data: &[u8]) -> Result<bool>
+where
+    T: Document<'i, T> + Serializable + TestSerialization,
+{
+    let mut dom = parse::<T>(args, data)?;
+
+    let output = match args.format {
+        Format::Tree => dom.serialize(),
+        Format::Html => dom.to_html(),
+        Format::Json => serde_json::to_string_pretty(&dom.to_json())?,
+    };
+
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{output}")?;
+
+    if args.errors {
+        let name = args.path.as_deref().unwrap_or("<stdin>");
+        let mut stderr = io::stderr().lock();
+
+        for error in dom.errors() {
+            match error.col {
+                Some(col) => writeln!(stderr, "{name}:{}:{col}: {}", error.line, error.message)?,
+                None => writeln!(stderr, "{name}:{}: {}", error.line, error.message)?,
+            }
+        }
+    }
+
+    Ok(!dom.errors().is_empty())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("parse: {err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = read_input(args.path.as_deref()).and_then(|data| match args.backend {
+        Backend::Html5ever => run::<html5ever::Dom>(&args, &data),
+        Backend::QuickXml => run::<quick_xml::Dom>(&args, &data),
+    });
+
+    match result {
+        Ok(true) if args.errors => ExitCode::FAILURE,
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("parse: {err}");
+            ExitCode::from(2)
+        }
+    }
 }
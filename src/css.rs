@@ -0,0 +1,54 @@
+//! CSS stylesheets, tokenized and parsed as in CSS Syntax Module Level 3, and the style sheets
+//! and `style` attributes of a parsed document.
+use crate::dom::{Arena, NodeId};
+use markup5ever::{namespace_url, ns};
+
+pub mod matching;
+pub mod parser;
+pub mod selector;
+pub mod stylesheet;
+pub mod tokenizer;
+
+pub use parser::{ComponentValue, Declaration, SyntaxError};
+pub use selector::SelectorList;
+pub use stylesheet::{CssRule, ImportRule, MediaRule, StyleRule, Stylesheet};
+pub use tokenizer::Token;
+
+// A style element without a type, or with an empty one, is CSS
+fn is_css(arena: &Arena, id: NodeId) -> bool {
+    arena
+        .node(id)
+        .attr("type")
+        .map_or(true, |t| t.is_empty() || t.eq_ignore_ascii_case("text/css"))
+}
+
+/// The style sheets of the document's HTML and SVG `style` elements, in tree order.  Those in
+/// templates are inert and left out.
+pub fn stylesheets(arena: &Arena) -> Vec<(NodeId, Stylesheet)> {
+    arena
+        .descendants(arena.document())
+        .filter(|&id| {
+            arena.node(id).element_name().is_some_and(|name| {
+                (name.ns == ns!(html) || name.ns == ns!(svg)) && &*name.local == "style"
+            })
+        })
+        .filter(|&id| is_css(arena, id))
+        .map(|id| (id, Stylesheet::parse(&arena.text_content(id))))
+        .collect()
+}
+
+/// Parses the value of a `style` attribute, dropping anything that is not a valid declaration.
+pub fn parse_style_attribute(value: &str) -> Vec<Declaration> {
+    stylesheet::declarations(parser::parse_declaration_list(value), &mut vec![])
+}
+
+/// The declarations of every element with a `style` attribute, in tree order.
+pub fn style_attributes(arena: &Arena) -> Vec<(NodeId, Vec<Declaration>)> {
+    arena
+        .descendants(arena.document())
+        .filter_map(|id| {
+            let style = arena.node(id).attr("style")?;
+            Some((id, parse_style_attribute(style)))
+        })
+        .collect()
+}
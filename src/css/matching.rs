@@ -0,0 +1,325 @@
+//! Matching selectors against the elements of an `Arena`.
+use super::selector::{
+    AttributeOperator, Combinator, ComplexSelector, NamespaceConstraint, PseudoClass, SelectorList,
+    SimpleSelector,
+};
+use crate::dom::{Arena, NodeData, NodeId};
+use markup5ever::interface::QuirksMode;
+use markup5ever::{namespace_url, ns, Namespace};
+
+/// What matching depends on besides the element.
+#[derive(Clone, Copy, Debug)]
+pub struct MatchContext {
+    /// Whether the tree is an HTML document, where the names of HTML elements and of their
+    /// attributes are matched without regard to ASCII case.
+    pub html: bool,
+    /// Whether classes and IDs are matched without regard to ASCII case, as in quirks mode.
+    pub quirks: bool,
+    /// The element `:scope` matches.  Without one it matches the root element.
+    pub scope: Option<NodeId>,
+}
+
+impl MatchContext {
+    pub fn new(arena: &Arena, html: bool) -> Self {
+        Self {
+            html,
+            quirks: html && arena.quirks_mode == QuirksMode::Quirks,
+            scope: None,
+        }
+    }
+}
+
+fn is_element(arena: &Arena, id: NodeId) -> bool {
+    arena.node(id).element_name().is_some()
+}
+
+fn parent_element(arena: &Arena, id: NodeId) -> Option<NodeId> {
+    arena.parent(id).filter(|&parent| is_element(arena, parent))
+}
+
+fn previous_elements(arena: &Arena, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+    std::iter::successors(arena.node(id).prev_sibling, |&id| {
+        arena.node(id).prev_sibling
+    })
+    .filter(|&id| is_element(arena, id))
+}
+
+fn next_elements(arena: &Arena, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+    std::iter::successors(arena.node(id).next_sibling, |&id| {
+        arena.node(id).next_sibling
+    })
+    .filter(|&id| is_element(arena, id))
+}
+
+fn namespace_matches(constraint: &NamespaceConstraint, ns: &Namespace) -> bool {
+    match constraint {
+        NamespaceConstraint::Any => true,
+        NamespaceConstraint::None => *ns == ns!(),
+        NamespaceConstraint::Url(url) => &**ns == url,
+    }
+}
+
+fn eq(a: &str, b: &str, case_insensitive: bool) -> bool {
+    if case_insensitive {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
+fn attribute_matches(value: &str, op: AttributeOperator, expected: &str, ci: bool) -> bool {
+    let (value, expected) = if ci {
+        (value.to_ascii_lowercase(), expected.to_ascii_lowercase())
+    } else {
+        (value.to_owned(), expected.to_owned())
+    };
+
+    match op {
+        AttributeOperator::Equals => value == expected,
+        AttributeOperator::Includes => {
+            !expected.is_empty()
+                && !expected.contains(|c: char| c.is_ascii_whitespace())
+                && value.split_ascii_whitespace().any(|word| word == expected)
+        }
+        AttributeOperator::DashMatch => {
+            value == expected
+                || value
+                    .strip_prefix(&expected)
+                    .is_some_and(|rest| rest.starts_with('-'))
+        }
+        AttributeOperator::Prefix => !expected.is_empty() && value.starts_with(&expected),
+        AttributeOperator::Suffix => !expected.is_empty() && value.ends_with(&expected),
+        AttributeOperator::Substring => !expected.is_empty() && value.contains(&expected),
+    }
+}
+
+struct Matcher<'a> {
+    arena: &'a Arena,
+    cx: MatchContext,
+}
+
+impl Matcher<'_> {
+    // Names of HTML elements in HTML documents, and their attributes, are lowercased in the
+    // tree, so selectors are lowercased to match them
+    fn fold_case(&self, id: NodeId) -> bool {
+        self.cx.html
+            && self
+                .arena
+                .node(id)
+                .element_name()
+                .is_some_and(|name| name.ns == ns!(html))
+    }
+
+    fn simple(&self, id: NodeId, selector: &SimpleSelector) -> bool {
+        let node = self.arena.node(id);
+        let Some(name) = node.element_name() else {
+            return false;
+        };
+
+        match selector {
+            SimpleSelector::Type { ns, name: local } => {
+                namespace_matches(ns, &name.ns)
+                    && local.as_ref().map_or(true, |local| {
+                        if self.fold_case(id) {
+                            local.to_ascii_lowercase() == *name.local
+                        } else {
+                            **local == *name.local
+                        }
+                    })
+            }
+
+            SimpleSelector::Id(expected) => node
+                .attr("id")
+                .is_some_and(|id| eq(id, expected, self.cx.quirks)),
+
+            SimpleSelector::Class(expected) => node.attr("class").is_some_and(|classes| {
+                classes
+                    .split_ascii_whitespace()
+                    .any(|class| eq(class, expected, self.cx.quirks))
+            }),
+
+            SimpleSelector::Attribute {
+                ns,
+                name: local,
+                matcher,
+                case_insensitive,
+            } => {
+                let local = if self.fold_case(id) {
+                    local.to_ascii_lowercase()
+                } else {
+                    local.clone()
+                };
+
+                node.attrs().iter().any(|attr| {
+                    namespace_matches(ns, &attr.name.ns)
+                        && *attr.name.local == *local
+                        && matcher.as_ref().map_or(true, |(op, expected)| {
+                            attribute_matches(&attr.value, *op, expected, *case_insensitive)
+                        })
+                })
+            }
+
+            SimpleSelector::PseudoClass(pseudo) => self.pseudo_class(id, pseudo),
+        }
+    }
+
+    fn same_type(&self, a: NodeId, b: NodeId) -> bool {
+        let (a, b) = (self.arena.node(a), self.arena.node(b));
+        a.element_name().map(|n| n.expanded()) == b.element_name().map(|n| n.expanded())
+    }
+
+    fn pseudo_class(&self, id: NodeId, pseudo: &PseudoClass) -> bool {
+        let arena = self.arena;
+
+        match pseudo {
+            PseudoClass::Root => arena.parent(id) == Some(arena.document()),
+            PseudoClass::Scope => match self.cx.scope {
+                Some(scope) => scope == id,
+                None => arena.parent(id) == Some(arena.document()),
+            },
+            PseudoClass::Empty => arena
+                .children(id)
+                .all(|child| match arena.node(child).data {
+                    NodeData::Element { .. } => false,
+                    NodeData::Text { ref contents } => contents.is_empty(),
+                    _ => true,
+                }),
+
+            PseudoClass::FirstChild => previous_elements(arena, id).next().is_none(),
+            PseudoClass::LastChild => next_elements(arena, id).next().is_none(),
+            PseudoClass::OnlyChild => {
+                previous_elements(arena, id).next().is_none()
+                    && next_elements(arena, id).next().is_none()
+            }
+            PseudoClass::FirstOfType => {
+                !previous_elements(arena, id).any(|s| self.same_type(s, id))
+            }
+            PseudoClass::LastOfType => !next_elements(arena, id).any(|s| self.same_type(s, id)),
+            PseudoClass::OnlyOfType => {
+                !previous_elements(arena, id).any(|s| self.same_type(s, id))
+                    && !next_elements(arena, id).any(|s| self.same_type(s, id))
+            }
+
+            // Positions count from 1, and only count siblings that `of` allows
+            PseudoClass::NthChild(nth, of) | PseudoClass::NthLastChild(nth, of) => {
+                let counts =
+                    |&sibling: &NodeId| of.as_ref().map_or(true, |of| self.list(sibling, of));
+                if !counts(&id) {
+                    return false;
+                }
+                let before = match pseudo {
+                    PseudoClass::NthChild(..) => {
+                        previous_elements(arena, id).filter(counts).count()
+                    }
+                    _ => next_elements(arena, id).filter(counts).count(),
+                };
+                nth.matches(before as i32 + 1)
+            }
+            PseudoClass::NthOfType(nth) => {
+                let before = previous_elements(arena, id).filter(|&s| self.same_type(s, id));
+                nth.matches(before.count() as i32 + 1)
+            }
+            PseudoClass::NthLastOfType(nth) => {
+                let before = next_elements(arena, id).filter(|&s| self.same_type(s, id));
+                nth.matches(before.count() as i32 + 1)
+            }
+
+            PseudoClass::Not(list) => !self.list(id, list),
+            PseudoClass::Is(list) | PseudoClass::Where(list) => self.list(id, list),
+            PseudoClass::Has(relative) => self.has(id, relative),
+        }
+    }
+
+    // Relative selectors are anchored at the element, which their leading `:scope` matches
+    fn has(&self, id: NodeId, relative: &[ComplexSelector]) -> bool {
+        let anchored = Matcher {
+            arena: self.arena,
+            cx: MatchContext {
+                scope: Some(id),
+                ..self.cx
+            },
+        };
+
+        relative.iter().any(|selector| {
+            let candidates: Vec<NodeId> = match selector.combinators.first() {
+                Some(Combinator::Descendant | Combinator::Child) | None => {
+                    self.arena.descendants(id).skip(1).collect()
+                }
+                Some(Combinator::NextSibling | Combinator::SubsequentSibling) => {
+                    next_elements(self.arena, id)
+                        .flat_map(|sibling| self.arena.descendants(sibling))
+                        .collect()
+                }
+            };
+
+            candidates
+                .into_iter()
+                .any(|candidate| anchored.complex(candidate, selector))
+        })
+    }
+
+    fn compound(&self, id: NodeId, compound: &[SimpleSelector]) -> bool {
+        compound.iter().all(|selector| self.simple(id, selector))
+    }
+
+    // Matches right to left, trying every element a combinator allows
+    fn complex_at(&self, id: NodeId, selector: &ComplexSelector, i: usize) -> bool {
+        if !self.compound(id, &selector.compounds[i]) {
+            return false;
+        }
+        if i == 0 {
+            return true;
+        }
+
+        let arena = self.arena;
+        let rest = |other: NodeId| self.complex_at(other, selector, i - 1);
+        match selector.combinators[i - 1] {
+            Combinator::Child => parent_element(arena, id).is_some_and(rest),
+            Combinator::Descendant => {
+                std::iter::successors(parent_element(arena, id), |&p| parent_element(arena, p))
+                    .any(rest)
+            }
+            Combinator::NextSibling => previous_elements(arena, id).next().is_some_and(rest),
+            Combinator::SubsequentSibling => previous_elements(arena, id).any(rest),
+        }
+    }
+
+    fn complex(&self, id: NodeId, selector: &ComplexSelector) -> bool {
+        !selector.compounds.is_empty()
+            && self.complex_at(id, selector, selector.compounds.len() - 1)
+    }
+
+    fn list(&self, id: NodeId, list: &SelectorList) -> bool {
+        list.0.iter().any(|selector| self.complex(id, selector))
+    }
+}
+
+/// Whether the element `id` matches any selector in `list`.
+pub fn matches(arena: &Arena, id: NodeId, list: &SelectorList, cx: &MatchContext) -> bool {
+    Matcher { arena, cx: *cx }.list(id, list)
+}
+
+/// Whether the element `id` matches `selector`.
+pub fn matches_complex(
+    arena: &Arena,
+    id: NodeId,
+    selector: &ComplexSelector,
+    cx: &MatchContext,
+) -> bool {
+    Matcher { arena, cx: *cx }.complex(id, selector)
+}
+
+/// The elements under `root` that match `list`, in tree order, not counting `root` itself or
+/// the contents of templates.
+pub fn select<'a>(
+    arena: &'a Arena,
+    root: NodeId,
+    list: &'a SelectorList,
+    cx: &MatchContext,
+) -> impl Iterator<Item = NodeId> + 'a {
+    let matcher = Matcher { arena, cx: *cx };
+    arena
+        .descendants(root)
+        .skip(1)
+        .filter(move |&id| matcher.list(id, list))
+}
@@ -0,0 +1,415 @@
+//! The parser of CSS Syntax Module Level 3, section 5, which groups tokens into rules,
+//! declarations and component values without knowing what any of them mean.
+//! See https://www.w3.org/TR/css-syntax-3/#parsing
+use super::tokenizer::{Token, Tokenizer};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockKind {
+    Curly,
+    Square,
+    Paren,
+}
+
+impl BlockKind {
+    fn closing(self) -> Token {
+        match self {
+            Self::Curly => Token::CloseCurly,
+            Self::Square => Token::CloseSquare,
+            Self::Paren => Token::CloseParen,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ComponentValue {
+    /// Any token but an opening bracket or a function token.  Closing brackets only appear
+    /// when they were not matched.
+    Token(Token),
+    Block {
+        kind: BlockKind,
+        contents: Vec<ComponentValue>,
+    },
+    Function {
+        name: String,
+        arguments: Vec<ComponentValue>,
+    },
+}
+
+impl ComponentValue {
+    pub fn is_whitespace(&self) -> bool {
+        matches!(self, Self::Token(Token::Whitespace))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    /// The value, without leading and trailing whitespace or "!important".
+    pub value: Vec<ComponentValue>,
+    pub important: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtRule {
+    pub name: String,
+    pub prelude: Vec<ComponentValue>,
+    /// The contents of the block, or None for a statement ending in ";".
+    pub block: Option<Vec<ComponentValue>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualifiedRule {
+    pub prelude: Vec<ComponentValue>,
+    pub block: Vec<ComponentValue>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Rule {
+    At(AtRule),
+    Qualified(QualifiedRule),
+}
+
+/// An item of a declaration list, where at-rules may appear alongside declarations.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeclarationOrAtRule {
+    Declaration(Declaration),
+    AtRule(AtRule),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyntaxError {
+    /// There was nothing but whitespace where something was expected.
+    Empty,
+    /// Something followed what was parsed.
+    ExtraInput,
+    /// What was parsed was not a valid rule or declaration.
+    Invalid,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Empty => "empty",
+            Self::ExtraInput => "extra-input",
+            Self::Invalid => "invalid",
+        };
+        f.write_str(s)
+    }
+}
+
+// The parser's input, as component values, which the spec's algorithms treat the same as the
+// tokens they were grouped from
+struct Input {
+    values: std::vec::IntoIter<ComponentValue>,
+    peeked: Option<ComponentValue>,
+}
+
+impl Input {
+    fn new(values: Vec<ComponentValue>) -> Self {
+        Self {
+            values: values.into_iter(),
+            peeked: None,
+        }
+    }
+
+    fn peek(&mut self) -> Option<&ComponentValue> {
+        if self.peeked.is_none() {
+            self.peeked = self.values.next();
+        }
+        self.peeked.as_ref()
+    }
+
+    fn next(&mut self) -> Option<ComponentValue> {
+        self.peeked.take().or_else(|| self.values.next())
+    }
+
+    fn reconsume(&mut self, value: ComponentValue) {
+        debug_assert!(self.peeked.is_none());
+        self.peeked = Some(value);
+    }
+
+    fn peek_token(&mut self) -> Option<&Token> {
+        match self.peek() {
+            Some(ComponentValue::Token(token)) => Some(token),
+            _ => None,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(ComponentValue::is_whitespace) {
+            self.next();
+        }
+    }
+}
+
+// Groups tokens into component values, matching brackets and function arguments
+fn component_values(
+    tokens: &mut impl Iterator<Item = Token>,
+    until: Option<&Token>,
+) -> Vec<ComponentValue> {
+    let mut values = vec![];
+
+    while let Some(token) = tokens.next() {
+        if Some(&token) == until {
+            return values;
+        }
+
+        let value = match token {
+            Token::OpenCurly => block(tokens, BlockKind::Curly),
+            Token::OpenSquare => block(tokens, BlockKind::Square),
+            Token::OpenParen => block(tokens, BlockKind::Paren),
+            Token::Function(name) => ComponentValue::Function {
+                name,
+                arguments: component_values(tokens, Some(&Token::CloseParen)),
+            },
+            token => ComponentValue::Token(token),
+        };
+        values.push(value);
+    }
+
+    values
+}
+
+fn block(tokens: &mut impl Iterator<Item = Token>, kind: BlockKind) -> ComponentValue {
+    ComponentValue::Block {
+        kind,
+        contents: component_values(tokens, Some(&kind.closing())),
+    }
+}
+
+/// Parses `input` into component values.  Blocks and functions left open at the end of the
+/// input are closed.
+pub fn parse_component_value_list(input: &str) -> Vec<ComponentValue> {
+    component_values(&mut Tokenizer::new(input), None)
+}
+
+fn input(s: &str) -> Input {
+    Input::new(parse_component_value_list(s))
+}
+
+/// Parses a single component value, surrounded by optional whitespace.
+pub fn parse_component_value(s: &str) -> Result<ComponentValue, SyntaxError> {
+    let mut input = input(s);
+    input.skip_whitespace();
+    let value = input.next().ok_or(SyntaxError::Empty)?;
+    input.skip_whitespace();
+    match input.peek() {
+        None => Ok(value),
+        Some(_) => Err(SyntaxError::ExtraInput),
+    }
+}
+
+fn curly_contents(value: ComponentValue) -> Result<Vec<ComponentValue>, ComponentValue> {
+    match value {
+        ComponentValue::Block {
+            kind: BlockKind::Curly,
+            contents,
+        } => Ok(contents),
+        value => Err(value),
+    }
+}
+
+fn at_rule(input: &mut Input, name: String) -> AtRule {
+    let mut prelude = vec![];
+
+    while let Some(value) = input.next() {
+        match curly_contents(value) {
+            Ok(block) => {
+                return AtRule {
+                    name,
+                    prelude,
+                    block: Some(block),
+                }
+            }
+            Err(ComponentValue::Token(Token::Semicolon)) => break,
+            Err(value) => prelude.push(value),
+        }
+    }
+
+    AtRule {
+        name,
+        prelude,
+        block: None,
+    }
+}
+
+// A qualified rule runs until its block, and there is no rule if the input ends first
+fn qualified_rule(input: &mut Input) -> Option<QualifiedRule> {
+    let mut prelude = vec![];
+
+    while let Some(value) = input.next() {
+        match curly_contents(value) {
+            Ok(block) => return Some(QualifiedRule { prelude, block }),
+            Err(value) => prelude.push(value),
+        }
+    }
+
+    None
+}
+
+fn consume_rules(input: &mut Input, top_level: bool) -> Vec<Result<Rule, SyntaxError>> {
+    let mut rules = vec![];
+
+    while let Some(value) = input.next() {
+        match value {
+            ComponentValue::Token(Token::Whitespace) => {}
+            ComponentValue::Token(Token::Cdo | Token::Cdc) if top_level => {}
+            ComponentValue::Token(Token::AtKeyword(name)) => {
+                rules.push(Ok(Rule::At(at_rule(input, name))));
+            }
+            value => {
+                input.reconsume(value);
+                let rule = qualified_rule(input).ok_or(SyntaxError::Invalid);
+                rules.push(rule.map(Rule::Qualified));
+            }
+        }
+    }
+
+    rules
+}
+
+/// Parses the rules of a stylesheet, where "<!--" and "-->" are ignored.
+pub fn parse_stylesheet(s: &str) -> Vec<Result<Rule, SyntaxError>> {
+    consume_rules(&mut input(s), true)
+}
+
+/// Parses a list of rules, as found in the block of an at-rule like @media.
+pub fn parse_rule_list(s: &str) -> Vec<Result<Rule, SyntaxError>> {
+    consume_rules(&mut input(s), false)
+}
+
+/// Parses component values already grouped, such as the block of an at-rule, as a list of
+/// rules.
+pub fn rule_list(values: Vec<ComponentValue>) -> Vec<Result<Rule, SyntaxError>> {
+    consume_rules(&mut Input::new(values), false)
+}
+
+/// Parses a single rule, surrounded by optional whitespace.
+pub fn parse_rule(s: &str) -> Result<Rule, SyntaxError> {
+    let mut input = input(s);
+    input.skip_whitespace();
+
+    let rule = match input.next() {
+        None => return Err(SyntaxError::Empty),
+        Some(ComponentValue::Token(Token::AtKeyword(name))) => Rule::At(at_rule(&mut input, name)),
+        Some(value) => {
+            input.reconsume(value);
+            Rule::Qualified(qualified_rule(&mut input).ok_or(SyntaxError::Invalid)?)
+        }
+    };
+
+    input.skip_whitespace();
+    match input.peek() {
+        None => Ok(rule),
+        Some(_) => Err(SyntaxError::ExtraInput),
+    }
+}
+
+fn is_important(values: &[ComponentValue]) -> bool {
+    let mut tokens = values.iter().rev().filter(|value| !value.is_whitespace());
+    let (Some(last), Some(before)) = (tokens.next(), tokens.next()) else {
+        return false;
+    };
+
+    matches!(last, ComponentValue::Token(Token::Ident(ident)) if ident.eq_ignore_ascii_case("important"))
+        && matches!(before, ComponentValue::Token(Token::Delim('!')))
+}
+
+// Parses a declaration from values starting with its name
+fn declaration(values: Vec<ComponentValue>) -> Option<Declaration> {
+    let mut input = Input::new(values);
+    let Some(ComponentValue::Token(Token::Ident(name))) = input.next() else {
+        return None;
+    };
+
+    input.skip_whitespace();
+    if input.next() != Some(ComponentValue::Token(Token::Colon)) {
+        return None;
+    }
+    input.skip_whitespace();
+
+    let mut value: Vec<_> = std::iter::from_fn(|| input.next()).collect();
+    let important = is_important(&value);
+    if important {
+        // Drop "important", the "!" and whatever whitespace was between them
+        while value.last().is_some_and(ComponentValue::is_whitespace) {
+            value.pop();
+        }
+        value.pop();
+        while value.last().is_some_and(ComponentValue::is_whitespace) {
+            value.pop();
+        }
+        value.pop();
+    }
+    while value.last().is_some_and(ComponentValue::is_whitespace) {
+        value.pop();
+    }
+
+    Some(Declaration {
+        name,
+        value,
+        important,
+    })
+}
+
+/// Parses a single declaration, such as a `@supports` condition.
+pub fn parse_declaration(s: &str) -> Result<Declaration, SyntaxError> {
+    let mut input = input(s);
+    input.skip_whitespace();
+    match input.peek_token() {
+        Some(Token::Ident(_)) => {}
+        _ => return Err(SyntaxError::Invalid),
+    }
+
+    let values = std::iter::from_fn(|| input.next()).collect();
+    declaration(values).ok_or(SyntaxError::Invalid)
+}
+
+/// Parses the contents of a style rule's block or a `style` attribute.
+pub fn parse_declaration_list(s: &str) -> Vec<Result<DeclarationOrAtRule, SyntaxError>> {
+    declaration_list(parse_component_value_list(s))
+}
+
+/// Parses component values already grouped, such as the block of a qualified rule, as a list
+/// of declarations.
+pub fn declaration_list(
+    values: Vec<ComponentValue>,
+) -> Vec<Result<DeclarationOrAtRule, SyntaxError>> {
+    let mut input = Input::new(values);
+    let mut items = vec![];
+
+    // The values up to the next ";", which is consumed
+    let until_semicolon = |input: &mut Input, mut values: Vec<ComponentValue>| {
+        while let Some(value) = input.next() {
+            if value == ComponentValue::Token(Token::Semicolon) {
+                break;
+            }
+            values.push(value);
+        }
+        values
+    };
+
+    while let Some(value) = input.next() {
+        match value {
+            ComponentValue::Token(Token::Whitespace | Token::Semicolon) => {}
+            ComponentValue::Token(Token::AtKeyword(name)) => {
+                items.push(Ok(DeclarationOrAtRule::AtRule(at_rule(&mut input, name))));
+            }
+            value @ ComponentValue::Token(Token::Ident(_)) => {
+                let values = until_semicolon(&mut input, vec![value]);
+                items.push(
+                    declaration(values)
+                        .map(DeclarationOrAtRule::Declaration)
+                        .ok_or(SyntaxError::Invalid),
+                );
+            }
+            _ => {
+                until_semicolon(&mut input, vec![]);
+                items.push(Err(SyntaxError::Invalid));
+            }
+        }
+    }
+
+    items
+}
@@ -0,0 +1,620 @@
+//! Selectors Level 4, parsed from component values.
+//! See https://www.w3.org/TR/selectors-4/
+use super::parser::{parse_component_value_list, BlockKind, ComponentValue};
+use super::tokenizer::Token;
+use crate::types::{Error, Result};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Combinator {
+    /// Whitespace.
+    Descendant,
+    /// ">".
+    Child,
+    /// "+".
+    NextSibling,
+    /// "~".
+    SubsequentSibling,
+}
+
+/// Which namespace a type or attribute selector accepts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NamespaceConstraint {
+    Any,
+    /// Names in no namespace, written "|name".
+    None,
+    Url(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeOperator {
+    /// "=".
+    Equals,
+    /// "~=", one of a whitespace-separated list.
+    Includes,
+    /// "|=", the value or the value followed by "-".
+    DashMatch,
+    /// "^=".
+    Prefix,
+    /// "$=".
+    Suffix,
+    /// "*=".
+    Substring,
+}
+
+/// The "An+B" argument of the `:nth-*` pseudo-classes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Nth {
+    pub a: i32,
+    pub b: i32,
+}
+
+impl Nth {
+    /// Whether the element at `index`, counting from 1, is selected.
+    pub fn matches(self, index: i32) -> bool {
+        let offset = index - self.b;
+        if self.a == 0 {
+            offset == 0
+        } else {
+            offset % self.a == 0 && offset / self.a >= 0
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PseudoClass {
+    Root,
+    Scope,
+    Empty,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    FirstOfType,
+    LastOfType,
+    OnlyOfType,
+    /// `:nth-child()`, counting only the siblings that match the "of" selector if there is one.
+    NthChild(Nth, Option<SelectorList>),
+    NthLastChild(Nth, Option<SelectorList>),
+    NthOfType(Nth),
+    NthLastOfType(Nth),
+    Not(SelectorList),
+    Is(SelectorList),
+    Where(SelectorList),
+    Has(Vec<ComplexSelector>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimpleSelector {
+    /// A type selector, with no name for "*".
+    Type {
+        ns: NamespaceConstraint,
+        name: Option<String>,
+    },
+    Id(String),
+    Class(String),
+    Attribute {
+        ns: NamespaceConstraint,
+        name: String,
+        matcher: Option<(AttributeOperator, String)>,
+        case_insensitive: bool,
+    },
+    PseudoClass(PseudoClass),
+}
+
+pub type CompoundSelector = Vec<SimpleSelector>;
+
+/// Compound selectors joined by combinators.  `combinators[i]` joins `compounds[i]` and
+/// `compounds[i + 1]`.  The relative selectors of `:has()` start with a compound holding only
+/// `:scope`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComplexSelector {
+    pub compounds: Vec<CompoundSelector>,
+    pub combinators: Vec<Combinator>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectorList(pub Vec<ComplexSelector>);
+
+/// The (ID, class, type) counts that order selectors in the cascade.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Specificity(pub u32, pub u32, pub u32);
+
+impl std::ops::Add for Specificity {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+impl SimpleSelector {
+    pub fn specificity(&self) -> Specificity {
+        match self {
+            Self::Type { name: None, .. } => Specificity(0, 0, 0),
+            Self::Type { .. } => Specificity(0, 0, 1),
+            Self::Id(_) => Specificity(1, 0, 0),
+            Self::Class(_) | Self::Attribute { .. } => Specificity(0, 1, 0),
+            Self::PseudoClass(pseudo) => match pseudo {
+                PseudoClass::Where(_) => Specificity::default(),
+                PseudoClass::Not(list) | PseudoClass::Is(list) => list.specificity(),
+                // The leading `:scope` of a relative selector is implied, so does not count
+                PseudoClass::Has(relative) => relative
+                    .iter()
+                    .map(|selector| {
+                        selector.compounds[1..]
+                            .iter()
+                            .flatten()
+                            .map(SimpleSelector::specificity)
+                            .fold(Specificity::default(), |a, b| a + b)
+                    })
+                    .max()
+                    .unwrap_or_default(),
+                PseudoClass::NthChild(_, Some(of)) | PseudoClass::NthLastChild(_, Some(of)) => {
+                    Specificity(0, 1, 0) + of.specificity()
+                }
+                _ => Specificity(0, 1, 0),
+            },
+        }
+    }
+}
+
+impl ComplexSelector {
+    pub fn specificity(&self) -> Specificity {
+        self.compounds
+            .iter()
+            .flatten()
+            .map(SimpleSelector::specificity)
+            .fold(Specificity::default(), |a, b| a + b)
+    }
+}
+
+impl SelectorList {
+    /// The greatest specificity of the selectors in the list, as used by `:is()` and `:not()`.
+    pub fn specificity(&self) -> Specificity {
+        self.0
+            .iter()
+            .map(ComplexSelector::specificity)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// Namespace prefixes usable in selectors, as declared with `@namespace`.
+#[derive(Clone, Debug, Default)]
+pub struct Namespaces {
+    pub default: Option<String>,
+    pub prefixes: HashMap<String, String>,
+}
+
+fn invalid(values: &[ComponentValue]) -> Error {
+    Error::General(format!("invalid selector: {values:?}"))
+}
+
+fn is_delim(value: Option<&ComponentValue>, c: char) -> bool {
+    value == Some(&ComponentValue::Token(Token::Delim(c)))
+}
+
+fn ident(value: Option<&ComponentValue>) -> Option<&str> {
+    match value {
+        Some(ComponentValue::Token(Token::Ident(s))) => Some(s),
+        _ => None,
+    }
+}
+
+// Splits on top-level commas, trimming whitespace
+fn split_commas(values: &[ComponentValue]) -> Vec<&[ComponentValue]> {
+    values
+        .split(|value| value == &ComponentValue::Token(Token::Comma))
+        .map(trim)
+        .collect()
+}
+
+fn trim(mut values: &[ComponentValue]) -> &[ComponentValue] {
+    while values.first().is_some_and(ComponentValue::is_whitespace) {
+        values = &values[1..];
+    }
+    while values.last().is_some_and(ComponentValue::is_whitespace) {
+        values = &values[..values.len() - 1];
+    }
+    values
+}
+
+// The text of the argument of an `:nth-*` pseudo-class, with whitespace collapsed
+fn nth_text(values: &[ComponentValue]) -> Option<String> {
+    let mut s = String::new();
+    for value in values {
+        match value {
+            ComponentValue::Token(Token::Whitespace) => s.push(' '),
+            ComponentValue::Token(Token::Ident(ident)) => s.push_str(ident),
+            ComponentValue::Token(Token::Number(n)) if n.integer => s.push_str(&n.repr),
+            ComponentValue::Token(Token::Dimension { number, unit }) if number.integer => {
+                s.push_str(&number.repr);
+                s.push_str(unit);
+            }
+            ComponentValue::Token(Token::Delim(c @ ('+' | '-'))) => s.push(*c),
+            _ => return None,
+        }
+    }
+    Some(s.to_ascii_lowercase())
+}
+
+fn parse_nth(values: &[ComponentValue]) -> Option<Nth> {
+    let s = nth_text(trim(values))?;
+    match s.as_str() {
+        "odd" => return Some(Nth { a: 2, b: 1 }),
+        "even" => return Some(Nth { a: 2, b: 0 }),
+        _ => {}
+    }
+
+    let integer = |s: &str| -> Option<i32> {
+        let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok()
+    };
+
+    let Some((a, b)) = s.split_once('n') else {
+        return Some(Nth {
+            a: 0,
+            b: integer(&s)?,
+        });
+    };
+    let a = match a {
+        "" | "+" => 1,
+        "-" => -1,
+        a => integer(a)?,
+    };
+
+    let b = b.trim_start();
+    let b = if b.is_empty() {
+        0
+    } else {
+        let (sign, rest) = match b.as_bytes()[0] {
+            b'+' => (1, &b[1..]),
+            b'-' => (-1, &b[1..]),
+            _ => return None,
+        };
+        let rest = rest.trim_start();
+        if rest.starts_with(['+', '-']) {
+            return None;
+        }
+        sign * integer(rest)?
+    };
+
+    Some(Nth { a, b })
+}
+
+struct SelectorParser<'a> {
+    namespaces: &'a Namespaces,
+}
+
+impl SelectorParser<'_> {
+    fn namespace(&self, prefix: &str) -> Option<NamespaceConstraint> {
+        self.namespaces
+            .prefixes
+            .get(prefix)
+            .map(|url| NamespaceConstraint::Url(url.clone()))
+    }
+
+    // A namespace prefix and the "|" after it, and the rest of the values
+    fn namespace_prefix<'v>(
+        &self,
+        values: &'v [ComponentValue],
+    ) -> Result<Option<(NamespaceConstraint, &'v [ComponentValue])>> {
+        let skip = match values {
+            [v, ..] if is_delim(Some(v), '|') => 1,
+            [v, bar, ..]
+                if is_delim(Some(bar), '|')
+                    && (is_delim(Some(v), '*') || ident(Some(v)).is_some()) =>
+            {
+                2
+            }
+            _ => return Ok(None),
+        };
+        // "a|=b" in an attribute selector is an operator, not a prefix
+        if is_delim(values.get(skip), '=') {
+            return Ok(None);
+        }
+
+        let ns = match values {
+            _ if skip == 1 => NamespaceConstraint::None,
+            [ComponentValue::Token(Token::Ident(prefix)), ..] => {
+                self.namespace(prefix).ok_or_else(|| invalid(values))?
+            }
+            _ => NamespaceConstraint::Any,
+        };
+        Ok(Some((ns, &values[skip..])))
+    }
+
+    fn attribute(&self, values: &[ComponentValue]) -> Result<SimpleSelector> {
+        let values = trim(values);
+        let (ns, rest) = match self.namespace_prefix(values)? {
+            Some((ns, rest)) => (ns, rest),
+            None => (NamespaceConstraint::None, values),
+        };
+
+        let name = ident(rest.first())
+            .ok_or_else(|| invalid(values))?
+            .to_owned();
+        let rest = trim(&rest[1..]);
+        if rest.is_empty() {
+            return Ok(SimpleSelector::Attribute {
+                ns,
+                name,
+                matcher: None,
+                case_insensitive: false,
+            });
+        }
+
+        let (op, rest) = match rest {
+            [eq, rest @ ..] if is_delim(Some(eq), '=') => (AttributeOperator::Equals, rest),
+            [ComponentValue::Token(Token::Delim(c)), eq, rest @ ..] if is_delim(Some(eq), '=') => {
+                let op = match c {
+                    '~' => AttributeOperator::Includes,
+                    '|' => AttributeOperator::DashMatch,
+                    '^' => AttributeOperator::Prefix,
+                    '$' => AttributeOperator::Suffix,
+                    '*' => AttributeOperator::Substring,
+                    _ => return Err(invalid(values)),
+                };
+                (op, rest)
+            }
+            _ => return Err(invalid(values)),
+        };
+
+        let rest = trim(rest);
+        let value = match rest.first() {
+            Some(ComponentValue::Token(Token::Ident(s) | Token::String(s))) => s.clone(),
+            _ => return Err(invalid(values)),
+        };
+
+        let case_insensitive = match trim(&rest[1..]) {
+            [] => false,
+            [flag] => match ident(Some(flag)) {
+                Some(flag) if flag.eq_ignore_ascii_case("i") => true,
+                Some(flag) if flag.eq_ignore_ascii_case("s") => false,
+                _ => return Err(invalid(values)),
+            },
+            _ => return Err(invalid(values)),
+        };
+
+        Ok(SimpleSelector::Attribute {
+            ns,
+            name,
+            matcher: Some((op, value)),
+            case_insensitive,
+        })
+    }
+
+    // An `:nth-child()` argument, with an optional "of" selector list
+    fn nth_of(&self, values: &[ComponentValue]) -> Result<(Nth, Option<SelectorList>)> {
+        let of = values
+            .iter()
+            .position(|value| ident(Some(value)).is_some_and(|s| s.eq_ignore_ascii_case("of")));
+
+        let (nth, selectors) = match of {
+            Some(i) => (&values[..i], Some(self.list(&values[i + 1..])?)),
+            None => (values, None),
+        };
+        let nth = parse_nth(nth).ok_or_else(|| invalid(values))?;
+        Ok((nth, selectors))
+    }
+
+    fn pseudo_class(&self, value: &ComponentValue) -> Result<PseudoClass> {
+        let nth = |values: &[ComponentValue]| parse_nth(values).ok_or_else(|| invalid(values));
+
+        let pseudo = match value {
+            ComponentValue::Token(Token::Ident(name)) => match name.to_ascii_lowercase().as_str() {
+                "root" => PseudoClass::Root,
+                "scope" => PseudoClass::Scope,
+                "empty" => PseudoClass::Empty,
+                "first-child" => PseudoClass::FirstChild,
+                "last-child" => PseudoClass::LastChild,
+                "only-child" => PseudoClass::OnlyChild,
+                "first-of-type" => PseudoClass::FirstOfType,
+                "last-of-type" => PseudoClass::LastOfType,
+                "only-of-type" => PseudoClass::OnlyOfType,
+                _ => return Err(Error::General(format!("unknown pseudo-class: {name}"))),
+            },
+
+            ComponentValue::Function { name, arguments } => {
+                match name.to_ascii_lowercase().as_str() {
+                    "nth-child" => {
+                        let (nth, of) = self.nth_of(arguments)?;
+                        PseudoClass::NthChild(nth, of)
+                    }
+                    "nth-last-child" => {
+                        let (nth, of) = self.nth_of(arguments)?;
+                        PseudoClass::NthLastChild(nth, of)
+                    }
+                    "nth-of-type" => PseudoClass::NthOfType(nth(arguments)?),
+                    "nth-last-of-type" => PseudoClass::NthLastOfType(nth(arguments)?),
+                    "not" => PseudoClass::Not(self.list(arguments)?),
+                    "is" | "matches" => PseudoClass::Is(self.forgiving_list(arguments)),
+                    "where" => PseudoClass::Where(self.forgiving_list(arguments)),
+                    "has" => PseudoClass::Has(self.relative_list(arguments)?),
+                    _ => return Err(Error::General(format!("unknown pseudo-class: {name}()"))),
+                }
+            }
+
+            _ => return Err(invalid(std::slice::from_ref(value))),
+        };
+
+        Ok(pseudo)
+    }
+
+    // A compound selector runs until whitespace or a combinator
+    fn compound<'v>(
+        &self,
+        values: &'v [ComponentValue],
+    ) -> Result<(CompoundSelector, &'v [ComponentValue])> {
+        let mut compound = vec![];
+        let mut rest = values;
+
+        // The type selector, which takes the default namespace when it has no prefix
+        let (ns, after_ns) = match self.namespace_prefix(rest)? {
+            Some((ns, after)) => (Some(ns), after),
+            None => (None, rest),
+        };
+        let name = match after_ns.first() {
+            Some(ComponentValue::Token(Token::Ident(name))) => Some(Some(name.clone())),
+            v if is_delim(v, '*') => Some(None),
+            _ if ns.is_some() => return Err(invalid(values)),
+            _ => None,
+        };
+        if let Some(name) = name {
+            let ns = ns.unwrap_or_else(|| match self.namespaces.default {
+                Some(ref url) => NamespaceConstraint::Url(url.clone()),
+                None => NamespaceConstraint::Any,
+            });
+            compound.push(SimpleSelector::Type { ns, name });
+            rest = &after_ns[1..];
+        }
+
+        loop {
+            let selector = match rest {
+                [ComponentValue::Token(Token::Hash { value, id: true }), ..] => {
+                    rest = &rest[1..];
+                    SimpleSelector::Id(value.clone())
+                }
+                [dot, ComponentValue::Token(Token::Ident(class)), ..]
+                    if is_delim(Some(dot), '.') =>
+                {
+                    rest = &rest[2..];
+                    SimpleSelector::Class(class.clone())
+                }
+                [ComponentValue::Block {
+                    kind: BlockKind::Square,
+                    contents,
+                }, ..] => {
+                    rest = &rest[1..];
+                    self.attribute(contents)?
+                }
+                [ComponentValue::Token(Token::Colon), pseudo, ..] => {
+                    rest = &rest[2..];
+                    SimpleSelector::PseudoClass(self.pseudo_class(pseudo)?)
+                }
+                _ => break,
+            };
+            compound.push(selector);
+        }
+
+        if compound.is_empty() {
+            return Err(invalid(values));
+        }
+        // Without a type selector, a compound still only matches the default namespace
+        let has_type = matches!(compound[0], SimpleSelector::Type { .. });
+        if let (false, Some(url)) = (has_type, &self.namespaces.default) {
+            let ns = NamespaceConstraint::Url(url.clone());
+            compound.insert(0, SimpleSelector::Type { ns, name: None });
+        }
+        Ok((compound, rest))
+    }
+
+    fn combinator(values: &[ComponentValue]) -> (Option<Combinator>, &[ComponentValue]) {
+        let trimmed = trim_start(values);
+        let combinator = match trimmed.first() {
+            v if is_delim(v, '>') => Combinator::Child,
+            v if is_delim(v, '+') => Combinator::NextSibling,
+            v if is_delim(v, '~') => Combinator::SubsequentSibling,
+            _ if trimmed.len() < values.len() => return (Some(Combinator::Descendant), trimmed),
+            _ => return (None, values),
+        };
+        (Some(combinator), trim_start(&trimmed[1..]))
+    }
+
+    fn complex(
+        &self,
+        values: &[ComponentValue],
+        mut selector: ComplexSelector,
+    ) -> Result<ComplexSelector> {
+        let mut rest = values;
+
+        loop {
+            let (compound, after) = self.compound(rest)?;
+            selector.compounds.push(compound);
+            if after.is_empty() {
+                return Ok(selector);
+            }
+
+            let (combinator, after) = Self::combinator(after);
+            match combinator {
+                Some(combinator) if !after.is_empty() => {
+                    selector.combinators.push(combinator);
+                    rest = after;
+                }
+                _ => return Err(invalid(values)),
+            }
+        }
+    }
+
+    fn list(&self, values: &[ComponentValue]) -> Result<SelectorList> {
+        let empty = ComplexSelector {
+            compounds: vec![],
+            combinators: vec![],
+        };
+        split_commas(values)
+            .into_iter()
+            .map(|values| self.complex(values, empty.clone()))
+            .collect::<Result<_>>()
+            .map(SelectorList)
+    }
+
+    // Invalid selectors in the list of `:is()` and `:where()` are dropped rather than making
+    // the whole list invalid
+    fn forgiving_list(&self, values: &[ComponentValue]) -> SelectorList {
+        let empty = ComplexSelector {
+            compounds: vec![],
+            combinators: vec![],
+        };
+        SelectorList(
+            split_commas(values)
+                .into_iter()
+                .filter_map(|values| self.complex(values, empty.clone()).ok())
+                .collect(),
+        )
+    }
+
+    fn relative_list(&self, values: &[ComponentValue]) -> Result<Vec<ComplexSelector>> {
+        split_commas(values)
+            .into_iter()
+            .map(|values| {
+                let (combinator, rest) = Self::combinator(values);
+                let scope = ComplexSelector {
+                    compounds: vec![vec![SimpleSelector::PseudoClass(PseudoClass::Scope)]],
+                    combinators: vec![combinator.unwrap_or(Combinator::Descendant)],
+                };
+                self.complex(rest, scope)
+            })
+            .collect()
+    }
+}
+
+fn trim_start(mut values: &[ComponentValue]) -> &[ComponentValue] {
+    while values.first().is_some_and(ComponentValue::is_whitespace) {
+        values = &values[1..];
+    }
+    values
+}
+
+impl SelectorList {
+    pub fn parse(s: &str) -> Result<Self> {
+        Self::parse_with_namespaces(s, &Namespaces::default())
+    }
+
+    /// Parses a selector list whose namespace prefixes are resolved with `namespaces`.
+    /// Undeclared prefixes make the selector invalid.
+    pub fn parse_with_namespaces(s: &str, namespaces: &Namespaces) -> Result<Self> {
+        Self::from_values(&parse_component_value_list(s), namespaces)
+    }
+
+    /// Parses the prelude of a style rule.
+    pub fn from_values(values: &[ComponentValue], namespaces: &Namespaces) -> Result<Self> {
+        let values = trim(values);
+        if values.is_empty() {
+            return Err(invalid(values));
+        }
+        SelectorParser { namespaces }.list(values)
+    }
+}
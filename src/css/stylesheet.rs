@@ -0,0 +1,170 @@
+use super::parser::{
+    self, declaration_list, rule_list, AtRule, ComponentValue, Declaration, DeclarationOrAtRule,
+    Rule, SyntaxError,
+};
+use super::tokenizer::Token;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StyleRule {
+    /// The selector list, as yet unparsed.
+    pub prelude: Vec<ComponentValue>,
+    pub declarations: Vec<Declaration>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaRule {
+    /// The media query list, as yet unparsed.
+    pub query: Vec<ComponentValue>,
+    pub rules: Vec<CssRule>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportRule {
+    pub url: String,
+    /// Whatever follows the URL, such as a media query list.
+    pub conditions: Vec<ComponentValue>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CssRule {
+    Style(StyleRule),
+    Media(MediaRule),
+    Import(ImportRule),
+    /// An at-rule this model does not interpret.
+    Other(AtRule),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stylesheet {
+    pub rules: Vec<CssRule>,
+    /// Rules and declarations that were dropped because they could not be parsed.
+    pub errors: Vec<SyntaxError>,
+}
+
+fn trim(mut values: Vec<ComponentValue>) -> Vec<ComponentValue> {
+    while values.last().is_some_and(ComponentValue::is_whitespace) {
+        values.pop();
+    }
+    let start = values
+        .iter()
+        .position(|value| !value.is_whitespace())
+        .unwrap_or(values.len());
+    values.drain(..start);
+    values
+}
+
+/// The declarations of a style rule's block or a `style` attribute.  At-rules are not
+/// allowed there and are dropped along with invalid declarations.
+pub fn declarations(
+    items: Vec<Result<DeclarationOrAtRule, SyntaxError>>,
+    errors: &mut Vec<SyntaxError>,
+) -> Vec<Declaration> {
+    let mut declarations = vec![];
+
+    for item in items {
+        match item {
+            Ok(DeclarationOrAtRule::Declaration(declaration)) => declarations.push(declaration),
+            Ok(DeclarationOrAtRule::AtRule(_)) => errors.push(SyntaxError::Invalid),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    declarations
+}
+
+fn import(rule: &AtRule) -> Option<ImportRule> {
+    if rule.block.is_some() {
+        return None;
+    }
+
+    let mut prelude = rule
+        .prelude
+        .iter()
+        .skip_while(|value| value.is_whitespace());
+    let url = match prelude.next()? {
+        ComponentValue::Token(Token::String(url) | Token::Url(url)) => url.clone(),
+        ComponentValue::Function { name, arguments } if name.eq_ignore_ascii_case("url") => {
+            match trim(arguments.clone()).as_slice() {
+                [ComponentValue::Token(Token::String(url))] => url.clone(),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    Some(ImportRule {
+        url,
+        conditions: trim(prelude.cloned().collect()),
+    })
+}
+
+impl Stylesheet {
+    pub fn parse(s: &str) -> Self {
+        let mut stylesheet = Self::default();
+        stylesheet.rules = stylesheet.rules(parser::parse_stylesheet(s));
+        stylesheet
+    }
+
+    fn rules(&mut self, rules: Vec<Result<Rule, SyntaxError>>) -> Vec<CssRule> {
+        let mut css_rules = vec![];
+
+        for rule in rules {
+            let rule = match rule {
+                Ok(Rule::Qualified(rule)) => CssRule::Style(StyleRule {
+                    prelude: trim(rule.prelude),
+                    declarations: declarations(declaration_list(rule.block), &mut self.errors),
+                }),
+
+                Ok(Rule::At(rule)) if rule.name.eq_ignore_ascii_case("media") => {
+                    let Some(block) = rule.block else {
+                        self.errors.push(SyntaxError::Invalid);
+                        continue;
+                    };
+                    let rules = rule_list(block);
+                    CssRule::Media(MediaRule {
+                        query: trim(rule.prelude),
+                        rules: self.rules(rules),
+                    })
+                }
+
+                Ok(Rule::At(rule)) if rule.name.eq_ignore_ascii_case("import") => {
+                    match import(&rule) {
+                        Some(import) => CssRule::Import(import),
+                        None => {
+                            self.errors.push(SyntaxError::Invalid);
+                            continue;
+                        }
+                    }
+                }
+
+                Ok(Rule::At(rule)) => CssRule::Other(rule),
+
+                Err(e) => {
+                    self.errors.push(e);
+                    continue;
+                }
+            };
+
+            css_rules.push(rule);
+        }
+
+        css_rules
+    }
+
+    /// The style rules, including those nested in media rules, in the order they appear.
+    pub fn style_rules(&self) -> impl Iterator<Item = &StyleRule> {
+        fn walk<'a>(rules: &'a [CssRule], out: &mut Vec<&'a StyleRule>) {
+            for rule in rules {
+                match rule {
+                    CssRule::Style(style) => out.push(style),
+                    CssRule::Media(media) => walk(&media.rules, out),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut out = vec![];
+        walk(&self.rules, &mut out);
+        out.into_iter()
+    }
+}
@@ -0,0 +1,464 @@
+//! The tokenizer of CSS Syntax Module Level 3, section 4.
+//! See https://www.w3.org/TR/css-syntax-3/#tokenization
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Numeric {
+    /// The number as written, which is kept so that it can be serialized the same way.
+    pub repr: String,
+    pub value: f64,
+    /// Whether the number was written without a fraction or exponent.
+    pub integer: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Ident(String),
+    Function(String),
+    AtKeyword(String),
+    Hash {
+        value: String,
+        /// Whether the value would be a valid identifier, as needed for an ID selector.
+        id: bool,
+    },
+    String(String),
+    BadString,
+    Url(String),
+    BadUrl,
+    Delim(char),
+    Number(Numeric),
+    Percentage(Numeric),
+    Dimension {
+        number: Numeric,
+        unit: String,
+    },
+    Whitespace,
+    Cdo,
+    Cdc,
+    Colon,
+    Semicolon,
+    Comma,
+    OpenSquare,
+    CloseSquare,
+    OpenParen,
+    CloseParen,
+    OpenCurly,
+    CloseCurly,
+}
+
+// Newlines are normalized to LF, and NULs replaced, before tokenizing
+fn preprocess(input: &str) -> Vec<char> {
+    let mut chars = Vec::with_capacity(input.len());
+    let mut iter = input.chars().peekable();
+
+    while let Some(c) = iter.next() {
+        match c {
+            '\r' => {
+                iter.next_if_eq(&'\n');
+                chars.push('\n');
+            }
+            '\x0C' => chars.push('\n'),
+            '\0' => chars.push('\u{FFFD}'),
+            c => chars.push(c),
+        }
+    }
+
+    chars
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, '\n' | '\t' | ' ')
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c as u32 >= 0x80
+}
+
+fn is_name(c: char) -> bool {
+    is_name_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+fn is_non_printable(c: char) -> bool {
+    matches!(c, '\0'..='\x08' | '\x0B' | '\x0E'..='\x1F' | '\x7F')
+}
+
+fn is_valid_escape(first: Option<char>, second: Option<char>) -> bool {
+    first == Some('\\') && second != Some('\n')
+}
+
+fn starts_identifier(first: Option<char>, second: Option<char>, third: Option<char>) -> bool {
+    match first {
+        Some('-') => {
+            second.is_some_and(|c| is_name_start(c) || c == '-') || is_valid_escape(second, third)
+        }
+        Some('\\') => is_valid_escape(first, second),
+        Some(c) => is_name_start(c),
+        None => false,
+    }
+}
+
+fn starts_number(first: Option<char>, second: Option<char>, third: Option<char>) -> bool {
+    let digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+    match first {
+        Some('+' | '-') => digit(second) || (second == Some('.') && digit(third)),
+        Some('.') => digit(second),
+        c => digit(c),
+    }
+}
+
+pub struct Tokenizer {
+    chars: Vec<char>,
+    pos: usize,
+    /// The number of parse errors met so far.  Errors do not stop tokenizing.
+    pub errors: usize,
+}
+
+impl Tokenizer {
+    pub fn new(input: &str) -> Self {
+        Self {
+            chars: preprocess(input),
+            pos: 0,
+            errors: 0,
+        }
+    }
+
+    fn peek(&self, n: usize) -> Option<char> {
+        self.chars.get(self.pos + n).copied()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek(0);
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+
+    fn skip_comments(&mut self) {
+        while self.peek(0) == Some('/') && self.peek(1) == Some('*') {
+            self.pos += 2;
+            loop {
+                match self.next_char() {
+                    Some('*') if self.peek(0) == Some('/') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.errors += 1;
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    // Called just after the backslash of a valid escape
+    fn escaped(&mut self) -> char {
+        let Some(c) = self.next_char() else {
+            self.errors += 1;
+            return '\u{FFFD}';
+        };
+        if !c.is_ascii_hexdigit() {
+            return c;
+        }
+
+        let mut code = c.to_digit(16).unwrap_or_default();
+        for _ in 0..5 {
+            match self.peek(0).and_then(|c| c.to_digit(16)) {
+                Some(digit) => {
+                    code = code * 16 + digit;
+                    self.pos += 1;
+                }
+                None => break,
+            }
+        }
+        if self.peek(0).is_some_and(is_whitespace) {
+            self.pos += 1;
+        }
+
+        match char::from_u32(code) {
+            Some(c) if code != 0 => c,
+            _ => '\u{FFFD}',
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let mut name = String::new();
+        loop {
+            match self.peek(0) {
+                Some(c) if is_name(c) => {
+                    name.push(c);
+                    self.pos += 1;
+                }
+                c if is_valid_escape(c, self.peek(1)) => {
+                    self.pos += 1;
+                    name.push(self.escaped());
+                }
+                _ => return name,
+            }
+        }
+    }
+
+    fn number(&mut self) -> Numeric {
+        let mut repr = String::new();
+        let mut integer = true;
+
+        let digits = |t: &mut Self, repr: &mut String| {
+            while let Some(c) = t.peek(0).filter(char::is_ascii_digit) {
+                repr.push(c);
+                t.pos += 1;
+            }
+        };
+
+        if let Some(c @ ('+' | '-')) = self.peek(0) {
+            repr.push(c);
+            self.pos += 1;
+        }
+        digits(self, &mut repr);
+
+        if self.peek(0) == Some('.') && self.peek(1).is_some_and(|c| c.is_ascii_digit()) {
+            repr.push('.');
+            self.pos += 1;
+            integer = false;
+            digits(self, &mut repr);
+        }
+
+        if let Some(e @ ('e' | 'E')) = self.peek(0) {
+            let digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+            let sign = matches!(self.peek(1), Some('+' | '-'));
+            if digit(self.peek(1)) || (sign && digit(self.peek(2))) {
+                repr.push(e);
+                self.pos += 1;
+                if sign {
+                    repr.extend(self.next_char());
+                }
+                integer = false;
+                digits(self, &mut repr);
+            }
+        }
+
+        let value = repr.parse().unwrap_or_default();
+        Numeric {
+            repr,
+            value,
+            integer,
+        }
+    }
+
+    fn numeric(&mut self) -> Token {
+        let number = self.number();
+
+        if starts_identifier(self.peek(0), self.peek(1), self.peek(2)) {
+            let unit = self.name();
+            Token::Dimension { number, unit }
+        } else if self.peek(0) == Some('%') {
+            self.pos += 1;
+            Token::Percentage(number)
+        } else {
+            Token::Number(number)
+        }
+    }
+
+    fn string(&mut self, ending: char) -> Token {
+        let mut s = String::new();
+        loop {
+            match self.next_char() {
+                Some(c) if c == ending => return Token::String(s),
+                None => {
+                    self.errors += 1;
+                    return Token::String(s);
+                }
+                Some('\n') => {
+                    self.errors += 1;
+                    self.pos -= 1;
+                    return Token::BadString;
+                }
+                Some('\\') => match self.peek(0) {
+                    None => {}
+                    Some('\n') => self.pos += 1,
+                    Some(_) => s.push(self.escaped()),
+                },
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn bad_url_remnants(&mut self) {
+        loop {
+            match self.peek(0) {
+                None => return,
+                Some(')') => {
+                    self.pos += 1;
+                    return;
+                }
+                c if is_valid_escape(c, self.peek(1)) => {
+                    self.pos += 1;
+                    self.escaped();
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek(0).is_some_and(is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    // Called after "url(" when the argument is not quoted
+    fn url(&mut self) -> Token {
+        let mut url = String::new();
+        self.skip_whitespace();
+
+        loop {
+            match self.next_char() {
+                Some(')') => return Token::Url(url),
+                None => {
+                    self.errors += 1;
+                    return Token::Url(url);
+                }
+                Some(c) if is_whitespace(c) => {
+                    self.skip_whitespace();
+                    match self.peek(0) {
+                        Some(')') => {
+                            self.pos += 1;
+                            return Token::Url(url);
+                        }
+                        None => {
+                            self.errors += 1;
+                            return Token::Url(url);
+                        }
+                        Some(_) => {
+                            self.bad_url_remnants();
+                            return Token::BadUrl;
+                        }
+                    }
+                }
+                Some(c) if matches!(c, '"' | '\'' | '(') || is_non_printable(c) => {
+                    self.errors += 1;
+                    self.bad_url_remnants();
+                    return Token::BadUrl;
+                }
+                Some('\\') => {
+                    if is_valid_escape(Some('\\'), self.peek(0)) {
+                        url.push(self.escaped());
+                    } else {
+                        self.errors += 1;
+                        self.bad_url_remnants();
+                        return Token::BadUrl;
+                    }
+                }
+                Some(c) => url.push(c),
+            }
+        }
+    }
+
+    fn ident_like(&mut self) -> Token {
+        let name = self.name();
+
+        if name.eq_ignore_ascii_case("url") && self.peek(0) == Some('(') {
+            self.pos += 1;
+            while self.peek(0).is_some_and(is_whitespace) && self.peek(1).is_some_and(is_whitespace)
+            {
+                self.pos += 1;
+            }
+
+            let quote = |c: Option<char>| matches!(c, Some('"' | '\''));
+            let next = self.peek(0);
+            if quote(next) || (next.is_some_and(is_whitespace) && quote(self.peek(1))) {
+                Token::Function(name)
+            } else {
+                self.url()
+            }
+        } else if self.peek(0) == Some('(') {
+            self.pos += 1;
+            Token::Function(name)
+        } else {
+            Token::Ident(name)
+        }
+    }
+
+    pub fn next_token(&mut self) -> Option<Token> {
+        self.skip_comments();
+
+        let c = self.next_char()?;
+        let (first, second, third) = (Some(c), self.peek(0), self.peek(1));
+
+        let token = match c {
+            c if is_whitespace(c) => {
+                self.skip_whitespace();
+                Token::Whitespace
+            }
+            '"' | '\'' => self.string(c),
+            '#' if second.is_some_and(is_name) || is_valid_escape(second, third) => {
+                let id = starts_identifier(second, third, self.peek(2));
+                Token::Hash {
+                    value: self.name(),
+                    id,
+                }
+            }
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            ';' => Token::Semicolon,
+            '[' => Token::OpenSquare,
+            ']' => Token::CloseSquare,
+            '{' => Token::OpenCurly,
+            '}' => Token::CloseCurly,
+            '+' | '.' if starts_number(first, second, third) => {
+                self.pos -= 1;
+                self.numeric()
+            }
+            '-' if starts_number(first, second, third) => {
+                self.pos -= 1;
+                self.numeric()
+            }
+            '-' if second == Some('-') && third == Some('>') => {
+                self.pos += 2;
+                Token::Cdc
+            }
+            '-' if starts_identifier(first, second, third) => {
+                self.pos -= 1;
+                self.ident_like()
+            }
+            '<' if second == Some('!') && third == Some('-') && self.peek(2) == Some('-') => {
+                self.pos += 3;
+                Token::Cdo
+            }
+            '@' if starts_identifier(second, third, self.peek(2)) => Token::AtKeyword(self.name()),
+            '\\' if is_valid_escape(first, second) => {
+                self.pos -= 1;
+                self.ident_like()
+            }
+            '\\' => {
+                self.errors += 1;
+                Token::Delim('\\')
+            }
+            c if c.is_ascii_digit() => {
+                self.pos -= 1;
+                self.numeric()
+            }
+            c if is_name_start(c) => {
+                self.pos -= 1;
+                self.ident_like()
+            }
+            c => Token::Delim(c),
+        };
+
+        Some(token)
+    }
+}
+
+impl Iterator for Tokenizer {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        self.next_token()
+    }
+}
+
+pub fn tokenize(input: &str) -> Vec<Token> {
+    Tokenizer::new(input).collect()
+}
@@ -0,0 +1,581 @@
+//! An arena-based DOM.  Nodes live in a `Vec` and refer to each other by `NodeId`, so a tree
+//! can be walked without reference counting and handed to other threads once it is built.
+use crate::html5::ParseError;
+use markup5ever::interface::{
+    tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeSink},
+    ExpandedName, QualName,
+};
+use markup5ever::tendril::StrTendril;
+use markup5ever::{namespace_url, ns};
+use std::borrow::Cow;
+use std::ops::Range;
+
+pub mod serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute {
+    pub name: QualName,
+    pub value: String,
+}
+
+#[derive(Clone, Debug)]
+pub enum NodeData {
+    /// The document, or the document fragment holding a template's contents.
+    Document,
+
+    Doctype {
+        name: String,
+        public_id: String,
+        system_id: String,
+    },
+
+    Text {
+        contents: String,
+    },
+
+    Comment {
+        contents: String,
+    },
+
+    Element {
+        name: QualName,
+        attrs: Vec<Attribute>,
+        template_contents: Option<NodeId>,
+        mathml_annotation_xml_integration_point: bool,
+    },
+
+    ProcessingInstruction {
+        target: String,
+        contents: String,
+    },
+}
+
+/// Where in the source the token that created a node was.  Backends that do not track columns
+/// or byte offsets leave `col` or `span` unset.  The span of an element covers its start tag,
+/// and that of a text node all of the text merged into it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub col: Option<usize>,
+    pub span: Option<Range<usize>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub parent: Option<NodeId>,
+    pub prev_sibling: Option<NodeId>,
+    pub next_sibling: Option<NodeId>,
+    pub first_child: Option<NodeId>,
+    pub last_child: Option<NodeId>,
+    pub data: NodeData,
+    /// Unset for the document and for nodes not created by the parser.
+    pub location: Option<SourceLocation>,
+}
+
+impl Node {
+    fn new(data: NodeData) -> Self {
+        Self {
+            parent: None,
+            prev_sibling: None,
+            next_sibling: None,
+            first_child: None,
+            last_child: None,
+            data,
+            location: None,
+        }
+    }
+
+    pub fn element_name(&self) -> Option<&QualName> {
+        match self.data {
+            NodeData::Element { ref name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn attrs(&self) -> &[Attribute] {
+        match self.data {
+            NodeData::Element { ref attrs, .. } => attrs,
+            _ => &[],
+        }
+    }
+
+    /// The value of the attribute with the given local name and no namespace.
+    pub fn attr(&self, local: &str) -> Option<&str> {
+        self.attrs()
+            .iter()
+            .find(|attr| attr.name.ns == ns!() && &*attr.name.local == local)
+            .map(|attr| attr.value.as_str())
+    }
+}
+
+/// The name of an element as the html5lib tree format writes it, with a prefix for SVG and
+/// MathML elements.
+pub(crate) fn dump_name(name: &QualName) -> String {
+    match name.ns {
+        ns!(svg) => format!("svg {}", name.local),
+        ns!(mathml) => format!("math {}", name.local),
+        _ => name.local.to_string(),
+    }
+}
+
+/// The nodes of a parsed document.  The document node is always `Arena::document()`.
+#[derive(Clone, Debug)]
+pub struct Arena {
+    nodes: Vec<Node>,
+    pub quirks_mode: QuirksMode,
+    pub(crate) errors: Vec<ParseError>,
+    line: u64,
+    col: Option<usize>,
+    span: Option<Range<usize>>,
+}
+
+impl Default for Arena {
+    // The tree builder only reports changes to the line number, starting from line 1
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node::new(NodeData::Document)],
+            quirks_mode: QuirksMode::NoQuirks,
+            errors: vec![],
+            line: 1,
+            col: None,
+            span: None,
+        }
+    }
+}
+
+impl Arena {
+    pub fn document(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).parent
+    }
+
+    pub fn children(&self, id: NodeId) -> Children<'_> {
+        Children {
+            arena: self,
+            next: self.node(id).first_child,
+        }
+    }
+
+    /// The node and all of its descendants, in tree order.  Template contents are not included.
+    pub fn descendants(&self, id: NodeId) -> Descendants<'_> {
+        Descendants {
+            arena: self,
+            root: id,
+            next: Some(id),
+        }
+    }
+
+    pub fn template_contents(&self, id: NodeId) -> Option<NodeId> {
+        match self.node(id).data {
+            NodeData::Element {
+                template_contents, ..
+            } => template_contents,
+            _ => None,
+        }
+    }
+
+    /// The last node in tree order under `root`, template contents included, whose span contains
+    /// byte `offset` of the source.
+    pub fn node_at(&self, root: NodeId, offset: usize) -> Option<NodeId> {
+        let mut found = None;
+
+        for id in self.descendants(root) {
+            let span = self
+                .node(id)
+                .location
+                .as_ref()
+                .and_then(|l| l.span.as_ref());
+            if span.is_some_and(|span| span.contains(&offset)) {
+                found = Some(id);
+            }
+            if let Some(contents) = self.template_contents(id) {
+                found = self.node_at(contents, offset).or(found);
+            }
+        }
+
+        found
+    }
+
+    /// The text of the node's descendants, in tree order.
+    pub fn text_content(&self, id: NodeId) -> String {
+        self.descendants(id)
+            .filter_map(|id| match self.node(id).data {
+                NodeData::Text { ref contents } => Some(contents.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Sets the column and byte span of the token about to be processed, for backends that
+    /// track them.
+    #[cfg(feature = "quick-xml")]
+    pub(crate) fn set_current_position(&mut self, col: usize, span: Option<Range<usize>>) {
+        self.col = Some(col);
+        self.span = span;
+    }
+
+    fn current_location(&self) -> SourceLocation {
+        SourceLocation {
+            line: self.line as usize,
+            col: self.col,
+            span: self.span.clone(),
+        }
+    }
+
+    fn new_node(&mut self, data: NodeData) -> NodeId {
+        let mut node = Node::new(data);
+        if !matches!(node.data, NodeData::Document) {
+            node.location = Some(self.current_location());
+        }
+        self.nodes.push(node);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+
+    fn detach(&mut self, id: NodeId) {
+        let Node {
+            parent,
+            prev_sibling,
+            next_sibling,
+            ..
+        } = *self.node(id);
+
+        if let Some(parent) = parent {
+            let parent = self.node_mut(parent);
+            if parent.first_child == Some(id) {
+                parent.first_child = next_sibling;
+            }
+            if parent.last_child == Some(id) {
+                parent.last_child = prev_sibling;
+            }
+        }
+
+        if let Some(prev) = prev_sibling {
+            self.node_mut(prev).next_sibling = next_sibling;
+        }
+
+        if let Some(next) = next_sibling {
+            self.node_mut(next).prev_sibling = prev_sibling;
+        }
+
+        let node = self.node_mut(id);
+        node.parent = None;
+        node.prev_sibling = None;
+        node.next_sibling = None;
+    }
+
+    fn append_child(&mut self, parent: NodeId, child: NodeId) {
+        self.detach(child);
+        let last = self.node(parent).last_child;
+
+        {
+            let node = self.node_mut(child);
+            node.parent = Some(parent);
+            node.prev_sibling = last;
+        }
+
+        match last {
+            Some(last) => self.node_mut(last).next_sibling = Some(child),
+            None => self.node_mut(parent).first_child = Some(child),
+        }
+        self.node_mut(parent).last_child = Some(child);
+    }
+
+    fn insert_before(&mut self, sibling: NodeId, child: NodeId) {
+        self.detach(child);
+        let Node {
+            parent,
+            prev_sibling,
+            ..
+        } = *self.node(sibling);
+
+        {
+            let node = self.node_mut(child);
+            node.parent = parent;
+            node.prev_sibling = prev_sibling;
+            node.next_sibling = Some(sibling);
+        }
+        self.node_mut(sibling).prev_sibling = Some(child);
+
+        match prev_sibling {
+            Some(prev) => self.node_mut(prev).next_sibling = Some(child),
+            None => {
+                if let Some(parent) = parent {
+                    self.node_mut(parent).first_child = Some(child);
+                }
+            }
+        }
+    }
+
+    // Adjacent text nodes are merged, as the tree builder expects, and the span of the merged
+    // node grows to take in the new text
+    fn append_text(&mut self, id: Option<NodeId>, text: &str) -> bool {
+        let Some(id) = id else {
+            return false;
+        };
+
+        let span = self.span.clone();
+        let node = &mut self.nodes[id.0];
+        let NodeData::Text { ref mut contents } = node.data else {
+            return false;
+        };
+
+        contents.push_str(text);
+        if let (Some(location), Some(span)) = (node.location.as_mut(), span) {
+            if let Some(ref mut merged) = location.span {
+                merged.end = merged.end.max(span.end);
+            }
+        }
+        true
+    }
+}
+
+pub struct Children<'a> {
+    arena: &'a Arena,
+    next: Option<NodeId>,
+}
+
+impl Iterator for Children<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = self.arena.node(id).next_sibling;
+        Some(id)
+    }
+}
+
+pub struct Descendants<'a> {
+    arena: &'a Arena,
+    root: NodeId,
+    next: Option<NodeId>,
+}
+
+impl Iterator for Descendants<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        let node = self.arena.node(id);
+
+        self.next = node.first_child.or_else(|| {
+            let mut current = id;
+            loop {
+                if current == self.root {
+                    return None;
+                }
+                let node = self.arena.node(current);
+                if let Some(next) = node.next_sibling {
+                    return Some(next);
+                }
+                current = node.parent?;
+            }
+        });
+
+        Some(id)
+    }
+}
+
+fn attributes(attrs: Vec<markup5ever::Attribute>) -> Vec<Attribute> {
+    attrs
+        .into_iter()
+        .map(|attr| Attribute {
+            name: attr.name,
+            value: attr.value.into(),
+        })
+        .collect()
+}
+
+impl TreeSink for Arena {
+    type Handle = NodeId;
+    type Output = Self;
+
+    fn finish(self) -> Self {
+        self
+    }
+
+    fn parse_error(&mut self, msg: Cow<'static, str>) {
+        self.errors.push(ParseError {
+            line: self.line as usize,
+            col: self.col,
+            message: msg.into_owned(),
+        });
+    }
+
+    fn get_document(&mut self) -> NodeId {
+        self.document()
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a NodeId) -> ExpandedName<'a> {
+        match self.node(*target).data {
+            NodeData::Element { ref name, .. } => name.expanded(),
+            _ => panic!("not an element"),
+        }
+    }
+
+    fn create_element(
+        &mut self,
+        name: QualName,
+        attrs: Vec<markup5ever::Attribute>,
+        flags: ElementFlags,
+    ) -> NodeId {
+        let template_contents = if flags.template {
+            Some(self.new_node(NodeData::Document))
+        } else {
+            None
+        };
+
+        self.new_node(NodeData::Element {
+            name,
+            attrs: attributes(attrs),
+            template_contents,
+            mathml_annotation_xml_integration_point: flags.mathml_annotation_xml_integration_point,
+        })
+    }
+
+    fn create_comment(&mut self, text: StrTendril) -> NodeId {
+        self.new_node(NodeData::Comment {
+            contents: text.into(),
+        })
+    }
+
+    fn create_pi(&mut self, target: StrTendril, data: StrTendril) -> NodeId {
+        self.new_node(NodeData::ProcessingInstruction {
+            target: target.into(),
+            contents: data.into(),
+        })
+    }
+
+    fn append(&mut self, parent: &NodeId, child: NodeOrText<NodeId>) {
+        match child {
+            NodeOrText::AppendNode(node) => self.append_child(*parent, node),
+            NodeOrText::AppendText(text) => {
+                if !self.append_text(self.node(*parent).last_child, &text) {
+                    let node = self.new_node(NodeData::Text {
+                        contents: text.into(),
+                    });
+                    self.append_child(*parent, node);
+                }
+            }
+        }
+    }
+
+    fn append_based_on_parent_node(
+        &mut self,
+        element: &NodeId,
+        prev_element: &NodeId,
+        child: NodeOrText<NodeId>,
+    ) {
+        if self.parent(*element).is_some() {
+            self.append_before_sibling(element, child);
+        } else {
+            self.append(prev_element, child);
+        }
+    }
+
+    fn append_doctype_to_document(
+        &mut self,
+        name: StrTendril,
+        public_id: StrTendril,
+        system_id: StrTendril,
+    ) {
+        let node = self.new_node(NodeData::Doctype {
+            name: name.into(),
+            public_id: public_id.into(),
+            system_id: system_id.into(),
+        });
+        self.append_child(self.document(), node);
+    }
+
+    fn get_template_contents(&mut self, target: &NodeId) -> NodeId {
+        self.template_contents(*target)
+            .expect("not a template element")
+    }
+
+    fn same_node(&self, x: &NodeId, y: &NodeId) -> bool {
+        x == y
+    }
+
+    fn set_quirks_mode(&mut self, mode: QuirksMode) {
+        self.quirks_mode = mode;
+    }
+
+    fn append_before_sibling(&mut self, sibling: &NodeId, new_node: NodeOrText<NodeId>) {
+        match new_node {
+            NodeOrText::AppendNode(node) => self.insert_before(*sibling, node),
+            NodeOrText::AppendText(text) => {
+                if !self.append_text(self.node(*sibling).prev_sibling, &text) {
+                    let node = self.new_node(NodeData::Text {
+                        contents: text.into(),
+                    });
+                    self.insert_before(*sibling, node);
+                }
+            }
+        }
+    }
+
+    fn add_attrs_if_missing(&mut self, target: &NodeId, attrs: Vec<markup5ever::Attribute>) {
+        if let NodeData::Element {
+            attrs: ref mut existing,
+            ..
+        } = self.node_mut(*target).data
+        {
+            for attr in attributes(attrs) {
+                if !existing.iter().any(|e| e.name == attr.name) {
+                    existing.push(attr);
+                }
+            }
+        }
+    }
+
+    fn remove_from_parent(&mut self, target: &NodeId) {
+        self.detach(*target);
+    }
+
+    fn reparent_children(&mut self, node: &NodeId, new_parent: &NodeId) {
+        while let Some(child) = self.node(*node).first_child {
+            self.append_child(*new_parent, child);
+        }
+    }
+
+    fn is_mathml_annotation_xml_integration_point(&self, handle: &NodeId) -> bool {
+        match self.node(*handle).data {
+            NodeData::Element {
+                mathml_annotation_xml_integration_point,
+                ..
+            } => mathml_annotation_xml_integration_point,
+            _ => false,
+        }
+    }
+
+    fn set_current_line(&mut self, line_number: u64) {
+        self.line = line_number;
+    }
+}
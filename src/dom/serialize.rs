@@ -0,0 +1,786 @@
+// See https://html.spec.whatwg.org/multipage/parsing.html#serialising-html-fragments
+//
+// The tree is first flattened into tokens so that filters needing to look at neighbouring
+// tokens, such as the omission of optional tags, can be applied before the markup is written.
+// With the default options the output follows the fragment serialization algorithm; the other
+// options mirror those of the html5lib serializer.
+use super::{Arena, Attribute, NodeData, NodeId};
+use markup5ever::{namespace_url, ns, QualName};
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "basefont", "bgsound", "br", "col", "embed", "frame", "hr", "img", "input",
+    "keygen", "link", "meta", "param", "source", "track", "wbr",
+];
+
+const RAW_TEXT_ELEMENTS: &[&str] = &[
+    "style",
+    "script",
+    "xmp",
+    "iframe",
+    "noembed",
+    "noframes",
+    "plaintext",
+];
+
+const SPACE_PRESERVING_ELEMENTS: &[&str] = &[
+    "pre", "textarea", "style", "script", "xmp", "iframe", "noembed", "noframes", "noscript",
+];
+
+const SPACE_CHARACTERS: &[char] = &['\t', '\n', '\x0C', '\r', ' '];
+
+// Attributes that are written without a value when `minimize_boolean_attributes` is set, keyed
+// by element name.  The empty name applies to every element.
+const BOOLEAN_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("", &["irrelevant", "itemscope"]),
+    ("style", &["scoped"]),
+    ("img", &["ismap"]),
+    ("audio", &["autoplay", "controls"]),
+    ("video", &["autoplay", "controls"]),
+    ("script", &["defer", "async"]),
+    ("details", &["open"]),
+    ("datagrid", &["multiple", "disabled"]),
+    ("command", &["hidden", "disabled", "checked", "default"]),
+    ("hr", &["noshade"]),
+    ("menu", &["autosubmit"]),
+    ("fieldset", &["disabled", "readonly"]),
+    ("option", &["disabled", "readonly", "selected"]),
+    ("optgroup", &["disabled", "readonly"]),
+    ("button", &["disabled", "autofocus"]),
+    (
+        "input",
+        &[
+            "disabled",
+            "readonly",
+            "required",
+            "autofocus",
+            "checked",
+            "ismap",
+        ],
+    ),
+    ("select", &["disabled", "readonly", "autofocus", "multiple"]),
+    ("output", &["disabled", "readonly"]),
+    ("iframe", &["seamless"]),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttrQuoting {
+    /// Always quote attribute values.
+    Always,
+    /// Quote values that could not be written unquoted according to the HTML syntax.
+    Spec,
+    /// Also quote values containing characters that older browsers mishandle when unquoted.
+    Legacy,
+}
+
+#[derive(Clone, Debug)]
+pub struct SerializeOpts {
+    /// Whether the document was parsed with scripting enabled, which makes the contents of
+    /// `<noscript>` raw text.
+    pub scripting_enabled: bool,
+    pub quote_attr_values: AttrQuoting,
+    pub quote_char: char,
+    /// Use whichever quote character avoids escaping quotes in the value.
+    pub use_best_quote_char: bool,
+    pub minimize_boolean_attributes: bool,
+    /// Write void elements as `<br />`.
+    pub use_trailing_solidus: bool,
+    pub space_before_trailing_solidus: bool,
+    pub escape_lt_in_attrs: bool,
+    /// Escape the contents of raw text elements.
+    pub escape_rcdata: bool,
+    pub omit_optional_tags: bool,
+    /// Collapse runs of whitespace outside of elements that preserve it.
+    pub strip_whitespace: bool,
+    pub alphabetical_attributes: bool,
+    /// Declare this character encoding in a `<meta>` element in the head.
+    pub inject_meta_charset: Option<String>,
+    /// Write the public and system identifiers of a DOCTYPE.
+    pub doctype_ids: bool,
+}
+
+impl Default for SerializeOpts {
+    fn default() -> Self {
+        Self {
+            scripting_enabled: false,
+            quote_attr_values: AttrQuoting::Always,
+            quote_char: '"',
+            use_best_quote_char: false,
+            minimize_boolean_attributes: false,
+            use_trailing_solidus: false,
+            space_before_trailing_solidus: true,
+            escape_lt_in_attrs: false,
+            escape_rcdata: false,
+            omit_optional_tags: false,
+            strip_whitespace: false,
+            alphabetical_attributes: false,
+            inject_meta_charset: None,
+            doctype_ids: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Doctype {
+        name: String,
+        public_id: String,
+        system_id: String,
+    },
+
+    StartTag {
+        name: QualName,
+        attrs: Vec<Attribute>,
+    },
+
+    /// A start tag that is not followed by an end tag, such as that of a void element.
+    EmptyTag {
+        name: QualName,
+        attrs: Vec<Attribute>,
+    },
+
+    EndTag {
+        name: QualName,
+    },
+
+    Text(String),
+
+    Comment(String),
+
+    ProcessingInstruction {
+        target: String,
+        contents: String,
+    },
+}
+
+impl Token {
+    fn tag_name(&self) -> Option<&str> {
+        match self {
+            Self::StartTag { name, .. } | Self::EmptyTag { name, .. } | Self::EndTag { name } => {
+                Some(&name.local)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_start_tag(&self) -> bool {
+        matches!(self, Self::StartTag { .. })
+    }
+
+    fn is_start_or_empty_tag(&self) -> bool {
+        matches!(self, Self::StartTag { .. } | Self::EmptyTag { .. })
+    }
+
+    fn is_end_tag(&self) -> bool {
+        matches!(self, Self::EndTag { .. })
+    }
+
+    // Comments and text starting with whitespace keep the tag in front of them from being
+    // omitted
+    fn is_comment_or_space(&self) -> bool {
+        match self {
+            Self::Comment(_) => true,
+            Self::Text(text) => text.starts_with(SPACE_CHARACTERS),
+            _ => false,
+        }
+    }
+}
+
+fn is_html(name: &QualName, set: &[&str]) -> bool {
+    name.ns == ns!(html) && set.contains(&&*name.local)
+}
+
+/// Whether an HTML element has no contents and no end tag.
+pub fn is_void(name: &QualName) -> bool {
+    is_html(name, VOID_ELEMENTS)
+}
+
+fn push_tokens(arena: &Arena, id: NodeId, tokens: &mut Vec<Token>) {
+    for child in arena.children(id) {
+        push_node_tokens(arena, child, tokens);
+    }
+}
+
+fn push_node_tokens(arena: &Arena, id: NodeId, tokens: &mut Vec<Token>) {
+    match arena.node(id).data {
+        NodeData::Document => push_tokens(arena, id, tokens),
+
+        NodeData::Doctype {
+            ref name,
+            ref public_id,
+            ref system_id,
+        } => tokens.push(Token::Doctype {
+            name: name.clone(),
+            public_id: public_id.clone(),
+            system_id: system_id.clone(),
+        }),
+
+        NodeData::Text { ref contents } => tokens.push(Token::Text(contents.clone())),
+
+        NodeData::Comment { ref contents } => tokens.push(Token::Comment(contents.clone())),
+
+        NodeData::ProcessingInstruction {
+            ref target,
+            ref contents,
+        } => tokens.push(Token::ProcessingInstruction {
+            target: target.clone(),
+            contents: contents.clone(),
+        }),
+
+        NodeData::Element {
+            ref name,
+            ref attrs,
+            template_contents,
+            ..
+        } => {
+            if is_void(name) {
+                tokens.push(Token::EmptyTag {
+                    name: name.clone(),
+                    attrs: attrs.clone(),
+                });
+                return;
+            }
+
+            tokens.push(Token::StartTag {
+                name: name.clone(),
+                attrs: attrs.clone(),
+            });
+            push_tokens(arena, template_contents.unwrap_or(id), tokens);
+            tokens.push(Token::EndTag { name: name.clone() });
+        }
+    }
+}
+
+/// The tokens for the children of `id`, as the fragment serialization algorithm visits them.
+pub fn tokens(arena: &Arena, id: NodeId) -> Vec<Token> {
+    let mut tokens = vec![];
+    push_tokens(arena, id, &mut tokens);
+    tokens
+}
+
+/// Serializes the children of `id`, as `innerHTML` does.
+pub fn serialize_children(arena: &Arena, id: NodeId, opts: &SerializeOpts) -> String {
+    serialize_tokens(tokens(arena, id), opts)
+}
+
+/// Serializes `id` itself along with its children, as `outerHTML` does.
+pub fn serialize_node(arena: &Arena, id: NodeId, opts: &SerializeOpts) -> String {
+    let mut tokens = vec![];
+    push_node_tokens(arena, id, &mut tokens);
+    serialize_tokens(tokens, opts)
+}
+
+pub fn serialize_tokens(tokens: Vec<Token>, opts: &SerializeOpts) -> String {
+    let mut tokens = tokens;
+
+    if let Some(ref encoding) = opts.inject_meta_charset {
+        tokens = inject_meta_charset(tokens, encoding);
+    }
+
+    if opts.alphabetical_attributes {
+        for token in tokens.iter_mut() {
+            if let Token::StartTag { attrs, .. } | Token::EmptyTag { attrs, .. } = token {
+                attrs.sort_by(|a, b| (&a.name.ns, &a.name.local).cmp(&(&b.name.ns, &b.name.local)));
+            }
+        }
+    }
+
+    if opts.strip_whitespace {
+        strip_whitespace(&mut tokens);
+    }
+
+    if opts.omit_optional_tags {
+        tokens = omit_optional_tags(tokens);
+    }
+
+    let mut buf = String::new();
+    let mut raw_text = false;
+
+    for token in tokens {
+        match token {
+            Token::Doctype {
+                name,
+                public_id,
+                system_id,
+            } => {
+                buf.push_str("<!DOCTYPE ");
+                buf.push_str(&name);
+                if opts.doctype_ids {
+                    push_doctype_ids(&mut buf, &public_id, &system_id);
+                }
+                buf.push('>');
+            }
+
+            Token::StartTag { name, attrs } => {
+                push_start_tag(&mut buf, &name, &attrs, opts);
+                if !opts.escape_rcdata && is_raw_text(&name, opts) {
+                    raw_text = true;
+                }
+            }
+
+            Token::EmptyTag { name, attrs } => push_start_tag(&mut buf, &name, &attrs, opts),
+
+            Token::EndTag { name } => {
+                if is_raw_text(&name, opts) {
+                    raw_text = false;
+                }
+                buf.push_str("</");
+                buf.push_str(&tag_name(&name));
+                buf.push('>');
+            }
+
+            Token::Text(text) => {
+                if raw_text {
+                    buf.push_str(&text);
+                } else {
+                    escape_text(&mut buf, &text);
+                }
+            }
+
+            Token::Comment(text) => {
+                buf.push_str("<!--");
+                buf.push_str(&text);
+                buf.push_str("-->");
+            }
+
+            Token::ProcessingInstruction { target, contents } => {
+                buf.push_str("<?");
+                buf.push_str(&target);
+                buf.push(' ');
+                buf.push_str(&contents);
+                buf.push('>');
+            }
+        }
+    }
+
+    buf
+}
+
+fn is_raw_text(name: &QualName, opts: &SerializeOpts) -> bool {
+    is_html(name, RAW_TEXT_ELEMENTS)
+        || (opts.scripting_enabled && name.ns == ns!(html) && &*name.local == "noscript")
+}
+
+fn push_doctype_ids(buf: &mut String, public_id: &str, system_id: &str) {
+    if !public_id.is_empty() {
+        buf.push_str(" PUBLIC \"");
+        buf.push_str(public_id);
+        buf.push('"');
+    } else if !system_id.is_empty() {
+        buf.push_str(" SYSTEM");
+    }
+
+    if !system_id.is_empty() {
+        let quote = if system_id.contains('"') { '\'' } else { '"' };
+        buf.push(' ');
+        buf.push(quote);
+        buf.push_str(system_id);
+        buf.push(quote);
+    }
+}
+
+fn tag_name(name: &QualName) -> String {
+    match (&name.ns, &name.prefix) {
+        (&ns!(html) | &ns!(svg) | &ns!(mathml), _) | (_, None) => name.local.to_string(),
+        (_, Some(prefix)) => format!("{prefix}:{}", name.local),
+    }
+}
+
+fn attr_name(name: &QualName) -> String {
+    match name.ns {
+        ns!() => name.local.to_string(),
+        ns!(xml) => format!("xml:{}", name.local),
+        ns!(xmlns) if &*name.local == "xmlns" => "xmlns".into(),
+        ns!(xmlns) => format!("xmlns:{}", name.local),
+        ns!(xlink) => format!("xlink:{}", name.local),
+        _ => match name.prefix {
+            Some(ref prefix) => format!("{prefix}:{}", name.local),
+            None => name.local.to_string(),
+        },
+    }
+}
+
+fn is_boolean_attribute(element: &str, attr: &str) -> bool {
+    BOOLEAN_ATTRIBUTES
+        .iter()
+        .any(|(name, attrs)| (name.is_empty() || *name == element) && attrs.contains(&attr))
+}
+
+fn needs_quotes(value: &str, quoting: AttrQuoting) -> bool {
+    let spec = |c: char| SPACE_CHARACTERS.contains(&c) || "\"'=<>`".contains(c);
+    let legacy = |c: char| {
+        spec(c)
+            || matches!(c,
+                '\0'..='\x20' | '/' | '\u{a0}' | '\u{1680}' | '\u{180e}' | '\u{180f}'
+                | '\u{2000}'..='\u{200a}' | '\u{2028}' | '\u{2029}' | '\u{202f}' | '\u{205f}'
+                | '\u{3000}')
+    };
+
+    value.is_empty()
+        || match quoting {
+            AttrQuoting::Always => true,
+            AttrQuoting::Spec => value.contains(spec),
+            AttrQuoting::Legacy => value.contains(legacy),
+        }
+}
+
+fn push_start_tag(buf: &mut String, name: &QualName, attrs: &[Attribute], opts: &SerializeOpts) {
+    buf.push('<');
+    buf.push_str(&tag_name(name));
+
+    for attr in attrs {
+        let key = attr_name(&attr.name);
+        buf.push(' ');
+        buf.push_str(&key);
+
+        if opts.minimize_boolean_attributes && is_boolean_attribute(&name.local, &key) {
+            continue;
+        }
+
+        buf.push('=');
+        let mut value = escape_attribute(&attr.value, opts.escape_lt_in_attrs);
+
+        if needs_quotes(&attr.value, opts.quote_attr_values) {
+            let mut quote = opts.quote_char;
+            if opts.use_best_quote_char {
+                if value.contains('\'') && !value.contains('"') {
+                    quote = '"';
+                } else if value.contains('"') && !value.contains('\'') {
+                    quote = '\'';
+                }
+            }
+
+            value = if quote == '\'' {
+                value.replace('\'', "&#39;")
+            } else {
+                value.replace('"', "&quot;")
+            };
+
+            buf.push(quote);
+            buf.push_str(&value);
+            buf.push(quote);
+        } else {
+            buf.push_str(&value);
+        }
+    }
+
+    if opts.use_trailing_solidus && is_void(name) {
+        buf.push_str(if opts.space_before_trailing_solidus {
+            " /"
+        } else {
+            "/"
+        });
+    }
+
+    buf.push('>');
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#escapingString
+fn escape_text(buf: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '\u{a0}' => buf.push_str("&nbsp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            c => buf.push(c),
+        }
+    }
+}
+
+// Quotes are escaped once the quote character is known
+fn escape_attribute(value: &str, escape_lt: bool) -> String {
+    let mut buf = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '\u{a0}' => buf.push_str("&nbsp;"),
+            '<' if escape_lt => buf.push_str("&lt;"),
+            c => buf.push(c),
+        }
+    }
+    buf
+}
+
+fn collapse_spaces(text: &str) -> String {
+    let mut buf = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if SPACE_CHARACTERS.contains(&c) {
+            if !in_space {
+                buf.push(' ');
+            }
+            in_space = true;
+        } else {
+            buf.push(c);
+            in_space = false;
+        }
+    }
+    buf
+}
+
+fn strip_whitespace(tokens: &mut [Token]) {
+    let mut preserve = 0;
+
+    for token in tokens.iter_mut() {
+        match token {
+            Token::StartTag { name, .. }
+                if preserve > 0 || SPACE_PRESERVING_ELEMENTS.contains(&&*name.local) =>
+            {
+                preserve += 1
+            }
+            Token::EndTag { .. } if preserve > 0 => preserve -= 1,
+            Token::Text(text) if preserve == 0 => *text = collapse_spaces(text),
+            _ => {}
+        }
+    }
+}
+
+fn inject_meta_charset(tokens: Vec<Token>, encoding: &str) -> Vec<Token> {
+    let meta = || Token::EmptyTag {
+        name: QualName::new(None, ns!(html), "meta".into()),
+        attrs: vec![Attribute {
+            name: QualName::new(None, ns!(), "charset".into()),
+            value: encoding.into(),
+        }],
+    };
+
+    let mut output = vec![];
+    let mut pending: Vec<Token> = vec![];
+    let mut in_head = false;
+    let mut meta_found = false;
+
+    for mut token in tokens {
+        match token {
+            Token::StartTag { ref name, .. } if str::eq_ignore_ascii_case(&name.local, "head") => {
+                in_head = true;
+            }
+
+            Token::EmptyTag {
+                ref name,
+                ref mut attrs,
+            } if str::eq_ignore_ascii_case(&name.local, "meta") => {
+                let mut http_equiv = false;
+                let mut charset = false;
+
+                for attr in attrs.iter_mut().filter(|attr| attr.name.ns == ns!()) {
+                    if str::eq_ignore_ascii_case(&attr.name.local, "charset") {
+                        attr.value = encoding.into();
+                        charset = true;
+                        break;
+                    } else if &*attr.name.local == "http-equiv"
+                        && attr.value.eq_ignore_ascii_case("content-type")
+                    {
+                        http_equiv = true;
+                    }
+                }
+
+                if charset {
+                    meta_found = true;
+                } else if http_equiv {
+                    let content = attrs
+                        .iter_mut()
+                        .find(|attr| attr.name.ns == ns!() && &*attr.name.local == "content");
+                    if let Some(content) = content {
+                        content.value = format!("text/html; charset={encoding}");
+                        meta_found = true;
+                    }
+                }
+            }
+
+            Token::EmptyTag {
+                ref name,
+                ref attrs,
+            } if str::eq_ignore_ascii_case(&name.local, "head") && !meta_found => {
+                output.push(Token::StartTag {
+                    name: name.clone(),
+                    attrs: attrs.clone(),
+                });
+                output.push(meta());
+                output.push(Token::EndTag { name: name.clone() });
+                meta_found = true;
+                continue;
+            }
+
+            Token::EndTag { ref name }
+                if str::eq_ignore_ascii_case(&name.local, "head") && !pending.is_empty() =>
+            {
+                let mut pending = std::mem::take(&mut pending).into_iter();
+                output.extend(pending.next());
+                if !meta_found {
+                    output.push(meta());
+                }
+                output.extend(pending);
+                meta_found = true;
+                in_head = false;
+                output.push(token);
+                continue;
+            }
+
+            _ => {}
+        }
+
+        if in_head {
+            pending.push(token);
+        } else {
+            output.push(token);
+        }
+    }
+
+    output.extend(pending);
+    output
+}
+
+fn omit_optional_tags(tokens: Vec<Token>) -> Vec<Token> {
+    let mut output = Vec::with_capacity(tokens.len());
+
+    for (i, token) in tokens.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| &tokens[i]);
+        let next = tokens.get(i + 1);
+
+        let omit = match token {
+            Token::StartTag { name, attrs } => {
+                attrs.is_empty() && is_optional_start(&name.local, previous, next)
+            }
+            Token::EndTag { name } => is_optional_end(&name.local, next),
+            _ => false,
+        };
+
+        if !omit {
+            output.push(token.clone());
+        }
+    }
+
+    output
+}
+
+// https://html.spec.whatwg.org/multipage/syntax.html#optional-tags
+fn is_optional_start(name: &str, previous: Option<&Token>, next: Option<&Token>) -> bool {
+    let next_name = next.and_then(Token::tag_name);
+
+    match name {
+        "html" => !next.is_some_and(Token::is_comment_or_space),
+
+        // The start tag of an empty head is omitted as well
+        "head" => match next {
+            Some(token) if token.is_start_or_empty_tag() => true,
+            Some(Token::EndTag { name }) => &*name.local == "head",
+            _ => false,
+        },
+
+        // The start tag is never omitted before a script or style element, since the preceding
+        // head element is not looked at
+        "body" => match next {
+            Some(token) if token.is_comment_or_space() => false,
+            Some(Token::StartTag { name, .. }) => !matches!(&*name.local, "script" | "style"),
+            _ => true,
+        },
+
+        "colgroup" => next.is_some_and(Token::is_start_or_empty_tag) && next_name == Some("col"),
+
+        "tbody" => {
+            let after_section = matches!(
+                previous,
+                Some(Token::EndTag { name }) if matches!(&*name.local, "tbody" | "thead" | "tfoot")
+            );
+            next.is_some_and(Token::is_start_tag) && !after_section && next_name == Some("tr")
+        }
+
+        _ => false,
+    }
+}
+
+fn is_optional_end(name: &str, next: Option<&Token>) -> bool {
+    let next_start = next
+        .filter(|token| token.is_start_tag())
+        .and_then(Token::tag_name);
+    let end_of_parent = next.map_or(true, Token::is_end_tag);
+
+    match name {
+        "html" | "head" | "body" => !next.is_some_and(Token::is_comment_or_space),
+
+        "li" | "optgroup" | "tr" => match next_start {
+            Some(next) => next == name,
+            None => end_of_parent,
+        },
+
+        "dt" | "dd" => match next_start {
+            Some(next) => matches!(next, "dt" | "dd"),
+            None => name == "dd" && end_of_parent,
+        },
+
+        "p" => {
+            if next.is_some_and(Token::is_start_or_empty_tag) {
+                matches!(
+                    next.and_then(Token::tag_name),
+                    Some(
+                        "address"
+                            | "article"
+                            | "aside"
+                            | "blockquote"
+                            | "datagrid"
+                            | "dialog"
+                            | "dir"
+                            | "div"
+                            | "dl"
+                            | "fieldset"
+                            | "footer"
+                            | "form"
+                            | "h1"
+                            | "h2"
+                            | "h3"
+                            | "h4"
+                            | "h5"
+                            | "h6"
+                            | "header"
+                            | "hr"
+                            | "menu"
+                            | "nav"
+                            | "ol"
+                            | "p"
+                            | "pre"
+                            | "section"
+                            | "table"
+                            | "ul"
+                    )
+                )
+            } else {
+                end_of_parent
+            }
+        }
+
+        "option" => match next_start {
+            Some(next) => matches!(next, "option" | "optgroup"),
+            None => end_of_parent,
+        },
+
+        "rt" | "rp" => match next_start {
+            Some(next) => matches!(next, "rt" | "rp"),
+            None => end_of_parent,
+        },
+
+        // The end tag is kept before another colgroup, whose start tag might be omitted
+        "colgroup" => match next {
+            Some(token) if token.is_comment_or_space() => false,
+            Some(Token::StartTag { name, .. }) => &*name.local != "colgroup",
+            _ => true,
+        },
+
+        // The end tag is kept before a tbody, whose start tag might be omitted
+        "thead" | "tbody" => match next_start {
+            Some(next) => matches!(next, "tbody" | "tfoot"),
+            None => name == "tbody" && end_of_parent,
+        },
+
+        "tfoot" => match next_start {
+            Some(next) => next == "tbody",
+            None => end_of_parent,
+        },
+
+        "td" | "th" => match next_start {
+            Some(next) => matches!(next, "td" | "th"),
+            None => end_of_parent,
+        },
+
+        _ => false,
+    }
+}
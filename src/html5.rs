@@ -1,15 +1,197 @@
+use crate::css::matching::{self, MatchContext};
+use crate::css::SelectorList;
+use crate::dom::serialize::SerializeOpts;
+use crate::dom::{Arena, NodeId, SourceLocation};
+use crate::style::{Styles, Viewport};
 use crate::types::Result;
+use encoding::CharacterEncoding;
+use std::fmt;
 
+#[cfg(feature = "quick-xml")]
+mod char_ref;
+pub mod encoding;
 #[cfg(feature = "html5ever")]
 pub mod html5ever;
+#[cfg(feature = "html5ever")]
+mod output;
 #[cfg(feature = "quick-xml")]
 pub mod quick_xml;
+#[cfg(feature = "quick-xml")]
+pub mod xml;
+
+/// The name of a fragment parsing context element.  Contexts in foreign content are given as
+/// "svg path" or "math mi", as in the html5lib tests.
+#[cfg(any(feature = "html5ever", feature = "quick-xml"))]
+pub(crate) fn context_name(context: &str) -> markup5ever::QualName {
+    use markup5ever::{namespace_url, ns, LocalName, QualName};
+
+    if let Some(cx) = context.strip_prefix("svg ") {
+        QualName::new(None, ns!(svg), LocalName::from(cx))
+    } else if let Some(cx) = context.strip_prefix("math ") {
+        QualName::new(None, ns!(mathml), LocalName::from(cx))
+    } else {
+        QualName::new(None, ns!(html), LocalName::from(context))
+    }
+}
+
+/// A parse error reported by a backend.  Backends that do not track columns leave `col` unset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub col: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.col {
+            Some(col) => write!(f, "({},{}): {}", self.line, col, self.message),
+            None => write!(f, "({}): {}", self.line, self.message),
+        }
+    }
+}
+
+/// Serializations of a parsed tree, other than the html5lib test format.
+pub trait Serializable {
+    /// Serializes the tree with the HTML fragment serialization algorithm.
+    fn to_html(&self) -> String {
+        let opts = SerializeOpts {
+            scripting_enabled: self.scripting_enabled(),
+            ..SerializeOpts::default()
+        };
+        self.to_html_with(&opts)
+    }
+
+    fn to_html_with(&self, opts: &SerializeOpts) -> String;
+
+    /// Whether the tree was parsed with scripting enabled, which affects how `<noscript>` is
+    /// serialized.
+    fn scripting_enabled(&self) -> bool;
+
+    fn to_json(&self) -> serde_json::Value;
+}
+
+/// Runs the scripts a parser comes across.  A script runs as soon as its end tag has been
+/// parsed, and whatever it writes is parsed next, ahead of the rest of the input, as with
+/// `document.write()`.
+pub trait ScriptHost {
+    /// Runs `script`, a `<script>` element in `arena`, and returns the markup it wrote, if any.
+    fn run(&mut self, arena: &Arena, script: NodeId) -> Option<String>;
+}
+
+/// A push-style parser that accepts input in arbitrary chunks. The resulting tree is the same
+/// no matter how the input is split.
+pub trait Parser<T> {
+    fn feed(&mut self, data: &[u8]) -> Result<()>;
+
+    fn finish(self) -> Result<T>;
+
+    /// Hands scripts to `host` to run as they are parsed.  Without a host, scripts are left
+    /// alone.
+    fn set_script_host(&mut self, host: Box<dyn ScriptHost>);
+}
 
 pub trait Document<'i, T> {
+    type Parser: Parser<T>;
+
     fn parse_document(data: &'i str, scripting_enabled: bool) -> Result<T>;
 
     #[allow(unused_variables)]
     fn parse_fragment(data: &'i str, scripting_enabled: bool, context: &'i str) -> Result<T> {
         Self::parse_document(data, scripting_enabled)
     }
+
+    fn document_parser(scripting_enabled: bool) -> Result<Self::Parser>;
+
+    #[allow(unused_variables)]
+    fn fragment_parser(scripting_enabled: bool, context: &str) -> Result<Self::Parser> {
+        Self::document_parser(scripting_enabled)
+    }
+
+    /// Parses a document from bytes, using the encoding sniffing algorithm to pick the character
+    /// encoding.  `transport_encoding` is a charset label from the transport layer, if any.
+    fn parse_document_bytes(
+        data: &[u8],
+        transport_encoding: Option<&str>,
+        scripting_enabled: bool,
+    ) -> Result<(T, CharacterEncoding)> {
+        let (decoded, encoding) = encoding::decode(data, transport_encoding);
+        let mut parser = Self::document_parser(scripting_enabled)?;
+        parser.feed(decoded.as_bytes())?;
+        Ok((parser.finish()?, encoding))
+    }
+
+    /// Parses a document, running its scripts with `host`.
+    fn parse_document_with_host(
+        data: &'i str,
+        scripting_enabled: bool,
+        host: Box<dyn ScriptHost>,
+    ) -> Result<T> {
+        let mut parser = Self::document_parser(scripting_enabled)?;
+        parser.set_script_host(host);
+        parser.feed(data.as_bytes())?;
+        parser.finish()
+    }
+
+    /// The arena holding the parsed tree.
+    fn arena(&self) -> &Arena;
+
+    fn is_fragment(&self) -> bool;
+
+    /// The node whose children are the result of the parse.  For a fragment this is the `<html>`
+    /// element that takes the place of the context element.
+    fn root(&self) -> NodeId {
+        let arena = self.arena();
+        let document = arena.document();
+        if self.is_fragment() {
+            arena.children(document).next().unwrap_or(document)
+        } else {
+            document
+        }
+    }
+
+    /// Where in the source `node` was parsed from, if the backend recorded it.
+    fn location(&self, node: NodeId) -> Option<&SourceLocation> {
+        self.arena().node(node).location.as_ref()
+    }
+
+    /// The innermost node parsed from byte `offset` of the source, for backends that track
+    /// byte offsets.
+    fn node_at(&self, offset: usize) -> Option<NodeId> {
+        self.arena().node_at(self.root(), offset)
+    }
+
+    /// The parse errors reported while building the tree, in the order they occurred.
+    fn errors(&self) -> &[ParseError] {
+        &self.arena().errors
+    }
+
+    /// Whether this is an HTML document, where selectors match the names of HTML elements and
+    /// their attributes without regard to ASCII case.
+    fn is_html(&self) -> bool {
+        true
+    }
+
+    /// The elements under the root matching `selectors`, in tree order, like
+    /// `querySelectorAll()`.
+    fn query_selector_all(&self, selectors: &str) -> Result<Vec<NodeId>> {
+        let list = SelectorList::parse(selectors)?;
+        let arena = self.arena();
+        let cx = MatchContext::new(arena, self.is_html());
+        Ok(matching::select(arena, self.root(), &list, &cx).collect())
+    }
+
+    /// The first element under the root matching `selectors`, like `querySelector()`.
+    fn query_selector(&self, selectors: &str) -> Result<Option<NodeId>> {
+        let list = SelectorList::parse(selectors)?;
+        let arena = self.arena();
+        let cx = MatchContext::new(arena, self.is_html());
+        let first = matching::select(arena, self.root(), &list, &cx).next();
+        Ok(first)
+    }
+
+    /// The computed style of every element of the document in a viewport.
+    fn compute_styles(&self, viewport: &Viewport) -> Styles {
+        Styles::compute(self.arena(), self.is_html(), viewport)
+    }
 }
@@ -0,0 +1,109 @@
+// See https://html.spec.whatwg.org/multipage/parsing.html#character-reference-state
+use markup5ever::data::{C1_REPLACEMENTS, NAMED_ENTITIES};
+use std::borrow::Cow;
+
+// Numeric references to these code points are replaced rather than decoded
+fn numeric_char(code: u32) -> char {
+    match code {
+        0 | 0xD800..=0xDFFF | 0x110000.. => '\u{FFFD}',
+        0x80..=0x9F => C1_REPLACEMENTS[(code - 0x80) as usize]
+            .unwrap_or_else(|| char::from_u32(code).unwrap_or('\u{FFFD}')),
+        _ => char::from_u32(code).unwrap_or('\u{FFFD}'),
+    }
+}
+
+// Decodes a numeric reference at the start of `s`, which follows "&#".  Returns the character
+// and the number of bytes used, or None if there are no digits.
+fn numeric(s: &str) -> Option<(char, usize)> {
+    let (radix, start) = match s.as_bytes().first() {
+        Some(b'x' | b'X') => (16, 1),
+        _ => (10, 0),
+    };
+
+    let digits = s[start..]
+        .bytes()
+        .take_while(|b| (*b as char).is_digit(radix))
+        .count();
+    if digits == 0 {
+        return None;
+    }
+
+    let code = s[start..start + digits]
+        .chars()
+        .filter_map(|c| c.to_digit(radix))
+        .fold(0u32, |code, digit| {
+            code.saturating_mul(radix).saturating_add(digit)
+        });
+
+    let mut len = start + digits;
+    if s[len..].starts_with(';') {
+        len += 1;
+    }
+
+    Some((numeric_char(code), len))
+}
+
+// Finds the longest entity name at the start of `s`, which follows "&".  The table has an
+// entry for every prefix of an entity name, with zeroes for prefixes that are not names
+// themselves, so the search can stop as soon as a prefix is missing.
+fn named(s: &str, in_attribute: bool) -> Option<(&'static (u32, u32), usize)> {
+    let mut best = None;
+
+    for (i, c) in s.char_indices() {
+        let end = i + c.len_utf8();
+        match NAMED_ENTITIES.get(&s[..end]) {
+            Some(&(0, _)) => {}
+            Some(chars) => best = Some((chars, end)),
+            None => break,
+        }
+    }
+
+    let (chars, len) = best?;
+
+    // For historical reasons, a reference without a semicolon in an attribute value is left
+    // alone if it is followed by "=" or an alphanumeric character, as in "?a=1&copy=2"
+    if in_attribute && !s[..len].ends_with(';') {
+        let next = s[len..].chars().next();
+        if next.is_some_and(|c| c == '=' || c.is_ascii_alphanumeric()) {
+            return None;
+        }
+    }
+
+    Some((chars, len))
+}
+
+/// Replaces the character references in text or, if `in_attribute` is set, in an attribute
+/// value.  References that do not match are left as they are.
+pub(crate) fn decode(text: &str, in_attribute: bool) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut buf = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(i) = rest.find('&') {
+        buf.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+
+        if let Some(after) = rest.strip_prefix('#') {
+            if let Some((c, len)) = numeric(after) {
+                buf.push(c);
+                rest = &after[len..];
+                continue;
+            }
+        } else if let Some((&(first, second), len)) = named(rest, in_attribute) {
+            buf.extend(char::from_u32(first));
+            if second != 0 {
+                buf.extend(char::from_u32(second));
+            }
+            rest = &rest[len..];
+            continue;
+        }
+
+        buf.push('&');
+    }
+
+    buf.push_str(rest);
+    Cow::Owned(buf)
+}
@@ -0,0 +1,373 @@
+// See https://html.spec.whatwg.org/multipage/parsing.html#determining-the-character-encoding
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252, X_USER_DEFINED};
+
+/// The number of bytes examined by the prescan.
+const PRESCAN_LENGTH: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Confidence {
+    Tentative,
+    Certain,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CharacterEncoding {
+    pub encoding: &'static Encoding,
+    pub confidence: Confidence,
+}
+
+impl CharacterEncoding {
+    fn certain(encoding: &'static Encoding) -> Self {
+        Self {
+            encoding,
+            confidence: Confidence::Certain,
+        }
+    }
+
+    fn tentative(encoding: &'static Encoding) -> Self {
+        Self {
+            encoding,
+            confidence: Confidence::Tentative,
+        }
+    }
+}
+
+/// Determines the character encoding of a byte stream: a byte order mark wins, then the
+/// transport layer hint (e.g. the charset of a `Content-Type` header), then a prescan of the
+/// first 1024 bytes for a `<meta>` declaration, and finally windows-1252.
+pub fn sniff(data: &[u8], transport_encoding: Option<&str>) -> CharacterEncoding {
+    if let Some((encoding, _)) = Encoding::for_bom(data) {
+        return CharacterEncoding::certain(encoding);
+    }
+
+    if let Some(encoding) =
+        transport_encoding.and_then(|label| Encoding::for_label(label.as_bytes()))
+    {
+        return CharacterEncoding::certain(encoding);
+    }
+
+    let end = data.len().min(PRESCAN_LENGTH);
+    if let Some(encoding) = prescan(&data[..end]) {
+        return CharacterEncoding::tentative(encoding);
+    }
+
+    CharacterEncoding::tentative(WINDOWS_1252)
+}
+
+/// Decodes `data` with the sniffed encoding, removing the byte order mark if there is one.
+pub fn decode(data: &[u8], transport_encoding: Option<&str>) -> (String, CharacterEncoding) {
+    let encoding = sniff(data, transport_encoding);
+    let (decoded, _) = encoding.encoding.decode_with_bom_removal(data);
+    (decoded.into_owned(), encoding)
+}
+
+fn is_space(b: u8) -> bool {
+    matches!(b, 0x09 | 0x0A | 0x0C | 0x0D | 0x20)
+}
+
+fn starts_with_ignore_case(data: &[u8], prefix: &[u8]) -> bool {
+    data.len() >= prefix.len() && data[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding
+fn prescan(data: &[u8]) -> Option<&'static Encoding> {
+    if data.starts_with(&[0x3C, 0x00, 0x3F, 0x00]) {
+        return Some(UTF_16LE);
+    }
+
+    if data.starts_with(&[0x00, 0x3C, 0x00, 0x3F]) {
+        return Some(UTF_16BE);
+    }
+
+    let fallback = if data.starts_with(b"<?xml") {
+        xml_encoding(data)
+    } else {
+        None
+    };
+
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let rest = &data[pos..];
+
+        if rest.starts_with(b"<!--") {
+            // The dashes of "<!--" may be shared with those of "-->"
+            match find(&data[pos + 2..], b"-->") {
+                Some(i) => pos += 2 + i + 3,
+                None => break,
+            }
+        } else if starts_with_ignore_case(rest, b"<meta")
+            && rest.get(5).is_some_and(|&b| is_space(b) || b == b'/')
+        {
+            pos += 6;
+            match meta_encoding(data, &mut pos) {
+                MetaResult::Found(encoding) => return Some(encoding),
+                MetaResult::NotFound => pos += 1,
+                MetaResult::Eof => break,
+            }
+        } else if rest.len() >= 2
+            && rest[0] == b'<'
+            && (rest[1].is_ascii_alphabetic()
+                || (rest[1] == b'/' && rest.get(2).is_some_and(u8::is_ascii_alphabetic)))
+        {
+            match data[pos..].iter().position(|&b| is_space(b) || b == b'>') {
+                Some(i) => pos += i,
+                None => break,
+            }
+
+            loop {
+                match attribute(data, &mut pos) {
+                    AttributeResult::Attribute(..) => continue,
+                    AttributeResult::None => break,
+                    AttributeResult::Eof => return fallback,
+                }
+            }
+
+            pos += 1;
+        } else if rest.starts_with(b"<!") || rest.starts_with(b"</") || rest.starts_with(b"<?") {
+            match rest.iter().position(|&b| b == b'>') {
+                Some(i) => pos += i + 1,
+                None => break,
+            }
+        } else {
+            pos += 1;
+        }
+    }
+
+    fallback
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+enum MetaResult {
+    Found(&'static Encoding),
+    NotFound,
+    Eof,
+}
+
+fn meta_encoding(data: &[u8], pos: &mut usize) -> MetaResult {
+    let mut seen: Vec<Vec<u8>> = vec![];
+    let mut got_pragma = false;
+    let mut need_pragma: Option<bool> = None;
+    let mut charset: Option<&'static Encoding> = None;
+
+    loop {
+        let (name, value) = match attribute(data, pos) {
+            AttributeResult::Attribute(name, value) => (name, value),
+            AttributeResult::None => break,
+            AttributeResult::Eof => return MetaResult::Eof,
+        };
+
+        if seen.contains(&name) {
+            continue;
+        }
+
+        match name.as_slice() {
+            b"http-equiv" => {
+                if value == b"content-type" {
+                    got_pragma = true;
+                }
+            }
+
+            b"content" => {
+                if charset.is_none() {
+                    if let Some(encoding) = content_encoding(&value) {
+                        charset = Some(encoding);
+                        need_pragma = Some(true);
+                    }
+                }
+            }
+
+            b"charset" => {
+                charset = Encoding::for_label(&value);
+                need_pragma = Some(false);
+            }
+
+            _ => {}
+        }
+
+        seen.push(name);
+    }
+
+    let Some(need_pragma) = need_pragma else {
+        return MetaResult::NotFound;
+    };
+
+    if need_pragma && !got_pragma {
+        return MetaResult::NotFound;
+    }
+
+    match charset {
+        None => MetaResult::NotFound,
+        Some(encoding) if encoding == UTF_16BE || encoding == UTF_16LE => MetaResult::Found(UTF_8),
+        Some(encoding) if encoding == X_USER_DEFINED => MetaResult::Found(WINDOWS_1252),
+        Some(encoding) => MetaResult::Found(encoding),
+    }
+}
+
+enum AttributeResult {
+    Attribute(Vec<u8>, Vec<u8>),
+    None,
+    Eof,
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#concept-get-attributes-when-sniffing
+fn attribute(data: &[u8], pos: &mut usize) -> AttributeResult {
+    macro_rules! byte {
+        () => {
+            match data.get(*pos) {
+                Some(&b) => b,
+                None => return AttributeResult::Eof,
+            }
+        };
+    }
+
+    while is_space(byte!()) || byte!() == b'/' {
+        *pos += 1;
+    }
+
+    if byte!() == b'>' {
+        return AttributeResult::None;
+    }
+
+    let mut name = vec![];
+    let mut value = vec![];
+
+    loop {
+        let b = byte!();
+        if b == b'=' && !name.is_empty() {
+            *pos += 1;
+            break;
+        } else if is_space(b) {
+            while is_space(byte!()) {
+                *pos += 1;
+            }
+            if byte!() != b'=' {
+                return AttributeResult::Attribute(name, value);
+            }
+            *pos += 1;
+            break;
+        } else if b == b'/' || b == b'>' {
+            return AttributeResult::Attribute(name, value);
+        } else {
+            name.push(b.to_ascii_lowercase());
+        }
+        *pos += 1;
+    }
+
+    while is_space(byte!()) {
+        *pos += 1;
+    }
+
+    let b = byte!();
+    if b == b'"' || b == b'\'' {
+        let quote = b;
+        loop {
+            *pos += 1;
+            let b = byte!();
+            if b == quote {
+                *pos += 1;
+                return AttributeResult::Attribute(name, value);
+            }
+            value.push(b.to_ascii_lowercase());
+        }
+    }
+
+    if b == b'>' {
+        return AttributeResult::Attribute(name, value);
+    }
+
+    value.push(b.to_ascii_lowercase());
+    *pos += 1;
+
+    loop {
+        let b = byte!();
+        if is_space(b) || b == b'>' {
+            return AttributeResult::Attribute(name, value);
+        }
+        value.push(b.to_ascii_lowercase());
+        *pos += 1;
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#algorithm-for-extracting-a-character-encoding-from-a-meta-element
+fn content_encoding(content: &[u8]) -> Option<&'static Encoding> {
+    let mut pos = 0;
+
+    loop {
+        pos += content[pos..]
+            .windows(7)
+            .position(|window| window.eq_ignore_ascii_case(b"charset"))?
+            + 7;
+
+        while content.get(pos).copied().is_some_and(is_space) {
+            pos += 1;
+        }
+
+        if content.get(pos) == Some(&b'=') {
+            pos += 1;
+            break;
+        }
+    }
+
+    while content.get(pos).copied().is_some_and(is_space) {
+        pos += 1;
+    }
+
+    match content.get(pos)? {
+        &quote @ (b'"' | b'\'') => {
+            let rest = &content[pos + 1..];
+            let end = rest.iter().position(|&b| b == quote)?;
+            Encoding::for_label(&rest[..end])
+        }
+        _ => {
+            let rest = &content[pos..];
+            let end = rest
+                .iter()
+                .position(|&b| is_space(b) || b == b';')
+                .unwrap_or(rest.len());
+            Encoding::for_label(&rest[..end])
+        }
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#concept-get-xml-encoding-when-sniffing
+fn xml_encoding(data: &[u8]) -> Option<&'static Encoding> {
+    let end = data.iter().position(|&b| b == b'>')?;
+    let decl = &data[..end];
+    let mut pos = find(decl, b"encoding")? + 8;
+
+    while decl.get(pos).is_some_and(|&b| b <= 0x20) {
+        pos += 1;
+    }
+
+    if decl.get(pos) != Some(&b'=') {
+        return None;
+    }
+    pos += 1;
+
+    while decl.get(pos).is_some_and(|&b| b <= 0x20) {
+        pos += 1;
+    }
+
+    let quote = *decl.get(pos)?;
+    if quote != b'"' && quote != b'\'' {
+        return None;
+    }
+
+    let rest = &decl[pos + 1..];
+    let end = rest.iter().position(|&b| b == quote)?;
+    let value = &rest[..end];
+    if value.iter().any(|&b| b <= 0x20) {
+        return None;
+    }
+
+    match Encoding::for_label(value)? {
+        encoding if encoding == UTF_16BE || encoding == UTF_16LE => Some(UTF_8),
+        encoding => Some(encoding),
+    }
+}
@@ -1,45 +1,168 @@
-use super::Document;
+use super::{context_name, output, Document, Parser, ScriptHost, Serializable};
+use crate::dom::serialize::{self, SerializeOpts};
+use crate::dom::Arena;
 use crate::types::Result;
 use html5ever::{
-    namespace_url, ns, parse_document, parse_fragment, tendril::TendrilSink, LocalName, ParseOpts,
-    QualName,
+    driver, parse_document, parse_fragment,
+    tendril::{fmt::UTF8, stream::Utf8LossyDecoder, ByteTendril, StrTendril, TendrilSink},
+    tokenizer::TokenizerResult,
+    ParseOpts,
 };
-use markup5ever_rcdom::RcDom;
+use std::borrow::Cow;
 
-fn context_name(context: &str) -> QualName {
-    if let Some(cx) = context.strip_prefix("svg ") {
-        QualName::new(None, ns!(svg), LocalName::from(cx))
-    } else if let Some(cx) = context.strip_prefix("math ") {
-        QualName::new(None, ns!(mathml), LocalName::from(cx))
-    } else {
-        QualName::new(None, ns!(html), LocalName::from(context))
-    }
+fn parse_opts(scripting_enabled: bool) -> ParseOpts {
+    let mut opts: ParseOpts = Default::default();
+    opts.tokenizer.exact_errors = true;
+    opts.tree_builder.scripting_enabled = scripting_enabled;
+    opts
 }
 
 pub struct Dom {
-    pub(crate) dom: RcDom,
+    pub(crate) dom: Arena,
     pub(crate) fragment: bool,
+    pub(crate) scripting_enabled: bool,
+}
+
+impl Dom {
+    fn new(dom: Arena, fragment: bool, scripting_enabled: bool) -> Self {
+        Self {
+            dom,
+            fragment,
+            scripting_enabled,
+        }
+    }
+}
+
+// The html5ever driver, except that the tokenizer stopping for a script runs it, and what the
+// script writes goes to the front of the input queue.  Scripts only run with scripting enabled.
+struct ScriptedParser {
+    parser: driver::Parser<Arena>,
+    host: Option<Box<dyn ScriptHost>>,
+    scripting_enabled: bool,
+}
+
+impl ScriptedParser {
+    fn run(&mut self) {
+        let driver::Parser {
+            tokenizer,
+            input_buffer,
+        } = &mut self.parser;
+
+        while let TokenizerResult::Script(script) = tokenizer.feed(input_buffer) {
+            let Some(host) = self.host.as_mut().filter(|_| self.scripting_enabled) else {
+                continue;
+            };
+
+            if let Some(markup) = host.run(&tokenizer.sink.sink, script) {
+                input_buffer.push_front(StrTendril::from(markup));
+            }
+        }
+    }
+}
+
+impl TendrilSink<UTF8> for ScriptedParser {
+    type Output = Arena;
+
+    fn process(&mut self, t: StrTendril) {
+        self.parser.input_buffer.push_back(t);
+        self.run();
+    }
+
+    fn error(&mut self, desc: Cow<'static, str>) {
+        self.parser.error(desc)
+    }
+
+    fn finish(mut self) -> Arena {
+        self.run();
+        self.parser.finish()
+    }
+}
+
+pub struct DomParser {
+    parser: Utf8LossyDecoder<ScriptedParser>,
+    fragment: bool,
+    scripting_enabled: bool,
+}
+
+impl DomParser {
+    fn new(parser: driver::Parser<Arena>, fragment: bool, scripting_enabled: bool) -> Self {
+        let parser = ScriptedParser {
+            parser,
+            host: None,
+            scripting_enabled,
+        };
+        Self {
+            parser: Utf8LossyDecoder::new(parser),
+            fragment,
+            scripting_enabled,
+        }
+    }
+}
+
+impl Parser<Dom> for DomParser {
+    fn feed(&mut self, data: &[u8]) -> Result<()> {
+        self.parser.process(ByteTendril::from_slice(data));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Dom> {
+        let dom = self.parser.finish();
+        Ok(Dom::new(dom, self.fragment, self.scripting_enabled))
+    }
+
+    fn set_script_host(&mut self, host: Box<dyn ScriptHost>) {
+        self.parser.inner_sink.host = Some(host);
+    }
 }
 
 impl<'i> Document<'i, Dom> for Dom {
+    type Parser = DomParser;
+
     fn parse_document(data: &'i str, scripting_enabled: bool) -> Result<Dom> {
-        let mut opts: ParseOpts = Default::default();
-        opts.tree_builder.scripting_enabled = scripting_enabled;
-        let dom = parse_document(RcDom::default(), opts).one(data);
-        Ok(Dom {
-            dom,
-            fragment: false,
-        })
+        let opts = parse_opts(scripting_enabled);
+        let dom = parse_document(Arena::default(), opts).one(data);
+        Ok(Dom::new(dom, false, scripting_enabled))
     }
 
     fn parse_fragment(data: &'i str, scripting_enabled: bool, context: &'i str) -> Result<Dom> {
-        let mut opts: ParseOpts = Default::default();
-        opts.tree_builder.scripting_enabled = scripting_enabled;
+        let opts = parse_opts(scripting_enabled);
         let context = context_name(context);
-        let dom = parse_fragment(RcDom::default(), opts, context, vec![]).one(data);
-        Ok(Dom {
-            dom,
-            fragment: true,
-        })
+        let dom = parse_fragment(Arena::default(), opts, context, vec![]).one(data);
+        Ok(Dom::new(dom, true, scripting_enabled))
+    }
+
+    fn document_parser(scripting_enabled: bool) -> Result<DomParser> {
+        let opts = parse_opts(scripting_enabled);
+        let parser = parse_document(Arena::default(), opts);
+        Ok(DomParser::new(parser, false, scripting_enabled))
+    }
+
+    fn fragment_parser(scripting_enabled: bool, context: &str) -> Result<DomParser> {
+        let opts = parse_opts(scripting_enabled);
+        let context = context_name(context);
+        let parser = parse_fragment(Arena::default(), opts, context, vec![]);
+        Ok(DomParser::new(parser, true, scripting_enabled))
+    }
+
+    fn arena(&self) -> &Arena {
+        &self.dom
+    }
+
+    fn is_fragment(&self) -> bool {
+        self.fragment
+    }
+}
+
+impl Serializable for Dom {
+    fn to_html_with(&self, opts: &SerializeOpts) -> String {
+        serialize::serialize_children(&self.dom, self.root(), opts)
+    }
+
+    fn scripting_enabled(&self) -> bool {
+        self.scripting_enabled
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        output::json(&self.dom, self.root(), self.fragment)
     }
 }
@@ -0,0 +1,86 @@
+use crate::dom::{Arena, NodeData, NodeId};
+use html5ever::{namespace_url, ns};
+use serde_json::{json, Map, Value};
+
+fn node_json(arena: &Arena, id: NodeId) -> Value {
+    let children = |id: NodeId| -> Vec<Value> {
+        arena
+            .children(id)
+            .map(|child| node_json(arena, child))
+            .collect()
+    };
+
+    match arena.node(id).data {
+        NodeData::Document => json!({ "type": "document", "children": children(id) }),
+
+        NodeData::Doctype {
+            ref name,
+            ref public_id,
+            ref system_id,
+        } => json!({
+            "type": "doctype",
+            "name": name,
+            "public_id": public_id,
+            "system_id": system_id,
+        }),
+
+        NodeData::Text { ref contents } => json!({ "type": "text", "data": contents }),
+
+        NodeData::Comment { ref contents } => json!({ "type": "comment", "data": contents }),
+
+        NodeData::Element {
+            ref name,
+            ref attrs,
+            template_contents,
+            ..
+        } => {
+            let mut attributes = Map::new();
+            for attr in attrs {
+                let key = match attr.name.ns {
+                    ns!(xlink) => format!("xlink:{}", attr.name.local),
+                    ns!(xml) => format!("xml:{}", attr.name.local),
+                    ns!(xmlns) if &*attr.name.local != "xmlns" => {
+                        format!("xmlns:{}", attr.name.local)
+                    }
+                    _ => attr.name.local.to_string(),
+                };
+                attributes.insert(key, Value::String(attr.value.clone()));
+            }
+
+            let mut element = json!({
+                "type": "element",
+                "namespace": name.ns.to_string(),
+                "name": name.local.to_string(),
+                "attrs": attributes,
+                "children": children(id),
+            });
+
+            if let Some(content) = template_contents {
+                element["content"] = children(content).into();
+            }
+
+            element
+        }
+
+        NodeData::ProcessingInstruction {
+            ref target,
+            ref contents,
+        } => json!({
+            "type": "processing-instruction",
+            "target": target,
+            "data": contents,
+        }),
+    }
+}
+
+pub(crate) fn json(arena: &Arena, root: NodeId, fragment: bool) -> Value {
+    if fragment {
+        let children: Vec<Value> = arena
+            .children(root)
+            .map(|child| node_json(arena, child))
+            .collect();
+        json!({ "type": "fragment", "children": children })
+    } else {
+        node_json(arena, root)
+    }
+}
@@ -1,8 +1,13 @@
-use super::Document;
+use super::{
+    char_ref, context_name, output, Document, ParseError, Parser, ScriptHost, Serializable,
+};
+use crate::dom::serialize::{self, SerializeOpts};
+use crate::dom::{Arena, NodeId};
 use crate::types::{Error, Result};
 use html5ever::interface::create_element;
 use html5ever::tendril::fmt::UTF8;
 use html5ever::tendril::Tendril;
+use html5ever::tokenizer::states::{RawKind, State};
 use html5ever::tokenizer::{Doctype, TokenSink};
 use html5ever::{
     namespace_url, ns,
@@ -16,11 +21,12 @@ use html5ever::{
     Attribute, QualName,
 };
 use markup5ever::LocalName;
-use markup5ever_rcdom::{Node, RcDom};
+use quick_xml::errors::{Error as XmlError, IllFormedError, SyntaxError};
 use quick_xml::events::attributes::Attributes;
 use quick_xml::name::QName;
+use quick_xml::parser::{ElementParser, Parser as _};
 use quick_xml::{events::Event, reader::Reader};
-use std::rc::Rc;
+use std::ops::Range;
 use tracing::{event, Level};
 
 fn str_tendril(bytes: &[u8]) -> Result<Tendril<UTF8>> {
@@ -29,32 +35,35 @@ fn str_tendril(bytes: &[u8]) -> Result<Tendril<UTF8>> {
     Ok(tendril)
 }
 
+// Names are kept whole, as the HTML tokenizer does; the tree builder works out namespaces and
+// adjusts foreign attributes like "xlink:href" itself
+fn tag_name(name: QName<'_>) -> Result<LocalName> {
+    let name = std::str::from_utf8(name.as_ref())?;
+    Ok(LocalName::from(
+        name.to_ascii_lowercase().replace('\0', "\u{FFFD}"),
+    ))
+}
+
 fn start_tag(name: QName<'_>, attributes: Attributes<'_>, self_closing: bool) -> Result<Token> {
-    let name = match name.decompose() {
-        (name, None) => LocalName::from(std::str::from_utf8(name.as_ref())?),
-        (name, Some(prefix)) => {
-            let name = std::str::from_utf8(name.as_ref())?;
-            let prefix = std::str::from_utf8(prefix.as_ref())?;
-            let combined = format!("{prefix}:{name}");
-            LocalName::from(combined)
-        }
-    };
-    let mut attrs = vec![];
+    let name = tag_name(name)?;
+    let mut attrs: Vec<Attribute> = vec![];
 
     for result in attributes {
         let Ok(attr) = result else {
             continue;
         };
 
-        let local = std::str::from_utf8(attr.key.into_inner()).unwrap();
-        let name = QualName {
-            prefix: None,
-            ns: ns!(html),
-            local: local.into(),
-        };
-        let value = StrTendril::try_from_byte_slice(attr.value.as_bytes()).unwrap();
-        let attribute = Attribute { name, value };
-        attrs.push(attribute);
+        let local = tag_name(attr.key)?;
+        // Later duplicates are dropped
+        if attrs.iter().any(|a| a.name.local == local) {
+            continue;
+        }
+
+        let name = QualName::new(None, ns!(), local);
+        let value = std::str::from_utf8(attr.value.as_ref())?;
+        let value = char_ref::decode(value, true).replace('\0', "\u{FFFD}");
+        let value = StrTendril::from(value);
+        attrs.push(Attribute { name, value });
     }
 
     let tag = Tag {
@@ -67,156 +76,855 @@ fn start_tag(name: QName<'_>, attributes: Attributes<'_>, self_closing: bool) ->
     Ok(Token::TagToken(tag))
 }
 
-fn build_tree(mut builder: TreeBuilder<Rc<Node>, RcDom>, mut reader: Reader<&[u8]>) -> Result<Dom> {
-    macro_rules! break_early {
-        ($result:ident) => {
-            if !matches!($result, TokenSinkResult::Continue) {
-                break;
+fn is_space(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' ')
+}
+
+fn doctype_tendril(s: &str) -> StrTendril {
+    StrTendril::from(s.replace('\0', "\u{FFFD}"))
+}
+
+// Splits off an identifier in single or double quotes.  The remainder is None if the closing
+// quote is missing.
+pub(super) fn quoted(s: &str) -> Option<(StrTendril, Option<&str>)> {
+    let quote = s.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+    let s = &s[1..];
+    match s.find(quote) {
+        Some(end) => Some((doctype_tendril(&s[..end]), Some(&s[end + 1..]))),
+        None => Some((doctype_tendril(s), None)),
+    }
+}
+
+// Parses the contents of a DOCTYPE following the tokenizer's DOCTYPE states.  quick-xml has
+// already removed "<!DOCTYPE", the whitespace after it and the closing ">", so the end of the
+// contents is where the tokenizer would see the ">".
+fn doctype(contents: &str) -> Doctype {
+    let mut doctype = Doctype {
+        name: None,
+        public_id: None,
+        system_id: None,
+        force_quirks: false,
+    };
+
+    let end = contents.find(is_space).unwrap_or(contents.len());
+    let mut name = doctype_tendril(&contents[..end]);
+    name.make_ascii_lowercase();
+    doctype.name = Some(name);
+
+    let rest = contents[end..].trim_start_matches(is_space);
+    if rest.is_empty() {
+        return doctype;
+    }
+
+    let public = match rest.get(..6).map(str::to_ascii_uppercase).as_deref() {
+        Some("PUBLIC") => true,
+        Some("SYSTEM") => false,
+        _ => {
+            doctype.force_quirks = true;
+            return doctype;
+        }
+    };
+
+    // A missing identifier, or one cut short by the ">", forces quirks mode
+    let Some((id, rest)) = quoted(rest[6..].trim_start_matches(is_space)) else {
+        doctype.force_quirks = true;
+        return doctype;
+    };
+
+    if !public {
+        doctype.system_id = Some(id);
+        doctype.force_quirks = rest.is_none();
+        return doctype;
+    }
+
+    doctype.public_id = Some(id);
+    let Some(rest) = rest else {
+        doctype.force_quirks = true;
+        return doctype;
+    };
+
+    let rest = rest.trim_start_matches(is_space);
+    if rest.is_empty() {
+        return doctype;
+    }
+
+    match quoted(rest) {
+        Some((id, rest)) => {
+            doctype.system_id = Some(id);
+            doctype.force_quirks = rest.is_none();
+        }
+        None => doctype.force_quirks = true,
+    }
+
+    doctype
+}
+
+fn event_token(event: Event<'_>) -> Result<Vec<Token>> {
+    let token = match event {
+        Event::Start(e) => start_tag(e.name(), e.html_attributes(), false)?,
+
+        Event::End(e) => {
+            // quick-xml keeps whatever follows the name, as in "</p foo>"
+            let name = e.name().into_inner();
+            let len = name
+                .iter()
+                .position(|b| b.is_ascii_whitespace() || *b == b'/')
+                .unwrap_or(name.len());
+            let name = tag_name(QName(&name[..len]))?;
+
+            let tag = Tag {
+                kind: EndTag,
+                name,
+                self_closing: false,
+                attrs: vec![],
+            };
+
+            Token::TagToken(tag)
+        }
+
+        Event::Empty(e) => start_tag(e.name(), e.html_attributes(), true)?,
+
+        // NULs are tokens of their own, since the tree builder drops or replaces them depending
+        // on where they are
+        Event::Text(e) => {
+            let text = char_ref::decode(std::str::from_utf8(e.as_ref())?, false);
+            let mut tokens = vec![];
+            for (i, part) in text.split('\0').enumerate() {
+                if i > 0 {
+                    tokens.push(Token::NullCharacterToken);
+                }
+                if !part.is_empty() {
+                    tokens.push(Token::CharacterTokens(StrTendril::from(part)));
+                }
             }
-        };
+            return Ok(tokens);
+        }
+
+        Event::Comment(e) => {
+            let text = std::str::from_utf8(e.as_ref())?;
+            Token::CommentToken(StrTendril::from(text.replace('\0', "\u{FFFD}")))
+        }
+
+        Event::CData(e) => {
+            let buf = String::from_utf8_lossy_owned(e.into_inner().into_owned());
+            let cdata = format!("[CDATA[{buf}]]");
+            Token::CommentToken(StrTendril::from(cdata))
+        }
+
+        Event::DocType(e) => Token::DoctypeToken(doctype(std::str::from_utf8(e.as_ref())?)),
+
+        Event::PI(_) | Event::Decl(_) => return Ok(vec![]),
+
+        Event::Eof => Token::EOFToken,
+    };
+
+    Ok(vec![token])
+}
+
+fn process_event(
+    builder: &mut TreeBuilder<NodeId, Arena>,
+    event: Event<'_>,
+    location: Location,
+    span: Option<Range<usize>>,
+) -> Result<TokenSinkResult<NodeId>> {
+    let line = location.line as u64;
+    builder.sink.set_current_position(location.col, span);
+
+    // CDATA sections are only recognized in foreign content; elsewhere they are bogus comments
+    let tokens = match event {
+        Event::CData(e) if builder.adjusted_current_node_present_but_not_in_html_namespace() => {
+            vec![Token::CharacterTokens(str_tendril(&e)?)]
+        }
+        event => event_token(event)?,
+    };
+
+    let mut result = TokenSinkResult::Continue;
+    for token in tokens {
+        if let Token::EOFToken = token {
+            let _ = builder.process_token(Token::EOFToken, line);
+            builder.end();
+        } else {
+            result = builder.process_token(token, line);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Returns the tokens the tree builder would be given for `data`, with the lines they start
+/// on, starting with the content model of the tokenizer `state`.  The text of a raw text
+/// element is ended by the end tag for `last_start_tag`.  Errors reported by quick-xml become
+/// parse error tokens.
+pub(crate) fn tokenize(
+    data: &[u8],
+    state: State,
+    last_start_tag: Option<&str>,
+) -> Result<Vec<(Token, u64)>> {
+    let mut tokens = vec![];
+    let line = |offset: usize| {
+        let end = offset.min(data.len());
+        data[..end].iter().filter(|&&b| b == b'\n').count() as u64 + 1
+    };
+
+    let (len, rcdata) = match ContentModel::for_context(state) {
+        ContentModel::RawText { kind, .. } => {
+            let mut scan = RawTextScan::default();
+            let len = raw_text_len(data, kind, last_start_tag, true, &mut scan);
+            (len.unwrap_or(data.len()), kind == RawKind::Rcdata)
+        }
+        ContentModel::Plaintext => (data.len(), false),
+        ContentModel::Data => (0, false),
+    };
+    let text = raw_text(&data[..len], rcdata)?;
+    if !text.is_empty() {
+        tokens.push((Token::CharacterTokens(StrTendril::from(text)), 1));
     }
 
+    // quick-xml reads no further after an error, so it starts again after it
+    let mut offset = len;
     loop {
-        match reader.read_event() {
-            Ok(Event::Start(e)) => {
-                let token = start_tag(e.name(), e.html_attributes(), false)?;
-                let result = builder.process_token(token, 0);
-                break_early!(result);
+        let mut reader = make_reader(&data[offset..]);
+
+        loop {
+            let start = line(offset + reader.buffer_position() as usize);
+            match reader.read_event() {
+                Ok(Event::Eof) => return Ok(tokens),
+                Ok(event) => tokens.extend(event_token(event)?.into_iter().map(|t| (t, start))),
+                Err(e) => {
+                    event!(Level::DEBUG, "error tokenizing document: {e}");
+                    let error = Token::ParseError(e.to_string().into());
+                    tokens.push((error, line(offset + reader.error_position() as usize)));
+                    offset += reader.buffer_position() as usize;
+                    break;
+                }
             }
+        }
+    }
+}
 
-            Ok(Event::End(e)) => {
-                let name = match e.name().decompose() {
-                    (name, None) => LocalName::from(std::str::from_utf8(name.as_ref())?),
-                    (name, Some(prefix)) => {
-                        let name = std::str::from_utf8(name.as_ref())?;
-                        let prefix = std::str::from_utf8(prefix.as_ref())?;
-                        LocalName::from(format!("{prefix}:{name}"))
-                    }
-                };
-
-                let tag = Tag {
-                    kind: EndTag,
-                    name,
-                    self_closing: false,
-                    attrs: vec![],
-                };
-
-                let token = Token::TagToken(tag);
-                let result = builder.process_token(token, 0);
-                break_early!(result);
+fn make_reader(data: &[u8]) -> Reader<&[u8]> {
+    let mut reader = Reader::from_reader(data);
+    let config = reader.config_mut();
+    config.check_end_names = false;
+    config.allow_unmatched_ends = true;
+    config.trim_text_start = false;
+    config.trim_text_end = false;
+    reader
+}
+
+// Maps offsets into the input the parser has seen, which has had its newlines normalized and
+// markup written by scripts inserted, back to byte offsets in the source
+#[derive(Default)]
+pub(super) struct SourceMap {
+    // Input offsets at which an LF was dropped from a CRLF pair
+    pub(super) dropped: Vec<usize>,
+    // Ranges of input holding markup written by scripts, in order
+    written: Vec<Range<usize>>,
+}
+
+impl SourceMap {
+    fn is_written(&self, offset: usize) -> bool {
+        let i = self.written.partition_point(|r| r.end <= offset);
+        self.written.get(i).is_some_and(|r| r.start <= offset)
+    }
+
+    // Markup written inside or right after earlier markup from a script extends its range
+    fn insert(&mut self, at: usize, len: usize) {
+        for offset in self.dropped.iter_mut().filter(|offset| **offset >= at) {
+            *offset += len;
+        }
+
+        let i = self.written.partition_point(|r| r.end < at);
+        match self.written.get_mut(i) {
+            Some(r) if r.start < at || r.end == at => r.end += len,
+            _ => self.written.insert(i, at..at + len),
+        }
+        for r in &mut self.written[i + 1..] {
+            *r = r.start + len..r.end + len;
+        }
+    }
+
+    fn source_offset(&self, offset: usize) -> usize {
+        let dropped = self.dropped.partition_point(|&d| d <= offset);
+        let written: usize = self
+            .written
+            .iter()
+            .take_while(|r| r.end <= offset)
+            .map(|r| r.len())
+            .sum();
+        offset + dropped - written
+    }
+
+    pub(super) fn span(&self, range: Range<usize>) -> Option<Range<usize>> {
+        if self.is_written(range.start) {
+            return None;
+        }
+        Some(self.source_offset(range.start)..self.source_offset(range.end))
+    }
+}
+
+// Tracks the line and column of a byte offset into the buffer, and the offset into the whole
+// input that it corresponds to.  Markup written by scripts does not count towards either.
+#[derive(Clone, Copy)]
+pub(super) struct Location {
+    offset: usize,
+    position: usize,
+    pub(super) line: usize,
+    pub(super) col: usize,
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            position: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+}
+
+impl Location {
+    pub(super) fn advance(&mut self, data: &[u8], offset: usize, map: &SourceMap) {
+        if offset <= self.offset {
+            return;
+        }
+
+        for (i, &b) in data[self.offset..offset].iter().enumerate() {
+            if map.is_written(self.position + i) {
+                continue;
             }
 
-            Ok(Event::Empty(e)) => {
-                let token = start_tag(e.name(), e.html_attributes(), true)?;
-                let result = builder.process_token(token, 0);
-                break_early!(result);
+            if b == b'\n' {
+                self.line += 1;
+                self.col = 1;
+            } else if b & 0xC0 != 0x80 {
+                // Continuation bytes do not start a new character
+                self.col += 1;
             }
+        }
+        self.position += offset - self.offset;
+        self.offset = offset;
+    }
+
+    // The offset into the whole input of an offset into the buffer
+    fn input_offset(&self, offset: usize) -> usize {
+        self.position + offset - self.offset
+    }
+}
+
+// The tokenizer state, as far as the tree builder can switch it
+#[derive(Clone, Debug)]
+enum ContentModel {
+    Data,
+    /// Text up to the end tag for `end`.  Character references are only decoded in RCDATA.
+    RawText {
+        kind: RawKind,
+        end: Option<LocalName>,
+    },
+    /// Text up to the end of the input.
+    Plaintext,
+}
+
+impl ContentModel {
+    // No start tag has been seen when parsing a fragment, so no end tag ends its text
+    fn for_context(state: State) -> Self {
+        match state {
+            State::RawData(kind) => Self::RawText { kind, end: None },
+            State::Plaintext => Self::Plaintext,
+            _ => Self::Data,
+        }
+    }
+}
+
+// The text of a raw text element, with character references decoded in RCDATA
+fn raw_text(data: &[u8], rcdata: bool) -> Result<String> {
+    let text = std::str::from_utf8(data)?.replace('\0', "\u{FFFD}");
+    if rcdata {
+        return Ok(char_ref::decode(&text, false).into_owned());
+    }
+    Ok(text)
+}
+
+// Whether `data` starts with `pattern`, ignoring ASCII case.  None if it is too short to tell
+// and more input may follow.
+fn literal_at(data: &[u8], pattern: &[u8], eof: bool) -> Option<bool> {
+    let n = data.len().min(pattern.len());
+    if !data[..n].eq_ignore_ascii_case(&pattern[..n]) {
+        return Some(false);
+    }
+    (n == pattern.len() || eof).then_some(n == pattern.len())
+}
 
-            Ok(Event::Text(e)) => {
-                let buf = e.as_ref();
-                let token = Token::CharacterTokens(str_tendril(buf)?);
-                let result = builder.process_token(token, 0);
-                break_early!(result);
+// Whether `data` starts with `prefix` and the tag name `name`, followed by something that ends
+// a tag name
+fn tag_at(data: &[u8], prefix: &[u8], name: &str, eof: bool) -> Option<bool> {
+    if !literal_at(data, prefix, eof)? || !literal_at(&data[prefix.len()..], name.as_bytes(), eof)?
+    {
+        return Some(false);
+    }
+
+    match data.get(prefix.len() + name.len()) {
+        Some(b) => Some(matches!(
+            b,
+            b'\t' | b'\n' | b'\x0C' | b'\r' | b' ' | b'/' | b'>'
+        )),
+        None => eof.then_some(false),
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum ScriptEscape {
+    #[default]
+    None,
+    Escaped,
+    DoubleEscaped,
+}
+
+// How far the text of a raw text element has been scanned for its end, so that scanning
+// resumes there when more input comes in
+#[derive(Clone, Copy, Default)]
+struct RawTextScan {
+    len: usize,
+    escape: ScriptEscape,
+}
+
+// The length of the text before the end tag that closes a raw text element.  Script data
+// follows the tokenizer's escape states, so that "</script>" inside "<!--<script>" does not end
+// it.  None if the end has not been seen yet, in which case `scan` records where to resume.
+fn raw_text_len(
+    data: &[u8],
+    kind: RawKind,
+    end: Option<&str>,
+    eof: bool,
+    scan: &mut RawTextScan,
+) -> Option<usize> {
+    let script = matches!(kind, RawKind::ScriptData | RawKind::ScriptDataEscaped(_));
+    let RawTextScan {
+        len: mut i,
+        mut escape,
+    } = *scan;
+    // Everything before `i` has been decided, so a check that needs more input resumes there
+    let mut scan = |i, escape| {
+        *scan = RawTextScan { len: i, escape };
+        None
+    };
+
+    while i < data.len() {
+        let rest = &data[i..];
+
+        if let Some(end) = end.filter(|_| escape != ScriptEscape::DoubleEscaped) {
+            match tag_at(rest, b"</", end, eof) {
+                Some(true) => return Some(i),
+                Some(false) => {}
+                None => return scan(i, escape),
             }
+        }
+
+        if script {
+            let at = |pattern: &[u8]| literal_at(rest, pattern, eof);
+            let tag = |prefix: &[u8]| tag_at(rest, prefix, "script", eof);
+            // "<!--" leaves the position at its dashes, since "<!-->" goes straight back
+            let next = match escape {
+                ScriptEscape::None => at(b"<!--").map(|m| m.then_some((ScriptEscape::Escaped, 2))),
+                ScriptEscape::Escaped => match at(b"-->") {
+                    Some(true) => Some(Some((ScriptEscape::None, 3))),
+                    Some(false) => tag(b"<").map(|m| m.then_some((ScriptEscape::DoubleEscaped, 7))),
+                    None => None,
+                },
+                ScriptEscape::DoubleEscaped => match at(b"-->") {
+                    Some(true) => Some(Some((ScriptEscape::None, 3))),
+                    Some(false) => tag(b"</").map(|m| m.then_some((ScriptEscape::Escaped, 8))),
+                    None => None,
+                },
+            };
+            let Some(next) = next else {
+                return scan(i, escape);
+            };
+            let (next, skip) = next.unwrap_or((escape, 1));
+            escape = next;
+            i += skip;
+        } else {
+            i += 1;
+        }
+    }
+
+    if eof {
+        Some(data.len())
+    } else {
+        scan(i, escape)
+    }
+}
+
+// What text or markup held back at the front of the buffer needs before it can be complete
+#[derive(Clone, Copy)]
+enum Awaiting {
+    // The "<" that ends text, or the "-->" of a comment and so on
+    Delimiter(&'static [u8]),
+    // The ">" of a tag, which quick-xml does not look for in quoted attribute values
+    TagEnd(ElementParser),
+}
 
-            Ok(Event::Comment(e)) => {
-                let buf = e.as_ref();
-                let token = Token::CommentToken(str_tendril(buf)?);
-                let result = builder.process_token(token, 0);
-                break_early!(result);
+impl Awaiting {
+    // What markup that quick-xml found unclosed at the end of the buffer needs, or None if it
+    // has its closing delimiter after all, in which case the error is not for want of input
+    fn markup(markup: &[u8], error: &SyntaxError) -> Option<Self> {
+        // The end is looked for after the opening delimiter, which it cannot overlap, so that
+        // "<!-->" is not taken for a whole comment
+        let (mut awaiting, from) = match error {
+            SyntaxError::UnclosedTag => (Self::TagEnd(ElementParser::Outside), 1),
+            // quick-xml only gives up on a DOCTYPE once it has skipped every bracketed ">"
+            SyntaxError::UnclosedDoctype => return Some(Self::Delimiter(b">")),
+            SyntaxError::UnclosedComment => (Self::Delimiter(b"-->"), b"<!--".len()),
+            SyntaxError::UnclosedCData => (Self::Delimiter(b"]]>"), b"<![CDATA[".len()),
+            SyntaxError::UnclosedPIOrXmlDecl => (Self::Delimiter(b"?>"), b"<?".len()),
+            SyntaxError::InvalidBangMarkup => (Self::Delimiter(b">"), 1),
+        };
+        let rest = markup.get(from..).unwrap_or_default();
+        (!awaiting.arrived(rest, 0)).then_some(awaiting)
+    }
+
+    // Looks for the end in `buffer` from `from`, where it was last looked at up to
+    fn arrived(&mut self, buffer: &[u8], from: usize) -> bool {
+        match self {
+            Self::Delimiter(end) => {
+                let from = from.saturating_sub(end.len() - 1);
+                buffer[from..].windows(end.len()).any(|w| w == *end)
             }
+            Self::TagEnd(parser) => parser.feed(&buffer[from..]).is_some(),
+        }
+    }
+}
+
+pub struct Dom {
+    pub(crate) dom: Arena,
+    pub(crate) fragment: bool,
+    pub(crate) scripting_enabled: bool,
+}
+
+pub struct DomParser {
+    builder: TreeBuilder<NodeId, Arena>,
+    buffer: Vec<u8>,
+    location: Location,
+    content_model: ContentModel,
+    raw_text: RawTextScan,
+    // What is held back at the front of the buffer needs before it can be complete, and how
+    // much of the buffer has been checked for it
+    awaiting: Option<Awaiting>,
+    scanned: usize,
+    // Whether the last chunk ended with a CR, so that an LF starting the next one is dropped
+    after_cr: bool,
+    source_map: SourceMap,
+    host: Option<Box<dyn ScriptHost>>,
+    fragment: bool,
+    scripting_enabled: bool,
+}
+
+impl DomParser {
+    fn new(builder: TreeBuilder<NodeId, Arena>, fragment: bool, scripting_enabled: bool) -> Self {
+        Self {
+            builder,
+            buffer: vec![],
+            location: Location::default(),
+            content_model: ContentModel::Data,
+            raw_text: RawTextScan::default(),
+            awaiting: None,
+            scanned: 0,
+            after_cr: false,
+            source_map: SourceMap::default(),
+            host: None,
+            fragment,
+            scripting_enabled,
+        }
+    }
 
-            Ok(Event::CData(e)) => {
-                let buf = e.as_ref();
-                let buf = String::from_utf8_lossy(buf.as_bytes());
-                let cdata = format!("[CDATA[{buf}]]");
-                let token = Token::CommentToken(StrTendril::from(cdata));
-                let result = builder.process_token(token, 0);
-                break_early!(result);
+    // Hands everything that is complete at the front of the buffer to the tree builder.  Until
+    // `eof` is reached, trailing text and markup that has not been closed yet are held back,
+    // since they might continue in the next chunk.
+    fn process(&mut self, eof: bool) -> Result<()> {
+        // Only the new input is looked at until what was held back can be complete, so that
+        // input fed in small pieces is not parsed again and again
+        if let (false, Some(awaiting)) = (eof, &mut self.awaiting) {
+            let arrived = awaiting.arrived(&self.buffer, self.scanned);
+            self.scanned = self.buffer.len();
+            if !arrived {
+                return Ok(());
             }
+        }
+        self.awaiting = None;
+
+        let mut location = self.location;
+        let mut consumed = 0;
 
-            Ok(Event::DocType(e)) => {
-                let buf = e.as_ref();
-                let mut name = str_tendril(buf)?;
-                name.make_ascii_lowercase();
-
-                let doc_type = Doctype {
-                    name: Some(name),
-                    public_id: None,
-                    system_id: None,
-                    force_quirks: false,
-                };
-
-                let token = Token::DoctypeToken(doc_type);
-                let result = builder.process_token(token, 0);
-                break_early!(result);
+        loop {
+            let wait = match self.content_model {
+                ContentModel::Data => self.process_markup(&mut consumed, &mut location, eof)?,
+                _ => self.process_text(&mut consumed, &mut location, eof)?,
+            };
+
+            if wait {
+                break;
             }
+        }
 
-            Ok(Event::PI(_) | Event::Decl(_)) => {
-                continue;
+        location.advance(&self.buffer, consumed, &self.source_map);
+        self.location = Location {
+            offset: 0,
+            ..location
+        };
+        self.buffer.drain(..consumed);
+        self.scanned = self.buffer.len();
+        Ok(())
+    }
+
+    // Reads markup with quick-xml until the tree builder switches to a text content model or
+    // the markup is in error, in which case false is returned, or until the input runs out.
+    fn process_markup(
+        &mut self,
+        consumed: &mut usize,
+        location: &mut Location,
+        eof: bool,
+    ) -> Result<bool> {
+        let base = *consumed;
+        let mut reader = make_reader(&self.buffer[base..]);
+
+        loop {
+            let start = base + reader.buffer_position() as usize;
+            let event = reader.read_event();
+            let position = base + reader.buffer_position() as usize;
+
+            // Markup cut off by the end of the buffer waits for the rest, but markup that is in
+            // error even though it has been closed is reported right away
+            if let (false, Err(XmlError::Syntax(e))) = (eof, &event) {
+                if let Some(awaiting) = Awaiting::markup(&self.buffer[start..], e) {
+                    self.awaiting = Some(awaiting);
+                    return Ok(true);
+                }
             }
 
-            Ok(Event::Eof) => {
-                let _ = builder.process_token(Token::EOFToken, 0);
-                builder.end();
-                break;
+            let event = match event {
+                Ok(Event::Eof) if !eof => return Ok(true),
+                Ok(Event::Text(_)) if !eof && position >= self.buffer.len() => {
+                    self.awaiting = Some(Awaiting::Delimiter(b"<"));
+                    return Ok(true);
+                }
+                Ok(event) => event,
+                Err(e) => {
+                    let mut at = *location;
+                    at.advance(
+                        &self.buffer,
+                        base + reader.error_position() as usize,
+                        &self.source_map,
+                    );
+                    self.builder.sink.errors.push(ParseError {
+                        line: at.line,
+                        col: Some(at.col),
+                        message: e.to_string(),
+                    });
+
+                    // "<!DOCTYPE>" still makes a DOCTYPE, one that forces quirks mode
+                    if let XmlError::IllFormed(IllFormedError::MissingDoctypeName) = e {
+                        let doctype = Doctype {
+                            name: None,
+                            public_id: None,
+                            system_id: None,
+                            force_quirks: true,
+                        };
+                        let span = self
+                            .source_map
+                            .span(at.input_offset(start)..at.input_offset(position));
+                        self.builder.sink.set_current_position(at.col, span);
+                        let _ = self
+                            .builder
+                            .process_token(Token::DoctypeToken(doctype), at.line as u64);
+                    }
+
+                    // quick-xml reads no further after an error, so it starts again after it
+                    *consumed = position;
+                    return Ok(false);
+                }
+            };
+
+            location.advance(&self.buffer, start, &self.source_map);
+            let name = match event {
+                Event::Start(ref e) | Event::Empty(ref e) => Some(tag_name(e.name())?),
+                _ => None,
+            };
+            let done = matches!(event, Event::Eof);
+            let span = self
+                .source_map
+                .span(location.input_offset(start)..location.input_offset(position));
+            let result = process_event(&mut self.builder, event, *location, span)?;
+            *consumed = position;
+
+            if done {
+                return Ok(true);
             }
 
-            Err(e) => {
-                event!(Level::WARN, "error parsing document: {e}");
+            match (result, name) {
+                (TokenSinkResult::RawData(kind), Some(end)) => {
+                    self.content_model = ContentModel::RawText {
+                        kind,
+                        end: Some(end),
+                    };
+                    return Ok(false);
+                }
+                (TokenSinkResult::Plaintext, _) => {
+                    self.content_model = ContentModel::Plaintext;
+                    return Ok(false);
+                }
+                // What a script writes is inserted right after its end tag, so it is read next
+                (TokenSinkResult::Script(script), _) => {
+                    let written = self
+                        .host
+                        .as_mut()
+                        .filter(|_| self.scripting_enabled)
+                        .and_then(|host| host.run(&self.builder.sink, script));
+
+                    if let Some(markup) = written {
+                        let markup = markup.replace("\r\n", "\n").replace('\r', "\n");
+                        let at = location.input_offset(position);
+                        self.source_map.insert(at, markup.len());
+                        self.buffer.splice(position..position, markup.into_bytes());
+                        return Ok(false);
+                    }
+                }
+                _ => {}
             }
         }
     }
 
-    Ok(Dom {
-        dom: builder.sink,
-        fragment: false,
-    })
-}
+    // Hands the text of a raw text element, or everything after <plaintext>, to the tree
+    // builder as a single token.  Returns true if the end of the text has not been seen yet.
+    fn process_text(
+        &mut self,
+        consumed: &mut usize,
+        location: &mut Location,
+        eof: bool,
+    ) -> Result<bool> {
+        let data = &self.buffer[*consumed..];
+        let (len, rcdata) = match self.content_model {
+            ContentModel::RawText { kind, ref end } => (
+                raw_text_len(data, kind, end.as_deref(), eof, &mut self.raw_text),
+                kind == RawKind::Rcdata,
+            ),
+            ContentModel::Plaintext => (eof.then_some(data.len()), false),
+            ContentModel::Data => (Some(0), false),
+        };
 
-fn make_reader(data: &str) -> Reader<&[u8]> {
-    let mut reader = Reader::from_str(data);
-    let config = reader.config_mut();
-    config.check_end_names = false;
-    config.allow_unmatched_ends = true;
-    config.trim_text_start = false;
-    config.trim_text_end = false;
-    reader
+        let Some(len) = len else {
+            return Ok(true);
+        };
+
+        let text = raw_text(&data[..len], rcdata)?;
+
+        location.advance(&self.buffer, *consumed, &self.source_map);
+        if !text.is_empty() {
+            let start = location.input_offset(*consumed);
+            let span = self.source_map.span(start..start + len);
+            self.builder.sink.set_current_position(location.col, span);
+            let token = Token::CharacterTokens(StrTendril::from(text));
+            let _ = self.builder.process_token(token, location.line as u64);
+        }
+
+        *consumed += len;
+        self.content_model = ContentModel::Data;
+        self.raw_text = RawTextScan::default();
+        Ok(false)
+    }
 }
 
-pub struct Dom {
-    pub(crate) dom: RcDom,
-    pub(crate) fragment: bool,
+impl Parser<Dom> for DomParser {
+    // Newlines are normalized as the input comes in, as in the HTML input stream
+    fn feed(&mut self, data: &[u8]) -> Result<()> {
+        for &b in data {
+            match b {
+                b'\r' => self.buffer.push(b'\n'),
+                b'\n' if self.after_cr => {
+                    let offset = self.location.input_offset(self.buffer.len());
+                    self.source_map.dropped.push(offset);
+                }
+                b => self.buffer.push(b),
+            }
+            self.after_cr = b == b'\r';
+        }
+        self.process(false)
+    }
+
+    fn finish(mut self) -> Result<Dom> {
+        self.process(true)?;
+        Ok(Dom {
+            dom: self.builder.sink,
+            fragment: self.fragment,
+            scripting_enabled: self.scripting_enabled,
+        })
+    }
+
+    fn set_script_host(&mut self, host: Box<dyn ScriptHost>) {
+        self.host = Some(host);
+    }
 }
 
 impl Document<'_, Dom> for Dom {
+    type Parser = DomParser;
+
     fn parse_document(data: &str, scripting_enabled: bool) -> Result<Dom> {
-        let reader = make_reader(data);
+        let mut parser = Self::document_parser(scripting_enabled)?;
+        parser.feed(data.as_bytes())?;
+        parser.finish()
+    }
+
+    fn parse_fragment(data: &'_ str, scripting_enabled: bool, context: &'_ str) -> Result<Dom> {
+        let mut parser = Self::fragment_parser(scripting_enabled, context)?;
+        parser.feed(data.as_bytes())?;
+        parser.finish()
+    }
+
+    fn document_parser(scripting_enabled: bool) -> Result<DomParser> {
         let opts = TreeBuilderOpts {
             scripting_enabled,
             ..TreeBuilderOpts::default()
         };
-        let builder = TreeBuilder::new(RcDom::default(), opts);
-        build_tree(builder, reader)
+        let builder = TreeBuilder::new(Arena::default(), opts);
+        Ok(DomParser::new(builder, false, scripting_enabled))
     }
 
-    fn parse_fragment(data: &'_ str, scripting_enabled: bool, context: &'_ str) -> Result<Dom> {
-        let reader = make_reader(data);
+    fn fragment_parser(scripting_enabled: bool, context: &str) -> Result<DomParser> {
         let opts = TreeBuilderOpts {
             scripting_enabled,
             ..TreeBuilderOpts::default()
         };
 
-        let mut sink = RcDom::default();
-        let local = LocalName::from(context);
-        let name = QualName {
-            prefix: None,
-            ns: ns!(html),
-            local,
-        };
+        let mut sink = Arena::default();
+        let name = context_name(context);
         let context = create_element(&mut sink, name, vec![]);
         let builder = TreeBuilder::new_for_fragment(sink, context, None, opts);
 
-        build_tree(builder, reader)
+        let state = builder.tokenizer_state_for_context_elem();
+        let mut parser = DomParser::new(builder, true, scripting_enabled);
+        parser.content_model = ContentModel::for_context(state);
+        Ok(parser)
+    }
+
+    fn arena(&self) -> &Arena {
+        &self.dom
+    }
+
+    fn is_fragment(&self) -> bool {
+        self.fragment
+    }
+}
+
+impl Serializable for Dom {
+    fn to_html_with(&self, opts: &SerializeOpts) -> String {
+        serialize::serialize_children(&self.dom, self.root(), opts)
+    }
+
+    fn scripting_enabled(&self) -> bool {
+        self.scripting_enabled
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        output::json(&self.dom, self.root(), self.fragment)
     }
 }
@@ -0,0 +1,332 @@
+//! XML documents, such as XHTML and SVG served as XML.  Unlike the HTML backends, names are
+//! case-sensitive and bound to the namespaces declared in scope, CDATA sections are text and
+//! processing instructions are kept.  Malformed input is recovered from where quick-xml can
+//! carry on, with the problems reported as parse errors.
+use super::quick_xml::{quoted, Location, SourceMap};
+use super::{Document, Parser, ScriptHost};
+use crate::dom::{Arena, NodeId};
+use crate::types::Result;
+use markup5ever::interface::{ElementFlags, NodeOrText, TreeSink};
+use markup5ever::tendril::StrTendril;
+use markup5ever::{namespace_url, ns, Attribute, LocalName, Namespace, Prefix, QualName};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::{PrefixDeclaration, QName, ResolveResult};
+use quick_xml::reader::NsReader;
+use std::borrow::Cow;
+
+pub struct Dom {
+    dom: Arena,
+}
+
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r')
+}
+
+// The name and the public and system identifiers of a DOCTYPE, whose contents quick-xml gives
+// without "<!DOCTYPE" and the closing ">".  An internal subset is skipped.
+fn doctype(contents: &str) -> (StrTendril, StrTendril, StrTendril) {
+    let end = contents.find(is_space).unwrap_or(contents.len());
+    let name = StrTendril::from(&contents[..end]);
+    let rest = contents[end..].trim_start_matches(is_space);
+
+    let ids = || -> Option<(StrTendril, StrTendril)> {
+        if let Some(rest) = rest.strip_prefix("SYSTEM") {
+            let (system, _) = quoted(rest.trim_start_matches(is_space))?;
+            return Some((StrTendril::new(), system));
+        }
+
+        let rest = rest.strip_prefix("PUBLIC")?;
+        let (public, rest) = quoted(rest.trim_start_matches(is_space))?;
+        let system = rest
+            .and_then(|rest| quoted(rest.trim_start_matches(is_space)))
+            .map(|(system, _)| system)
+            .unwrap_or_default();
+        Some((public, system))
+    };
+
+    let (public_id, system_id) = ids().unwrap_or_default();
+    (name, public_id, system_id)
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str> {
+    Ok(std::str::from_utf8(bytes)?)
+}
+
+// What a prefix resolved to, kept apart from the reader so it can read on
+enum Binding {
+    Bound(Namespace),
+    Unbound,
+    Unknown(String),
+}
+
+impl From<ResolveResult<'_>> for Binding {
+    fn from(resolved: ResolveResult<'_>) -> Self {
+        match resolved {
+            ResolveResult::Bound(ns) => Self::Bound(Namespace::from(
+                String::from_utf8_lossy(ns.as_ref()).as_ref(),
+            )),
+            ResolveResult::Unbound => Self::Unbound,
+            ResolveResult::Unknown(prefix) => {
+                Self::Unknown(String::from_utf8_lossy(&prefix).into_owned())
+            }
+        }
+    }
+}
+
+// An element name, which takes the default namespace when it has no prefix
+fn element_name(arena: &mut Arena, binding: Binding, name: QName<'_>) -> Result<QualName> {
+    let prefix = match name.prefix() {
+        Some(prefix) => Some(Prefix::from(utf8(prefix.into_inner())?)),
+        None => None,
+    };
+    let ns = match binding {
+        Binding::Bound(ns) => ns,
+        Binding::Unbound => ns!(),
+        Binding::Unknown(prefix) => {
+            arena.parse_error(Cow::Owned(format!("unbound namespace prefix: {prefix}")));
+            ns!()
+        }
+    };
+
+    Ok(QualName::new(
+        prefix,
+        ns,
+        LocalName::from(utf8(name.local_name().as_ref())?),
+    ))
+}
+
+// Namespace declarations are attributes in the XMLNS namespace.  Other attributes without a
+// prefix are in no namespace.
+fn attributes(
+    arena: &mut Arena,
+    reader: &NsReader<&[u8]>,
+    start: &BytesStart<'_>,
+) -> Result<Vec<Attribute>> {
+    let mut attrs = vec![];
+
+    for attr in start.attributes() {
+        let attr = match attr {
+            Ok(attr) => attr,
+            Err(e) => {
+                arena.parse_error(Cow::Owned(e.to_string()));
+                continue;
+            }
+        };
+
+        let name = match attr.key.as_namespace_binding() {
+            Some(PrefixDeclaration::Default) => {
+                QualName::new(None, ns!(xmlns), LocalName::from("xmlns"))
+            }
+            Some(PrefixDeclaration::Named(prefix)) => QualName::new(
+                Some(Prefix::from("xmlns")),
+                ns!(xmlns),
+                LocalName::from(utf8(prefix)?),
+            ),
+            None => {
+                let (resolved, _) = reader.resolve_attribute(attr.key);
+                element_name(arena, resolved.into(), attr.key)?
+            }
+        };
+
+        let value = match attr.unescape_value() {
+            Ok(value) => value,
+            Err(e) => {
+                arena.parse_error(Cow::Owned(e.to_string()));
+                Cow::Borrowed(utf8(&attr.value)?)
+            }
+        };
+
+        attrs.push(Attribute {
+            name,
+            value: StrTendril::from(value.as_ref()),
+        });
+    }
+
+    Ok(attrs)
+}
+
+// Builds the tree from the whole input, whose newlines have been normalized
+fn build(data: &[u8], map: &SourceMap) -> Result<Arena> {
+    let mut arena = Arena::default();
+    let document = arena.document();
+    let mut reader = NsReader::from_reader(data);
+    let config = reader.config_mut();
+    config.check_end_names = false;
+    config.allow_unmatched_ends = true;
+
+    // Open elements, along with their names as written so end tags can be matched to them
+    let mut open: Vec<(NodeId, Vec<u8>)> = vec![];
+    let mut location = Location::default();
+
+    loop {
+        let start = reader.buffer_position() as usize;
+        let event = reader
+            .read_resolved_event()
+            .map(|(resolved, event)| (Binding::from(resolved), event));
+        let end = reader.buffer_position() as usize;
+
+        let at = match event {
+            Err(_) => reader.error_position() as usize,
+            Ok(_) => start,
+        };
+        location.advance(data, at, map);
+        arena.set_current_line(location.line as u64);
+        arena.set_current_position(location.col, map.span(start..end));
+
+        let (resolved, event) = match event {
+            Ok(event) => event,
+            Err(e) => {
+                arena.parse_error(Cow::Owned(e.to_string()));
+                continue;
+            }
+        };
+
+        let parent = open.last().map_or(document, |(id, _)| *id);
+        let text = |arena: &mut Arena, text: &str| {
+            if parent != document {
+                arena.append(&parent, NodeOrText::AppendText(StrTendril::from(text)));
+            } else if !text.trim_matches(is_space).is_empty() {
+                arena.parse_error(Cow::Borrowed("text outside the root element"));
+            }
+        };
+
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let name = element_name(&mut arena, resolved, e.name())?;
+                let attrs = attributes(&mut arena, &reader, e)?;
+                let element = arena.create_element(name, attrs, ElementFlags::default());
+
+                if parent == document && open.is_empty() && has_root(&arena) {
+                    arena.parse_error(Cow::Borrowed("more than one root element"));
+                }
+                arena.append(&parent, NodeOrText::AppendNode(element));
+
+                if let Event::Start(_) = event {
+                    open.push((element, e.name().as_ref().to_vec()));
+                }
+            }
+
+            Event::End(e) => {
+                let name = e.name();
+                match open.iter().rposition(|(_, open)| open == name.as_ref()) {
+                    Some(i) => {
+                        if i + 1 != open.len() {
+                            arena.parse_error(Cow::Borrowed("end tag closes unclosed elements"));
+                        }
+                        open.truncate(i);
+                    }
+                    None => {
+                        let name = String::from_utf8_lossy(name.as_ref());
+                        arena.parse_error(Cow::Owned(format!("unexpected end tag: {name}")));
+                    }
+                }
+            }
+
+            Event::Text(e) => match e.unescape() {
+                Ok(s) => text(&mut arena, &s),
+                Err(err) => {
+                    arena.parse_error(Cow::Owned(err.to_string()));
+                    text(&mut arena, utf8(&e)?);
+                }
+            },
+
+            Event::CData(e) => text(&mut arena, utf8(&e)?),
+
+            Event::Comment(e) => {
+                let comment = arena.create_comment(StrTendril::from(utf8(&e)?));
+                arena.append(&parent, NodeOrText::AppendNode(comment));
+            }
+
+            Event::PI(e) => {
+                let target = StrTendril::from(utf8(e.target())?);
+                let contents = utf8(e.content())?.trim_start_matches(is_space);
+                let pi = arena.create_pi(target, StrTendril::from(contents));
+                arena.append(&parent, NodeOrText::AppendNode(pi));
+            }
+
+            Event::DocType(e) => {
+                let (name, public_id, system_id) = doctype(utf8(&e)?);
+                arena.append_doctype_to_document(name, public_id, system_id);
+            }
+
+            // The XML declaration is not part of the tree
+            Event::Decl(_) => {}
+
+            Event::Eof => break,
+        }
+    }
+
+    if !open.is_empty() {
+        arena.parse_error(Cow::Borrowed("unclosed elements at end of input"));
+    }
+
+    Ok(arena)
+}
+
+fn has_root(arena: &Arena) -> bool {
+    arena
+        .children(arena.document())
+        .any(|id| arena.node(id).element_name().is_some())
+}
+
+/// Collects the input and builds the tree once it is all there, since well-formedness can
+/// only be judged as a whole.
+pub struct DomParser {
+    buffer: Vec<u8>,
+    // Whether the last chunk ended with a CR, so that an LF starting the next one is dropped
+    after_cr: bool,
+    source_map: SourceMap,
+}
+
+impl Parser<Dom> for DomParser {
+    // Line breaks are normalized to LF, as in the XML specification
+    fn feed(&mut self, data: &[u8]) -> Result<()> {
+        for &b in data {
+            match b {
+                b'\r' => self.buffer.push(b'\n'),
+                b'\n' if self.after_cr => self.source_map.dropped.push(self.buffer.len()),
+                b => self.buffer.push(b),
+            }
+            self.after_cr = b == b'\r';
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Dom> {
+        let dom = build(&self.buffer, &self.source_map)?;
+        Ok(Dom { dom })
+    }
+
+    // Scripts in XML documents are not run
+    fn set_script_host(&mut self, _host: Box<dyn ScriptHost>) {}
+}
+
+impl Document<'_, Dom> for Dom {
+    type Parser = DomParser;
+
+    fn parse_document(data: &str, scripting_enabled: bool) -> Result<Dom> {
+        let mut parser = Self::document_parser(scripting_enabled)?;
+        parser.feed(data.as_bytes())?;
+        parser.finish()
+    }
+
+    fn document_parser(_scripting_enabled: bool) -> Result<DomParser> {
+        Ok(DomParser {
+            buffer: vec![],
+            after_cr: false,
+            source_map: SourceMap::default(),
+        })
+    }
+
+    fn arena(&self) -> &Arena {
+        &self.dom
+    }
+
+    fn is_fragment(&self) -> bool {
+        false
+    }
+
+    fn is_html(&self) -> bool {
+        false
+    }
+}
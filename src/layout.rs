@@ -0,0 +1,36 @@
+//! Layout, which turns a styled document into boxes with positions and sizes.
+pub mod block;
+pub mod box_tree;
+pub mod fragment;
+pub mod inline;
+pub mod text;
+
+pub use box_tree::{BoxKind, BoxTree, LayoutBox};
+pub use fragment::{Fragment, FragmentKind, FragmentTree, Rect};
+pub use text::FontMetrics;
+
+use crate::style::Viewport;
+
+// What layout depends on besides the boxes
+pub(crate) struct LayoutContext {
+    pub metrics: FontMetrics,
+    pub viewport: Viewport,
+}
+
+/// Lays out the boxes of a document in normal flow in the viewport, with text in the metrics
+/// of the Ahem font.
+pub fn layout(boxes: &BoxTree, viewport: &Viewport) -> FragmentTree {
+    let cx = LayoutContext {
+        metrics: FontMetrics::AHEM,
+        viewport: *viewport,
+    };
+    let root = boxes.root.as_ref().map(|root| {
+        let mut fragment = block::layout_root(&cx, root);
+        fragment.make_absolute();
+        fragment
+    });
+    FragmentTree {
+        root,
+        viewport: Rect::new(0.0, 0.0, viewport.width, viewport.height),
+    }
+}
@@ -0,0 +1,519 @@
+//! Block layout: the widths and heights of boxes, and how the vertical margins of block-level
+//! boxes collapse.
+//! See https://www.w3.org/TR/CSS2/visudet.html and
+//! https://www.w3.org/TR/CSS2/box.html#collapsing-margins
+use super::box_tree::{BoxKind, LayoutBox, Replaced};
+use super::fragment::{Fragment, FragmentKind, Rect};
+use super::{inline, LayoutContext};
+use crate::style::values::{BoxSizing, Display, LengthPercentage, LengthPercentageOrAuto};
+use crate::style::values::{Overflow, Sides};
+use crate::style::ComputedStyle;
+
+/// The size of a replaced element without a size of its own.
+/// See https://www.w3.org/TR/CSS2/visudet.html#inline-replaced-width
+const DEFAULT_REPLACED_SIZE: (f32, f32) = (300.0, 150.0);
+
+/// Adjoining margins, which collapse to the largest positive one plus the most negative one.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct CollapsedMargin {
+    positive: f32,
+    negative: f32,
+}
+
+impl CollapsedMargin {
+    pub fn new(margin: f32) -> Self {
+        Self {
+            positive: margin.max(0.0),
+            negative: margin.min(0.0),
+        }
+    }
+
+    pub fn adjoin(self, other: Self) -> Self {
+        Self {
+            positive: self.positive.max(other.positive),
+            negative: self.negative.min(other.negative),
+        }
+    }
+
+    pub fn resolve(self) -> f32 {
+        self.positive + self.negative
+    }
+}
+
+/// A laid out block-level box, whose fragment is placed horizontally in the content box of its
+/// containing block and with its top at zero.
+pub(crate) struct BlockLayout {
+    pub fragment: Fragment,
+    pub margin_top: CollapsedMargin,
+    pub margin_bottom: CollapsedMargin,
+    /// Whether its top and bottom margins adjoin, as it has no height or content between.
+    pub collapses_through: bool,
+    /// The baseline of its last line box, from the top of its border box.
+    pub baseline: Option<f32>,
+}
+
+/// A laid out atomic inline-level box, whose fragment is placed from the top left of its margin
+/// box.
+pub(crate) struct AtomicLayout {
+    pub fragment: Fragment,
+    pub width: f32,
+    pub height: f32,
+    /// The baseline, from the top of the margin box.
+    pub baseline: f32,
+}
+
+fn horizontal(sides: &Sides<f32>) -> f32 {
+    sides.left + sides.right
+}
+
+fn vertical(sides: &Sides<f32>) -> f32 {
+    sides.top + sides.bottom
+}
+
+/// The used padding and border widths of a box.
+pub(crate) fn edges(style: &ComputedStyle, cb_width: f32) -> (Sides<f32>, Sides<f32>) {
+    let padding = Sides {
+        top: style.padding.top.resolve(cb_width),
+        right: style.padding.right.resolve(cb_width),
+        bottom: style.padding.bottom.resolve(cb_width),
+        left: style.padding.left.resolve(cb_width),
+    };
+    (padding, style.border_width)
+}
+
+/// The used margins of a box, with `auto` as zero.
+pub(crate) fn margins(style: &ComputedStyle, cb_width: f32) -> Sides<f32> {
+    let resolve = |margin: LengthPercentageOrAuto| margin.resolve(cb_width).unwrap_or(0.0);
+    Sides {
+        top: resolve(style.margin.top),
+        right: resolve(style.margin.right),
+        bottom: resolve(style.margin.bottom),
+        left: resolve(style.margin.left),
+    }
+}
+
+// The content size a value of `width` or `height` gives, given the padding and border
+fn content_size(style: &ComputedStyle, size: f32, edges: f32) -> f32 {
+    match style.box_sizing {
+        BoxSizing::ContentBox => size,
+        BoxSizing::BorderBox => (size - edges).max(0.0),
+    }
+}
+
+// A content width within `min-width` and `max-width`
+fn clamp_width(style: &ComputedStyle, width: f32, cb_width: f32, edges: f32) -> f32 {
+    let max = style
+        .max_width
+        .map(|max| content_size(style, max.resolve(cb_width), edges));
+    let min = content_size(style, style.min_width.resolve(cb_width), edges);
+    width.min(max.unwrap_or(f32::INFINITY)).max(min)
+}
+
+// A length or percentage of the height of the containing block, if that is known
+fn resolve_height(value: LengthPercentage, cb_height: Option<f32>) -> Option<f32> {
+    match value {
+        LengthPercentage::Length(px) => Some(px),
+        LengthPercentage::Percentage(_) => cb_height.map(|height| value.resolve(height)),
+    }
+}
+
+// The content height `height` gives, or None for `auto`
+fn specified_height(style: &ComputedStyle, cb_height: Option<f32>, edges: f32) -> Option<f32> {
+    match style.height {
+        LengthPercentageOrAuto::LengthPercentage(height) => resolve_height(height, cb_height),
+        LengthPercentageOrAuto::Auto => None,
+    }
+    .map(|height| content_size(style, height, edges))
+}
+
+// A content height within `min-height` and `max-height`
+fn clamp_height(style: &ComputedStyle, height: f32, cb_height: Option<f32>, edges: f32) -> f32 {
+    let max = style
+        .max_height
+        .and_then(|max| resolve_height(max, cb_height))
+        .map(|max| content_size(style, max, edges));
+    let min = resolve_height(style.min_height, cb_height)
+        .map(|min| content_size(style, min, edges))
+        .unwrap_or(0.0);
+    height.min(max.unwrap_or(f32::INFINITY)).max(min)
+}
+
+/// The used left margin, content width and right margin of a block-level box in normal flow,
+/// where `width` is its content width or None for `auto`.
+/// See https://www.w3.org/TR/CSS2/visudet.html#blockwidth
+fn block_widths(
+    style: &ComputedStyle,
+    cb_width: f32,
+    edges: f32,
+    width: Option<f32>,
+) -> (f32, f32, f32) {
+    let margin_left = style.margin.left.resolve(cb_width);
+    let margin_right = style.margin.right.resolve(cb_width);
+
+    let Some(width) = width else {
+        let (left, right) = (margin_left.unwrap_or(0.0), margin_right.unwrap_or(0.0));
+        let width = (cb_width - left - right - edges).max(0.0);
+        return (left, width, cb_width - left - width - edges);
+    };
+
+    let rest = cb_width - width - edges;
+    match (margin_left, margin_right) {
+        (None, None) if rest >= 0.0 => (rest / 2.0, width, rest / 2.0),
+        (None, Some(right)) => (rest - right, width, right),
+        // Over-constrained, so the right margin gives way
+        (Some(left), _) => (left, width, rest - left),
+        (None, None) => (0.0, width, rest),
+    }
+}
+
+fn establishes_formatting_context(layout_box: &LayoutBox) -> bool {
+    layout_box.kind == BoxKind::InlineBlock
+        || layout_box.style.overflow != Overflow::Visible
+        || matches!(
+            layout_box.style.display,
+            Display::FlowRoot | Display::Table | Display::TableCell | Display::TableCaption
+        )
+}
+
+/// The content size of a replaced element.
+/// See https://www.w3.org/TR/CSS2/visudet.html#inline-replaced-width
+fn replaced_size(
+    layout_box: &LayoutBox,
+    replaced: Replaced,
+    cb_width: f32,
+    cb_height: Option<f32>,
+) -> (f32, f32) {
+    let style = &layout_box.style;
+    let (padding, border) = edges(style, cb_width);
+    let (edges_x, edges_y) = (
+        horizontal(&padding) + horizontal(&border),
+        vertical(&padding) + vertical(&border),
+    );
+
+    let width = style
+        .width
+        .resolve(cb_width)
+        .map(|width| content_size(style, width, edges_x))
+        .or(replaced.width)
+        .unwrap_or(DEFAULT_REPLACED_SIZE.0);
+    let height = specified_height(style, cb_height, edges_y)
+        .or(replaced.height)
+        .unwrap_or(DEFAULT_REPLACED_SIZE.1);
+    (
+        clamp_width(style, width, cb_width, edges_x),
+        clamp_height(style, height, cb_height, edges_y),
+    )
+}
+
+/// Lays out a block-level box in a block container whose content box has the given size.
+pub(crate) fn layout_block_level(
+    cx: &LayoutContext,
+    layout_box: &LayoutBox,
+    cb_width: f32,
+    cb_height: Option<f32>,
+) -> BlockLayout {
+    let style = &layout_box.style;
+    let (padding, border) = edges(style, cb_width);
+    let edges_x = horizontal(&padding) + horizontal(&border);
+
+    if let BoxKind::Replaced(replaced) = layout_box.kind {
+        let (width, height) = replaced_size(layout_box, replaced, cb_width, cb_height);
+        let (margin_left, _, _) = block_widths(style, cb_width, edges_x, Some(width));
+        let margins = margins(style, cb_width);
+
+        let mut fragment = Fragment::new(FragmentKind::Replaced, layout_box.node, style.clone());
+        fragment.rect = Rect::new(
+            margin_left,
+            0.0,
+            width + edges_x,
+            height + vertical(&padding) + vertical(&border),
+        );
+        fragment.padding = padding;
+        fragment.border = border;
+        return BlockLayout {
+            fragment,
+            margin_top: CollapsedMargin::new(margins.top),
+            margin_bottom: CollapsedMargin::new(margins.bottom),
+            collapses_through: false,
+            baseline: None,
+        };
+    }
+
+    let width = style
+        .width
+        .resolve(cb_width)
+        .map(|width| content_size(style, width, edges_x));
+    let (mut margin_left, mut width, _) = block_widths(style, cb_width, edges_x, width);
+    let clamped = clamp_width(style, width, cb_width, edges_x);
+    if clamped != width {
+        (margin_left, width, _) = block_widths(style, cb_width, edges_x, Some(clamped));
+    }
+
+    let formatting_context = establishes_formatting_context(layout_box);
+    layout_block_container(
+        cx,
+        layout_box,
+        FragmentKind::Block,
+        (margin_left, width),
+        cb_width,
+        cb_height,
+        formatting_context,
+    )
+}
+
+/// Lays out the root element's box in the initial containing block.
+pub(crate) fn layout_root(cx: &LayoutContext, root: &LayoutBox) -> Fragment {
+    let (width, height) = (cx.viewport.width, cx.viewport.height);
+    let mut layout = if root.kind == BoxKind::Block {
+        let style = &root.style;
+        let (padding, border) = edges(style, width);
+        let edges_x = horizontal(&padding) + horizontal(&border);
+        let specified = style
+            .width
+            .resolve(width)
+            .map(|width| content_size(style, width, edges_x));
+        let (margin_left, content_width, _) = block_widths(style, width, edges_x, specified);
+        let content_width = clamp_width(style, content_width, width, edges_x);
+        layout_block_container(
+            cx,
+            root,
+            FragmentKind::Block,
+            (margin_left, content_width),
+            width,
+            Some(height),
+            true,
+        )
+    } else {
+        layout_block_level(cx, root, width, Some(height))
+    };
+
+    // The margins of the root element do not collapse
+    let top = margins(&root.style, width).top;
+    layout.fragment.rect.y = top;
+    layout.fragment
+}
+
+/// Lays out a block container with the given left margin and content width.  One that
+/// establishes a block formatting context keeps the margins of its children inside.
+fn layout_block_container(
+    cx: &LayoutContext,
+    layout_box: &LayoutBox,
+    kind: FragmentKind,
+    (margin_left, width): (f32, f32),
+    cb_width: f32,
+    cb_height: Option<f32>,
+    formatting_context: bool,
+) -> BlockLayout {
+    let style = &layout_box.style;
+    let (padding, border) = edges(style, cb_width);
+    let margins = margins(style, cb_width);
+    let (top_edge, bottom_edge) = (padding.top + border.top, padding.bottom + border.bottom);
+    let content_left = padding.left + border.left;
+    let edges_y = top_edge + bottom_edge;
+
+    let specified = specified_height(style, cb_height, edges_y);
+    let min_height = clamp_height(style, 0.0, cb_height, edges_y);
+
+    let mut fragment = Fragment::new(kind, layout_box.node, style.clone());
+    fragment.padding = padding;
+    fragment.border = border;
+
+    // The top margin of the box collapses with that of its first child if nothing separates
+    // them, and likewise at the bottom
+    let mut top_open = !formatting_context && top_edge == 0.0;
+    let collapse_bottom =
+        !formatting_context && bottom_edge == 0.0 && specified.is_none() && min_height == 0.0;
+    let mut margin_top = CollapsedMargin::new(margins.top);
+    let mut pending = CollapsedMargin::default();
+    let mut cursor = top_edge;
+    let mut baseline = None;
+
+    if layout_box.has_inline_children() {
+        let lines = inline::layout(cx, layout_box, width);
+        if !lines.lines.is_empty() {
+            for mut line in lines.lines {
+                line.rect = line.rect.translate(content_left, top_edge);
+                fragment.children.push(line);
+            }
+            top_open = false;
+            cursor = top_edge + lines.height;
+            baseline = lines.baseline.map(|baseline| baseline + top_edge);
+        }
+    } else {
+        let child_cb_height =
+            specified.map(|height| clamp_height(style, height, cb_height, edges_y));
+        for child in &layout_box.children {
+            let mut child = layout_block_level(cx, child, width, child_cb_height);
+            child.fragment.rect.x += content_left;
+
+            if top_open {
+                margin_top = margin_top.adjoin(child.margin_top);
+                child.fragment.rect.y = cursor;
+                if child.collapses_through {
+                    margin_top = margin_top.adjoin(child.margin_bottom);
+                } else {
+                    top_open = false;
+                    cursor += child.fragment.rect.height;
+                    pending = child.margin_bottom;
+                }
+            } else {
+                let adjoining = pending.adjoin(child.margin_top);
+                child.fragment.rect.y = cursor + adjoining.resolve();
+                if child.collapses_through {
+                    pending = adjoining.adjoin(child.margin_bottom);
+                } else {
+                    cursor = child.fragment.rect.y + child.fragment.rect.height;
+                    pending = child.margin_bottom;
+                }
+            }
+
+            if let Some(child_baseline) = child.baseline {
+                baseline = Some(child.fragment.rect.y + child_baseline);
+            }
+            fragment.children.push(child.fragment);
+        }
+    }
+
+    let collapses_through = top_open && collapse_bottom;
+    let (content_height, margin_bottom) = if collapse_bottom {
+        (
+            cursor - top_edge,
+            pending.adjoin(CollapsedMargin::new(margins.bottom)),
+        )
+    } else {
+        (
+            cursor + pending.resolve() - top_edge,
+            CollapsedMargin::new(margins.bottom),
+        )
+    };
+    let content_height = clamp_height(
+        style,
+        specified.unwrap_or(content_height),
+        cb_height,
+        edges_y,
+    );
+
+    fragment.rect = Rect::new(
+        margin_left,
+        0.0,
+        width + horizontal(&padding) + horizontal(&border),
+        content_height + edges_y,
+    );
+    BlockLayout {
+        fragment,
+        margin_top,
+        margin_bottom,
+        collapses_through,
+        baseline,
+    }
+}
+
+/// Lays out an inline-block or inline replaced element in a line of the given width.
+/// See https://www.w3.org/TR/CSS2/visudet.html#inlineblock-width
+pub(crate) fn layout_atomic(
+    cx: &LayoutContext,
+    layout_box: &LayoutBox,
+    cb_width: f32,
+) -> AtomicLayout {
+    let style = &layout_box.style;
+    let (padding, border) = edges(style, cb_width);
+    let margins = margins(style, cb_width);
+    let edges_x = horizontal(&padding) + horizontal(&border);
+
+    let (fragment, baseline) = if let BoxKind::Replaced(replaced) = layout_box.kind {
+        let (width, height) = replaced_size(layout_box, replaced, cb_width, None);
+        let mut fragment = Fragment::new(FragmentKind::Replaced, layout_box.node, style.clone());
+        fragment.rect = Rect::new(
+            0.0,
+            0.0,
+            width + edges_x,
+            height + vertical(&padding) + vertical(&border),
+        );
+        fragment.padding = padding;
+        fragment.border = border;
+        (fragment, None)
+    } else {
+        let width = match style.width.resolve(cb_width) {
+            Some(width) => content_size(style, width, edges_x),
+            // Shrink to fit
+            None => {
+                let available = cb_width - horizontal(&margins) - edges_x;
+                let (min, max) = content_widths(cx, layout_box);
+                max.min(available.max(min))
+            }
+        };
+        let width = clamp_width(style, width, cb_width, edges_x);
+        let layout = layout_block_container(
+            cx,
+            layout_box,
+            FragmentKind::InlineBlock,
+            (0.0, width),
+            cb_width,
+            None,
+            true,
+        );
+        let baseline = layout
+            .baseline
+            .filter(|_| style.overflow == Overflow::Visible);
+        (layout.fragment, baseline)
+    };
+
+    let (width, height) = (
+        fragment.rect.width + horizontal(&margins),
+        fragment.rect.height + vertical(&margins),
+    );
+    let mut fragment = fragment;
+    fragment.rect = fragment.rect.translate(margins.left, margins.top);
+    AtomicLayout {
+        baseline: baseline.map_or(height, |baseline| margins.top + baseline),
+        fragment,
+        width,
+        height,
+    }
+}
+
+/// The min-content and max-content widths of the margin box of a block-level or atomic
+/// inline-level box.
+/// See https://www.w3.org/TR/css-sizing-3/#intrinsic-sizes
+pub(crate) fn intrinsic_widths(cx: &LayoutContext, layout_box: &LayoutBox) -> (f32, f32) {
+    let style = &layout_box.style;
+    let fixed = |value: LengthPercentage| match value {
+        LengthPercentage::Length(px) => px,
+        LengthPercentage::Percentage(_) => 0.0,
+    };
+    let fixed_or_auto = |value: LengthPercentageOrAuto| match value {
+        LengthPercentageOrAuto::LengthPercentage(value) => fixed(value),
+        LengthPercentageOrAuto::Auto => 0.0,
+    };
+    let edges =
+        fixed(style.padding.left) + fixed(style.padding.right) + horizontal(&style.border_width);
+    let outside = edges + fixed_or_auto(style.margin.left) + fixed_or_auto(style.margin.right);
+
+    let (min, max) = match (&layout_box.kind, style.width) {
+        (BoxKind::Replaced(replaced), _) => {
+            let (width, _) = replaced_size(layout_box, *replaced, 0.0, None);
+            (width, width)
+        }
+        (_, LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Length(width))) => {
+            let width = content_size(style, width, edges);
+            (width, width)
+        }
+        _ => content_widths(cx, layout_box),
+    };
+    let clamp = |width: f32| clamp_width(style, width, 0.0, edges) + outside;
+    (clamp(min), clamp(max))
+}
+
+// The min-content and max-content widths of the content of a block container
+fn content_widths(cx: &LayoutContext, layout_box: &LayoutBox) -> (f32, f32) {
+    if layout_box.has_inline_children() {
+        return inline::intrinsic_widths(cx, layout_box);
+    }
+    layout_box
+        .children
+        .iter()
+        .map(|child| intrinsic_widths(cx, child))
+        .fold((0.0, 0.0), |(min, max), (child_min, child_max)| {
+            (min.max(child_min), max.max(child_max))
+        })
+}
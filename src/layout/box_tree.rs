@@ -0,0 +1,326 @@
+//! The box tree: the boxes that elements and text generate, from their computed `display`.
+//! See https://www.w3.org/TR/CSS2/visuren.html#box-gen
+use crate::dom::{self, Arena, NodeData, NodeId};
+use crate::style::values::{Display, WhiteSpace};
+use crate::style::{ComputedStyle, Styles};
+use markup5ever::{local_name, namespace_url, ns};
+use std::fmt::Write;
+use std::rc::Rc;
+
+/// The size a replaced element asks for with its `width` and `height` attributes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Replaced {
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BoxKind {
+    /// A block-level block container, whose children are either all block-level or all
+    /// inline-level.
+    Block,
+    /// An inline-level block container, laid out as a unit in a line.
+    InlineBlock,
+    /// An inline box, whose contents flow in the lines of its block container.  One broken
+    /// around block-level boxes is split into several, each with the same node.
+    Inline,
+    /// A replaced element such as an image, whose contents are outside the box tree.  It is
+    /// inline-level or block-level as its `display` is.
+    Replaced(Replaced),
+    /// A forced line break, from a `br` element.
+    LineBreak,
+    /// A run of text, in the style of its parent.
+    Text(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct LayoutBox {
+    pub kind: BoxKind,
+    /// The node that generates the box, or None for an anonymous box.
+    pub node: Option<NodeId>,
+    pub style: Rc<ComputedStyle>,
+    pub children: Vec<LayoutBox>,
+}
+
+impl LayoutBox {
+    fn anonymous_block(parent: &ComputedStyle, children: Vec<LayoutBox>) -> Self {
+        Self {
+            kind: BoxKind::Block,
+            node: None,
+            style: Rc::new(ComputedStyle::anonymous(parent, Display::Block)),
+            children,
+        }
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.node.is_none()
+    }
+
+    pub fn is_inline_level(&self) -> bool {
+        match self.kind {
+            BoxKind::Block => false,
+            BoxKind::Replaced(_) => self.style.display.is_inline_level(),
+            _ => true,
+        }
+    }
+
+    /// Whether its children are inline-level, so that it lays them out in lines.
+    pub fn has_inline_children(&self) -> bool {
+        self.children.iter().any(LayoutBox::is_inline_level)
+    }
+
+    // Text that generates no boxes, because it would be collapsed away
+    fn is_collapsible_whitespace(&self) -> bool {
+        match &self.kind {
+            BoxKind::Text(text) => {
+                matches!(
+                    self.style.white_space,
+                    WhiteSpace::Normal | WhiteSpace::Nowrap
+                ) && text
+                    .chars()
+                    .all(|c| matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0C'))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The boxes of a document.
+#[derive(Clone, Debug)]
+pub struct BoxTree {
+    /// The principal box of the root element, or None if it has `display: none`.
+    pub root: Option<LayoutBox>,
+}
+
+// The elements whose contents are outside the box tree
+fn is_replaced(arena: &Arena, id: NodeId) -> bool {
+    let Some(name) = arena.node(id).element_name() else {
+        return false;
+    };
+    match name.ns {
+        ns!(html) => matches!(
+            name.local,
+            local_name!("img")
+                | local_name!("canvas")
+                | local_name!("embed")
+                | local_name!("iframe")
+                | local_name!("object")
+                | local_name!("video")
+        ),
+        // An outermost svg element, which draws its descendants itself
+        ns!(svg) => {
+            name.local == local_name!("svg")
+                && arena
+                    .parent(id)
+                    .and_then(|parent| arena.node(parent).element_name())
+                    .map_or(true, |parent| parent.ns != ns!(svg))
+        }
+        _ => false,
+    }
+}
+
+fn is_br(arena: &Arena, id: NodeId) -> bool {
+    arena
+        .node(id)
+        .element_name()
+        .is_some_and(|name| name.ns == ns!(html) && name.local == local_name!("br"))
+}
+
+fn dimension(arena: &Arena, id: NodeId, name: &str) -> Option<f32> {
+    arena
+        .node(id)
+        .attr(name)?
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite() && *value >= 0.0)
+}
+
+struct Builder<'a> {
+    arena: &'a Arena,
+    styles: &'a Styles,
+}
+
+impl Builder<'_> {
+    // The boxes of the children of a node, in the style of the box they are in
+    fn children(&self, id: NodeId, style: &Rc<ComputedStyle>) -> Vec<LayoutBox> {
+        let mut boxes = vec![];
+        for child in self.arena.children(id) {
+            self.push_boxes(child, style, &mut boxes);
+        }
+        boxes
+    }
+
+    // The boxes a node generates in the box of its parent, in order.  An inline box broken
+    // around block-level boxes comes out in pieces, with the block-level boxes between them.
+    fn push_boxes(&self, id: NodeId, parent: &Rc<ComputedStyle>, boxes: &mut Vec<LayoutBox>) {
+        let style = match &self.arena.node(id).data {
+            NodeData::Text { contents } if !contents.is_empty() => {
+                boxes.push(LayoutBox {
+                    kind: BoxKind::Text(contents.clone()),
+                    node: Some(id),
+                    style: parent.clone(),
+                    children: vec![],
+                });
+                return;
+            }
+            NodeData::Element { .. } => match self.styles.get(id) {
+                Some(style) => Rc::new(style.clone()),
+                None => return,
+            },
+            _ => return,
+        };
+
+        let replaced = is_replaced(self.arena, id);
+        let kind = match style.display {
+            Display::None | Display::TableColumn | Display::TableColumnGroup => return,
+            // See https://www.w3.org/TR/css-display-3/#unbox
+            Display::Contents if replaced => return,
+            Display::Contents => {
+                for child in self.arena.children(id) {
+                    self.push_boxes(child, &style, boxes);
+                }
+                return;
+            }
+            _ if replaced => BoxKind::Replaced(Replaced {
+                width: dimension(self.arena, id, "width"),
+                height: dimension(self.arena, id, "height"),
+            }),
+            Display::Inline if is_br(self.arena, id) => BoxKind::LineBreak,
+            Display::Inline => {
+                self.push_inline(id, style, boxes);
+                return;
+            }
+            Display::InlineBlock | Display::InlineTable => BoxKind::InlineBlock,
+            _ => BoxKind::Block,
+        };
+
+        let children = match kind {
+            BoxKind::Block | BoxKind::InlineBlock => wrap_inline(self.children(id, &style), &style),
+            _ => vec![],
+        };
+        boxes.push(LayoutBox {
+            kind,
+            node: Some(id),
+            style,
+            children,
+        });
+    }
+
+    // See https://www.w3.org/TR/CSS2/visuren.html#anonymous-block-level
+    fn push_inline(&self, id: NodeId, style: Rc<ComputedStyle>, boxes: &mut Vec<LayoutBox>) {
+        let mut piece = vec![];
+        for child in self.children(id, &style) {
+            if child.is_inline_level() {
+                piece.push(child);
+                continue;
+            }
+            boxes.push(LayoutBox {
+                kind: BoxKind::Inline,
+                node: Some(id),
+                style: style.clone(),
+                children: std::mem::take(&mut piece),
+            });
+            boxes.push(child);
+        }
+        boxes.push(LayoutBox {
+            kind: BoxKind::Inline,
+            node: Some(id),
+            style,
+            children: piece,
+        });
+    }
+}
+
+// The children of a block container: if some are block-level, each run of inline-level ones
+// goes in an anonymous block box.  Runs of collapsible white space generate no boxes.
+fn wrap_inline(children: Vec<LayoutBox>, style: &ComputedStyle) -> Vec<LayoutBox> {
+    let is_blank = |run: &[LayoutBox]| run.iter().all(LayoutBox::is_collapsible_whitespace);
+
+    if children.iter().all(LayoutBox::is_inline_level) {
+        return if is_blank(&children) {
+            vec![]
+        } else {
+            children
+        };
+    }
+
+    let mut wrapped = vec![];
+    let mut run = vec![];
+    for child in children {
+        if child.is_inline_level() {
+            run.push(child);
+            continue;
+        }
+        if !is_blank(&run) {
+            wrapped.push(LayoutBox::anonymous_block(style, std::mem::take(&mut run)));
+        }
+        run.clear();
+        wrapped.push(child);
+    }
+    if !is_blank(&run) {
+        wrapped.push(LayoutBox::anonymous_block(style, run));
+    }
+    wrapped
+}
+
+impl BoxTree {
+    /// Builds the boxes of the document from the computed styles of its elements.
+    pub fn new(arena: &Arena, styles: &Styles) -> Self {
+        let builder = Builder { arena, styles };
+        let initial = Rc::new(ComputedStyle::initial());
+        let root = arena
+            .children(arena.document())
+            .find(|&id| arena.node(id).element_name().is_some());
+
+        let mut boxes = vec![];
+        if let Some(root) = root {
+            builder.push_boxes(root, &initial, &mut boxes);
+        }
+        Self { root: boxes.pop() }
+    }
+
+    /// A dump of the boxes, one per line indented by its depth as in the html5lib tree format.
+    /// Each line has the kind of box, then its element, `(anonymous)` or its text.
+    pub fn dump(&self, arena: &Arena) -> String {
+        let mut dump = String::new();
+        if let Some(root) = &self.root {
+            dump_box(arena, root, 0, &mut dump);
+        }
+        dump
+    }
+}
+
+fn dump_box(arena: &Arena, layout_box: &LayoutBox, depth: usize, dump: &mut String) {
+    dump.push_str("| ");
+    dump.push_str(&"  ".repeat(depth));
+
+    let kind = match &layout_box.kind {
+        BoxKind::Block => "block",
+        BoxKind::InlineBlock => "inline-block",
+        BoxKind::Inline => "inline",
+        BoxKind::Replaced(_) if layout_box.is_inline_level() => "inline replaced",
+        BoxKind::Replaced(_) => "block replaced",
+        BoxKind::LineBreak => "line-break",
+        BoxKind::Text(text) => {
+            let _ = writeln!(dump, "\"{text}\"");
+            return;
+        }
+    };
+    dump.push_str(kind);
+    match layout_box.node.and_then(|id| arena.node(id).element_name()) {
+        Some(name) => {
+            let _ = write!(dump, " <{}>", dom::dump_name(name));
+        }
+        None => dump.push_str(" (anonymous)"),
+    }
+    if let BoxKind::Replaced(Replaced { width, height }) = layout_box.kind {
+        let size = |size: Option<f32>| size.map_or("auto".to_owned(), |size| size.to_string());
+        let _ = write!(dump, " {}x{}", size(width), size(height));
+    }
+    dump.push('\n');
+
+    for child in &layout_box.children {
+        dump_box(arena, child, depth + 1, dump);
+    }
+}
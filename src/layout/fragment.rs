@@ -0,0 +1,179 @@
+//! The fragment tree: the output of layout, with a rectangle for every box on every line.
+use crate::dom::{self, Arena, NodeId};
+use crate::style::values::{Number, Sides};
+use crate::style::ComputedStyle;
+use serde::Serialize;
+use std::fmt::Write;
+use std::rc::Rc;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+
+    pub fn translate(self, dx: f32, dy: f32) -> Self {
+        Self {
+            x: self.x + dx,
+            y: self.y + dy,
+            ..self
+        }
+    }
+
+    /// The rectangle shrunk by the given amount on each side.
+    pub fn inset(self, sides: &Sides<f32>) -> Self {
+        Self {
+            x: self.x + sides.left,
+            y: self.y + sides.top,
+            width: (self.width - sides.left - sides.right).max(0.0),
+            height: (self.height - sides.top - sides.bottom).max(0.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FragmentKind {
+    /// A block-level box, or an anonymous block.
+    Block,
+    /// An inline-level block container.
+    InlineBlock,
+    /// The part of an inline box on one line.
+    Inline,
+    Replaced,
+    /// A line box.
+    Line,
+    /// Text on one line, after white space processing.
+    Text(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Fragment {
+    pub kind: FragmentKind,
+    /// The node of the box, or None for anonymous blocks and lines.
+    pub node: Option<NodeId>,
+    pub style: Rc<ComputedStyle>,
+    /// The border box, from the top left of the initial containing block once layout is done.
+    /// Lines and text have no border, so it is their whole area.
+    pub rect: Rect,
+    pub border: Sides<f32>,
+    pub padding: Sides<f32>,
+    pub children: Vec<Fragment>,
+}
+
+impl Fragment {
+    pub(crate) fn new(kind: FragmentKind, node: Option<NodeId>, style: Rc<ComputedStyle>) -> Self {
+        Self {
+            kind,
+            node,
+            style,
+            rect: Rect::default(),
+            border: Sides::all(0.0),
+            padding: Sides::all(0.0),
+            children: vec![],
+        }
+    }
+
+    /// The padding box, inside the border.
+    pub fn padding_rect(&self) -> Rect {
+        self.rect.inset(&self.border)
+    }
+
+    /// The content box, inside the padding.
+    pub fn content_rect(&self) -> Rect {
+        self.padding_rect().inset(&self.padding)
+    }
+
+    // Layout places each fragment relative to its parent's border box.  This moves the
+    // descendants so that their positions have the same origin as this fragment's.
+    pub(crate) fn make_absolute(&mut self) {
+        let (x, y) = (self.rect.x, self.rect.y);
+        for child in &mut self.children {
+            child.rect = child.rect.translate(x, y);
+            child.make_absolute();
+        }
+    }
+}
+
+/// The fragments of a laid out document.
+#[derive(Clone, Debug)]
+pub struct FragmentTree {
+    /// The fragment of the root element, or None if it has no box.
+    pub root: Option<Fragment>,
+    /// The initial containing block, the size of the viewport.
+    pub viewport: Rect,
+}
+
+impl FragmentTree {
+    /// A dump of the fragments, one per line indented by its depth as in the html5lib tree
+    /// format.  Each line has the kind of fragment, its element or text, and the position
+    /// and size of its border box.
+    pub fn dump(&self, arena: &Arena) -> String {
+        let mut dump = String::new();
+        if let Some(root) = &self.root {
+            dump_fragment(arena, root, 0, &mut dump);
+        }
+        dump
+    }
+}
+
+fn dump_fragment(arena: &Arena, fragment: &Fragment, depth: usize, dump: &mut String) {
+    dump.push_str("| ");
+    dump.push_str(&"  ".repeat(depth));
+
+    match &fragment.kind {
+        FragmentKind::Block => dump.push_str("block"),
+        FragmentKind::InlineBlock => dump.push_str("inline-block"),
+        FragmentKind::Inline => dump.push_str("inline"),
+        FragmentKind::Replaced => dump.push_str("replaced"),
+        FragmentKind::Line => dump.push_str("line"),
+        FragmentKind::Text(text) => {
+            let _ = write!(dump, "\"{text}\"");
+        }
+    }
+    match fragment.node.and_then(|id| arena.node(id).element_name()) {
+        Some(name) => {
+            let _ = write!(dump, " <{}>", dom::dump_name(name));
+        }
+        None if fragment.kind == FragmentKind::Block => dump.push_str(" (anonymous)"),
+        None => {}
+    }
+
+    let Rect {
+        x,
+        y,
+        width,
+        height,
+    } = fragment.rect;
+    let _ = writeln!(
+        dump,
+        " {},{} {}x{}",
+        Number(x),
+        Number(y),
+        Number(width),
+        Number(height)
+    );
+
+    for child in &fragment.children {
+        dump_fragment(arena, child, depth + 1, dump);
+    }
+}
@@ -0,0 +1,895 @@
+//! Inline layout: breaking the inline-level content of a block container into lines, and
+//! aligning it in them.
+//! See https://www.w3.org/TR/CSS2/visuren.html#inline-formatting and
+//! https://www.w3.org/TR/CSS2/visudet.html#line-height
+use super::block::{self, AtomicLayout};
+use super::box_tree::{BoxKind, LayoutBox};
+use super::fragment::{Fragment, FragmentKind, Rect};
+use super::text;
+use super::text::line_break::{self, Break};
+use super::LayoutContext;
+use crate::style::values::{
+    Sides, TextAlign, VerticalAlign, VerticalAlignKeyword, WhiteSpace, WordBreak,
+};
+use crate::style::ComputedStyle;
+use std::ops::Range;
+use std::rc::Rc;
+
+/// The lines of a block container, placed from the top left of its content box.
+pub(crate) struct InlineLayout {
+    pub lines: Vec<Fragment>,
+    pub height: f32,
+    /// The baseline of the last line, from the top of the content box.
+    pub baseline: Option<f32>,
+}
+
+// An inline box, which may be broken across lines
+struct InlineBox<'a> {
+    layout_box: &'a LayoutBox,
+    parent: Option<usize>,
+    margin: Sides<f32>,
+    padding: Sides<f32>,
+    border: Sides<f32>,
+}
+
+impl InlineBox<'_> {
+    fn start(&self) -> f32 {
+        self.margin.left + self.border.left + self.padding.left
+    }
+
+    fn end(&self) -> f32 {
+        self.margin.right + self.border.right + self.padding.right
+    }
+}
+
+// What atomic inlines are in the text of a line when finding where it may break
+const OBJECT_REPLACEMENT: char = '\u{FFFC}';
+
+fn is_newline(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\x0B' | '\x0C' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+// The inline-level content of a block container, in order
+enum Piece<'a> {
+    Start(usize),
+    End(usize),
+    /// Text up to a soft wrap opportunity, or up to the end of its box.  Trailing spaces that
+    /// hang at the end of a line do not count towards its width there.
+    Text {
+        text: String,
+        layout_box: &'a LayoutBox,
+        width: f32,
+        hang: f32,
+        break_after: bool,
+    },
+    /// An inline-block or replaced element, with its min-content and max-content widths.
+    Atomic {
+        layout_box: &'a LayoutBox,
+        widths: (f32, f32),
+        layout: Option<AtomicLayout>,
+    },
+    ForcedBreak,
+}
+
+// Content between two places a line may break, with the pieces it ends at
+struct Unit {
+    end: usize,
+    width: f32,
+    hang: f32,
+    forced: bool,
+}
+
+struct Collector<'a, 'c> {
+    cx: &'c LayoutContext,
+    /// The width of the lines, or None when only measuring intrinsic widths.
+    available: Option<f32>,
+    boxes: Vec<InlineBox<'a>>,
+    pieces: Vec<Piece<'a>>,
+    after_space: bool,
+}
+
+impl<'a, 'c> Collector<'a, 'c> {
+    fn new(cx: &'c LayoutContext, layout_box: &'a LayoutBox, available: Option<f32>) -> Self {
+        let mut collector = Collector {
+            cx,
+            available,
+            boxes: vec![],
+            pieces: vec![],
+            after_space: true,
+        };
+        collector.collect(layout_box, None);
+        collector.segment();
+        collector
+    }
+
+    fn collect(&mut self, layout_box: &'a LayoutBox, parent: Option<usize>) {
+        for child in &layout_box.children {
+            match &child.kind {
+                BoxKind::Text(text) => {
+                    let text = text::collapse(text, child.style.white_space, &mut self.after_space);
+                    if !text.is_empty() {
+                        self.pieces.push(Piece::Text {
+                            text,
+                            layout_box: child,
+                            width: 0.0,
+                            hang: 0.0,
+                            break_after: false,
+                        });
+                    }
+                }
+                BoxKind::Inline => {
+                    let cb_width = self.available.unwrap_or(0.0);
+                    let (padding, border) = block::edges(&child.style, cb_width);
+                    let index = self.boxes.len();
+                    self.boxes.push(InlineBox {
+                        layout_box: child,
+                        parent,
+                        margin: block::margins(&child.style, cb_width),
+                        padding,
+                        border,
+                    });
+                    self.pieces.push(Piece::Start(index));
+                    self.collect(child, Some(index));
+                    self.pieces.push(Piece::End(index));
+                }
+                BoxKind::LineBreak => {
+                    self.pieces.push(Piece::ForcedBreak);
+                    self.after_space = true;
+                }
+                BoxKind::InlineBlock | BoxKind::Replaced(_) => {
+                    let (widths, layout) = match self.available {
+                        Some(available) => {
+                            let layout = block::layout_atomic(self.cx, child, available);
+                            ((layout.width, layout.width), Some(layout))
+                        }
+                        None => (block::intrinsic_widths(self.cx, child), None),
+                    };
+                    self.pieces.push(Piece::Atomic {
+                        layout_box: child,
+                        widths,
+                        layout,
+                    });
+                    self.after_space = false;
+                }
+                BoxKind::Block => {}
+            }
+        }
+    }
+
+    // Finds where lines may break in the content, as the Unicode line breaking algorithm says
+    // for all of it as one string, and splits text there
+    fn segment(&mut self) {
+        let mut contents = String::new();
+        let mut word_breaks = vec![];
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text {
+                    text, layout_box, ..
+                } => {
+                    word_breaks.push((contents.len(), layout_box.style.word_break));
+                    contents.push_str(text);
+                }
+                Piece::Atomic { .. } => {
+                    word_breaks.push((contents.len(), WordBreak::Normal));
+                    contents.push(OBJECT_REPLACEMENT);
+                }
+                Piece::ForcedBreak => {
+                    word_breaks.push((contents.len(), WordBreak::Normal));
+                    contents.push('\n');
+                }
+                Piece::Start(_) | Piece::End(_) => {}
+            }
+        }
+        let mut breaks = line_break::line_breaks_by(&contents, |offset| {
+            let i = word_breaks.partition_point(|&(start, _)| start <= offset);
+            word_breaks[i - 1].1
+        });
+        // The end of the content is not a break between lines
+        breaks.pop();
+
+        let mut offset = 0;
+        for piece in std::mem::take(&mut self.pieces) {
+            match piece {
+                Piece::Text {
+                    text, layout_box, ..
+                } => {
+                    let end = offset + text.len();
+                    let breaks = &breaks[breaks.partition_point(|&(at, _)| at <= offset)
+                        ..breaks.partition_point(|&(at, _)| at <= end)];
+                    self.push_text(&text, layout_box, offset, breaks);
+                    offset = end;
+                }
+                Piece::Atomic { .. } => {
+                    offset += OBJECT_REPLACEMENT.len_utf8();
+                    self.pieces.push(piece);
+                }
+                Piece::ForcedBreak => {
+                    offset += 1;
+                    self.pieces.push(piece);
+                }
+                Piece::Start(_) | Piece::End(_) => self.pieces.push(piece),
+            }
+        }
+    }
+
+    // Pushes text that starts at an offset in the content, split at the breaks in it
+    fn push_text(
+        &mut self,
+        contents: &str,
+        layout_box: &'a LayoutBox,
+        offset: usize,
+        breaks: &[(usize, Break)],
+    ) {
+        let white_space = layout_box.style.white_space;
+        let wraps = text::wraps(white_space);
+        let mut breaks: Vec<(usize, Break)> = breaks
+            .iter()
+            .map(|&(at, kind)| (at - offset, kind))
+            .filter(|&(_, kind)| wraps || kind == Break::Mandatory)
+            .collect();
+        // Lines may also break after every space that is kept
+        if wraps && white_space == WhiteSpace::BreakSpaces {
+            breaks.extend(
+                contents
+                    .match_indices(' ')
+                    .map(|(i, _)| (i + 1, Break::Allowed)),
+            );
+            breaks.sort_by_key(|&(at, kind)| (at, kind == Break::Allowed));
+            breaks.dedup_by_key(|&mut (at, _)| at);
+        }
+
+        let mut start = 0;
+        for (end, kind) in breaks {
+            let word = &contents[start..end];
+            start = end;
+            if kind == Break::Mandatory {
+                let word = word.trim_end_matches(is_newline);
+                if !word.is_empty() {
+                    self.pieces.push(self.text_piece(word, layout_box, false));
+                }
+                self.pieces.push(Piece::ForcedBreak);
+            } else {
+                self.pieces.push(self.text_piece(word, layout_box, true));
+            }
+        }
+        if start < contents.len() {
+            self.pieces
+                .push(self.text_piece(&contents[start..], layout_box, false));
+        }
+    }
+
+    fn text_piece(&self, text: &str, layout_box: &'a LayoutBox, break_after: bool) -> Piece<'a> {
+        let style = &layout_box.style;
+        let spaces = if style.white_space == WhiteSpace::BreakSpaces {
+            ""
+        } else {
+            &text[text.trim_end_matches(' ').len()..]
+        };
+        Piece::Text {
+            text: text.to_owned(),
+            layout_box,
+            width: self.cx.metrics.text_width(text, style.font_size),
+            hang: self.cx.metrics.text_width(spaces, style.font_size),
+            break_after,
+        }
+    }
+
+    // Splits a text piece after the last cluster of characters that fits in the width, or
+    // after the first if none does, returning whether there was more than one
+    fn split_text(&mut self, i: usize, width: f32) -> bool {
+        let Piece::Text {
+            text,
+            layout_box,
+            break_after,
+            ..
+        } = &self.pieces[i]
+        else {
+            return false;
+        };
+        let font_size = layout_box.style.font_size;
+        let mut at = None;
+        for end in line_break::cluster_boundaries(text).into_iter().skip(1) {
+            let fits = self
+                .cx
+                .metrics
+                .text_width(text[..end].trim_end_matches(' '), font_size)
+                <= width;
+            if !fits && at.is_some() {
+                break;
+            }
+            at = Some(end);
+        }
+        let Some(at) = at else {
+            return false;
+        };
+
+        let (layout_box, break_after) = (*layout_box, *break_after);
+        let (head, tail) = text.split_at(at);
+        let (head, tail) = (
+            self.text_piece(head, layout_box, true),
+            self.text_piece(tail, layout_box, break_after),
+        );
+        self.pieces[i] = head;
+        self.pieces.insert(i + 1, tail);
+        true
+    }
+
+    // Splits the first text in the content from a piece up to the next place a line may break
+    // that overflows the width, if its style allows, returning whether it did
+    fn split_overflow(&mut self, start: usize, available: f32) -> bool {
+        let mut x = 0.0;
+        for i in start..self.pieces.len() {
+            match &self.pieces[i] {
+                Piece::Start(index) => x += self.boxes[*index].start(),
+                Piece::End(index) => x += self.boxes[*index].end(),
+                Piece::Text {
+                    layout_box,
+                    width,
+                    hang,
+                    break_after,
+                    ..
+                } => {
+                    if x + width - hang > available {
+                        return text::overflow_wraps(&layout_box.style)
+                            && self.split_text(i, available - x);
+                    }
+                    if *break_after {
+                        return false;
+                    }
+                    x += width;
+                }
+                Piece::Atomic { .. } | Piece::ForcedBreak => return false,
+            }
+        }
+        false
+    }
+
+    // The content from a piece up to the next place a line may break, along with the ends of
+    // inline boxes there.  `max` measures atomic inlines at their max-content width.
+    fn unit(&self, start: usize, max: bool) -> Unit {
+        let mut unit = Unit {
+            end: start,
+            width: 0.0,
+            hang: 0.0,
+            forced: false,
+        };
+        while unit.end < self.pieces.len() {
+            let piece = &self.pieces[unit.end];
+            unit.end += 1;
+            match piece {
+                Piece::Start(index) => {
+                    unit.width += self.boxes[*index].start();
+                    unit.hang = 0.0;
+                }
+                Piece::End(index) => unit.width += self.boxes[*index].end(),
+                Piece::Text {
+                    width,
+                    hang,
+                    break_after,
+                    ..
+                } => {
+                    unit.width += width;
+                    unit.hang = *hang;
+                    if *break_after {
+                        break;
+                    }
+                }
+                Piece::Atomic { widths, .. } => {
+                    unit.width += if max { widths.1 } else { widths.0 };
+                    unit.hang = 0.0;
+                    break;
+                }
+                Piece::ForcedBreak => {
+                    unit.forced = true;
+                    break;
+                }
+            }
+        }
+        while let Some(Piece::End(index)) = self.pieces.get(unit.end) {
+            unit.width += self.boxes[*index].end();
+            unit.end += 1;
+        }
+        unit
+    }
+
+    // Breaks the pieces into lines greedily, at the last place each may break before it
+    // overflows, splitting text that overflows on its own where its style allows
+    fn break_lines(&mut self, available: f32) -> Vec<Range<usize>> {
+        let mut lines = vec![];
+        let (mut start, mut i, mut x) = (0, 0, 0.0);
+        while i < self.pieces.len() {
+            let mut unit = self.unit(i, true);
+            if i > start && x + unit.width - unit.hang > available {
+                lines.push(start..i);
+                (start, x) = (i, 0.0);
+            }
+            // Content too wide for a line of its own may break within words
+            if x + unit.width - unit.hang > available && self.split_overflow(i, available) {
+                unit = self.unit(i, true);
+            }
+            x += unit.width;
+            i = unit.end;
+            if unit.forced {
+                lines.push(start..i);
+                (start, x) = (i, 0.0);
+            }
+        }
+        if start < self.pieces.len() {
+            lines.push(start..self.pieces.len());
+        }
+        lines
+    }
+}
+
+// How far above and below its baseline a box of the style reaches, with half the leading on
+// each side
+fn strut(cx: &LayoutContext, style: &ComputedStyle) -> (f32, f32) {
+    let font_size = style.font_size;
+    let (ascent, descent) = (
+        cx.metrics.ascent * font_size,
+        cx.metrics.descent * font_size,
+    );
+    let half_leading = (style.line_height.resolve(font_size) - ascent - descent) / 2.0;
+    (ascent + half_leading, descent + half_leading)
+}
+
+// How far `vertical-align` raises the baseline of a box above that of its parent, or None for
+// `top` and `bottom`, which align it with the line box instead
+fn baseline_shift(
+    cx: &LayoutContext,
+    style: &ComputedStyle,
+    parent: &ComputedStyle,
+    (above, below): (f32, f32),
+) -> Option<f32> {
+    let font_size = parent.font_size;
+    Some(match style.vertical_align {
+        VerticalAlign::Length(px) => px,
+        VerticalAlign::Keyword(keyword) => match keyword {
+            VerticalAlignKeyword::Baseline => 0.0,
+            VerticalAlignKeyword::Sub => -font_size / 5.0,
+            VerticalAlignKeyword::Super => font_size / 3.0,
+            VerticalAlignKeyword::TextTop => cx.metrics.ascent * font_size - above,
+            VerticalAlignKeyword::TextBottom => below - cx.metrics.descent * font_size,
+            VerticalAlignKeyword::Middle => (cx.metrics.x_height * font_size - above + below) / 2.0,
+            VerticalAlignKeyword::Top | VerticalAlignKeyword::Bottom => return None,
+        },
+    })
+}
+
+// The extent of a box aligned with the top or bottom of the line, and of its descendants
+struct Group {
+    bottom: bool,
+    above: f32,
+    below: f32,
+    shift: f32,
+}
+
+// Where the boxes of a line sit vertically: the shift of each baseline above that of the
+// line, for inline boxes by index and atomic inlines by piece
+struct LineMetrics {
+    baseline: f32,
+    height: f32,
+    boxes: Vec<f32>,
+    atomics: Vec<(usize, f32)>,
+}
+
+struct LineBuilder<'a, 'b, 'c> {
+    collector: &'b mut Collector<'a, 'c>,
+    block_style: &'a Rc<ComputedStyle>,
+    available: f32,
+}
+
+impl LineBuilder<'_, '_, '_> {
+    // See https://www.w3.org/TR/CSS2/visudet.html#line-height
+    fn metrics(&self, range: &Range<usize>, open: &[usize]) -> LineMetrics {
+        let cx = self.collector.cx;
+        let boxes = &self.collector.boxes;
+        let mut groups: Vec<Group> = vec![];
+        let mut group_of = vec![None; boxes.len()];
+        let mut shifts = vec![0.0; boxes.len()];
+        let mut atomics = vec![];
+        let (mut above, mut below) = strut(cx, self.block_style);
+
+        let mut place = |groups: &mut Vec<Group>,
+                         style: &ComputedStyle,
+                         parent: Option<usize>,
+                         shifts: &[f32],
+                         group_of: &[Option<usize>],
+                         extent: (f32, f32)| {
+            let (parent_style, parent_shift, parent_group) = match parent {
+                Some(parent) => (
+                    &*boxes[parent].layout_box.style,
+                    shifts[parent],
+                    group_of[parent],
+                ),
+                None => (&**self.block_style, 0.0, None),
+            };
+            let (shift, group) = match baseline_shift(cx, style, parent_style, extent) {
+                Some(shift) => (parent_shift + shift, parent_group),
+                None => {
+                    groups.push(Group {
+                        bottom: style.vertical_align
+                            == VerticalAlign::Keyword(VerticalAlignKeyword::Bottom),
+                        above: 0.0,
+                        below: 0.0,
+                        shift: 0.0,
+                    });
+                    (0.0, Some(groups.len() - 1))
+                }
+            };
+            match group {
+                Some(group) => {
+                    let group = &mut groups[group];
+                    group.above = group.above.max(shift + extent.0);
+                    group.below = group.below.max(extent.1 - shift);
+                }
+                None => {
+                    above = above.max(shift + extent.0);
+                    below = below.max(extent.1 - shift);
+                }
+            }
+            (shift, group)
+        };
+
+        let mut stack = open.to_vec();
+        for &index in open {
+            let style = &boxes[index].layout_box.style;
+            let extent = strut(cx, style);
+            (shifts[index], group_of[index]) = place(
+                &mut groups,
+                style,
+                boxes[index].parent,
+                &shifts,
+                &group_of,
+                extent,
+            );
+        }
+        for i in range.clone() {
+            match &self.collector.pieces[i] {
+                Piece::Start(index) => {
+                    let style = &boxes[*index].layout_box.style;
+                    let extent = strut(cx, style);
+                    (shifts[*index], group_of[*index]) = place(
+                        &mut groups,
+                        style,
+                        boxes[*index].parent,
+                        &shifts,
+                        &group_of,
+                        extent,
+                    );
+                    stack.push(*index);
+                }
+                Piece::End(_) => {
+                    stack.pop();
+                }
+                Piece::Atomic {
+                    layout_box,
+                    layout: Some(layout),
+                    ..
+                } => {
+                    let extent = (layout.baseline, layout.height - layout.baseline);
+                    let placed = place(
+                        &mut groups,
+                        &layout_box.style,
+                        stack.last().copied(),
+                        &shifts,
+                        &group_of,
+                        extent,
+                    );
+                    atomics.push((i, placed));
+                }
+                _ => {}
+            }
+        }
+
+        // Boxes aligned with the top or bottom of the line make it taller if they need to
+        for group in &groups {
+            let height = group.above + group.below;
+            if group.bottom {
+                above = above.max(height - below);
+            } else {
+                below = below.max(height - above);
+            }
+        }
+        for group in &mut groups {
+            group.shift = if group.bottom {
+                group.below - below
+            } else {
+                above - group.above
+            };
+        }
+
+        let total = |(shift, group): (f32, Option<usize>)| {
+            shift + group.map_or(0.0, |group: usize| groups[group].shift)
+        };
+        LineMetrics {
+            baseline: above,
+            height: above + below,
+            boxes: (0..boxes.len())
+                .map(|index| total((shifts[index], group_of[index])))
+                .collect(),
+            atomics: atomics
+                .into_iter()
+                .map(|(i, placed)| (i, total(placed)))
+                .collect(),
+        }
+    }
+
+    // The fragment of an inline box on a line, from its start to end x, which has the edges
+    // it started or ended with on the line
+    fn close(
+        &self,
+        index: usize,
+        mut fragment: Fragment,
+        (start, end): (f32, f32),
+        metrics: &LineMetrics,
+    ) -> Fragment {
+        let cx = self.collector.cx;
+        let style = &self.collector.boxes[index].layout_box.style;
+        let (padding, border) = (fragment.padding, fragment.border);
+        let baseline = metrics.baseline - metrics.boxes[index];
+        fragment.rect = Rect::new(
+            start,
+            baseline - cx.metrics.ascent * style.font_size - padding.top - border.top,
+            end - start,
+            (cx.metrics.ascent + cx.metrics.descent) * style.font_size
+                + padding.top
+                + padding.bottom
+                + border.top
+                + border.bottom,
+        );
+        let (x, y) = (fragment.rect.x, fragment.rect.y);
+        for child in &mut fragment.children {
+            child.rect = child.rect.translate(-x, -y);
+        }
+        fragment
+    }
+
+    // The line box of the pieces in the range, which starts inside the open inline boxes and
+    // updates them for the next line.  A line with nothing in it takes no space.
+    fn line(
+        &mut self,
+        range: Range<usize>,
+        open: &mut Vec<usize>,
+        y: f32,
+    ) -> Option<(Fragment, f32)> {
+        let cx = self.collector.cx;
+
+        // Collapsible spaces at the end of the line are removed
+        let last = range.clone().rev().find(|&i| {
+            !matches!(
+                self.collector.pieces[i],
+                Piece::Start(_) | Piece::End(_) | Piece::ForcedBreak
+            )
+        });
+        if let Some(Piece::Text {
+            text,
+            layout_box,
+            width,
+            hang,
+            ..
+        }) = last.map(|i| &mut self.collector.pieces[i])
+        {
+            if text::collapses_spaces(layout_box.style.white_space) {
+                text.truncate(text.trim_end_matches(' ').len());
+                *width -= *hang;
+                *hang = 0.0;
+            }
+        }
+
+        let pieces = &self.collector.pieces[range.clone()];
+        let boxes = &self.collector.boxes;
+        let has_content = pieces.iter().any(|piece| match piece {
+            Piece::Start(index) | Piece::End(index) => {
+                boxes[*index].start() != 0.0 || boxes[*index].end() != 0.0
+            }
+            Piece::Text { text, .. } => !text.is_empty(),
+            Piece::Atomic { .. } | Piece::ForcedBreak => true,
+        });
+        let width: f32 = pieces
+            .iter()
+            .map(|piece| match piece {
+                Piece::Start(index) => boxes[*index].start(),
+                Piece::End(index) => boxes[*index].end(),
+                Piece::Text { width, .. } => *width,
+                Piece::Atomic { widths, .. } => widths.1,
+                Piece::ForcedBreak => 0.0,
+            })
+            .sum();
+        let metrics = self.metrics(&range, open);
+
+        let free = (self.available - width).max(0.0);
+        let mut x = match self.block_style.text_align {
+            TextAlign::Start | TextAlign::Left | TextAlign::Justify => 0.0,
+            TextAlign::End | TextAlign::Right => free,
+            TextAlign::Center => free / 2.0,
+        };
+
+        // Inline boxes that continue from the last line have no start edge on this one
+        let mut stack: Vec<(usize, Fragment, f32)> = open
+            .iter()
+            .map(|&index| {
+                let inline = &boxes[index];
+                let mut fragment = Fragment::new(
+                    FragmentKind::Inline,
+                    inline.layout_box.node,
+                    inline.layout_box.style.clone(),
+                );
+                fragment.padding = Sides {
+                    left: 0.0,
+                    ..inline.padding
+                };
+                fragment.border = Sides {
+                    left: 0.0,
+                    ..inline.border
+                };
+                (index, fragment, x)
+            })
+            .collect();
+        let mut children = vec![];
+
+        for i in range {
+            let fragment = match &mut self.collector.pieces[i] {
+                Piece::Start(index) => {
+                    let inline = &self.collector.boxes[*index];
+                    let mut fragment = Fragment::new(
+                        FragmentKind::Inline,
+                        inline.layout_box.node,
+                        inline.layout_box.style.clone(),
+                    );
+                    fragment.padding = inline.padding;
+                    fragment.border = inline.border;
+                    x += inline.margin.left;
+                    stack.push((*index, fragment, x));
+                    x += inline.border.left + inline.padding.left;
+                    open.push(*index);
+                    continue;
+                }
+                Piece::End(_) => {
+                    let Some((index, fragment, start)) = stack.pop() else {
+                        continue;
+                    };
+                    let inline = &self.collector.boxes[index];
+                    x += inline.padding.right + inline.border.right;
+                    let margin = inline.margin.right;
+                    let fragment = self.close(index, fragment, (start, x), &metrics);
+                    x += margin;
+                    open.pop();
+                    fragment
+                }
+                Piece::Text { text, .. } if text.is_empty() => continue,
+                Piece::Text {
+                    text,
+                    layout_box,
+                    width,
+                    ..
+                } => {
+                    let style = &layout_box.style;
+                    let shift = stack
+                        .last()
+                        .map_or(0.0, |(index, ..)| metrics.boxes[*index]);
+                    let mut fragment = Fragment::new(
+                        FragmentKind::Text(text.clone()),
+                        layout_box.node,
+                        style.clone(),
+                    );
+                    fragment.rect = Rect::new(
+                        x,
+                        metrics.baseline - shift - cx.metrics.ascent * style.font_size,
+                        *width,
+                        (cx.metrics.ascent + cx.metrics.descent) * style.font_size,
+                    );
+                    x += *width;
+                    fragment
+                }
+                Piece::Atomic { layout, .. } => {
+                    let Some(layout) = layout.take() else {
+                        continue;
+                    };
+                    let shift = metrics
+                        .atomics
+                        .iter()
+                        .find(|(piece, _)| *piece == i)
+                        .map_or(0.0, |(_, shift)| *shift);
+                    let mut fragment = layout.fragment;
+                    fragment.rect = fragment
+                        .rect
+                        .translate(x, metrics.baseline - shift - layout.baseline);
+                    x += layout.width;
+                    fragment
+                }
+                Piece::ForcedBreak => continue,
+            };
+            match stack.last_mut() {
+                Some((_, parent, _)) => parent.children.push(fragment),
+                None => children.push(fragment),
+            }
+        }
+
+        // Inline boxes that continue on the next line have no end edge on this one
+        while let Some((index, mut fragment, start)) = stack.pop() {
+            fragment.padding.right = 0.0;
+            fragment.border.right = 0.0;
+            let fragment = self.close(index, fragment, (start, x), &metrics);
+            match stack.last_mut() {
+                Some((_, parent, _)) => parent.children.push(fragment),
+                None => children.push(fragment),
+            }
+        }
+
+        if !has_content {
+            return None;
+        }
+        let mut line = Fragment::new(FragmentKind::Line, None, self.block_style.clone());
+        line.rect = Rect::new(0.0, y, self.available, metrics.height);
+        line.children = children;
+        Some((line, metrics.baseline))
+    }
+}
+
+/// Lays out the inline-level children of a block container in lines of the given width.
+pub(crate) fn layout(cx: &LayoutContext, layout_box: &LayoutBox, available: f32) -> InlineLayout {
+    let mut collector = Collector::new(cx, layout_box, Some(available));
+    let ranges = collector.break_lines(available);
+
+    let mut builder = LineBuilder {
+        collector: &mut collector,
+        block_style: &layout_box.style,
+        available,
+    };
+    let mut open = vec![];
+    let mut lines = vec![];
+    let (mut height, mut baseline) = (0.0, None);
+    for range in ranges {
+        if let Some((line, line_baseline)) = builder.line(range, &mut open, height) {
+            baseline = Some(height + line_baseline);
+            height += line.rect.height;
+            lines.push(line);
+        }
+    }
+
+    InlineLayout {
+        lines,
+        height,
+        baseline,
+    }
+}
+
+/// The min-content and max-content widths of the inline-level children of a block container.
+pub(crate) fn intrinsic_widths(cx: &LayoutContext, layout_box: &LayoutBox) -> (f32, f32) {
+    let mut collector = Collector::new(cx, layout_box, None);
+
+    let (mut max, mut line) = (0.0_f32, 0.0);
+    let mut i = 0;
+    while i < collector.pieces.len() {
+        let unit = collector.unit(i, true);
+        line += unit.width;
+        max = max.max(line - unit.hang);
+        if unit.forced {
+            line = 0.0;
+        }
+        i = unit.end;
+    }
+
+    // Text that may break anywhere is as narrow as its widest cluster of characters
+    let mut i = 0;
+    while i < collector.pieces.len() {
+        if let Piece::Text { layout_box, .. } = &collector.pieces[i] {
+            if text::overflow_wraps_anywhere(&layout_box.style) {
+                collector.split_text(i, 0.0);
+            }
+        }
+        i += 1;
+    }
+    let mut min = 0.0_f32;
+    let mut i = 0;
+    while i < collector.pieces.len() {
+        let unit = collector.unit(i, false);
+        min = min.max(unit.width - unit.hang);
+        i = unit.end;
+    }
+    (min, max)
+}
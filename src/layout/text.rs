@@ -0,0 +1,114 @@
+//! Text measurement, white space processing and where lines may wrap.
+//! See https://www.w3.org/TR/css-text-3/#white-space-processing
+use crate::style::values::{OverflowWrap, WhiteSpace, WordBreak};
+use crate::style::ComputedStyle;
+
+pub mod line_break;
+
+/// The metrics of the one font layout uses, in ems, so that it does not depend on the fonts
+/// of the system.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FontMetrics {
+    /// The advance of every visible character.
+    pub advance: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    pub x_height: f32,
+}
+
+impl FontMetrics {
+    /// The metrics of the Ahem test font, whose glyphs are boxes one em square that reach
+    /// 0.2em below the baseline.
+    pub const AHEM: Self = Self {
+        advance: 1.0,
+        ascent: 0.8,
+        descent: 0.2,
+        x_height: 0.8,
+    };
+
+    pub fn char_width(&self, c: char, font_size: f32) -> f32 {
+        match c {
+            // Tab stops are every eight spaces
+            '\t' => 8.0 * self.advance * font_size,
+            '\u{00AD}' | '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' => 0.0,
+            _ if c.is_control() => 0.0,
+            _ => self.advance * font_size,
+        }
+    }
+
+    pub fn text_width(&self, text: &str, font_size: f32) -> f32 {
+        text.chars().map(|c| self.char_width(c, font_size)).sum()
+    }
+}
+
+/// Whether sequences of spaces and tabs collapse to a single space.
+pub fn collapses_spaces(white_space: WhiteSpace) -> bool {
+    matches!(
+        white_space,
+        WhiteSpace::Normal | WhiteSpace::Nowrap | WhiteSpace::PreLine
+    )
+}
+
+/// Whether newlines are forced line breaks rather than spaces.
+pub fn preserves_newlines(white_space: WhiteSpace) -> bool {
+    !matches!(white_space, WhiteSpace::Normal | WhiteSpace::Nowrap)
+}
+
+/// Whether lines may wrap at soft wrap opportunities.
+pub fn wraps(white_space: WhiteSpace) -> bool {
+    !matches!(white_space, WhiteSpace::Nowrap | WhiteSpace::Pre)
+}
+
+/// Collapses the white space of text as its `white-space` says, leaving newlines only where
+/// they are forced line breaks.  `after_space` says whether the text follows a collapsible
+/// space, which it then leaves out, and is updated for the text that follows.
+pub fn collapse(text: &str, white_space: WhiteSpace, after_space: &mut bool) -> String {
+    let collapses = collapses_spaces(white_space);
+    let newlines = preserves_newlines(white_space);
+    let mut collapsed = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        let c = match c {
+            '\r' | '\x0C' if collapses => ' ',
+            '\n' if !newlines => ' ',
+            '\t' if collapses => ' ',
+            _ => c,
+        };
+        if c == '\n' {
+            // Spaces around a preserved newline are removed
+            if collapses {
+                while collapsed.ends_with(' ') {
+                    collapsed.pop();
+                }
+            }
+            collapsed.push('\n');
+            *after_space = collapses;
+            continue;
+        }
+        if c == ' ' && collapses {
+            if !*after_space {
+                collapsed.push(' ');
+            }
+            *after_space = true;
+            continue;
+        }
+        collapsed.push(c);
+        *after_space = false;
+    }
+
+    collapsed
+}
+
+/// Whether a word too long for a line may break where it otherwise could not, as
+/// `overflow-wrap` and `word-break: break-word` say.
+pub fn overflow_wraps(style: &ComputedStyle) -> bool {
+    wraps(style.white_space)
+        && (style.overflow_wrap != OverflowWrap::Normal || style.word_break == WordBreak::BreakWord)
+}
+
+/// Whether those breaks count when finding the min-content width.
+pub fn overflow_wraps_anywhere(style: &ComputedStyle) -> bool {
+    wraps(style.white_space)
+        && (style.overflow_wrap == OverflowWrap::Anywhere
+            || style.word_break == WordBreak::BreakWord)
+}
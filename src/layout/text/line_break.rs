@@ -0,0 +1,344 @@
+//! The Unicode line breaking algorithm, which finds where lines may break in text, tailored for
+//! `word-break` as CSS says.
+//! See https://www.unicode.org/reports/tr14/tr14-49.html and
+//! https://www.w3.org/TR/css-text-3/#word-break-property
+use crate::style::values::WordBreak;
+use unicode_linebreak::{break_property, BreakClass};
+
+use BreakClass::{
+    After as BA, Alphabetic as AL, Ambiguous as AI, Before as BB, BeforeAndAfter as B2,
+    CarriageReturn as CR, CloseParenthesis as CP, ClosePunctuation as CL, CombiningMark as CM,
+    ComplexContext as SA, ConditionalJapaneseStarter as CJ, Contingent as CB, EmojiBase as EB,
+    EmojiModifier as EM, Exclamation as EX, HangulLJamo as JL, HangulLvSyllable as H2,
+    HangulLvtSyllable as H3, HangulTJamo as JT, HangulVJamo as JV, HebrewLetter as HL,
+    Hyphen as HY, Ideographic as ID, InfixSeparator as IS, Inseparable as IN, LineFeed as LF,
+    Mandatory as BK, NextLine as NL, NonBreakingGlue as GL, NonStarter as NS, Numeric as NU,
+    OpenPunctuation as OP, Postfix as PO, Prefix as PR, Quotation as QU, RegionalIndicator as RI,
+    Space as SP, Surrogate as SG, Symbol as SY, Unknown as XX, WordJoiner as WJ,
+    ZeroWidthJoiner as ZWJ, ZeroWidthSpace as ZW,
+};
+
+/// The version of Unicode whose data and rules this follows.
+pub const UNICODE_VERSION: (u8, u8, u8) = unicode_linebreak::UNICODE_VERSION;
+
+// The nonspacing and spacing marks of class SA, which LB1 resolves to CM.  From
+// LineBreak-15.0.0.txt.
+const SA_MARKS: &[(u32, u32)] = &[
+    (0x0E31, 0x0E31),
+    (0x0E34, 0x0E3A),
+    (0x0E47, 0x0E4E),
+    (0x0EB1, 0x0EB1),
+    (0x0EB4, 0x0EBC),
+    (0x0EC8, 0x0ECE),
+    (0x102B, 0x103E),
+    (0x1056, 0x1059),
+    (0x105E, 0x1060),
+    (0x1062, 0x1064),
+    (0x1067, 0x106D),
+    (0x1071, 0x1074),
+    (0x1082, 0x108D),
+    (0x108F, 0x108F),
+    (0x109A, 0x109D),
+    (0x17B4, 0x17D3),
+    (0x17DD, 0x17DD),
+    (0x1A55, 0x1A5E),
+    (0x1A60, 0x1A7C),
+    (0xA9E5, 0xA9E5),
+    (0xAA7B, 0xAA7D),
+    (0xAAB0, 0xAAB0),
+    (0xAAB2, 0xAAB4),
+    (0xAAB7, 0xAAB8),
+    (0xAABE, 0xAABF),
+    (0xAAC1, 0xAAC1),
+    (0x1171D, 0x1172B),
+];
+
+// The opening punctuation that is fullwidth, wide or halfwidth, which LB30 leaves out
+const WIDE_OPENING: &[(u32, u32)] = &[
+    (0x2329, 0x2329),
+    (0x3008, 0x3008),
+    (0x300A, 0x300A),
+    (0x300C, 0x300C),
+    (0x300E, 0x300E),
+    (0x3010, 0x3010),
+    (0x3014, 0x3014),
+    (0x3016, 0x3016),
+    (0x3018, 0x3018),
+    (0x301A, 0x301A),
+    (0x301D, 0x301D),
+    (0xFE17, 0xFE17),
+    (0xFE35, 0xFE35),
+    (0xFE37, 0xFE37),
+    (0xFE39, 0xFE39),
+    (0xFE3B, 0xFE3B),
+    (0xFE3D, 0xFE3D),
+    (0xFE3F, 0xFE3F),
+    (0xFE41, 0xFE41),
+    (0xFE43, 0xFE43),
+    (0xFE47, 0xFE47),
+    (0xFE59, 0xFE59),
+    (0xFE5B, 0xFE5B),
+    (0xFE5D, 0xFE5D),
+    (0xFF08, 0xFF08),
+    (0xFF3B, 0xFF3B),
+    (0xFF5B, 0xFF5B),
+    (0xFF5F, 0xFF5F),
+    (0xFF62, 0xFF62),
+    (0x1343C, 0x1343C),
+    (0x1343E, 0x1343E),
+];
+
+// The unassigned code points that are Extended_Pictographic, which LB30b keeps with a
+// following emoji modifier.  From LineBreak-15.0.0.txt and emoji-data.txt.
+const UNASSIGNED_PICTOGRAPHIC: &[(u32, u32)] = &[
+    (0x1F02C, 0x1F02F),
+    (0x1F094, 0x1F09F),
+    (0x1F0AF, 0x1F0B0),
+    (0x1F0C0, 0x1F0C0),
+    (0x1F0D0, 0x1F0D0),
+    (0x1F0F6, 0x1F0FF),
+    (0x1F1AE, 0x1F1E5),
+    (0x1F203, 0x1F20F),
+    (0x1F23C, 0x1F23F),
+    (0x1F249, 0x1F24F),
+    (0x1F252, 0x1F25F),
+    (0x1F266, 0x1F2FF),
+    (0x1F6D8, 0x1F6DB),
+    (0x1F6ED, 0x1F6EF),
+    (0x1F6FD, 0x1F6FF),
+    (0x1F777, 0x1F77A),
+    (0x1F7DA, 0x1F7DF),
+    (0x1F7EC, 0x1F7EF),
+    (0x1F7F1, 0x1F7FF),
+    (0x1F80C, 0x1F80F),
+    (0x1F848, 0x1F84F),
+    (0x1F85A, 0x1F85F),
+    (0x1F888, 0x1F88F),
+    (0x1F8AE, 0x1F8AF),
+    (0x1F8B2, 0x1F8FF),
+    (0x1FA54, 0x1FA5F),
+    (0x1FA6E, 0x1FA6F),
+    (0x1FA7D, 0x1FA7F),
+    (0x1FA89, 0x1FA8F),
+    (0x1FABE, 0x1FABE),
+    (0x1FAC6, 0x1FACD),
+    (0x1FADC, 0x1FADF),
+    (0x1FAE9, 0x1FAEF),
+    (0x1FAF9, 0x1FAFF),
+    (0x1FC00, 0x1FFFD),
+];
+
+fn in_ranges(ranges: &[(u32, u32)], c: char) -> bool {
+    let c = c as u32;
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if end < c {
+                std::cmp::Ordering::Less
+            } else if start > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Break {
+    /// A soft wrap opportunity.
+    Allowed,
+    /// A forced line break, after a newline or similar.
+    Mandatory,
+}
+
+/// The line breaking class of a character, resolved as LB1 says.
+pub fn break_class(c: char) -> BreakClass {
+    match break_property(c as u32) {
+        AI | SG | XX => AL,
+        SA if in_ranges(SA_MARKS, c) => CM,
+        SA => AL,
+        CJ => NS,
+        class => class,
+    }
+}
+
+// A character with the combining marks and joiners after it, which LB9 treats as one
+#[derive(Clone, Copy, Debug)]
+struct Cluster {
+    start: usize,
+    /// The class of its first character, resolved as LB9 and LB10 say.
+    class: BreakClass,
+    /// The class of its last character.
+    last: BreakClass,
+    first: char,
+    keep_all: bool,
+}
+
+// Whether a class is of a letter or ideograph, which `word-break: keep-all` keeps together
+fn is_letter(class: BreakClass) -> bool {
+    matches!(class, AL | HL | NU | ID | H2 | H3 | JL | JV | JT)
+}
+
+fn clusters(text: &str, word_break: &impl Fn(usize) -> WordBreak) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = vec![];
+    for (i, c) in text.char_indices() {
+        let style = word_break(i);
+        let class = match break_class(c) {
+            // Breaks are allowed within words, as between ideographs
+            AL | HL | NU if style == WordBreak::BreakAll => ID,
+            class => class,
+        };
+
+        if let (CM | ZWJ, Some(cluster)) = (class, clusters.last_mut()) {
+            if !matches!(cluster.class, BK | CR | LF | NL | SP | ZW) {
+                cluster.last = class;
+                continue;
+            }
+        }
+        clusters.push(Cluster {
+            start: i,
+            class: if matches!(class, CM | ZWJ) { AL } else { class },
+            last: class,
+            first: c,
+            keep_all: style == WordBreak::KeepAll,
+        });
+    }
+    clusters
+}
+
+// Whether a numeric expression as in LB25 ends at the cluster: a number followed by numbers,
+// symbols and infix separators
+fn ends_number(clusters: &[Cluster], mut i: usize) -> bool {
+    loop {
+        match clusters[i].class {
+            NU => return true,
+            SY | IS if i > 0 => i -= 1,
+            _ => return false,
+        }
+    }
+}
+
+// Whether a line may break between two clusters, or must.  See the rules of
+// https://www.unicode.org/reports/tr14/tr14-49.html#Algorithm, with numbers tailored as in
+// example 7 of section 8.2, as the conformance tests do.
+fn pair(clusters: &[Cluster], i: usize) -> Option<Break> {
+    let (before, after) = (&clusters[i - 1], &clusters[i]);
+    let (a, b) = (before.class, after.class);
+    // The class before any spaces before the break
+    let before_spaces = clusters[..i]
+        .iter()
+        .rev()
+        .find(|cluster| cluster.class != SP)
+        .map(|cluster| cluster.class);
+
+    // LB4 - LB6
+    match (before.last, b) {
+        (CR, LF) => return None,
+        (BK | CR | LF | NL, _) => return Some(Break::Mandatory),
+        (_, BK | CR | LF | NL) => return None,
+        _ => {}
+    }
+    // LB7 - LB8a
+    if matches!(b, SP | ZW) {
+        return None;
+    }
+    if before_spaces == Some(ZW) {
+        return Some(Break::Allowed);
+    }
+    if before.last == ZWJ {
+        return None;
+    }
+
+    let next = clusters.get(i + 1).map(|cluster| cluster.class);
+    let no_break = match (a, b) {
+        // LB11 - LB12a
+        (WJ, _) | (_, WJ) | (GL, _) => true,
+        (_, GL) if !matches!(a, SP | BA | HY) => true,
+        // LB13
+        (_, CL | CP | EX | IS | SY) => true,
+        // LB14 - LB17
+        _ if before_spaces == Some(OP) => true,
+        (_, OP) if before_spaces == Some(QU) => true,
+        (_, NS) if matches!(before_spaces, Some(CL | CP)) => true,
+        (_, B2) if before_spaces == Some(B2) => true,
+        // LB18
+        (SP, _) => false,
+        // LB19 - LB20
+        (QU, _) | (_, QU) => true,
+        (CB, _) | (_, CB) => false,
+        // LB21 - LB22
+        (_, BA | HY | NS) | (BB, _) => true,
+        (HY | BA, _) if i >= 2 && clusters[i - 2].class == HL => true,
+        (SY, HL) => true,
+        (_, IN) => true,
+        // LB23 - LB24
+        (AL | HL, NU) | (NU, AL | HL) => true,
+        (PR, ID | EB | EM) | (ID | EB | EM, PO) => true,
+        (PR | PO, AL | HL) | (AL | HL, PR | PO) => true,
+        // LB25
+        (PR | PO, NU) | (OP | HY, NU) => true,
+        (PR | PO, OP) if next == Some(NU) => true,
+        (_, NU) if ends_number(clusters, i - 1) => true,
+        (_, PO | PR) if ends_number(clusters, i - 1) => true,
+        (CL | CP, PO | PR) if i >= 2 && ends_number(clusters, i - 2) => true,
+        // LB26 - LB27
+        (JL, JL | JV | H2 | H3) | (JV | H2, JV | JT) | (JT | H3, JT) => true,
+        (JL | JV | JT | H2 | H3, PO) | (PR, JL | JV | JT | H2 | H3) => true,
+        // LB28 - LB29
+        (AL | HL, AL | HL) | (IS, AL | HL) => true,
+        // LB30
+        (AL | HL | NU, OP) => !in_ranges(WIDE_OPENING, after.first),
+        (CP, AL | HL | NU) => true,
+        // LB30a: regional indicators pair up
+        (RI, RI) => {
+            let run = clusters[..i]
+                .iter()
+                .rev()
+                .take_while(|cluster| cluster.class == RI)
+                .count();
+            run % 2 == 1
+        }
+        // LB30b
+        (EB, EM) => true,
+        (_, EM) => in_ranges(UNASSIGNED_PICTOGRAPHIC, before.first),
+        _ => false,
+    };
+
+    if no_break || (before.keep_all && is_letter(a) && is_letter(b)) {
+        None
+    } else {
+        Some(Break::Allowed)
+    }
+}
+
+/// The places a line may or must break in text, as the byte offsets after them, in order.  The
+/// end of the text is always a mandatory break.
+pub fn line_breaks(text: &str) -> Vec<(usize, Break)> {
+    line_breaks_by(text, |_| WordBreak::Normal)
+}
+
+/// The places a line may or must break in text, where `word_break` gives the `word-break` of
+/// the character at each byte offset.
+pub fn line_breaks_by(text: &str, word_break: impl Fn(usize) -> WordBreak) -> Vec<(usize, Break)> {
+    let clusters = clusters(text, &word_break);
+    let mut breaks: Vec<(usize, Break)> = (1..clusters.len())
+        .filter_map(|i| pair(&clusters, i).map(|kind| (clusters[i].start, kind)))
+        .collect();
+    if !text.is_empty() {
+        breaks.push((text.len(), Break::Mandatory));
+    }
+    breaks
+}
+
+/// The byte offsets where a character with the combining marks and joiners after it starts,
+/// along with any it joins, which approximate grapheme clusters.
+pub fn cluster_boundaries(text: &str) -> Vec<usize> {
+    let clusters = clusters(text, &|_| WordBreak::Normal);
+    let mut boundaries = vec![];
+    for (i, cluster) in clusters.iter().enumerate() {
+        if i == 0 || clusters[i - 1].last != ZWJ {
+            boundaries.push(cluster.start);
+        }
+    }
+    boundaries
+}
@@ -1,5 +1,10 @@
-#![feature(string_from_utf8_lossy_owned)]
+#![cfg_attr(feature = "quick-xml", feature(string_from_utf8_lossy_owned))]
 
+pub mod css;
+pub mod dom;
 pub mod html5;
+pub mod layout;
+pub mod paint;
+pub mod style;
 pub mod testing;
 pub mod types;
@@ -0,0 +1,143 @@
+//! Painting, which draws laid out fragments into pixels on the CPU.
+//! See https://www.w3.org/TR/css-backgrounds-3/
+pub mod canvas;
+pub mod display_list;
+pub mod png;
+
+pub use canvas::{Canvas, Gradient, Paint, RoundedRect};
+pub use display_list::{DisplayItem, DisplayList, Image};
+
+use crate::layout::{FontMetrics, FragmentTree, Rect};
+use crate::style::values::{BorderStyle, Side, Sides};
+use crate::style::Color;
+
+/// Paints the fragments onto a canvas the size of the viewport, with text in the Ahem font
+/// whose glyphs are squares.
+pub fn paint(tree: &FragmentTree) -> Canvas {
+    rasterize(&DisplayList::new(tree))
+}
+
+/// Draws the items of a display list in order onto a canvas the size of its viewport.
+pub fn rasterize(list: &DisplayList) -> Canvas {
+    let viewport = list.viewport;
+    let mut canvas = Canvas::new(viewport.width.ceil() as u32, viewport.height.ceil() as u32);
+    for item in &list.items {
+        match item {
+            DisplayItem::Rect { rect, color } => canvas.fill(rect, &Paint::Solid(*color)),
+            DisplayItem::Image {
+                rect,
+                image: Image::LinearGradient(gradient),
+            } => canvas.fill(rect, &Paint::LinearGradient(gradient.clone())),
+            DisplayItem::Border {
+                rect,
+                widths,
+                styles,
+                colors,
+            } => paint_border(&mut canvas, rect, widths, styles, colors),
+            DisplayItem::Text {
+                rect,
+                text,
+                font_size,
+                color,
+                ..
+            } => paint_text(&mut canvas, *rect, text, *font_size, *color),
+            DisplayItem::PushClip { rect } => canvas.push_clip(*rect),
+            DisplayItem::PopClip => canvas.pop_clip(),
+            DisplayItem::PushLayer { opacity } => canvas.push_layer(*opacity),
+            DisplayItem::PopLayer => canvas.pop_layer(),
+        }
+    }
+    canvas
+}
+
+// Darkens a color for the shaded parts of 3D border styles
+fn darken(color: Color) -> Color {
+    let shade = |c: u8| (c as u16 * 2 / 3) as u8;
+    Color::rgba(shade(color.r), shade(color.g), shade(color.b), color.a)
+}
+
+// The color at a point in a side of a border, from how far into the border it is, from 0 at
+// the outside to 1 at the inside, and how far along the side
+fn border_color(
+    style: BorderStyle,
+    side: Side,
+    color: Color,
+    depth: f32,
+    along: f32,
+    width: f32,
+) -> Option<Color> {
+    let top_left = matches!(side, Side::Top | Side::Left);
+    let outer = depth < 0.5;
+    let dark = match style {
+        BorderStyle::None | BorderStyle::Hidden => return None,
+        BorderStyle::Solid => false,
+        BorderStyle::Double => return (!(1.0 / 3.0..2.0 / 3.0).contains(&depth)).then_some(color),
+        BorderStyle::Dashed => {
+            return ((along / (3.0 * width)).floor() as i64 % 2 == 0).then_some(color)
+        }
+        BorderStyle::Dotted => {
+            let cell = 2.0 * width;
+            let center = ((along / cell).floor() + 0.5) * cell;
+            let (x, y) = (along - center, (depth - 0.5) * width);
+            return (x * x + y * y <= width * width / 4.0).then_some(color);
+        }
+        BorderStyle::Inset => top_left,
+        BorderStyle::Outset => !top_left,
+        BorderStyle::Groove => top_left == outer,
+        BorderStyle::Ridge => top_left != outer,
+    };
+    Some(if dark { darken(color) } else { color })
+}
+
+// Each point between the border box and the padding box belongs to the side it is
+// proportionally closest to, so that corners are split diagonally between the outer and inner
+// corners
+fn paint_border(
+    canvas: &mut Canvas,
+    border_box: &RoundedRect,
+    widths: &Sides<f32>,
+    styles: &Sides<BorderStyle>,
+    colors: &Sides<Color>,
+) {
+    let padding_box = border_box.inset(widths);
+    let rect = border_box.rect;
+    canvas.fill_samples(rect, |x, y| {
+        if !border_box.contains(x, y) || padding_box.contains(x, y) {
+            return None;
+        }
+        let distances = [
+            (Side::Top, y - rect.y, x - rect.x),
+            (Side::Right, rect.right() - x, y - rect.y),
+            (Side::Bottom, rect.bottom() - y, x - rect.x),
+            (Side::Left, x - rect.x, y - rect.y),
+        ];
+        let (side, depth, along) = distances
+            .into_iter()
+            .filter(|&(side, ..)| widths.get(side) > 0.0)
+            .map(|(side, distance, along)| (side, distance / widths.get(side), along))
+            .min_by(|a, b| a.1.total_cmp(&b.1))?;
+        border_color(
+            styles.get(side),
+            side,
+            colors.get(side),
+            depth.clamp(0.0, 1.0),
+            along,
+            widths.get(side),
+        )
+    });
+}
+
+// Ahem glyphs are squares filling the em box, except for spaces, which are blank
+fn paint_text(canvas: &mut Canvas, rect: Rect, text: &str, font_size: f32, color: Color) {
+    let metrics = FontMetrics::AHEM;
+    let paint = Paint::Solid(color);
+    let mut x = rect.x;
+    for c in text.chars() {
+        let width = metrics.char_width(c, font_size);
+        if width > 0.0 && !c.is_whitespace() {
+            let glyph = Rect::new(x, rect.y, width, rect.height);
+            canvas.fill(&glyph.into(), &paint);
+        }
+        x += width;
+    }
+}
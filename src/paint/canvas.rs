@@ -0,0 +1,378 @@
+//! A pixel buffer that shapes are filled into with anti-aliasing, clipped to rounded
+//! rectangles and grouped in layers with opacity.
+use super::png;
+use crate::layout::Rect;
+use crate::style::values::{Corners, Sides};
+use crate::style::Color;
+use serde::Serialize;
+
+// A color with premultiplied alpha, each channel from 0 to 1
+type Pixel = [f32; 4];
+
+// A point, or a pair of horizontal and vertical radii or directions
+type Point = (f32, f32);
+
+// The samples across and down a pixel where a shape's edge crosses it
+const SAMPLES: usize = 4;
+
+fn premultiply(color: Color) -> Pixel {
+    let a = color.a as f32 / 255.0;
+    [
+        color.r as f32 / 255.0 * a,
+        color.g as f32 / 255.0 * a,
+        color.b as f32 / 255.0 * a,
+        a,
+    ]
+}
+
+fn unpremultiply([r, g, b, a]: Pixel) -> Color {
+    if a <= 0.0 {
+        return Color::TRANSPARENT;
+    }
+    let channel = |c: f32| (c / a * 255.0).round().clamp(0.0, 255.0) as u8;
+    Color::rgba(
+        channel(r),
+        channel(g),
+        channel(b),
+        (a * 255.0).round().clamp(0.0, 255.0) as u8,
+    )
+}
+
+// Draws `source` over `dest`, with the source scaled by `alpha`
+fn blend(dest: &mut Pixel, source: Pixel, alpha: f32) {
+    let inverse = 1.0 - source[3] * alpha;
+    for (d, s) in dest.iter_mut().zip(source) {
+        *d = s * alpha + *d * inverse;
+    }
+}
+
+/// A rectangle with rounded corners, each with a horizontal and a vertical radius.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct RoundedRect {
+    pub rect: Rect,
+    pub radii: Corners<(f32, f32)>,
+}
+
+impl From<Rect> for RoundedRect {
+    fn from(rect: Rect) -> Self {
+        Self {
+            rect,
+            radii: Corners::all((0.0, 0.0)),
+        }
+    }
+}
+
+impl RoundedRect {
+    /// A rounded rectangle with radii scaled down so that those on each side fit along it.
+    /// See https://www.w3.org/TR/css-backgrounds-3/#corner-overlap
+    pub fn new(rect: Rect, radii: Corners<(f32, f32)>) -> Self {
+        let fits = |length: f32, a: f32, b: f32| {
+            if a + b > 0.0 {
+                length / (a + b)
+            } else {
+                1.0
+            }
+        };
+        let scale = [
+            fits(rect.width, radii.top_left.0, radii.top_right.0),
+            fits(rect.width, radii.bottom_left.0, radii.bottom_right.0),
+            fits(rect.height, radii.top_left.1, radii.bottom_left.1),
+            fits(rect.height, radii.top_right.1, radii.bottom_right.1),
+            1.0,
+        ]
+        .into_iter()
+        .fold(f32::INFINITY, f32::min)
+        .max(0.0);
+        let scaled = |(x, y): Point| ((x * scale).max(0.0), (y * scale).max(0.0));
+        Self {
+            rect,
+            radii: Corners {
+                top_left: scaled(radii.top_left),
+                top_right: scaled(radii.top_right),
+                bottom_right: scaled(radii.bottom_right),
+                bottom_left: scaled(radii.bottom_left),
+            },
+        }
+    }
+
+    /// The rounded rectangle inside this one by the given amount on each side, with the
+    /// radii shrunk to match, as the padding box is inside the border box.
+    pub fn inset(&self, sides: &Sides<f32>) -> Self {
+        let shrink = |(x, y): Point, dx: f32, dy: f32| ((x - dx).max(0.0), (y - dy).max(0.0));
+        let radii = &self.radii;
+        Self {
+            rect: self.rect.inset(sides),
+            radii: Corners {
+                top_left: shrink(radii.top_left, sides.left, sides.top),
+                top_right: shrink(radii.top_right, sides.right, sides.top),
+                bottom_right: shrink(radii.bottom_right, sides.right, sides.bottom),
+                bottom_left: shrink(radii.bottom_left, sides.left, sides.bottom),
+            },
+        }
+    }
+
+    // The corners, each with the center of its ellipse, its radii, and the directions away
+    // from the center towards the corner
+    fn corners(&self) -> [(Point, Point, Point); 4] {
+        let Rect {
+            x,
+            y,
+            width,
+            height,
+        } = self.rect;
+        let (right, bottom) = (x + width, y + height);
+        let Corners {
+            top_left: tl,
+            top_right: tr,
+            bottom_right: br,
+            bottom_left: bl,
+        } = self.radii;
+        [
+            ((x + tl.0, y + tl.1), tl, (-1.0, -1.0)),
+            ((right - tr.0, y + tr.1), tr, (1.0, -1.0)),
+            ((right - br.0, bottom - br.1), br, (1.0, 1.0)),
+            ((x + bl.0, bottom - bl.1), bl, (-1.0, 1.0)),
+        ]
+    }
+
+    pub fn contains(&self, px: f32, py: f32) -> bool {
+        let rect = &self.rect;
+        if px < rect.x || py < rect.y || px >= rect.right() || py >= rect.bottom() {
+            return false;
+        }
+        // Points beyond the center of a corner's ellipse both ways must be inside it
+        self.corners()
+            .into_iter()
+            .all(|((cx, cy), (rx, ry), (sx, sy))| {
+                let (dx, dy) = ((px - cx) * sx, (py - cy) * sy);
+                dx <= 0.0 || dy <= 0.0 || (dx / rx).powi(2) + (dy / ry).powi(2) <= 1.0
+            })
+    }
+
+    // How much of the pixel whose top left is at x, y the shape covers
+    fn coverage(&self, x: f32, y: f32) -> f32 {
+        let rect = &self.rect;
+        let across = (rect.right().min(x + 1.0) - rect.x.max(x)).clamp(0.0, 1.0);
+        let down = (rect.bottom().min(y + 1.0) - rect.y.max(y)).clamp(0.0, 1.0);
+        if across * down == 0.0 {
+            return 0.0;
+        }
+        // Only pixels that reach beyond the center of a rounded corner need sampling
+        let beyond = |start: f32, center: f32, direction: f32| {
+            if direction > 0.0 {
+                start + 1.0 > center
+            } else {
+                start < center
+            }
+        };
+        let in_corner = self
+            .corners()
+            .into_iter()
+            .any(|((cx, cy), (rx, ry), (sx, sy))| {
+                rx > 0.0 && ry > 0.0 && beyond(x, cx, sx) && beyond(y, cy, sy)
+            });
+        if !in_corner {
+            return across * down;
+        }
+        let inside = sample_points(x, y)
+            .filter(|&(sx, sy)| self.contains(sx, sy))
+            .count();
+        inside as f32 / (SAMPLES * SAMPLES) as f32
+    }
+}
+
+// Points spread evenly over the pixel whose top left is at x, y
+fn sample_points(x: f32, y: f32) -> impl Iterator<Item = Point> {
+    (0..SAMPLES * SAMPLES).map(move |i| {
+        let step = 1.0 / SAMPLES as f32;
+        (
+            x + ((i % SAMPLES) as f32 + 0.5) * step,
+            y + ((i / SAMPLES) as f32 + 0.5) * step,
+        )
+    })
+}
+
+/// Colors that change along the line from `start` to `end`, with stops at offsets from 0 at
+/// the start to 1 at the end, in order.  Points beyond the ends take the colors there.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Gradient {
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+    pub stops: Vec<(f32, Color)>,
+}
+
+/// What fills a shape.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Paint {
+    Solid(Color),
+    LinearGradient(Gradient),
+}
+
+impl Paint {
+    fn at(&self, x: f32, y: f32) -> Pixel {
+        let Gradient { start, end, stops } = match self {
+            Self::Solid(color) => return premultiply(*color),
+            Self::LinearGradient(gradient) => gradient,
+        };
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let length = dx * dx + dy * dy;
+        let t = if length > 0.0 {
+            ((x - start.0) * dx + (y - start.1) * dy) / length
+        } else {
+            0.0
+        };
+
+        let after = stops.partition_point(|&(offset, _)| offset <= t);
+        match (after.checked_sub(1).map(|i| stops[i]), stops.get(after)) {
+            (Some((_, color)), None) | (None, Some(&(_, color))) => premultiply(color),
+            (Some((from, a)), Some(&(to, b))) => {
+                // Colors are interpolated with premultiplied alpha
+                let f = (t - from) / (to - from);
+                let (a, b) = (premultiply(a), premultiply(b));
+                [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * f)
+            }
+            (None, None) => [0.0; 4],
+        }
+    }
+}
+
+/// An image that painting draws into, which starts out transparent.
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    /// The pixels, and those of layers drawn into until they are composited onto them, each
+    /// with its opacity.
+    layers: Vec<(Vec<Pixel>, f32)>,
+    clips: Vec<RoundedRect>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            layers: vec![(vec![[0.0; 4]; width as usize * height as usize], 1.0)],
+            clips: vec![],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // The pixels inside the bounds and the canvas, by their top left corners
+    fn pixels_in(&self, bounds: Rect) -> impl Iterator<Item = (u32, u32)> {
+        let clamp = |value: f32, max: u32| value.clamp(0.0, max as f32) as u32;
+        let (left, right) = (
+            clamp(bounds.x.floor(), self.width),
+            clamp(bounds.right().ceil(), self.width),
+        );
+        let (top, bottom) = (
+            clamp(bounds.y.floor(), self.height),
+            clamp(bounds.bottom().ceil(), self.height),
+        );
+        (top..bottom).flat_map(move |y| (left..right).map(move |x| (x, y)))
+    }
+
+    // Draws a pixel onto the top layer, as much as `coverage` and the clips cover it
+    fn draw(&mut self, x: u32, y: u32, source: Pixel, coverage: f32) {
+        let (fx, fy) = (x as f32, y as f32);
+        let alpha = self
+            .clips
+            .iter()
+            .fold(coverage, |alpha, clip| alpha * clip.coverage(fx, fy));
+        if alpha <= 0.0 {
+            return;
+        }
+        let index = (y * self.width + x) as usize;
+        let (pixels, _) = self.layers.last_mut().unwrap();
+        blend(&mut pixels[index], source, alpha);
+    }
+
+    /// Fills a rounded rectangle.
+    pub fn fill(&mut self, shape: &RoundedRect, paint: &Paint) {
+        let pixels: Vec<(u32, u32)> = self.pixels_in(shape.rect).collect();
+        for (x, y) in pixels {
+            let (fx, fy) = (x as f32, y as f32);
+            let coverage = shape.coverage(fx, fy);
+            if coverage > 0.0 {
+                self.draw(x, y, paint.at(fx + 0.5, fy + 0.5), coverage);
+            }
+        }
+    }
+
+    /// Fills the pixels in the bounds with the colors `sample` gives at points spread over
+    /// each, where None adds nothing, for shapes and patterns other than rounded rectangles.
+    pub fn fill_samples(&mut self, bounds: Rect, sample: impl Fn(f32, f32) -> Option<Color>) {
+        let pixels: Vec<(u32, u32)> = self.pixels_in(bounds).collect();
+        let weight = 1.0 / (SAMPLES * SAMPLES) as f32;
+        for (x, y) in pixels {
+            let mut total = [0.0; 4];
+            for (sx, sy) in sample_points(x as f32, y as f32) {
+                if let Some(color) = sample(sx, sy) {
+                    let color = premultiply(color);
+                    for (t, c) in total.iter_mut().zip(color) {
+                        *t += c * weight;
+                    }
+                }
+            }
+            if total[3] > 0.0 {
+                self.draw(x, y, total, 1.0);
+            }
+        }
+    }
+
+    /// Limits what is drawn to a rounded rectangle, inside any clips already pushed, until
+    /// the clip is popped.
+    pub fn push_clip(&mut self, clip: RoundedRect) {
+        self.clips.push(clip);
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+
+    /// Draws into a new transparent layer until it is popped, and then onto what is below it
+    /// with the given opacity.
+    pub fn push_layer(&mut self, opacity: f32) {
+        let pixels = vec![[0.0; 4]; self.width as usize * self.height as usize];
+        self.layers.push((pixels, opacity.clamp(0.0, 1.0)));
+    }
+
+    pub fn pop_layer(&mut self) {
+        if self.layers.len() < 2 {
+            return;
+        }
+        let (layer, opacity) = self.layers.pop().unwrap();
+        let (pixels, _) = self.layers.last_mut().unwrap();
+        for (dest, source) in pixels.iter_mut().zip(layer) {
+            blend(dest, source, opacity);
+        }
+    }
+
+    /// The color of a pixel, of what is drawn outside any layers still pushed.
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        unpremultiply(self.layers[0].0[(y * self.width + x) as usize])
+    }
+
+    /// The pixels in rows from the top, each as red, green, blue and alpha bytes.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.layers[0]
+            .0
+            .iter()
+            .flat_map(|&pixel| {
+                let Color { r, g, b, a } = unpremultiply(pixel);
+                [r, g, b, a]
+            })
+            .collect()
+    }
+
+    /// The pixels as a PNG image.
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode(self.width, self.height, &self.to_rgba())
+    }
+}
@@ -0,0 +1,465 @@
+//! The display list: what painting draws, in the order it draws it, built from the fragment
+//! tree in the painting order of stacking contexts, which backends draw from.
+//! See https://www.w3.org/TR/CSS22/zindex.html
+use super::canvas::{Gradient, RoundedRect};
+use crate::layout::{FontMetrics, Fragment, FragmentKind, FragmentTree, Rect};
+use crate::style::image::LinearGradient;
+use crate::style::values::{
+    BorderStyle, Corners, LengthPercentage, Overflow, Position, Side, Sides, Visibility,
+};
+use crate::style::{Color, ComputedStyle};
+use serde::Serialize;
+use std::cmp::Ordering;
+
+/// An image that fills a rectangle.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Image {
+    LinearGradient(Gradient),
+}
+
+/// Something to draw, or a change to how what follows is drawn until it is undone.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum DisplayItem {
+    Rect {
+        rect: RoundedRect,
+        color: Color,
+    },
+    Image {
+        rect: RoundedRect,
+        image: Image,
+    },
+    /// The border between a rectangle and the one inside it by the width of each side.
+    Border {
+        rect: RoundedRect,
+        widths: Sides<f32>,
+        styles: Sides<BorderStyle>,
+        colors: Sides<Color>,
+    },
+    /// Text on one line, whose rectangle is the em box of its glyphs.
+    Text {
+        rect: Rect,
+        baseline: f32,
+        text: String,
+        font_size: f32,
+        color: Color,
+    },
+    /// Limits what is drawn to a rounded rectangle, inside any clips already pushed, until
+    /// the matching PopClip.
+    PushClip {
+        rect: RoundedRect,
+    },
+    PopClip,
+    /// Draws into a new transparent layer until the matching PopLayer, and then onto what is
+    /// below it with the opacity.  Clips pushed inside a layer are popped inside it.
+    PushLayer {
+        opacity: f32,
+    },
+    PopLayer,
+}
+
+/// The items to paint a laid out document, in order, onto a canvas the size of the viewport.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct DisplayList {
+    pub viewport: Rect,
+    pub items: Vec<DisplayItem>,
+}
+
+impl DisplayList {
+    /// Builds the display list of the fragments, with text in the Ahem font.  The canvas is
+    /// white under the root's background, which covers all of it.
+    pub fn new(tree: &FragmentTree) -> Self {
+        let mut builder = Builder {
+            items: vec![],
+            clips: vec![],
+            wanted: vec![],
+        };
+        let viewport = RoundedRect::from(tree.viewport);
+        builder.push(DisplayItem::Rect {
+            rect: viewport,
+            color: Color::WHITE,
+        });
+        if let Some(root) = &tree.root {
+            // https://www.w3.org/TR/css-backgrounds-3/#root-background
+            builder.background(&root.style, viewport, root.padding_rect());
+            builder.context(&Context::stacking(root, &[]), true);
+        }
+        for _ in builder.clips.drain(..) {
+            builder.items.push(DisplayItem::PopClip);
+        }
+        Self {
+            viewport: tree.viewport,
+            items: builder.items,
+        }
+    }
+}
+
+// A fragment with the clips of its ancestors, which apply to it wherever it is painted
+struct Entry<'a> {
+    fragment: &'a Fragment,
+    clips: Vec<RoundedRect>,
+}
+
+// What is painted with the in-flow content of an element, in the order of the fragments
+enum Content<'a> {
+    Box(Entry<'a>),
+    Text(Entry<'a>),
+    /// An inline block, painted as a unit.
+    Atomic(Context<'a>),
+}
+
+// The non-positioned descendants of an element
+#[derive(Default)]
+struct Flow<'a> {
+    /// Block-level boxes, whose backgrounds and borders are painted before any inline content.
+    blocks: Vec<Entry<'a>>,
+    inlines: Vec<Content<'a>>,
+}
+
+// The stacking contexts and positioned elements in a stacking context, by z-index
+#[derive(Default)]
+struct Layers<'a> {
+    negative: Vec<(i32, Context<'a>)>,
+    /// Those with z-index 0 or auto, in tree order.
+    zero: Vec<Context<'a>>,
+    positive: Vec<(i32, Context<'a>)>,
+}
+
+// An element painted as a unit: a stacking context, or a positioned element or inline block
+// painted as if it were one, whose positioned descendants and stacking contexts belong to the
+// enclosing stacking context instead
+struct Context<'a> {
+    entry: Entry<'a>,
+    flow: Flow<'a>,
+    /// Empty unless the element is a stacking context.
+    layers: Layers<'a>,
+    is_stacking_context: bool,
+}
+
+fn creates_stacking_context(style: &ComputedStyle) -> bool {
+    style.opacity < 1.0
+        || matches!(style.position, Position::Fixed | Position::Sticky)
+        || (style.position != Position::Static && style.z_index.is_some())
+}
+
+// The padding box of a fragment, if it clips its contents
+fn clip(fragment: &Fragment) -> Option<RoundedRect> {
+    let clips = fragment.style.overflow != Overflow::Visible
+        && matches!(
+            fragment.kind,
+            FragmentKind::Block | FragmentKind::InlineBlock
+        );
+    clips.then(|| border_box(fragment).inset(&fragment.border))
+}
+
+fn border_box(fragment: &Fragment) -> RoundedRect {
+    let rect = fragment.rect;
+    let resolve =
+        |radius: LengthPercentage| (radius.resolve(rect.width), radius.resolve(rect.height));
+    let radii = &fragment.style.border_radius;
+    RoundedRect::new(
+        rect,
+        Corners {
+            top_left: resolve(radii.top_left),
+            top_right: resolve(radii.top_right),
+            bottom_right: resolve(radii.bottom_right),
+            bottom_left: resolve(radii.bottom_left),
+        },
+    )
+}
+
+impl<'a> Context<'a> {
+    fn new(fragment: &'a Fragment, clips: &[RoundedRect]) -> Self {
+        Self {
+            entry: Entry {
+                fragment,
+                clips: clips.to_vec(),
+            },
+            flow: Flow::default(),
+            layers: Layers::default(),
+            is_stacking_context: false,
+        }
+    }
+
+    fn stacking(fragment: &'a Fragment, clips: &[RoundedRect]) -> Self {
+        let mut context = Self::new(fragment, clips);
+        context.is_stacking_context = true;
+        collect(fragment, clips, &mut context.flow, &mut context.layers);
+        // Sorting is stable, so those with the same z-index stay in tree order
+        context.layers.negative.sort_by_key(|&(z, _)| z);
+        context.layers.positive.sort_by_key(|&(z, _)| z);
+        context
+    }
+}
+
+// Sorts the descendants of a fragment into what is painted with its flow, and the positioned
+// elements and stacking contexts painted with the enclosing stacking context
+fn collect<'a>(
+    fragment: &'a Fragment,
+    clips: &[RoundedRect],
+    flow: &mut Flow<'a>,
+    layers: &mut Layers<'a>,
+) {
+    let mut clips = clips.to_vec();
+    clips.extend(clip(fragment));
+    for child in &fragment.children {
+        collect_fragment(child, &clips, flow, layers);
+    }
+}
+
+fn collect_fragment<'a>(
+    fragment: &'a Fragment,
+    clips: &[RoundedRect],
+    flow: &mut Flow<'a>,
+    layers: &mut Layers<'a>,
+) {
+    let style = &fragment.style;
+    let entry = || Entry {
+        fragment,
+        clips: clips.to_vec(),
+    };
+    match fragment.kind {
+        FragmentKind::Text(_) => flow.inlines.push(Content::Text(entry())),
+        FragmentKind::Line => {
+            for child in &fragment.children {
+                collect_fragment(child, clips, flow, layers);
+            }
+        }
+        _ if creates_stacking_context(style) => {
+            let z = style.z_index.unwrap_or(0);
+            let context = Context::stacking(fragment, clips);
+            match z.cmp(&0) {
+                Ordering::Less => layers.negative.push((z, context)),
+                Ordering::Equal => layers.zero.push(context),
+                Ordering::Greater => layers.positive.push((z, context)),
+            }
+        }
+        _ if style.position != Position::Static => {
+            // Its positioned descendants come after it in tree order
+            let index = layers.zero.len();
+            layers.zero.push(Context::new(fragment, clips));
+            let mut own = Flow::default();
+            collect(fragment, clips, &mut own, layers);
+            layers.zero[index].flow = own;
+        }
+        FragmentKind::InlineBlock => {
+            let mut context = Context::new(fragment, clips);
+            collect(fragment, clips, &mut context.flow, layers);
+            flow.inlines.push(Content::Atomic(context));
+        }
+        FragmentKind::Inline | FragmentKind::Replaced if style.display.is_inline_level() => {
+            flow.inlines.push(Content::Box(entry()));
+            collect(fragment, clips, flow, layers);
+        }
+        FragmentKind::Block | FragmentKind::Inline | FragmentKind::Replaced => {
+            flow.blocks.push(entry());
+            collect(fragment, clips, flow, layers);
+        }
+    }
+}
+
+struct Builder {
+    items: Vec<DisplayItem>,
+    /// The clips pushed and not yet popped.
+    clips: Vec<RoundedRect>,
+    /// The clips of what is being painted, pushed and popped only once something is, so that
+    /// boxes that draw nothing change nothing.
+    wanted: Vec<RoundedRect>,
+}
+
+impl Builder {
+    fn set_clips(&mut self, clips: &[RoundedRect]) {
+        self.wanted.clear();
+        self.wanted.extend_from_slice(clips);
+    }
+
+    // Pops and pushes clips so that those pushed are the wanted ones, and then adds the item
+    fn push(&mut self, item: DisplayItem) {
+        let common = self
+            .clips
+            .iter()
+            .zip(&self.wanted)
+            .take_while(|(a, b)| a == b)
+            .count();
+        while self.clips.len() > common {
+            self.clips.pop();
+            self.items.push(DisplayItem::PopClip);
+        }
+        for &rect in &self.wanted[common..] {
+            self.clips.push(rect);
+            self.items.push(DisplayItem::PushClip { rect });
+        }
+        self.items.push(item);
+    }
+
+    // https://www.w3.org/TR/CSS22/zindex.html#painting-order
+    fn context(&mut self, context: &Context, is_root: bool) {
+        let Entry { fragment, clips } = &context.entry;
+        self.set_clips(clips);
+        let opacity = fragment.style.opacity;
+        let layered = context.is_stacking_context && opacity < 1.0;
+        if layered {
+            self.push(DisplayItem::PushLayer { opacity });
+        }
+
+        if is_root {
+            self.border(fragment);
+        } else {
+            self.background_and_border(fragment);
+        }
+        for (_, child) in &context.layers.negative {
+            self.context(child, false);
+        }
+        for entry in &context.flow.blocks {
+            self.set_clips(&entry.clips);
+            self.background_and_border(entry.fragment);
+        }
+        for content in &context.flow.inlines {
+            match content {
+                Content::Box(entry) => {
+                    self.set_clips(&entry.clips);
+                    self.background_and_border(entry.fragment);
+                }
+                Content::Text(entry) => {
+                    self.set_clips(&entry.clips);
+                    self.text(entry.fragment);
+                }
+                Content::Atomic(child) => self.context(child, false),
+            }
+        }
+        for child in &context.layers.zero {
+            self.context(child, false);
+        }
+        for (_, child) in &context.layers.positive {
+            self.context(child, false);
+        }
+
+        if layered {
+            self.set_clips(clips);
+            self.push(DisplayItem::PopLayer);
+        }
+    }
+
+    fn background_and_border(&mut self, fragment: &Fragment) {
+        if fragment.style.visibility == Visibility::Visible {
+            self.background(
+                &fragment.style,
+                border_box(fragment),
+                fragment.padding_rect(),
+            );
+        }
+        self.border(fragment);
+    }
+
+    // Backgrounds cover the painting area, with images positioned in the padding box
+    fn background(&mut self, style: &ComputedStyle, area: RoundedRect, positioning: Rect) {
+        if style.background_color.a > 0 {
+            self.push(DisplayItem::Rect {
+                rect: area,
+                color: style.background_color,
+            });
+        }
+        if let Some(gradient) = &style.background_image {
+            self.push(DisplayItem::Image {
+                rect: area,
+                image: Image::LinearGradient(gradient_line(gradient, positioning)),
+            });
+        }
+    }
+
+    fn border(&mut self, fragment: &Fragment) {
+        let style = &fragment.style;
+        let visible = |side| fragment.border.get(side) > 0.0 && style.border_color.get(side).a > 0;
+        if style.visibility != Visibility::Visible || !Side::ALL.into_iter().any(visible) {
+            return;
+        }
+        self.push(DisplayItem::Border {
+            rect: border_box(fragment),
+            widths: fragment.border,
+            styles: style.border_style,
+            colors: style.border_color,
+        });
+    }
+
+    fn text(&mut self, fragment: &Fragment) {
+        let style = &fragment.style;
+        let FragmentKind::Text(text) = &fragment.kind else {
+            return;
+        };
+        if style.visibility != Visibility::Visible || style.color.a == 0 || text.is_empty() {
+            return;
+        }
+        self.push(DisplayItem::Text {
+            rect: fragment.rect,
+            baseline: fragment.rect.y + FontMetrics::AHEM.ascent * style.font_size,
+            text: text.clone(),
+            font_size: style.font_size,
+            color: style.color,
+        });
+    }
+}
+
+// The gradient line of a linear gradient in a box, which passes through the center of the
+// box at its angle and is long enough that the corners take the colors at its ends.
+// https://www.w3.org/TR/css-images-3/#linear-gradient-syntax
+fn gradient_line(gradient: &LinearGradient<Color, LengthPercentage>, area: Rect) -> Gradient {
+    // Quarter turns are exact, so that gradients along an axis have no rounding error
+    let angle = gradient.direction.angle(area.width, area.height);
+    let (sin, cos) = match angle.rem_euclid(360.0) {
+        0.0 => (0.0, 1.0),
+        90.0 => (1.0, 0.0),
+        180.0 => (0.0, -1.0),
+        270.0 => (-1.0, 0.0),
+        _ => angle.to_radians().sin_cos(),
+    };
+    let length = (area.width * sin).abs() + (area.height * cos).abs();
+    let (cx, cy) = (area.x + area.width / 2.0, area.y + area.height / 2.0);
+    let (dx, dy) = (sin * length / 2.0, -cos * length / 2.0);
+
+    // https://www.w3.org/TR/css-images-3/#color-stop-fixup
+    let last = gradient.stops.len().saturating_sub(1);
+    let mut offsets: Vec<Option<f32>> = gradient
+        .stops
+        .iter()
+        .enumerate()
+        .map(|(i, stop)| match stop.position {
+            Some(position) if length > 0.0 => Some(position.resolve(length) / length),
+            Some(_) => Some(0.0),
+            None if i == 0 => Some(0.0),
+            None if i == last => Some(1.0),
+            None => None,
+        })
+        .collect();
+    let mut largest = f32::NEG_INFINITY;
+    for offset in offsets.iter_mut().flatten() {
+        largest = largest.max(*offset);
+        *offset = largest;
+    }
+    // Runs of stops without positions are spaced evenly between the stops around them, and
+    // the first and last stops always have positions
+    let mut i = 1;
+    while i < last {
+        if offsets[i].is_some() {
+            i += 1;
+            continue;
+        }
+        let end = (i..=last).find(|&j| offsets[j].is_some()).unwrap();
+        let (from, to) = (offsets[i - 1].unwrap(), offsets[end].unwrap());
+        let steps = (end - i + 1) as f32;
+        for (n, offset) in offsets[i..end].iter_mut().enumerate() {
+            *offset = Some(from + (to - from) * (n + 1) as f32 / steps);
+        }
+        i = end;
+    }
+
+    Gradient {
+        start: (cx - dx, cy - dy),
+        end: (cx + dx, cy + dy),
+        stops: offsets
+            .into_iter()
+            .zip(&gradient.stops)
+            .map(|(offset, stop)| (offset.unwrap(), stop.color))
+            .collect(),
+    }
+}
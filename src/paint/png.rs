@@ -0,0 +1,108 @@
+//! A PNG encoder for 8-bit RGBA images, which stores the pixels without compression.
+//! See https://www.w3.org/TR/png/
+use std::sync::OnceLock;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// The most bytes a stored deflate block holds
+const MAX_STORED: usize = 0xffff;
+
+fn crc_table() -> &'static [u32; 256] {
+    static TABLE: OnceLock<[u32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if c & 1 == 1 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+            *entry = c;
+        }
+        table
+    })
+}
+
+/// The CRC-32 of the bytes, as each chunk ends with.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let table = crc_table();
+    !bytes.iter().fold(!0, |c, &byte| {
+        table[((c ^ byte as u32) & 0xff) as usize] ^ (c >> 8)
+    })
+}
+
+// The checksum that ends a zlib stream
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1, 0);
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    b << 16 | a
+}
+
+// A zlib stream of stored deflate blocks
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_STORED * 5 + 11);
+    out.extend([0x78, 0x01]);
+    let mut blocks = data.chunks(MAX_STORED).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        out.push(blocks.peek().is_none() as u8);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(kind);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+/// Encodes an image as PNG from its pixels in rows from the top, each as red, green, blue
+/// and alpha bytes.
+pub fn encode(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let stride = width as usize * 4;
+    debug_assert_eq!(rgba.len(), stride * height as usize);
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlacing
+    header.extend([8, 6, 0, 0, 0]);
+
+    // Each row starts with its filter type, which is always None
+    let mut scanlines = Vec::with_capacity((stride + 1) * height as usize);
+    if stride > 0 {
+        for row in rgba.chunks(stride) {
+            scanlines.push(0);
+            scanlines.extend(row);
+        }
+    } else {
+        scanlines.resize(height as usize, 0);
+    }
+
+    let mut out = SIGNATURE.to_vec();
+    chunk(&mut out, b"IHDR", &header);
+    chunk(&mut out, b"IDAT", &zlib(&scanlines));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
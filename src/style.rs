@@ -0,0 +1,237 @@
+//! The style cascade, which gives each element of a parsed document the computed values of
+//! its properties from the user-agent style sheet, the document's style sheets and its
+//! `style` attributes.
+use crate::css::matching::MatchContext;
+use crate::dom::{self, Arena, NodeId};
+use std::collections::BTreeMap;
+
+pub mod cascade;
+pub mod color;
+pub mod computed;
+pub mod image;
+pub mod properties;
+pub mod values;
+pub mod variables;
+
+pub use cascade::{Origin, RuleSet};
+pub use color::Color;
+pub use computed::ComputedStyle;
+pub use properties::{Longhand, Property, PropertyDeclaration, Specified};
+
+/// The size of the initial containing block, which viewport units and media queries refer to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            width: 800.0,
+            height: 600.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ElementStyle {
+    cascaded: BTreeMap<Property, Specified>,
+    computed: ComputedStyle,
+}
+
+/// The styles of the elements of a document.
+#[derive(Clone, Debug)]
+pub struct Styles {
+    elements: Vec<Option<ElementStyle>>,
+}
+
+fn parent_element(arena: &Arena, id: NodeId) -> Option<NodeId> {
+    arena
+        .parent(id)
+        .filter(|&parent| arena.node(parent).element_name().is_some())
+}
+
+impl Styles {
+    /// Computes the style of every element of the document, but not those in templates.
+    /// `html` is whether it is an HTML document rather than XML.
+    pub fn compute(arena: &Arena, html: bool, viewport: &Viewport) -> Self {
+        let mut author = RuleSet::new(Origin::Author);
+        for (_, stylesheet) in crate::css::stylesheets(arena) {
+            author.add_stylesheet(&stylesheet, viewport);
+        }
+        let rule_sets = [RuleSet::user_agent(), &author];
+        let cx = MatchContext::new(arena, html);
+        let initial = ComputedStyle::initial();
+        let mut root_font_size = computed::MEDIUM_FONT_SIZE;
+        let mut styles = Self {
+            elements: vec![None; arena.len()],
+        };
+
+        for id in arena.descendants(arena.document()) {
+            if arena.node(id).element_name().is_none() {
+                continue;
+            }
+            let parent = parent_element(arena, id);
+            let parent_style = parent.and_then(|p| styles.get(p)).unwrap_or(&initial);
+            let inline = arena
+                .node(id)
+                .attr("style")
+                .map(|style| cascade::declarations(&crate::css::parse_style_attribute(style)))
+                .unwrap_or_default();
+
+            let cascaded: BTreeMap<Property, Specified> =
+                cascade::cascade(arena, id, &rule_sets, &inline, &cx)
+                    .into_iter()
+                    .map(|(property, value)| (property, value.clone()))
+                    .collect();
+            let lengths = values::LengthContext {
+                font_size: parent_style.font_size,
+                root_font_size,
+                viewport_width: viewport.width,
+                viewport_height: viewport.height,
+            };
+            let computed = compute(&cascaded, parent_style, parent.is_none(), &lengths);
+
+            if parent.is_none() {
+                root_font_size = computed.font_size;
+            }
+            styles.elements[id.index()] = Some(ElementStyle { cascaded, computed });
+        }
+
+        styles
+    }
+
+    /// The computed style of an element, or None for other nodes and elements in templates.
+    pub fn get(&self, id: NodeId) -> Option<&ComputedStyle> {
+        self.elements
+            .get(id.index())?
+            .as_ref()
+            .map(|style| &style.computed)
+    }
+
+    /// The specified value of a property of an element: the value of the declaration that won
+    /// the cascade, or else `inherit` or `initial` as the property is inherited or not.
+    pub fn specified(&self, id: NodeId, longhand: Longhand) -> Option<&Specified> {
+        let style = self.elements.get(id.index())?.as_ref()?;
+        let defaulted = if longhand.inherited() {
+            &Specified::Inherit
+        } else {
+            &Specified::Initial
+        };
+        Some(
+            style
+                .cascaded
+                .get(&Property::Longhand(longhand))
+                .unwrap_or(defaulted),
+        )
+    }
+
+    /// A dump of the styles, with an element per line indented by its depth as in the
+    /// html5lib tree format.  Each element lists the properties whose value is not the one it
+    /// would have without declarations: its parent's for inherited properties, and the initial
+    /// value for others, where border colors are `currentcolor` and borders without a style
+    /// have no width.
+    pub fn dump(&self, arena: &Arena) -> String {
+        let initial = ComputedStyle::initial();
+        let mut dump = String::new();
+
+        for id in arena.descendants(arena.document()) {
+            let Some(style) = self.get(id) else {
+                continue;
+            };
+            let name = arena.node(id).element_name().unwrap();
+            let depth =
+                std::iter::successors(parent_element(arena, id), |&p| parent_element(arena, p))
+                    .count();
+            let parent = parent_element(arena, id)
+                .and_then(|p| self.get(p))
+                .unwrap_or(&initial);
+            let mut baseline = initial.clone();
+            baseline.color = style.color;
+            for side in values::Side::ALL {
+                baseline.reset(Longhand::BorderColor(side));
+            }
+            baseline.finish(false);
+
+            dump.push_str("| ");
+            dump.push_str(&"  ".repeat(depth));
+            dump.push('<');
+            dump.push_str(&dom::dump_name(name));
+            dump.push('>');
+
+            let properties: Vec<String> = Longhand::ALL
+                .iter()
+                .filter_map(|&longhand| {
+                    let value = style.to_css(longhand);
+                    let baseline = if longhand.inherited() {
+                        parent
+                    } else {
+                        &baseline
+                    };
+                    (value != baseline.to_css(longhand))
+                        .then(|| format!("{}: {value}", longhand.name()))
+                })
+                .collect();
+            if !properties.is_empty() {
+                dump.push(' ');
+                dump.push_str(&properties.join("; "));
+            }
+            dump.push('\n');
+        }
+
+        dump
+    }
+}
+
+// The computed values of an element from its cascaded values
+fn compute(
+    cascaded: &BTreeMap<Property, Specified>,
+    parent: &ComputedStyle,
+    is_root: bool,
+    lengths: &values::LengthContext,
+) -> ComputedStyle {
+    let mut style = ComputedStyle::inherit_from(parent);
+    let custom = cascaded
+        .iter()
+        .filter_map(|(property, value)| match property {
+            Property::Custom(name) => Some((name.as_str(), value)),
+            Property::Longhand(_) => None,
+        });
+    style.custom = variables::resolve(custom, &parent.custom);
+
+    for &longhand in Longhand::ALL {
+        let unset = |style: &mut ComputedStyle| {
+            if longhand.inherited() {
+                style.copy_from(parent, longhand);
+            } else {
+                style.reset(longhand);
+            }
+        };
+
+        match cascaded.get(&Property::Longhand(longhand)) {
+            None | Some(Specified::Unset) => unset(&mut style),
+            Some(Specified::Inherit) => style.copy_from(parent, longhand),
+            Some(Specified::Initial) => style.reset(longhand),
+            Some(Specified::Value(value)) => style.set(longhand, value, parent, lengths),
+            Some(Specified::Unparsed { property, value }) => {
+                let custom = &style.custom;
+                let value = variables::substitute(value, &mut |name| custom.get(name).cloned())
+                    .and_then(|value| properties::parse_value(property, &value))
+                    .and_then(|values| {
+                        values
+                            .into_iter()
+                            .find_map(|(l, value)| (l == longhand).then_some(value))
+                    });
+                // A value that is invalid once substituted makes the property unset
+                match value {
+                    Some(value) => style.set(longhand, &value, parent, lengths),
+                    None => unset(&mut style),
+                }
+            }
+        }
+    }
+
+    style.finish(is_root);
+    style
+}
@@ -0,0 +1,277 @@
+//! Which declarations apply to an element, and which of them win.
+//! See https://www.w3.org/TR/css-cascade-4/
+use super::properties::{self, Property, PropertyDeclaration, Specified};
+use super::values::{Length, LengthContext};
+use super::Viewport;
+use crate::css::matching::{self, MatchContext};
+use crate::css::parser::BlockKind;
+use crate::css::selector::{Namespaces, Specificity};
+use crate::css::{ComponentValue, CssRule, SelectorList, Stylesheet, Token};
+use crate::dom::{Arena, NodeId};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Origin {
+    UserAgent,
+    Author,
+}
+
+#[derive(Clone, Debug)]
+pub struct CascadeRule {
+    pub selectors: SelectorList,
+    pub declarations: Vec<PropertyDeclaration>,
+}
+
+/// The style rules of some style sheets of one origin, in the order they appear.
+#[derive(Clone, Debug)]
+pub struct RuleSet {
+    pub origin: Origin,
+    pub rules: Vec<CascadeRule>,
+}
+
+/// The user-agent style sheet, after the rendering section of the HTML standard.
+/// See https://html.spec.whatwg.org/multipage/rendering.html
+pub const USER_AGENT_CSS: &str = include_str!("ua.css");
+
+fn is_ident(value: &ComponentValue, expected: &str) -> bool {
+    matches!(value, ComponentValue::Token(Token::Ident(ident)) if ident.eq_ignore_ascii_case(expected))
+}
+
+// A media feature in parentheses, such as "(min-width: 600px)"
+fn media_feature(contents: &[ComponentValue], viewport: &Viewport) -> bool {
+    let items: Vec<&ComponentValue> = contents.iter().filter(|v| !v.is_whitespace()).collect();
+    let cx = LengthContext {
+        font_size: 16.0,
+        root_font_size: 16.0,
+        viewport_width: viewport.width,
+        viewport_height: viewport.height,
+    };
+
+    match items.as_slice() {
+        [ComponentValue::Token(Token::Ident(name)), ComponentValue::Token(Token::Colon), value] => {
+            let name = name.to_ascii_lowercase();
+            if name == "orientation" {
+                let portrait = viewport.height >= viewport.width;
+                return is_ident(value, if portrait { "portrait" } else { "landscape" });
+            }
+
+            let Some(length) = Length::parse(value).map(|length| length.to_px(&cx)) else {
+                return false;
+            };
+            match name.as_str() {
+                "width" => viewport.width == length,
+                "min-width" => viewport.width >= length,
+                "max-width" => viewport.width <= length,
+                "height" => viewport.height == length,
+                "min-height" => viewport.height >= length,
+                "max-height" => viewport.height <= length,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+// One media query: an optional "not" or "only", a media type, and features joined by "and"
+fn media_query(values: &[&ComponentValue], viewport: &Viewport) -> bool {
+    let mut rest = values;
+    let mut negated = false;
+    if let [first, after @ ..] = rest {
+        if is_ident(first, "not") {
+            negated = true;
+            rest = after;
+        } else if is_ident(first, "only") {
+            rest = after;
+        }
+    }
+
+    let mut matches = true;
+    let mut expect_feature = true;
+    if let [ComponentValue::Token(Token::Ident(media_type)), after @ ..] = rest {
+        matches = ["all", "screen"]
+            .iter()
+            .any(|t| media_type.eq_ignore_ascii_case(t));
+        expect_feature = false;
+        rest = after;
+    }
+
+    for value in rest {
+        match value {
+            ComponentValue::Block {
+                kind: BlockKind::Paren,
+                contents,
+            } if expect_feature => {
+                matches &= media_feature(contents, viewport);
+                expect_feature = false;
+            }
+            _ if !expect_feature && is_ident(value, "and") => expect_feature = true,
+            _ => return false,
+        }
+    }
+    // An empty query, or one ending in "and"
+    if expect_feature {
+        return false;
+    }
+
+    matches != negated
+}
+
+/// Whether a media query list applies to the viewport.  Only the `all` and `screen` media
+/// types, and the width, height and orientation features, are supported.
+pub fn media_matches(query: &[ComponentValue], viewport: &Viewport) -> bool {
+    let items: Vec<&ComponentValue> = query.iter().filter(|v| !v.is_whitespace()).collect();
+    if items.is_empty() {
+        return true;
+    }
+    items
+        .split(|value| matches!(value, ComponentValue::Token(Token::Comma)))
+        .any(|query| media_query(query, viewport))
+}
+
+// The default namespace and prefixes declared by `@namespace` rules
+fn namespaces(stylesheet: &Stylesheet) -> Namespaces {
+    let mut namespaces = Namespaces::default();
+
+    for rule in &stylesheet.rules {
+        let CssRule::Other(rule) = rule else {
+            continue;
+        };
+        if !rule.name.eq_ignore_ascii_case("namespace") || rule.block.is_some() {
+            continue;
+        }
+        let items: Vec<&ComponentValue> =
+            rule.prelude.iter().filter(|v| !v.is_whitespace()).collect();
+        let url = |value: &ComponentValue| match value {
+            ComponentValue::Token(Token::String(url) | Token::Url(url)) => Some(url.clone()),
+            _ => None,
+        };
+
+        match items.as_slice() {
+            [value] => namespaces.default = url(value),
+            [ComponentValue::Token(Token::Ident(prefix)), value] => {
+                if let Some(url) = url(value) {
+                    namespaces.prefixes.insert(prefix.clone(), url);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    namespaces
+}
+
+/// The declarations of a style rule's block or a `style` attribute that the cascade
+/// understands.
+pub fn declarations(declarations: &[crate::css::Declaration]) -> Vec<PropertyDeclaration> {
+    declarations
+        .iter()
+        .filter_map(properties::parse_declaration)
+        .flatten()
+        .collect()
+}
+
+impl RuleSet {
+    pub fn new(origin: Origin) -> Self {
+        Self {
+            origin,
+            rules: vec![],
+        }
+    }
+
+    /// The rules of the user-agent style sheet, which apply whatever the viewport.
+    pub fn user_agent() -> &'static Self {
+        static USER_AGENT: OnceLock<RuleSet> = OnceLock::new();
+        USER_AGENT.get_or_init(|| {
+            let mut rules = Self::new(Origin::UserAgent);
+            rules.add_stylesheet(&Stylesheet::parse(USER_AGENT_CSS), &Viewport::default());
+            rules
+        })
+    }
+
+    /// Adds the style rules of `stylesheet`, leaving out those of media rules that do not
+    /// apply and those with invalid selectors.
+    pub fn add_stylesheet(&mut self, stylesheet: &Stylesheet, viewport: &Viewport) {
+        let namespaces = namespaces(stylesheet);
+        self.add_rules(&stylesheet.rules, &namespaces, viewport);
+    }
+
+    fn add_rules(&mut self, rules: &[CssRule], namespaces: &Namespaces, viewport: &Viewport) {
+        for rule in rules {
+            match rule {
+                CssRule::Style(rule) => {
+                    let Ok(selectors) = SelectorList::from_values(&rule.prelude, namespaces) else {
+                        continue;
+                    };
+                    self.rules.push(CascadeRule {
+                        selectors,
+                        declarations: declarations(&rule.declarations),
+                    });
+                }
+                CssRule::Media(media) if media_matches(&media.query, viewport) => {
+                    self.add_rules(&media.rules, namespaces, viewport)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// Where a declaration falls in the cascade, from lowest to highest precedence: its origin and
+// importance, whether it is in a `style` attribute, its specificity, and its order
+type Precedence = (u8, bool, Specificity, usize);
+
+fn rank(origin: Origin, important: bool) -> u8 {
+    match (origin, important) {
+        (Origin::UserAgent, false) => 0,
+        (Origin::Author, false) => 1,
+        (Origin::Author, true) => 2,
+        (Origin::UserAgent, true) => 3,
+    }
+}
+
+/// The cascaded value of each property some declaration sets on the element: the value of the
+/// declaration with the highest precedence.  `inline` holds the declarations of its `style`
+/// attribute.
+pub fn cascade<'a>(
+    arena: &Arena,
+    id: NodeId,
+    rule_sets: &[&'a RuleSet],
+    inline: &'a [PropertyDeclaration],
+    cx: &MatchContext,
+) -> BTreeMap<Property, &'a Specified> {
+    let mut applicable: Vec<(Precedence, &PropertyDeclaration)> = vec![];
+    let mut order = 0;
+
+    for rule_set in rule_sets {
+        for rule in &rule_set.rules {
+            order += 1;
+            let specificity = rule
+                .selectors
+                .0
+                .iter()
+                .filter(|selector| matching::matches_complex(arena, id, selector, cx))
+                .map(|selector| selector.specificity())
+                .max();
+            let Some(specificity) = specificity else {
+                continue;
+            };
+
+            for declaration in &rule.declarations {
+                let rank = rank(rule_set.origin, declaration.important);
+                applicable.push(((rank, false, specificity, order), declaration));
+            }
+        }
+    }
+    for declaration in inline {
+        let rank = rank(Origin::Author, declaration.important);
+        applicable.push(((rank, true, Specificity::default(), order), declaration));
+    }
+
+    // Later declarations of the same precedence win, so the sort must be stable
+    applicable.sort_by_key(|(precedence, _)| *precedence);
+    applicable
+        .into_iter()
+        .map(|(_, declaration)| (declaration.property.clone(), &declaration.value))
+        .collect()
+}
@@ -0,0 +1,353 @@
+//! Colors, as written in CSS Color Module Level 4.
+//! See https://www.w3.org/TR/css-color-4/
+use crate::css::{ComponentValue, Token};
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// An sRGB color with alpha.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+const NAMED: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+fn clamp_u8(value: f64) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+// The arguments of a color function, as numbers, percentages and angles, in either the
+// comma-separated or the space-separated syntax
+fn arguments(values: &[ComponentValue]) -> Option<(Vec<&Token>, Option<&Token>)> {
+    let tokens: Vec<&Token> = values
+        .iter()
+        .filter(|value| !value.is_whitespace())
+        .map(|value| match value {
+            ComponentValue::Token(token) => Some(token),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+
+    if tokens.contains(&&Token::Comma) {
+        let mut parts: Vec<&Token> = vec![];
+        for (i, token) in tokens.iter().enumerate() {
+            if (i % 2 == 1) != (**token == Token::Comma) {
+                return None;
+            }
+            if i % 2 == 0 {
+                parts.push(token);
+            }
+        }
+        if tokens.len() % 2 == 0 {
+            return None;
+        }
+        let alpha = if parts.len() == 4 { parts.pop() } else { None };
+        return Some((parts, alpha));
+    }
+
+    match tokens.iter().position(|token| **token == Token::Delim('/')) {
+        Some(slash) if slash + 2 == tokens.len() => {
+            Some((tokens[..slash].to_vec(), Some(tokens[slash + 1])))
+        }
+        Some(_) => None,
+        None => Some((tokens, None)),
+    }
+}
+
+fn alpha(token: Option<&Token>) -> Option<u8> {
+    match token {
+        None => Some(255),
+        Some(Token::Number(n)) => Some(clamp_u8(n.value * 255.0)),
+        Some(Token::Percentage(n)) => Some(clamp_u8(n.value * 2.55)),
+        _ => None,
+    }
+}
+
+fn hue(token: &Token) -> Option<f64> {
+    let degrees = match token {
+        Token::Number(n) => n.value,
+        Token::Dimension { number, unit } => match unit.to_ascii_lowercase().as_str() {
+            "deg" => number.value,
+            "rad" => number.value.to_degrees(),
+            "grad" => number.value * 0.9,
+            "turn" => number.value * 360.0,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(degrees.rem_euclid(360.0))
+}
+
+fn percentage(token: &Token) -> Option<f64> {
+    match token {
+        Token::Percentage(n) | Token::Number(n) => Some(n.value.clamp(0.0, 100.0) / 100.0),
+        _ => None,
+    }
+}
+
+// See https://www.w3.org/TR/css-color-4/#hsl-to-rgb
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [f64; 3] {
+    let f = |n: f64| {
+        let k = (n + hue / 30.0) % 12.0;
+        let a = saturation * lightness.min(1.0 - lightness);
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    [f(0.0) * 255.0, f(8.0) * 255.0, f(4.0) * 255.0]
+}
+
+impl Color {
+    pub const TRANSPARENT: Self = Self::rgba(0, 0, 0, 0);
+    pub const BLACK: Self = Self::rgba(0, 0, 0, 255);
+    pub const WHITE: Self = Self::rgba(255, 255, 255, 255);
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    const fn from_u32(rgb: u32) -> Self {
+        Self::rgba((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+    }
+
+    /// A named color, or `transparent`.
+    pub fn named(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("transparent") {
+            return Some(Self::TRANSPARENT);
+        }
+        let name = name.to_ascii_lowercase();
+        NAMED
+            .binary_search_by(|(n, _)| n.cmp(&name.as_str()))
+            .ok()
+            .map(|i| Self::from_u32(NAMED[i].1))
+    }
+
+    /// A hex color without its "#", of 3, 4, 6 or 8 digits.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let digits: Vec<u8> = hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8))
+            .collect::<Option<_>>()?;
+
+        let channels: Vec<u8> = match digits.len() {
+            3 | 4 => digits.iter().map(|d| d * 17).collect(),
+            6 | 8 => digits
+                .chunks(2)
+                .map(|pair| pair[0] * 16 + pair[1])
+                .collect(),
+            _ => return None,
+        };
+        Some(Self::rgba(
+            channels[0],
+            channels[1],
+            channels[2],
+            channels.get(3).copied().unwrap_or(255),
+        ))
+    }
+
+    /// Parses a color other than `currentcolor`.
+    pub fn parse(value: &ComponentValue) -> Option<Self> {
+        match value {
+            ComponentValue::Token(Token::Ident(name)) => Self::named(name),
+            ComponentValue::Token(Token::Hash { value, .. }) => Self::from_hex(value),
+            ComponentValue::Function { name, arguments } => {
+                let name = name.to_ascii_lowercase();
+                let (channels, alpha_token) = self::arguments(arguments)?;
+                let alpha = alpha(alpha_token)?;
+                let [a, b, c] = channels[..] else {
+                    return None;
+                };
+
+                match name.as_str() {
+                    "rgb" | "rgba" => {
+                        let channel = |token: &Token| match token {
+                            Token::Number(n) => Some(clamp_u8(n.value)),
+                            Token::Percentage(n) => Some(clamp_u8(n.value * 2.55)),
+                            _ => None,
+                        };
+                        Some(Self::rgba(channel(a)?, channel(b)?, channel(c)?, alpha))
+                    }
+                    "hsl" | "hsla" => {
+                        let [r, g, b] = hsl_to_rgb(hue(a)?, percentage(b)?, percentage(c)?);
+                        Some(Self::rgba(clamp_u8(r), clamp_u8(g), clamp_u8(b), alpha))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+// Colors serialize as they are written in CSS
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { r, g, b, a } = *self;
+        if a == 255 {
+            write!(f, "rgb({r}, {g}, {b})")
+        } else {
+            // Two decimals if they round-trip to the same byte, as browsers serialize it
+            let mut alpha = (a as f64 / 255.0 * 100.0).round() / 100.0;
+            if (alpha * 255.0).round() as u8 != a {
+                alpha = (a as f64 / 255.0 * 1000.0).round() / 1000.0;
+            }
+            write!(f, "rgba({r}, {g}, {b}, {alpha})")
+        }
+    }
+}
@@ -0,0 +1,505 @@
+use super::color::Color;
+use super::image::{ColorStop, LinearGradient};
+use super::properties::{Longhand, Value};
+use super::values::{
+    BorderStyle, BoxSizing, Corners, Display, FontStyle, LengthContext, LengthPercentage,
+    LengthPercentageOrAuto, LineHeight, ListStyleType, Number, Overflow, OverflowWrap, Position,
+    Side, Sides, TextAlign, TextDecorationLine, VerticalAlign, VerticalAlignKeyword, Visibility,
+    WhiteSpace, WordBreak,
+};
+use super::variables::CustomProperties;
+
+/// The computed values of an element's properties.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComputedStyle {
+    pub display: Display,
+    pub position: Position,
+    /// None for `auto`.
+    pub z_index: Option<i32>,
+    pub margin: Sides<LengthPercentageOrAuto>,
+    pub padding: Sides<LengthPercentage>,
+    /// Border widths in pixels, which are zero where the style is `none` or `hidden`.
+    pub border_width: Sides<f32>,
+    pub border_style: Sides<BorderStyle>,
+    pub border_color: Sides<Color>,
+    pub border_radius: Corners<LengthPercentage>,
+    pub width: LengthPercentageOrAuto,
+    pub height: LengthPercentageOrAuto,
+    pub min_width: LengthPercentage,
+    pub min_height: LengthPercentage,
+    /// None for `none`.
+    pub max_width: Option<LengthPercentage>,
+    pub max_height: Option<LengthPercentage>,
+    pub box_sizing: BoxSizing,
+    pub overflow: Overflow,
+    pub opacity: f32,
+    pub background_color: Color,
+    /// None for `none`.
+    pub background_image: Option<LinearGradient<Color, LengthPercentage>>,
+    pub color: Color,
+    pub font_family: Vec<String>,
+    /// The font size in pixels.
+    pub font_size: f32,
+    pub font_style: FontStyle,
+    pub font_weight: u16,
+    pub line_height: LineHeight,
+    pub list_style_type: ListStyleType,
+    pub text_align: TextAlign,
+    pub text_decoration_line: TextDecorationLine,
+    pub vertical_align: VerticalAlign,
+    pub visibility: Visibility,
+    pub white_space: WhiteSpace,
+    pub word_break: WordBreak,
+    pub overflow_wrap: OverflowWrap,
+    pub custom: CustomProperties,
+}
+
+impl Default for ComputedStyle {
+    fn default() -> Self {
+        Self::initial()
+    }
+}
+
+/// The font size of `medium`, and of the initial value.
+pub const MEDIUM_FONT_SIZE: f32 = 16.0;
+
+fn font_size_keyword(keyword: &str, parent: f32) -> f32 {
+    match keyword {
+        "xx-small" => 9.0,
+        "x-small" => 10.0,
+        "small" => 13.0,
+        "large" => 18.0,
+        "x-large" => 24.0,
+        "xx-large" => 32.0,
+        "xxx-large" => 48.0,
+        "smaller" => parent / 1.2,
+        "larger" => parent * 1.2,
+        _ => MEDIUM_FONT_SIZE,
+    }
+}
+
+// See https://www.w3.org/TR/css-fonts-4/#relative-weights
+fn font_weight(value: &Value, parent: u16) -> u16 {
+    match value {
+        Value::Keyword(k) if k == "bold" => 700,
+        Value::Keyword(k) if k == "bolder" => match parent {
+            0..=349 => 400,
+            350..=549 => 700,
+            _ => 900,
+        },
+        Value::Keyword(k) if k == "lighter" => match parent {
+            0..=99 => parent,
+            100..=549 => 100,
+            550..=749 => 400,
+            _ => 700,
+        },
+        Value::Number(n) => *n as u16,
+        _ => 400,
+    }
+}
+
+fn keyword<T>(value: &Value, from_ident: fn(&str) -> Option<T>, default: T) -> T {
+    match value {
+        Value::Keyword(keyword) => from_ident(keyword).unwrap_or(default),
+        _ => default,
+    }
+}
+
+impl ComputedStyle {
+    pub fn initial() -> Self {
+        Self {
+            display: Display::Inline,
+            position: Position::Static,
+            z_index: None,
+            margin: Sides::all(LengthPercentageOrAuto::ZERO),
+            padding: Sides::all(LengthPercentage::ZERO),
+            border_width: Sides::all(3.0),
+            border_style: Sides::all(BorderStyle::None),
+            border_color: Sides::all(Color::BLACK),
+            border_radius: Corners::all(LengthPercentage::ZERO),
+            width: LengthPercentageOrAuto::Auto,
+            height: LengthPercentageOrAuto::Auto,
+            min_width: LengthPercentage::ZERO,
+            min_height: LengthPercentage::ZERO,
+            max_width: None,
+            max_height: None,
+            box_sizing: BoxSizing::ContentBox,
+            overflow: Overflow::Visible,
+            opacity: 1.0,
+            background_color: Color::TRANSPARENT,
+            background_image: None,
+            color: Color::BLACK,
+            font_family: vec!["serif".to_owned()],
+            font_size: MEDIUM_FONT_SIZE,
+            font_style: FontStyle::Normal,
+            font_weight: 400,
+            line_height: LineHeight::Normal,
+            list_style_type: ListStyleType::Disc,
+            text_align: TextAlign::Start,
+            text_decoration_line: TextDecorationLine::default(),
+            vertical_align: VerticalAlign::Keyword(VerticalAlignKeyword::Baseline),
+            visibility: Visibility::Visible,
+            white_space: WhiteSpace::Normal,
+            word_break: WordBreak::Normal,
+            overflow_wrap: OverflowWrap::Normal,
+            custom: CustomProperties::new(),
+        }
+    }
+
+    /// The style of a child of `parent` that no declaration applies to.
+    pub fn inherit_from(parent: &Self) -> Self {
+        let mut style = Self::initial();
+        for &longhand in Longhand::ALL {
+            if longhand.inherited() {
+                style.copy_from(parent, longhand);
+            }
+        }
+        style.custom = parent.custom.clone();
+        style
+    }
+
+    /// The style of an anonymous box with the given display in a box with the style `parent`.
+    /// See https://www.w3.org/TR/CSS2/visuren.html#anonymous-block-level
+    pub fn anonymous(parent: &Self, display: Display) -> Self {
+        let mut style = Self::inherit_from(parent);
+        style.display = display;
+        style.finish(false);
+        style
+    }
+
+    /// Sets one property to the value it has in `other`.
+    pub fn copy_from(&mut self, other: &Self, longhand: Longhand) {
+        match longhand {
+            Longhand::FontSize => self.font_size = other.font_size,
+            Longhand::Color => self.color = other.color,
+            Longhand::LineHeight => self.line_height = other.line_height,
+            Longhand::Display => self.display = other.display,
+            Longhand::Position => self.position = other.position,
+            Longhand::ZIndex => self.z_index = other.z_index,
+            Longhand::Margin(side) => self.margin.set(side, other.margin.get(side)),
+            Longhand::Padding(side) => self.padding.set(side, other.padding.get(side)),
+            Longhand::BorderWidth(side) => {
+                self.border_width.set(side, other.border_width.get(side))
+            }
+            Longhand::BorderStyle(side) => {
+                self.border_style.set(side, other.border_style.get(side))
+            }
+            Longhand::BorderColor(side) => {
+                self.border_color.set(side, other.border_color.get(side))
+            }
+            Longhand::BorderRadius(corner) => self
+                .border_radius
+                .set(corner, other.border_radius.get(corner)),
+            Longhand::Width => self.width = other.width,
+            Longhand::Height => self.height = other.height,
+            Longhand::MinWidth => self.min_width = other.min_width,
+            Longhand::MinHeight => self.min_height = other.min_height,
+            Longhand::MaxWidth => self.max_width = other.max_width,
+            Longhand::MaxHeight => self.max_height = other.max_height,
+            Longhand::BoxSizing => self.box_sizing = other.box_sizing,
+            Longhand::Overflow => self.overflow = other.overflow,
+            Longhand::Opacity => self.opacity = other.opacity,
+            Longhand::BackgroundColor => self.background_color = other.background_color,
+            Longhand::BackgroundImage => self.background_image = other.background_image.clone(),
+            Longhand::FontFamily => self.font_family = other.font_family.clone(),
+            Longhand::FontStyle => self.font_style = other.font_style,
+            Longhand::FontWeight => self.font_weight = other.font_weight,
+            Longhand::ListStyleType => self.list_style_type = other.list_style_type,
+            Longhand::TextAlign => self.text_align = other.text_align,
+            Longhand::TextDecorationLine => self.text_decoration_line = other.text_decoration_line,
+            Longhand::VerticalAlign => self.vertical_align = other.vertical_align,
+            Longhand::Visibility => self.visibility = other.visibility,
+            Longhand::WhiteSpace => self.white_space = other.white_space,
+            Longhand::WordBreak => self.word_break = other.word_break,
+            Longhand::OverflowWrap => self.overflow_wrap = other.overflow_wrap,
+        }
+    }
+
+    /// Sets one property to its initial value.
+    pub fn reset(&mut self, longhand: Longhand) {
+        match longhand {
+            // The initial border color is `currentcolor`
+            Longhand::BorderColor(side) => self.border_color.set(side, self.color),
+            _ => self.copy_from(&Self::initial(), longhand),
+        }
+    }
+
+    fn length_percentage(&self, value: &Value, cx: &LengthContext) -> LengthPercentage {
+        match value {
+            Value::Length(length) => LengthPercentage::Length(length.to_px(cx)),
+            Value::Percentage(p) => LengthPercentage::Percentage(*p),
+            _ => LengthPercentage::ZERO,
+        }
+    }
+
+    fn length_percentage_or_auto(
+        &self,
+        value: &Value,
+        cx: &LengthContext,
+    ) -> LengthPercentageOrAuto {
+        match value {
+            Value::Keyword(_) => LengthPercentageOrAuto::Auto,
+            _ => LengthPercentageOrAuto::LengthPercentage(self.length_percentage(value, cx)),
+        }
+    }
+
+    fn color_value(&self, value: &Value) -> Color {
+        match value {
+            Value::Color(color) => *color,
+            // currentcolor
+            _ => self.color,
+        }
+    }
+
+    /// Sets one property to the computed value of `value`.  Properties are set in the order of
+    /// `Longhand::ALL`, so that the font size and color are already computed when the values
+    /// relative to them are.  Only the root font size and viewport of `cx` are used.
+    pub fn set(&mut self, longhand: Longhand, value: &Value, parent: &Self, cx: &LengthContext) {
+        let cx = &LengthContext {
+            font_size: self.font_size,
+            ..*cx
+        };
+
+        match longhand {
+            Longhand::FontSize => {
+                self.font_size = match value {
+                    Value::Keyword(keyword) => font_size_keyword(keyword, parent.font_size),
+                    Value::Percentage(p) => parent.font_size * p / 100.0,
+                    Value::Length(length) => length.to_px(&LengthContext {
+                        font_size: parent.font_size,
+                        ..*cx
+                    }),
+                    _ => MEDIUM_FONT_SIZE,
+                }
+            }
+            Longhand::Color => self.color = self.color_value(value),
+            Longhand::LineHeight => {
+                self.line_height = match value {
+                    Value::Number(n) => LineHeight::Number(*n),
+                    Value::Length(length) => LineHeight::Length(length.to_px(cx)),
+                    Value::Percentage(p) => LineHeight::Length(self.font_size * p / 100.0),
+                    _ => LineHeight::Normal,
+                }
+            }
+            Longhand::Display => {
+                self.display = keyword(value, Display::from_ident, Display::Inline)
+            }
+            Longhand::Position => {
+                self.position = keyword(value, Position::from_ident, Position::Static)
+            }
+            Longhand::ZIndex => {
+                self.z_index = match value {
+                    Value::Number(n) => Some(*n as i32),
+                    _ => None,
+                }
+            }
+            Longhand::Margin(side) => self
+                .margin
+                .set(side, self.length_percentage_or_auto(value, cx)),
+            Longhand::Padding(side) => self.padding.set(side, self.length_percentage(value, cx)),
+            Longhand::BorderWidth(side) => {
+                let width = match value {
+                    Value::Keyword(k) if k == "thin" => 1.0,
+                    Value::Keyword(k) if k == "thick" => 5.0,
+                    Value::Length(length) => length.to_px(cx),
+                    _ => 3.0,
+                };
+                self.border_width.set(side, width);
+            }
+            Longhand::BorderStyle(side) => self.border_style.set(
+                side,
+                keyword(value, BorderStyle::from_ident, BorderStyle::None),
+            ),
+            Longhand::BorderColor(side) => self.border_color.set(side, self.color_value(value)),
+            Longhand::BorderRadius(corner) => self
+                .border_radius
+                .set(corner, self.length_percentage(value, cx)),
+            Longhand::Width => self.width = self.length_percentage_or_auto(value, cx),
+            Longhand::Height => self.height = self.length_percentage_or_auto(value, cx),
+            // `auto` is zero outside flex and grid layout
+            Longhand::MinWidth => self.min_width = self.length_percentage(value, cx),
+            Longhand::MinHeight => self.min_height = self.length_percentage(value, cx),
+            Longhand::MaxWidth => {
+                self.max_width = match value {
+                    Value::Keyword(_) => None,
+                    _ => Some(self.length_percentage(value, cx)),
+                }
+            }
+            Longhand::MaxHeight => {
+                self.max_height = match value {
+                    Value::Keyword(_) => None,
+                    _ => Some(self.length_percentage(value, cx)),
+                }
+            }
+            Longhand::BoxSizing => {
+                self.box_sizing = keyword(value, BoxSizing::from_ident, BoxSizing::ContentBox)
+            }
+            Longhand::Overflow => {
+                self.overflow = keyword(value, Overflow::from_ident, Overflow::Visible)
+            }
+            Longhand::Opacity => {
+                self.opacity = match value {
+                    Value::Number(n) => n.clamp(0.0, 1.0),
+                    Value::Percentage(p) => (p / 100.0).clamp(0.0, 1.0),
+                    _ => 1.0,
+                }
+            }
+            Longhand::BackgroundColor => self.background_color = self.color_value(value),
+            Longhand::BackgroundImage => {
+                self.background_image = match value {
+                    Value::Gradient(gradient) => Some(LinearGradient {
+                        direction: gradient.direction,
+                        stops: gradient
+                            .stops
+                            .iter()
+                            .map(|stop| ColorStop {
+                                color: self.color_value(&stop.color),
+                                position: stop
+                                    .position
+                                    .as_ref()
+                                    .map(|position| self.length_percentage(position, cx)),
+                            })
+                            .collect(),
+                    }),
+                    _ => None,
+                }
+            }
+            Longhand::FontFamily => {
+                if let Value::List(families) = value {
+                    self.font_family = families.clone();
+                }
+            }
+            Longhand::FontStyle => {
+                self.font_style = keyword(value, FontStyle::from_ident, FontStyle::Normal)
+            }
+            Longhand::FontWeight => self.font_weight = font_weight(value, parent.font_weight),
+            Longhand::ListStyleType => {
+                self.list_style_type =
+                    keyword(value, ListStyleType::from_ident, ListStyleType::Disc)
+            }
+            Longhand::TextAlign => {
+                self.text_align = keyword(value, TextAlign::from_ident, TextAlign::Start)
+            }
+            Longhand::TextDecorationLine => {
+                let lines = match value {
+                    Value::List(lines) => lines.as_slice(),
+                    _ => &[],
+                };
+                let has = |line: &str| lines.iter().any(|l| l == line);
+                self.text_decoration_line = TextDecorationLine {
+                    underline: has("underline"),
+                    overline: has("overline"),
+                    line_through: has("line-through"),
+                };
+            }
+            Longhand::VerticalAlign => {
+                self.vertical_align = match value {
+                    Value::Length(length) => VerticalAlign::Length(length.to_px(cx)),
+                    Value::Percentage(p) => {
+                        VerticalAlign::Length(self.line_height.resolve(self.font_size) * p / 100.0)
+                    }
+                    _ => VerticalAlign::Keyword(keyword(
+                        value,
+                        VerticalAlignKeyword::from_ident,
+                        VerticalAlignKeyword::Baseline,
+                    )),
+                }
+            }
+            Longhand::Visibility => {
+                self.visibility = keyword(value, Visibility::from_ident, Visibility::Visible)
+            }
+            Longhand::WhiteSpace => {
+                self.white_space = keyword(value, WhiteSpace::from_ident, WhiteSpace::Normal)
+            }
+            Longhand::WordBreak => {
+                self.word_break = keyword(value, WordBreak::from_ident, WordBreak::Normal)
+            }
+            Longhand::OverflowWrap => {
+                self.overflow_wrap = keyword(value, OverflowWrap::from_ident, OverflowWrap::Normal)
+            }
+        }
+    }
+
+    /// Adjusts values that depend on others once every property is set.
+    pub(crate) fn finish(&mut self, is_root: bool) {
+        for side in Side::ALL {
+            if matches!(
+                self.border_style.get(side),
+                BorderStyle::None | BorderStyle::Hidden
+            ) {
+                self.border_width.set(side, 0.0);
+            }
+        }
+
+        // See https://www.w3.org/TR/CSS2/visuren.html#dis-pos-flo
+        if is_root || matches!(self.position, Position::Absolute | Position::Fixed) {
+            self.display = self.display.blockify();
+        }
+        // See https://www.w3.org/TR/css-display-3/#valdef-display-contents
+        if is_root && self.display == Display::Contents {
+            self.display = Display::Block;
+        }
+    }
+
+    /// The computed value of a property, serialized.
+    pub fn to_css(&self, longhand: Longhand) -> String {
+        let length_percentage = |lp: Option<LengthPercentage>| match lp {
+            Some(lp) => lp.to_string(),
+            None => "none".to_owned(),
+        };
+
+        match longhand {
+            Longhand::FontSize => format!("{}px", Number(self.font_size)),
+            Longhand::Color => self.color.to_string(),
+            Longhand::LineHeight => self.line_height.to_string(),
+            Longhand::Display => self.display.to_string(),
+            Longhand::Position => self.position.to_string(),
+            Longhand::ZIndex => match self.z_index {
+                Some(z) => z.to_string(),
+                None => "auto".to_owned(),
+            },
+            Longhand::Margin(side) => self.margin.get(side).to_string(),
+            Longhand::Padding(side) => self.padding.get(side).to_string(),
+            Longhand::BorderWidth(side) => format!("{}px", Number(self.border_width.get(side))),
+            Longhand::BorderStyle(side) => self.border_style.get(side).to_string(),
+            Longhand::BorderColor(side) => self.border_color.get(side).to_string(),
+            Longhand::BorderRadius(corner) => self.border_radius.get(corner).to_string(),
+            Longhand::Width => self.width.to_string(),
+            Longhand::Height => self.height.to_string(),
+            Longhand::MinWidth => self.min_width.to_string(),
+            Longhand::MinHeight => self.min_height.to_string(),
+            Longhand::MaxWidth => length_percentage(self.max_width),
+            Longhand::MaxHeight => length_percentage(self.max_height),
+            Longhand::BoxSizing => self.box_sizing.to_string(),
+            Longhand::Overflow => self.overflow.to_string(),
+            Longhand::Opacity => Number(self.opacity).to_string(),
+            Longhand::BackgroundColor => self.background_color.to_string(),
+            Longhand::BackgroundImage => match &self.background_image {
+                Some(gradient) => gradient.to_string(),
+                None => "none".to_owned(),
+            },
+            Longhand::FontFamily => self
+                .font_family
+                .iter()
+                .map(|family| {
+                    if family.contains(' ') {
+                        format!("\"{family}\"")
+                    } else {
+                        family.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            Longhand::FontStyle => self.font_style.to_string(),
+            Longhand::FontWeight => self.font_weight.to_string(),
+            Longhand::ListStyleType => self.list_style_type.to_string(),
+            Longhand::TextAlign => self.text_align.to_string(),
+            Longhand::TextDecorationLine => self.text_decoration_line.to_string(),
+            Longhand::VerticalAlign => self.vertical_align.to_string(),
+            Longhand::Visibility => self.visibility.to_string(),
+            Longhand::WhiteSpace => self.white_space.to_string(),
+            Longhand::WordBreak => self.word_break.to_string(),
+            Longhand::OverflowWrap => self.overflow_wrap.to_string(),
+        }
+    }
+}
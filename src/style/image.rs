@@ -0,0 +1,177 @@
+//! Images, as written in CSS Images Module Level 3, of which only linear gradients are
+//! modelled.
+//! See https://www.w3.org/TR/css-images-3/#linear-gradients
+use super::values::{Number, Side};
+use crate::css::{ComponentValue, Token};
+use std::fmt;
+
+/// The direction of a linear gradient.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    /// An angle in degrees, clockwise from pointing up.
+    Angle(f32),
+    /// Towards a side, or towards a corner as a horizontal and a vertical side.
+    To(Option<Side>, Option<Side>),
+}
+
+impl Direction {
+    /// The angle in degrees of a gradient in a box of the given size.  Gradients towards a
+    /// corner are perpendicular to the diagonal between the two other corners.
+    pub fn angle(self, width: f32, height: f32) -> f32 {
+        let corner = height.atan2(width).to_degrees();
+        match self {
+            Self::Angle(degrees) => degrees,
+            Self::To(None, Some(Side::Top)) => 0.0,
+            Self::To(Some(Side::Right), None) => 90.0,
+            Self::To(Some(Side::Left), None) => 270.0,
+            Self::To(Some(Side::Right), Some(Side::Top)) => corner,
+            Self::To(Some(Side::Right), Some(Side::Bottom)) => 180.0 - corner,
+            Self::To(Some(Side::Left), Some(Side::Bottom)) => 180.0 + corner,
+            Self::To(Some(Side::Left), Some(Side::Top)) => 360.0 - corner,
+            Self::To(..) => 180.0,
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Angle(degrees) => write!(f, "{}deg", Number(degrees)),
+            Self::To(horizontal, vertical) => {
+                f.write_str("to")?;
+                for side in [horizontal, vertical].into_iter().flatten() {
+                    write!(f, " {}", side.as_str())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A color stop, with the types of its color and position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorStop<C, P> {
+    pub color: C,
+    /// None where the stop is spaced evenly between those around it.
+    pub position: Option<P>,
+}
+
+/// A `linear-gradient()`, with the types of the colors and positions of its stops.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearGradient<C, P> {
+    pub direction: Direction,
+    pub stops: Vec<ColorStop<C, P>>,
+}
+
+fn angle(value: &ComponentValue) -> Option<f32> {
+    let degrees = match value {
+        ComponentValue::Token(Token::Number(n)) if n.value == 0.0 => 0.0,
+        ComponentValue::Token(Token::Dimension { number, unit }) => {
+            match unit.to_ascii_lowercase().as_str() {
+                "deg" => number.value,
+                "rad" => number.value.to_degrees(),
+                "grad" => number.value * 0.9,
+                "turn" => number.value * 360.0,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(degrees as f32)
+}
+
+// `to` a side, or two sides that meet at a corner in either order
+fn to(items: &[&ComponentValue]) -> Option<Direction> {
+    let (first, sides) = items.split_first()?;
+    if !matches!(first, ComponentValue::Token(Token::Ident(to)) if to.eq_ignore_ascii_case("to")) {
+        return None;
+    }
+    let (mut horizontal, mut vertical) = (None, None);
+    for item in sides {
+        let ComponentValue::Token(Token::Ident(name)) = item else {
+            return None;
+        };
+        let side = Side::ALL
+            .into_iter()
+            .find(|side| name.eq_ignore_ascii_case(side.as_str()))?;
+        let slot = match side {
+            Side::Left | Side::Right => &mut horizontal,
+            Side::Top | Side::Bottom => &mut vertical,
+        };
+        if slot.replace(side).is_some() {
+            return None;
+        }
+    }
+    (!sides.is_empty() && sides.len() <= 2).then_some(Direction::To(horizontal, vertical))
+}
+
+impl<C, P> LinearGradient<C, P> {
+    /// Parses a `linear-gradient()`, whose stops have colors and optional positions that
+    /// `color` and `position` parse.
+    pub fn parse(
+        value: &ComponentValue,
+        color: impl Fn(&ComponentValue) -> Option<C>,
+        position: impl Fn(&ComponentValue) -> Option<P>,
+    ) -> Option<Self> {
+        let ComponentValue::Function { name, arguments } = value else {
+            return None;
+        };
+        if !name.eq_ignore_ascii_case("linear-gradient") {
+            return None;
+        }
+        let items: Vec<&ComponentValue> = arguments
+            .iter()
+            .filter(|value| !value.is_whitespace())
+            .collect();
+        let mut arguments = items.split(|item| matches!(item, ComponentValue::Token(Token::Comma)));
+
+        let mut first = arguments.next()?;
+        let direction = match first {
+            [item] => angle(item).map(Direction::Angle),
+            _ => to(first),
+        };
+        let direction = match direction {
+            Some(direction) => {
+                first = arguments.next()?;
+                direction
+            }
+            None => Direction::To(None, Some(Side::Bottom)),
+        };
+
+        let mut stops = vec![];
+        for stop in std::iter::once(first).chain(arguments) {
+            let stop = match stop {
+                [item] => ColorStop {
+                    color: color(item)?,
+                    position: None,
+                },
+                [item, at] => ColorStop {
+                    color: color(item)?,
+                    position: Some(position(at)?),
+                },
+                _ => return None,
+            };
+            stops.push(stop);
+        }
+        (stops.len() >= 2).then_some(Self { direction, stops })
+    }
+}
+
+impl<C: fmt::Display, P: fmt::Display> fmt::Display for LinearGradient<C, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("linear-gradient(")?;
+        if self.direction != Direction::To(None, Some(Side::Bottom)) {
+            write!(f, "{}, ", self.direction)?;
+        }
+        for (i, stop) in self.stops.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", stop.color)?;
+            if let Some(position) = &stop.position {
+                write!(f, " {position}")?;
+            }
+        }
+        f.write_str(")")
+    }
+}
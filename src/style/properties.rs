@@ -0,0 +1,750 @@
+//! The properties the cascade knows, and the parsing of their declarations into specified
+//! values.
+use super::color::Color;
+use super::image::LinearGradient;
+use super::values::{
+    BorderStyle, BoxSizing, Corner, Display, FontStyle, Length, ListStyleType, Overflow,
+    OverflowWrap, Position, Side, TextAlign, VerticalAlignKeyword, Visibility, WhiteSpace,
+    WordBreak,
+};
+use crate::css::{ComponentValue, Declaration, Token};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Longhand {
+    FontSize,
+    Color,
+    LineHeight,
+    Display,
+    Position,
+    ZIndex,
+    Margin(Side),
+    Padding(Side),
+    BorderWidth(Side),
+    BorderStyle(Side),
+    BorderColor(Side),
+    BorderRadius(Corner),
+    Width,
+    Height,
+    MinWidth,
+    MinHeight,
+    MaxWidth,
+    MaxHeight,
+    BoxSizing,
+    Overflow,
+    Opacity,
+    BackgroundColor,
+    BackgroundImage,
+    FontFamily,
+    FontStyle,
+    FontWeight,
+    ListStyleType,
+    TextAlign,
+    TextDecorationLine,
+    VerticalAlign,
+    Visibility,
+    WhiteSpace,
+    WordBreak,
+    OverflowWrap,
+}
+
+fn sides(longhand: fn(Side) -> Longhand) -> [Longhand; 4] {
+    [
+        longhand(Side::Top),
+        longhand(Side::Right),
+        longhand(Side::Bottom),
+        longhand(Side::Left),
+    ]
+}
+
+fn corners(longhand: fn(Corner) -> Longhand) -> [Longhand; 4] {
+    [
+        longhand(Corner::TopLeft),
+        longhand(Corner::TopRight),
+        longhand(Corner::BottomRight),
+        longhand(Corner::BottomLeft),
+    ]
+}
+
+impl Longhand {
+    /// Every longhand, in the order their values are computed.  The font size, color and line
+    /// height come first, since other values are computed from them.
+    pub const ALL: &'static [Self] = &[
+        Self::FontSize,
+        Self::Color,
+        Self::LineHeight,
+        Self::Display,
+        Self::Position,
+        Self::ZIndex,
+        Self::Margin(Side::Top),
+        Self::Margin(Side::Right),
+        Self::Margin(Side::Bottom),
+        Self::Margin(Side::Left),
+        Self::Padding(Side::Top),
+        Self::Padding(Side::Right),
+        Self::Padding(Side::Bottom),
+        Self::Padding(Side::Left),
+        Self::BorderWidth(Side::Top),
+        Self::BorderWidth(Side::Right),
+        Self::BorderWidth(Side::Bottom),
+        Self::BorderWidth(Side::Left),
+        Self::BorderStyle(Side::Top),
+        Self::BorderStyle(Side::Right),
+        Self::BorderStyle(Side::Bottom),
+        Self::BorderStyle(Side::Left),
+        Self::BorderColor(Side::Top),
+        Self::BorderColor(Side::Right),
+        Self::BorderColor(Side::Bottom),
+        Self::BorderColor(Side::Left),
+        Self::BorderRadius(Corner::TopLeft),
+        Self::BorderRadius(Corner::TopRight),
+        Self::BorderRadius(Corner::BottomRight),
+        Self::BorderRadius(Corner::BottomLeft),
+        Self::Width,
+        Self::Height,
+        Self::MinWidth,
+        Self::MinHeight,
+        Self::MaxWidth,
+        Self::MaxHeight,
+        Self::BoxSizing,
+        Self::Overflow,
+        Self::Opacity,
+        Self::BackgroundColor,
+        Self::BackgroundImage,
+        Self::FontFamily,
+        Self::FontStyle,
+        Self::FontWeight,
+        Self::ListStyleType,
+        Self::TextAlign,
+        Self::TextDecorationLine,
+        Self::VerticalAlign,
+        Self::Visibility,
+        Self::WhiteSpace,
+        Self::WordBreak,
+        Self::OverflowWrap,
+    ];
+
+    pub fn name(self) -> &'static str {
+        let side = |side: Side, names: [&'static str; 4]| names[side as usize];
+        match self {
+            Self::FontSize => "font-size",
+            Self::Color => "color",
+            Self::LineHeight => "line-height",
+            Self::Display => "display",
+            Self::Position => "position",
+            Self::ZIndex => "z-index",
+            Self::Margin(s) => side(
+                s,
+                ["margin-top", "margin-right", "margin-bottom", "margin-left"],
+            ),
+            Self::Padding(s) => side(
+                s,
+                [
+                    "padding-top",
+                    "padding-right",
+                    "padding-bottom",
+                    "padding-left",
+                ],
+            ),
+            Self::BorderWidth(s) => side(
+                s,
+                [
+                    "border-top-width",
+                    "border-right-width",
+                    "border-bottom-width",
+                    "border-left-width",
+                ],
+            ),
+            Self::BorderStyle(s) => side(
+                s,
+                [
+                    "border-top-style",
+                    "border-right-style",
+                    "border-bottom-style",
+                    "border-left-style",
+                ],
+            ),
+            Self::BorderColor(s) => side(
+                s,
+                [
+                    "border-top-color",
+                    "border-right-color",
+                    "border-bottom-color",
+                    "border-left-color",
+                ],
+            ),
+            Self::BorderRadius(corner) => match corner {
+                Corner::TopLeft => "border-top-left-radius",
+                Corner::TopRight => "border-top-right-radius",
+                Corner::BottomRight => "border-bottom-right-radius",
+                Corner::BottomLeft => "border-bottom-left-radius",
+            },
+            Self::Width => "width",
+            Self::Height => "height",
+            Self::MinWidth => "min-width",
+            Self::MinHeight => "min-height",
+            Self::MaxWidth => "max-width",
+            Self::MaxHeight => "max-height",
+            Self::BoxSizing => "box-sizing",
+            Self::Overflow => "overflow",
+            Self::Opacity => "opacity",
+            Self::BackgroundColor => "background-color",
+            Self::BackgroundImage => "background-image",
+            Self::FontFamily => "font-family",
+            Self::FontStyle => "font-style",
+            Self::FontWeight => "font-weight",
+            Self::ListStyleType => "list-style-type",
+            Self::TextAlign => "text-align",
+            Self::TextDecorationLine => "text-decoration-line",
+            Self::VerticalAlign => "vertical-align",
+            Self::Visibility => "visibility",
+            Self::WhiteSpace => "white-space",
+            Self::WordBreak => "word-break",
+            Self::OverflowWrap => "overflow-wrap",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|longhand| longhand.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Whether an element takes the value of its parent when no declaration sets it.
+    pub fn inherited(self) -> bool {
+        matches!(
+            self,
+            Self::FontSize
+                | Self::Color
+                | Self::LineHeight
+                | Self::FontFamily
+                | Self::FontStyle
+                | Self::FontWeight
+                | Self::ListStyleType
+                | Self::TextAlign
+                | Self::Visibility
+                | Self::WhiteSpace
+                | Self::WordBreak
+                | Self::OverflowWrap
+        )
+    }
+}
+
+/// The longhands a shorthand sets.
+pub fn shorthand(name: &str) -> Option<Vec<Longhand>> {
+    let border = |side| {
+        vec![
+            Longhand::BorderWidth(side),
+            Longhand::BorderStyle(side),
+            Longhand::BorderColor(side),
+        ]
+    };
+
+    let longhands = match name.to_ascii_lowercase().as_str() {
+        "margin" => sides(Longhand::Margin).to_vec(),
+        "padding" => sides(Longhand::Padding).to_vec(),
+        "border-width" => sides(Longhand::BorderWidth).to_vec(),
+        "border-style" => sides(Longhand::BorderStyle).to_vec(),
+        "border-color" => sides(Longhand::BorderColor).to_vec(),
+        "border-top" => border(Side::Top),
+        "border-right" => border(Side::Right),
+        "border-bottom" => border(Side::Bottom),
+        "border-left" => border(Side::Left),
+        "border" => Side::ALL.into_iter().flat_map(border).collect(),
+        "border-radius" => corners(Longhand::BorderRadius).to_vec(),
+        "background" => vec![Longhand::BackgroundColor, Longhand::BackgroundImage],
+        "font" => vec![
+            Longhand::FontStyle,
+            Longhand::FontWeight,
+            Longhand::FontSize,
+            Longhand::LineHeight,
+            Longhand::FontFamily,
+        ],
+        "text-decoration" => vec![Longhand::TextDecorationLine],
+        // A legacy name for overflow-wrap
+        "word-wrap" => vec![Longhand::OverflowWrap],
+        _ => return None,
+    };
+    Some(longhands)
+}
+
+/// A specified value that has been checked against the grammar of its property.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// A keyword, lowercased.
+    Keyword(String),
+    Length(Length),
+    Percentage(f32),
+    Number(f32),
+    Color(Color),
+    /// A gradient, whose stops have colors and lengths or percentages.
+    Gradient(LinearGradient<Value, Value>),
+    /// Font families, or text decoration lines.
+    List(Vec<String>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Specified {
+    Value(Value),
+    Inherit,
+    Initial,
+    Unset,
+    /// The value of a custom property, or a value with `var()` references that is parsed as
+    /// `property` once they are substituted.
+    Unparsed {
+        property: String,
+        value: Vec<ComponentValue>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Property {
+    Longhand(Longhand),
+    /// A custom property, whose name starts with "--".
+    Custom(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyDeclaration {
+    pub property: Property,
+    pub value: Specified,
+    pub important: bool,
+}
+
+fn items(values: &[ComponentValue]) -> Vec<&ComponentValue> {
+    values
+        .iter()
+        .filter(|value| !value.is_whitespace())
+        .collect()
+}
+
+fn ident(value: &ComponentValue) -> Option<&str> {
+    match value {
+        ComponentValue::Token(Token::Ident(ident)) => Some(ident),
+        _ => None,
+    }
+}
+
+fn is_ident(value: &ComponentValue, expected: &str) -> bool {
+    ident(value).is_some_and(|ident| ident.eq_ignore_ascii_case(expected))
+}
+
+fn keyword<T>(value: &ComponentValue, from_ident: fn(&str) -> Option<T>) -> Option<Value> {
+    let ident = ident(value)?;
+    from_ident(ident).map(|_| Value::Keyword(ident.to_ascii_lowercase()))
+}
+
+fn one_of(value: &ComponentValue, keywords: &[&str]) -> Option<Value> {
+    let ident = ident(value)?.to_ascii_lowercase();
+    keywords
+        .contains(&ident.as_str())
+        .then_some(Value::Keyword(ident))
+}
+
+fn length_percentage(value: &ComponentValue, negative: bool) -> Option<Value> {
+    let value = match value {
+        ComponentValue::Token(Token::Percentage(n)) => Value::Percentage(n.value as f32),
+        _ => Value::Length(Length::parse(value)?),
+    };
+    let sign = match value {
+        Value::Percentage(p) => p,
+        Value::Length(length) => length.value,
+        _ => 0.0,
+    };
+    (negative || sign >= 0.0).then_some(value)
+}
+
+fn number(value: &ComponentValue) -> Option<f32> {
+    match value {
+        ComponentValue::Token(Token::Number(n)) => Some(n.value as f32),
+        _ => None,
+    }
+}
+
+fn color(value: &ComponentValue) -> Option<Value> {
+    if is_ident(value, "currentcolor") {
+        return Some(Value::Keyword("currentcolor".to_owned()));
+    }
+    Color::parse(value).map(Value::Color)
+}
+
+// The family names of `font-family`, where unquoted names are the identifiers between commas
+fn families(items: &[&ComponentValue]) -> Option<Value> {
+    let mut families = vec![];
+    for family in items.split(|item| matches!(item, ComponentValue::Token(Token::Comma))) {
+        let name = match family {
+            [ComponentValue::Token(Token::String(name))] => name.clone(),
+            _ => family
+                .iter()
+                .map(|item| ident(item))
+                .collect::<Option<Vec<_>>>()
+                .filter(|idents| !idents.is_empty())?
+                .join(" "),
+        };
+        families.push(name);
+    }
+    Some(Value::List(families))
+}
+
+fn text_decoration_line(items: &[&ComponentValue]) -> Option<Value> {
+    if let [item] = items {
+        if is_ident(item, "none") {
+            return Some(Value::Keyword("none".to_owned()));
+        }
+    }
+
+    let mut lines = vec![];
+    for item in items {
+        let line = ident(item)?.to_ascii_lowercase();
+        if !["underline", "overline", "line-through"].contains(&line.as_str())
+            || lines.contains(&line)
+        {
+            return None;
+        }
+        lines.push(line);
+    }
+    (!lines.is_empty()).then_some(Value::List(lines))
+}
+
+fn gradient(value: &ComponentValue) -> Option<Value> {
+    LinearGradient::parse(value, color, |value| length_percentage(value, true)).map(Value::Gradient)
+}
+
+fn longhand(longhand: Longhand, value: &ComponentValue) -> Option<Value> {
+    match longhand {
+        Longhand::FontSize => one_of(
+            value,
+            &[
+                "xx-small",
+                "x-small",
+                "small",
+                "medium",
+                "large",
+                "x-large",
+                "xx-large",
+                "xxx-large",
+                "smaller",
+                "larger",
+            ],
+        )
+        .or_else(|| length_percentage(value, false)),
+        Longhand::Color | Longhand::BorderColor(_) | Longhand::BackgroundColor => color(value),
+        Longhand::LineHeight => one_of(value, &["normal"])
+            .or_else(|| number(value).filter(|n| *n >= 0.0).map(Value::Number))
+            .or_else(|| length_percentage(value, false)),
+        Longhand::Display => keyword(value, Display::from_ident),
+        Longhand::Position => keyword(value, Position::from_ident),
+        Longhand::ZIndex => one_of(value, &["auto"]).or(match value {
+            ComponentValue::Token(Token::Number(n)) if n.integer => {
+                Some(Value::Number(n.value as f32))
+            }
+            _ => None,
+        }),
+        Longhand::Margin(_) => one_of(value, &["auto"]).or_else(|| length_percentage(value, true)),
+        Longhand::Padding(_) => length_percentage(value, false),
+        Longhand::BorderWidth(_) => one_of(value, &["thin", "medium", "thick"]).or_else(|| {
+            Length::parse(value)
+                .filter(|l| l.value >= 0.0)
+                .map(Value::Length)
+        }),
+        Longhand::BorderStyle(_) => keyword(value, BorderStyle::from_ident),
+        Longhand::BorderRadius(_) => length_percentage(value, false),
+        Longhand::Width | Longhand::Height | Longhand::MinWidth | Longhand::MinHeight => {
+            one_of(value, &["auto"]).or_else(|| length_percentage(value, false))
+        }
+        Longhand::MaxWidth | Longhand::MaxHeight => {
+            one_of(value, &["none"]).or_else(|| length_percentage(value, false))
+        }
+        Longhand::BoxSizing => keyword(value, BoxSizing::from_ident),
+        Longhand::Overflow => keyword(value, Overflow::from_ident),
+        Longhand::Opacity => match value {
+            ComponentValue::Token(Token::Percentage(n)) => Some(Value::Percentage(n.value as f32)),
+            _ => number(value).map(Value::Number),
+        },
+        Longhand::BackgroundImage => one_of(value, &["none"]).or_else(|| gradient(value)),
+        Longhand::FontFamily => families(&[value]),
+        Longhand::FontStyle => keyword(value, FontStyle::from_ident),
+        Longhand::FontWeight => {
+            one_of(value, &["normal", "bold", "bolder", "lighter"]).or_else(|| {
+                number(value)
+                    .filter(|n| (1.0..=1000.0).contains(n))
+                    .map(Value::Number)
+            })
+        }
+        Longhand::ListStyleType => keyword(value, ListStyleType::from_ident),
+        Longhand::TextAlign => keyword(value, TextAlign::from_ident),
+        Longhand::TextDecorationLine => text_decoration_line(&[value]),
+        Longhand::VerticalAlign => keyword(value, VerticalAlignKeyword::from_ident)
+            .or_else(|| length_percentage(value, true)),
+        Longhand::Visibility => keyword(value, Visibility::from_ident),
+        Longhand::WhiteSpace => keyword(value, WhiteSpace::from_ident),
+        Longhand::WordBreak => keyword(value, WordBreak::from_ident),
+        Longhand::OverflowWrap => keyword(value, OverflowWrap::from_ident),
+    }
+}
+
+// One to four values for the top, right, bottom and left sides, or for the corners clockwise
+// from the top left
+fn four_values(
+    items: &[&ComponentValue],
+    longhands: [Longhand; 4],
+) -> Option<Vec<(Longhand, Value)>> {
+    let values: Vec<Value> = items
+        .iter()
+        .map(|item| self::longhand(longhands[0], item))
+        .collect::<Option<_>>()?;
+    let [top, right, bottom, left] = match values.as_slice() {
+        [all] => [all, all, all, all],
+        [vertical, horizontal] => [vertical, horizontal, vertical, horizontal],
+        [top, horizontal, bottom] => [top, horizontal, bottom, horizontal],
+        [top, right, bottom, left] => [top, right, bottom, left],
+        _ => return None,
+    };
+    Some(
+        longhands
+            .into_iter()
+            .zip([top, right, bottom, left])
+            .map(|(longhand, value)| (longhand, value.clone()))
+            .collect(),
+    )
+}
+
+// A width, style and color in any order, each of which may be left out
+fn border(items: &[&ComponentValue], sides: &[Side]) -> Option<Vec<(Longhand, Value)>> {
+    let mut width = None;
+    let mut style = None;
+    let mut color = None;
+
+    for item in items {
+        if let (None, Some(value)) = (&width, longhand(Longhand::BorderWidth(Side::Top), item)) {
+            width = Some(value);
+        } else if let (None, Some(value)) =
+            (&style, longhand(Longhand::BorderStyle(Side::Top), item))
+        {
+            style = Some(value);
+        } else if let (None, Some(value)) = (&color, self::color(item)) {
+            color = Some(value);
+        } else {
+            return None;
+        }
+    }
+    if items.is_empty() {
+        return None;
+    }
+
+    let width = width.unwrap_or_else(|| Value::Keyword("medium".to_owned()));
+    let style = style.unwrap_or_else(|| Value::Keyword("none".to_owned()));
+    let color = color.unwrap_or_else(|| Value::Keyword("currentcolor".to_owned()));
+    Some(
+        sides
+            .iter()
+            .flat_map(|&side| {
+                [
+                    (Longhand::BorderWidth(side), width.clone()),
+                    (Longhand::BorderStyle(side), style.clone()),
+                    (Longhand::BorderColor(side), color.clone()),
+                ]
+            })
+            .collect(),
+    )
+}
+
+// Only the color and gradient of the `background` shorthand are modelled; other images,
+// positions and the like are accepted and dropped
+fn background(items: &[&ComponentValue]) -> Option<Vec<(Longhand, Value)>> {
+    let mut colors = items.iter().filter_map(|item| Color::parse(item));
+    let color = colors.next().unwrap_or(Color::TRANSPARENT);
+    let mut images = items.iter().filter_map(|item| gradient(item));
+    let image = images
+        .next()
+        .unwrap_or_else(|| Value::Keyword("none".to_owned()));
+    if colors.next().is_some() || images.next().is_some() || items.is_empty() {
+        return None;
+    }
+    Some(vec![
+        (Longhand::BackgroundColor, Value::Color(color)),
+        (Longhand::BackgroundImage, image),
+    ])
+}
+
+// [ style || weight ]? size [ / line-height ]? family
+fn font(items: &[&ComponentValue]) -> Option<Vec<(Longhand, Value)>> {
+    let normal = || Value::Keyword("normal".to_owned());
+    let mut style = None;
+    let mut weight = None;
+    let mut rest = items;
+
+    while let [item, after @ ..] = rest {
+        if is_ident(item, "normal") || is_ident(item, "small-caps") {
+            // The font variant is not modelled
+        } else if let (None, Some(value)) = (&style, longhand(Longhand::FontStyle, item)) {
+            style = Some(value);
+        } else if let (None, Some(value)) = (&weight, longhand(Longhand::FontWeight, item)) {
+            weight = Some(value);
+        } else {
+            break;
+        }
+        rest = after;
+    }
+
+    let (size, mut rest) = rest.split_first()?;
+    let size = longhand(Longhand::FontSize, size)?;
+    let mut line_height = normal();
+    if let [slash, value, after @ ..] = rest {
+        if matches!(slash, ComponentValue::Token(Token::Delim('/'))) {
+            line_height = longhand(Longhand::LineHeight, value)?;
+            rest = after;
+        }
+    }
+    let families = families(rest)?;
+
+    Some(vec![
+        (Longhand::FontStyle, style.unwrap_or_else(normal)),
+        (Longhand::FontWeight, weight.unwrap_or_else(normal)),
+        (Longhand::FontSize, size),
+        (Longhand::LineHeight, line_height),
+        (Longhand::FontFamily, families),
+    ])
+}
+
+// The line of `text-decoration`, with its style and color accepted and dropped
+fn text_decoration(items: &[&ComponentValue]) -> Option<Vec<(Longhand, Value)>> {
+    let lines: Vec<&ComponentValue> = items
+        .iter()
+        .copied()
+        .filter(|item| {
+            Color::parse(item).is_none()
+                && !["solid", "double", "dotted", "dashed", "wavy"]
+                    .iter()
+                    .any(|style| is_ident(item, style))
+        })
+        .collect();
+    let line = if lines.is_empty() {
+        Value::Keyword("none".to_owned())
+    } else {
+        text_decoration_line(&lines)?
+    };
+    Some(vec![(Longhand::TextDecorationLine, line)])
+}
+
+/// Parses the value of a longhand or shorthand into the values of its longhands.  The value
+/// must not contain `var()` or be a CSS-wide keyword.
+pub fn parse_value(name: &str, values: &[ComponentValue]) -> Option<Vec<(Longhand, Value)>> {
+    let items = items(values);
+
+    if let Some(longhand) = Longhand::from_name(name) {
+        let value = match longhand {
+            Longhand::FontFamily => families(&items)?,
+            Longhand::TextDecorationLine => text_decoration_line(&items)?,
+            _ => match items.as_slice() {
+                [item] => self::longhand(longhand, item)?,
+                _ => return None,
+            },
+        };
+        return Some(vec![(longhand, value)]);
+    }
+
+    match name.to_ascii_lowercase().as_str() {
+        "margin" => four_values(&items, sides(Longhand::Margin)),
+        "padding" => four_values(&items, sides(Longhand::Padding)),
+        "border-width" => four_values(&items, sides(Longhand::BorderWidth)),
+        "border-style" => four_values(&items, sides(Longhand::BorderStyle)),
+        "border-color" => four_values(&items, sides(Longhand::BorderColor)),
+        "border-top" => border(&items, &[Side::Top]),
+        "border-right" => border(&items, &[Side::Right]),
+        "border-bottom" => border(&items, &[Side::Bottom]),
+        "border-left" => border(&items, &[Side::Left]),
+        "border" => border(&items, &Side::ALL),
+        "border-radius" => four_values(&items, corners(Longhand::BorderRadius)),
+        "background" => background(&items),
+        "font" => font(&items),
+        "text-decoration" => text_decoration(&items),
+        "word-wrap" => match items.as_slice() {
+            [item] => Some(vec![(
+                Longhand::OverflowWrap,
+                longhand(Longhand::OverflowWrap, item)?,
+            )]),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether `values` reference a custom property.
+pub fn contains_var(values: &[ComponentValue]) -> bool {
+    values.iter().any(|value| match value {
+        ComponentValue::Function { name, .. } if name.eq_ignore_ascii_case("var") => true,
+        ComponentValue::Function { arguments, .. } => contains_var(arguments),
+        ComponentValue::Block { contents, .. } => contains_var(contents),
+        ComponentValue::Token(_) => false,
+    })
+}
+
+fn css_wide_keyword(values: &[ComponentValue]) -> Option<Specified> {
+    let [ComponentValue::Token(Token::Ident(keyword))] = values else {
+        return None;
+    };
+    match keyword.to_ascii_lowercase().as_str() {
+        "inherit" => Some(Specified::Inherit),
+        "initial" => Some(Specified::Initial),
+        // There are no user style sheets or cascade layers to revert to, so reverting a
+        // property unsets it
+        "unset" | "revert" | "revert-layer" => Some(Specified::Unset),
+        _ => None,
+    }
+}
+
+/// The declarations of the longhands a declaration sets, or None if the property is not
+/// known or its value is invalid.
+pub fn parse_declaration(declaration: &Declaration) -> Option<Vec<PropertyDeclaration>> {
+    let Declaration {
+        name,
+        value,
+        important,
+    } = declaration;
+
+    if name.starts_with("--") {
+        let value = css_wide_keyword(value).unwrap_or_else(|| Specified::Unparsed {
+            property: name.clone(),
+            value: value.clone(),
+        });
+        return Some(vec![PropertyDeclaration {
+            property: Property::Custom(name.clone()),
+            value,
+            important: *important,
+        }]);
+    }
+
+    let longhands = match Longhand::from_name(name) {
+        Some(longhand) => vec![longhand],
+        None => shorthand(name)?,
+    };
+    let declare = |(longhand, value)| PropertyDeclaration {
+        property: Property::Longhand(longhand),
+        value,
+        important: *important,
+    };
+
+    let values: Vec<(Longhand, Specified)> = if let Some(keyword) = css_wide_keyword(value) {
+        longhands
+            .into_iter()
+            .map(|longhand| (longhand, keyword.clone()))
+            .collect()
+    } else if contains_var(value) {
+        let unparsed = Specified::Unparsed {
+            property: name.to_ascii_lowercase(),
+            value: value.clone(),
+        };
+        longhands
+            .into_iter()
+            .map(|longhand| (longhand, unparsed.clone()))
+            .collect()
+    } else {
+        parse_value(name, value)?
+            .into_iter()
+            .map(|(longhand, value)| (longhand, Specified::Value(value)))
+            .collect()
+    };
+
+    Some(values.into_iter().map(declare).collect())
+}
@@ -0,0 +1,561 @@
+//! The types of computed values, and the lengths they are computed from.
+use crate::css::{ComponentValue, Token};
+use serde::Serialize;
+use std::fmt;
+
+// An enum of keywords, each written as a string in CSS
+macro_rules! keywords {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident = $css:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+        }
+
+        impl $name {
+            pub fn from_ident(ident: &str) -> Option<Self> {
+                $(if ident.eq_ignore_ascii_case($css) {
+                    return Some(Self::$variant);
+                })*
+                None
+            }
+
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $css,)*
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+    };
+}
+
+keywords! {
+    Display {
+        None = "none",
+        Contents = "contents",
+        Block = "block",
+        Inline = "inline",
+        InlineBlock = "inline-block",
+        ListItem = "list-item",
+        FlowRoot = "flow-root",
+        Table = "table",
+        InlineTable = "inline-table",
+        TableRowGroup = "table-row-group",
+        TableHeaderGroup = "table-header-group",
+        TableFooterGroup = "table-footer-group",
+        TableRow = "table-row",
+        TableCell = "table-cell",
+        TableColumnGroup = "table-column-group",
+        TableColumn = "table-column",
+        TableCaption = "table-caption",
+    }
+}
+
+impl Display {
+    pub fn is_inline_level(self) -> bool {
+        matches!(self, Self::Inline | Self::InlineBlock | Self::InlineTable)
+    }
+
+    /// The block-level equivalent, as used for the root element.
+    /// See https://www.w3.org/TR/css-display-3/#blockify
+    pub fn blockify(self) -> Self {
+        match self {
+            Self::Inline | Self::InlineBlock => Self::Block,
+            Self::InlineTable => Self::Table,
+            Self::TableRowGroup
+            | Self::TableHeaderGroup
+            | Self::TableFooterGroup
+            | Self::TableRow
+            | Self::TableCell
+            | Self::TableColumnGroup
+            | Self::TableColumn
+            | Self::TableCaption => Self::Block,
+            _ => self,
+        }
+    }
+}
+
+keywords! {
+    Position {
+        Static = "static",
+        Relative = "relative",
+        Absolute = "absolute",
+        Fixed = "fixed",
+        Sticky = "sticky",
+    }
+}
+
+keywords! {
+    BorderStyle {
+        None = "none",
+        Hidden = "hidden",
+        Dotted = "dotted",
+        Dashed = "dashed",
+        Solid = "solid",
+        Double = "double",
+        Groove = "groove",
+        Ridge = "ridge",
+        Inset = "inset",
+        Outset = "outset",
+    }
+}
+
+keywords! {
+    BoxSizing {
+        ContentBox = "content-box",
+        BorderBox = "border-box",
+    }
+}
+
+keywords! {
+    Overflow {
+        Visible = "visible",
+        Hidden = "hidden",
+        Clip = "clip",
+        Scroll = "scroll",
+        Auto = "auto",
+    }
+}
+
+keywords! {
+    FontStyle {
+        Normal = "normal",
+        Italic = "italic",
+        Oblique = "oblique",
+    }
+}
+
+keywords! {
+    ListStyleType {
+        Disc = "disc",
+        Circle = "circle",
+        Square = "square",
+        Decimal = "decimal",
+        LowerAlpha = "lower-alpha",
+        UpperAlpha = "upper-alpha",
+        LowerRoman = "lower-roman",
+        UpperRoman = "upper-roman",
+        None = "none",
+    }
+}
+
+keywords! {
+    TextAlign {
+        Start = "start",
+        End = "end",
+        Left = "left",
+        Right = "right",
+        Center = "center",
+        Justify = "justify",
+    }
+}
+
+keywords! {
+    VerticalAlignKeyword {
+        Baseline = "baseline",
+        Sub = "sub",
+        Super = "super",
+        TextTop = "text-top",
+        TextBottom = "text-bottom",
+        Middle = "middle",
+        Top = "top",
+        Bottom = "bottom",
+    }
+}
+
+keywords! {
+    Visibility {
+        Visible = "visible",
+        Hidden = "hidden",
+        Collapse = "collapse",
+    }
+}
+
+keywords! {
+    OverflowWrap {
+        Normal = "normal",
+        BreakWord = "break-word",
+        Anywhere = "anywhere",
+    }
+}
+
+keywords! {
+    WhiteSpace {
+        Normal = "normal",
+        Pre = "pre",
+        Nowrap = "nowrap",
+        PreWrap = "pre-wrap",
+        PreLine = "pre-line",
+        BreakSpaces = "break-spaces",
+    }
+}
+
+keywords! {
+    WordBreak {
+        Normal = "normal",
+        BreakAll = "break-all",
+        KeepAll = "keep-all",
+        BreakWord = "break-word",
+    }
+}
+
+// Numbers are written with at most two decimals, so that dumps do not depend on rounding
+// errors
+pub(crate) struct Number(pub f32);
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rounded = (self.0 * 100.0).round() / 100.0;
+        write!(f, "{}", if rounded == 0.0 { 0.0 } else { rounded })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Px,
+    Em,
+    Rem,
+    Ex,
+    Ch,
+    Pt,
+    Pc,
+    In,
+    Cm,
+    Mm,
+    Q,
+    Vw,
+    Vh,
+    Vmin,
+    Vmax,
+}
+
+impl Unit {
+    pub fn parse(unit: &str) -> Option<Self> {
+        let unit = match unit.to_ascii_lowercase().as_str() {
+            "px" => Self::Px,
+            "em" => Self::Em,
+            "rem" => Self::Rem,
+            "ex" => Self::Ex,
+            "ch" => Self::Ch,
+            "pt" => Self::Pt,
+            "pc" => Self::Pc,
+            "in" => Self::In,
+            "cm" => Self::Cm,
+            "mm" => Self::Mm,
+            "q" => Self::Q,
+            "vw" => Self::Vw,
+            "vh" => Self::Vh,
+            "vmin" => Self::Vmin,
+            "vmax" => Self::Vmax,
+            _ => return None,
+        };
+        Some(unit)
+    }
+}
+
+/// What relative lengths are relative to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LengthContext {
+    pub font_size: f32,
+    pub root_font_size: f32,
+    pub viewport_width: f32,
+    pub viewport_height: f32,
+}
+
+/// A specified length.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Length {
+    pub value: f32,
+    pub unit: Unit,
+}
+
+impl Length {
+    /// Parses a dimension, or a unitless zero.
+    pub fn parse(value: &ComponentValue) -> Option<Self> {
+        match value {
+            ComponentValue::Token(Token::Dimension { number, unit }) => Some(Self {
+                value: number.value as f32,
+                unit: Unit::parse(unit)?,
+            }),
+            ComponentValue::Token(Token::Number(number)) if number.value == 0.0 => Some(Self {
+                value: 0.0,
+                unit: Unit::Px,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The length in CSS pixels.  The font-relative units treat the "x" and "0" as half an em
+    /// wide, since there are no fonts to measure.
+    pub fn to_px(self, cx: &LengthContext) -> f32 {
+        let v = self.value;
+        match self.unit {
+            Unit::Px => v,
+            Unit::Em => v * cx.font_size,
+            Unit::Rem => v * cx.root_font_size,
+            Unit::Ex | Unit::Ch => v * cx.font_size / 2.0,
+            Unit::Pt => v * 96.0 / 72.0,
+            Unit::Pc => v * 16.0,
+            Unit::In => v * 96.0,
+            Unit::Cm => v * 96.0 / 2.54,
+            Unit::Mm => v * 96.0 / 25.4,
+            Unit::Q => v * 96.0 / 101.6,
+            Unit::Vw => v * cx.viewport_width / 100.0,
+            Unit::Vh => v * cx.viewport_height / 100.0,
+            Unit::Vmin => v * cx.viewport_width.min(cx.viewport_height) / 100.0,
+            Unit::Vmax => v * cx.viewport_width.max(cx.viewport_height) / 100.0,
+        }
+    }
+}
+
+/// A computed length in pixels, or a percentage of some length layout knows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LengthPercentage {
+    Length(f32),
+    Percentage(f32),
+}
+
+impl LengthPercentage {
+    pub const ZERO: Self = Self::Length(0.0);
+
+    /// The length in pixels, with percentages taken of `basis`.
+    pub fn resolve(self, basis: f32) -> f32 {
+        match self {
+            Self::Length(px) => px,
+            Self::Percentage(p) => basis * p / 100.0,
+        }
+    }
+}
+
+impl fmt::Display for LengthPercentage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Length(px) => write!(f, "{}px", Number(px)),
+            Self::Percentage(p) => write!(f, "{}%", Number(p)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LengthPercentageOrAuto {
+    LengthPercentage(LengthPercentage),
+    Auto,
+}
+
+impl LengthPercentageOrAuto {
+    pub const ZERO: Self = Self::LengthPercentage(LengthPercentage::ZERO);
+
+    /// The length in pixels, or None for `auto`.
+    pub fn resolve(self, basis: f32) -> Option<f32> {
+        match self {
+            Self::LengthPercentage(lp) => Some(lp.resolve(basis)),
+            Self::Auto => None,
+        }
+    }
+}
+
+impl fmt::Display for LengthPercentageOrAuto {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LengthPercentage(lp) => lp.fmt(f),
+            Self::Auto => f.write_str("auto"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineHeight {
+    Normal,
+    /// A multiple of the font size, inherited as a multiple.
+    Number(f32),
+    Length(f32),
+}
+
+impl LineHeight {
+    /// The height in pixels of a line in a font of `font_size`.
+    pub fn resolve(self, font_size: f32) -> f32 {
+        match self {
+            Self::Normal => font_size * 1.2,
+            Self::Number(n) => font_size * n,
+            Self::Length(px) => px,
+        }
+    }
+}
+
+impl fmt::Display for LineHeight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Normal => f.write_str("normal"),
+            Self::Number(n) => write!(f, "{}", Number(n)),
+            Self::Length(px) => write!(f, "{}px", Number(px)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VerticalAlign {
+    Keyword(VerticalAlignKeyword),
+    /// A shift of the baseline upwards, in pixels.
+    Length(f32),
+}
+
+impl fmt::Display for VerticalAlign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Keyword(keyword) => keyword.fmt(f),
+            Self::Length(px) => write!(f, "{}px", Number(px)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TextDecorationLine {
+    pub underline: bool,
+    pub overline: bool,
+    pub line_through: bool,
+}
+
+impl fmt::Display for TextDecorationLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<&str> = [
+            (self.underline, "underline"),
+            (self.overline, "overline"),
+            (self.line_through, "line-through"),
+        ]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect();
+
+        if lines.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&lines.join(" "))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl Side {
+    pub const ALL: [Self; 4] = [Self::Top, Self::Right, Self::Bottom, Self::Left];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Top => "top",
+            Self::Right => "right",
+            Self::Bottom => "bottom",
+            Self::Left => "left",
+        }
+    }
+}
+
+/// A value for each side of a box.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Sides<T> {
+    pub top: T,
+    pub right: T,
+    pub bottom: T,
+    pub left: T,
+}
+
+impl<T: Copy> Sides<T> {
+    pub fn all(value: T) -> Self {
+        Self {
+            top: value,
+            right: value,
+            bottom: value,
+            left: value,
+        }
+    }
+
+    pub fn get(&self, side: Side) -> T {
+        match side {
+            Side::Top => self.top,
+            Side::Right => self.right,
+            Side::Bottom => self.bottom,
+            Side::Left => self.left,
+        }
+    }
+
+    pub fn set(&mut self, side: Side, value: T) {
+        match side {
+            Side::Top => self.top = value,
+            Side::Right => self.right = value,
+            Side::Bottom => self.bottom = value,
+            Side::Left => self.left = value,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomRight,
+    BottomLeft,
+}
+
+impl Corner {
+    pub const ALL: [Self; 4] = [
+        Self::TopLeft,
+        Self::TopRight,
+        Self::BottomRight,
+        Self::BottomLeft,
+    ];
+}
+
+/// A value for each corner of a box.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Corners<T> {
+    pub top_left: T,
+    pub top_right: T,
+    pub bottom_right: T,
+    pub bottom_left: T,
+}
+
+impl<T: Copy> Corners<T> {
+    pub fn all(value: T) -> Self {
+        Self {
+            top_left: value,
+            top_right: value,
+            bottom_right: value,
+            bottom_left: value,
+        }
+    }
+
+    pub fn get(&self, corner: Corner) -> T {
+        match corner {
+            Corner::TopLeft => self.top_left,
+            Corner::TopRight => self.top_right,
+            Corner::BottomRight => self.bottom_right,
+            Corner::BottomLeft => self.bottom_left,
+        }
+    }
+
+    pub fn set(&mut self, corner: Corner, value: T) {
+        match corner {
+            Corner::TopLeft => self.top_left = value,
+            Corner::TopRight => self.top_right = value,
+            Corner::BottomRight => self.bottom_right = value,
+            Corner::BottomLeft => self.bottom_left = value,
+        }
+    }
+}
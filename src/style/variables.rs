@@ -0,0 +1,140 @@
+//! Custom properties and the substitution of `var()`.
+//! See https://www.w3.org/TR/css-variables-1/
+use super::properties::Specified;
+use crate::css::{ComponentValue, Token};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The computed values of custom properties.  A property that is guaranteed-invalid, such as
+/// one in a reference cycle, is left out.
+pub type CustomProperties = BTreeMap<String, Vec<ComponentValue>>;
+
+// The name and fallback of a `var()`
+fn var_arguments(arguments: &[ComponentValue]) -> Option<(&str, Option<&[ComponentValue]>)> {
+    let mut rest = arguments;
+    while let [ComponentValue::Token(Token::Whitespace), after @ ..] = rest {
+        rest = after;
+    }
+    let [ComponentValue::Token(Token::Ident(name)), after @ ..] = rest else {
+        return None;
+    };
+    if !name.starts_with("--") {
+        return None;
+    }
+
+    rest = after;
+    while let [ComponentValue::Token(Token::Whitespace), after @ ..] = rest {
+        rest = after;
+    }
+    match rest {
+        [] => Some((name, None)),
+        [ComponentValue::Token(Token::Comma), fallback @ ..] => Some((name, Some(fallback))),
+        _ => None,
+    }
+}
+
+/// Replaces the `var()` references in `values` with the values `lookup` gives for them, or
+/// their fallbacks.  None means a reference could not be substituted, which makes the
+/// declaration invalid at computed-value time.
+pub fn substitute(
+    values: &[ComponentValue],
+    lookup: &mut dyn FnMut(&str) -> Option<Vec<ComponentValue>>,
+) -> Option<Vec<ComponentValue>> {
+    let mut substituted = vec![];
+
+    for value in values {
+        match value {
+            ComponentValue::Function { name, arguments } if name.eq_ignore_ascii_case("var") => {
+                let (name, fallback) = var_arguments(arguments)?;
+                match lookup(name) {
+                    Some(value) => substituted.extend(value),
+                    None => substituted.extend(substitute(fallback?, lookup)?),
+                }
+            }
+            ComponentValue::Function { name, arguments } => {
+                substituted.push(ComponentValue::Function {
+                    name: name.clone(),
+                    arguments: substitute(arguments, lookup)?,
+                });
+            }
+            ComponentValue::Block { kind, contents } => {
+                substituted.push(ComponentValue::Block {
+                    kind: *kind,
+                    contents: substitute(contents, lookup)?,
+                });
+            }
+            ComponentValue::Token(_) => substituted.push(value.clone()),
+        }
+    }
+
+    Some(substituted)
+}
+
+struct Resolver<'a> {
+    declared: BTreeMap<&'a str, &'a [ComponentValue]>,
+    inherited: &'a CustomProperties,
+    resolved: BTreeMap<&'a str, Option<Vec<ComponentValue>>>,
+    stack: Vec<&'a str>,
+    cyclic: BTreeSet<&'a str>,
+}
+
+impl Resolver<'_> {
+    fn get(&mut self, name: &str) -> Option<Vec<ComponentValue>> {
+        let Some((&name, &value)) = self.declared.get_key_value(name) else {
+            return self.inherited.get(name).cloned();
+        };
+        if let Some(resolved) = self.resolved.get(name) {
+            return resolved.clone();
+        }
+        if let Some(start) = self.stack.iter().position(|&n| n == name) {
+            self.cyclic.extend(&self.stack[start..]);
+            return None;
+        }
+
+        self.stack.push(name);
+        let substituted = substitute(value, &mut |name| self.get(name));
+        self.stack.pop();
+
+        // Every property in a cycle is invalid, even those with a fallback
+        let substituted = substituted.filter(|_| !self.cyclic.contains(name));
+        self.resolved.insert(name, substituted.clone());
+        substituted
+    }
+}
+
+/// The custom properties of an element, from those it inherits and the winning declarations
+/// of its own.
+pub fn resolve<'a>(
+    declared: impl IntoIterator<Item = (&'a str, &'a Specified)>,
+    inherited: &CustomProperties,
+) -> CustomProperties {
+    let mut custom = inherited.clone();
+    let mut resolver = Resolver {
+        declared: BTreeMap::new(),
+        inherited,
+        resolved: BTreeMap::new(),
+        stack: vec![],
+        cyclic: BTreeSet::new(),
+    };
+
+    for (name, value) in declared {
+        match value {
+            Specified::Initial => {
+                custom.remove(name);
+            }
+            Specified::Unparsed { value, .. } => {
+                resolver.declared.insert(name, value);
+            }
+            // Custom properties are inherited, so these keep the parent's value
+            Specified::Inherit | Specified::Unset | Specified::Value(_) => {}
+        }
+    }
+
+    let names: Vec<&str> = resolver.declared.keys().copied().collect();
+    for name in names {
+        match resolver.get(name) {
+            Some(value) => custom.insert(name.to_owned(), value),
+            None => custom.remove(name),
+        };
+    }
+    custom
+}
@@ -1,3 +1,15 @@
+pub mod conformance;
+pub mod css_parsing;
+pub mod encoding;
+pub mod fuzz;
+pub mod golden;
+pub mod line_break;
+pub mod manifest;
+pub mod render;
+pub mod serializer;
+pub mod tokenizer;
 pub mod tree_construction;
+#[cfg(feature = "quick-xml")]
+pub mod xml5;
 
 pub const FIXTURE_DIR: &str = "./tests/data/html5lib-tests";
@@ -0,0 +1,329 @@
+// Runs the tree construction fixtures against every backend that is built in, for comparing
+// how close the backends are to passing them
+use crate::testing::tree_construction::parser::fixture_from_path;
+use crate::testing::tree_construction::Test;
+use crate::testing::FIXTURE_DIR;
+use crate::types::Result;
+use serde::Serialize;
+use std::any::Any;
+use std::fmt::Write;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+/// Parses a test's input in each of its scripting modes and returns the serialized trees.
+pub type RunFn = fn(&Test) -> Result<Vec<(bool, String)>>;
+
+/// Parses a document with scripting disabled and returns the serialized tree.
+pub type ParseFn = fn(&str) -> Result<String>;
+
+pub struct Backend {
+    pub name: &'static str,
+    pub run: RunFn,
+    pub parse: ParseFn,
+}
+
+#[cfg(any(feature = "html5ever", feature = "quick-xml"))]
+fn run_test<T>(test: &Test) -> Result<Vec<(bool, String)>>
+where
+    T: for<'i> crate::html5::Document<'i, T> + crate::testing::tree_construction::TestSerialization,
+{
+    let mut trees = vec![];
+    for mut result in test.results::<T>()? {
+        let scripting_enabled = result.scripting_enabled;
+        trees.push((scripting_enabled, result.run().0));
+    }
+    Ok(trees)
+}
+
+#[cfg(any(feature = "html5ever", feature = "quick-xml"))]
+fn parse<T>(data: &str) -> Result<String>
+where
+    T: for<'i> crate::html5::Document<'i, T> + crate::testing::tree_construction::TestSerialization,
+{
+    Ok(T::parse_document(data, false)?.serialize())
+}
+
+/// The backends enabled by the crate's features.
+pub fn backends() -> Vec<Backend> {
+    vec![
+        #[cfg(feature = "html5ever")]
+        Backend {
+            name: "html5ever",
+            run: run_test::<crate::html5::html5ever::Dom>,
+            parse: parse::<crate::html5::html5ever::Dom>,
+        },
+        #[cfg(feature = "quick-xml")]
+        Backend {
+            name: "quick_xml",
+            run: run_test::<crate::html5::quick_xml::Dom>,
+            parse: parse::<crate::html5::quick_xml::Dom>,
+        },
+    ]
+}
+
+#[derive(Debug, Serialize)]
+pub struct Mismatch {
+    pub id: String,
+    pub data: String,
+    /// The expected tree against the actual one, with "-" marking lines only expected and "+"
+    /// lines only produced.
+    pub diff: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Panic {
+    pub id: String,
+    pub message: String,
+}
+
+/// The outcomes for one backend over one fixture file.  Tests are counted once per scripting
+/// mode they run in.
+#[derive(Debug, Default, Serialize)]
+pub struct Tally {
+    pub backend: String,
+    pub passed: usize,
+    pub failed: usize,
+    pub panicked: usize,
+    pub first_mismatch: Option<Mismatch>,
+    pub panics: Vec<Panic>,
+}
+
+impl Tally {
+    pub fn total(&self) -> usize {
+        self.passed + self.failed + self.panicked
+    }
+
+    fn add(&mut self, other: &Tally) {
+        self.passed += other.passed;
+        self.failed += other.failed;
+        self.panicked += other.panicked;
+    }
+
+    fn summary(&self) -> String {
+        let mut s = format!("{}/{}", self.passed, self.total());
+        if self.failed > 0 {
+            write!(s, ", {} failed", self.failed).unwrap();
+        }
+        if self.panicked > 0 {
+            write!(s, ", {} panicked", self.panicked).unwrap();
+        }
+        s
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileReport {
+    pub file: String,
+    /// One tally per backend, in the order of `Report::backends`.
+    pub tallies: Vec<Tally>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub backends: Vec<String>,
+    pub files: Vec<FileReport>,
+}
+
+// Compares trees line by line, by way of their longest common subsequence
+fn diff(expected: &str, actual: &str) -> String {
+    let a: Vec<_> = expected.lines().collect();
+    let b: Vec<_> = actual.lines().collect();
+
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            writeln!(out, " {}", a[i]).unwrap();
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            writeln!(out, "-{}", a[i]).unwrap();
+            i += 1;
+        } else {
+            writeln!(out, "+{}", b[j]).unwrap();
+            j += 1;
+        }
+    }
+    out
+}
+
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(s) => *s,
+        Err(payload) => payload
+            .downcast_ref::<&str>()
+            .map_or_else(|| "unknown panic".to_owned(), |s| (*s).to_owned()),
+    }
+}
+
+fn tally(backend: &Backend, tests: &[Test]) -> Tally {
+    let mut tally = Tally {
+        backend: backend.name.to_owned(),
+        ..Tally::default()
+    };
+
+    for test in tests {
+        let trees = match panic::catch_unwind(AssertUnwindSafe(|| (backend.run)(test))) {
+            Ok(Ok(trees)) => trees,
+            Ok(Err(e)) => test
+                .scripting_modes()
+                .into_iter()
+                .map(|scripting_enabled| (scripting_enabled, format!("error: {e}")))
+                .collect(),
+            Err(payload) => {
+                let message = panic_message(payload);
+                for scripting_enabled in test.scripting_modes() {
+                    tally.panicked += 1;
+                    tally.panics.push(Panic {
+                        id: test.id(scripting_enabled),
+                        message: message.clone(),
+                    });
+                }
+                continue;
+            }
+        };
+
+        for (scripting_enabled, actual) in trees {
+            if actual == test.document {
+                tally.passed += 1;
+                continue;
+            }
+
+            tally.failed += 1;
+            tally.first_mismatch.get_or_insert_with(|| Mismatch {
+                id: test.id(scripting_enabled),
+                data: test.data.clone(),
+                diff: diff(&test.document, &actual),
+            });
+        }
+    }
+
+    tally
+}
+
+impl Report {
+    /// Runs every fixture file in `dir` against `backends`.
+    pub fn run_with(dir: &Path, backends: &[Backend]) -> Result<Report> {
+        let mut paths = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "dat") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut files = vec![];
+        for path in paths {
+            let tests = fixture_from_path(&path)?;
+            let tallies = backends
+                .iter()
+                .map(|backend| tally(backend, &tests.tests))
+                .collect();
+
+            files.push(FileReport {
+                file: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                tallies,
+            });
+        }
+
+        Ok(Report {
+            backends: backends.iter().map(|b| b.name.to_owned()).collect(),
+            files,
+        })
+    }
+
+    /// Runs the html5lib tree construction fixtures against every backend.
+    pub fn run() -> Result<Report> {
+        let dir = PathBuf::from(FIXTURE_DIR).join("tree-construction");
+        Self::run_with(&dir, &backends())
+    }
+
+    /// The totals for each backend over all files.
+    pub fn totals(&self) -> Vec<Tally> {
+        let mut totals: Vec<_> = self
+            .backends
+            .iter()
+            .map(|backend| Tally {
+                backend: backend.clone(),
+                ..Tally::default()
+            })
+            .collect();
+
+        for file in &self.files {
+            for (total, tally) in totals.iter_mut().zip(&file.tallies) {
+                total.add(tally);
+            }
+        }
+        totals
+    }
+
+    /// A table of passes per file and backend, followed by the first mismatch and any panics
+    /// for each file a backend does not pass.
+    pub fn to_markdown(&self) -> String {
+        let mut s = String::from("# Tree construction conformance\n\n| File |");
+        for backend in &self.backends {
+            write!(s, " {backend} |").unwrap();
+        }
+        s.push_str("\n| --- |");
+        s.push_str(&" --- |".repeat(self.backends.len()));
+        s.push('\n');
+
+        for file in &self.files {
+            write!(s, "| {} |", file.file).unwrap();
+            for tally in &file.tallies {
+                write!(s, " {} |", tally.summary()).unwrap();
+            }
+            s.push('\n');
+        }
+
+        s.push_str("| **Total** |");
+        for total in self.totals() {
+            write!(s, " **{}** |", total.summary()).unwrap();
+        }
+        s.push('\n');
+
+        let failing = self
+            .files
+            .iter()
+            .flat_map(|file| file.tallies.iter().map(move |tally| (&file.file, tally)))
+            .filter(|(_, tally)| tally.failed > 0 || tally.panicked > 0);
+
+        for (file, tally) in failing {
+            write!(s, "\n## {file}: {}\n", tally.backend).unwrap();
+
+            if let Some(ref mismatch) = tally.first_mismatch {
+                write!(
+                    s,
+                    "\nFirst mismatch, `{}`:\n\n```html\n{}\n```\n\n```diff\n{}```\n",
+                    mismatch.id, mismatch.data, mismatch.diff
+                )
+                .unwrap();
+            }
+
+            if !tally.panics.is_empty() {
+                s.push_str("\nPanics:\n\n");
+                for panic in &tally.panics {
+                    writeln!(s, "- `{}`: {}", panic.id, panic.message).unwrap();
+                }
+            }
+        }
+
+        s
+    }
+}
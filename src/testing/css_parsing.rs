@@ -0,0 +1,258 @@
+// See https://github.com/SimonSapin/css-parsing-tests
+use crate::css::parser::{
+    self, AtRule, BlockKind, ComponentValue, Declaration, DeclarationOrAtRule, Rule, SyntaxError,
+};
+use crate::css::tokenizer::{Numeric, Token};
+use crate::types::{Error, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+
+pub const FIXTURE_DIR: &str = "./tests/data/css-parsing-tests";
+
+/// What a fixture file parses its inputs as, which follows from its name.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    ComponentValueList,
+    OneComponentValue,
+    DeclarationList,
+    OneDeclaration,
+    RuleList,
+    OneRule,
+    Stylesheet,
+}
+
+impl Kind {
+    pub fn from_filename(filename: &str) -> Result<Self> {
+        let kind = match filename {
+            "component_value_list.json" => Self::ComponentValueList,
+            "one_component_value.json" => Self::OneComponentValue,
+            "declaration_list.json" => Self::DeclarationList,
+            "one_declaration.json" => Self::OneDeclaration,
+            "rule_list.json" => Self::RuleList,
+            "one_rule.json" => Self::OneRule,
+            "stylesheet.json" => Self::Stylesheet,
+            _ => return Err(Error::General(format!("unknown fixture: {filename}"))),
+        };
+        Ok(kind)
+    }
+}
+
+fn numeric(kind: &str, number: &Numeric) -> Vec<Value> {
+    let value = if number.integer && number.value.abs() < i64::MAX as f64 {
+        json!(number.value as i64)
+    } else {
+        json!(number.value)
+    };
+    let kind_of_number = if number.integer { "integer" } else { "number" };
+    vec![
+        json!(kind),
+        json!(number.repr),
+        value,
+        json!(kind_of_number),
+    ]
+}
+
+fn token(token: &Token) -> Value {
+    match token {
+        Token::Ident(s) => json!(["ident", s]),
+        Token::Function(s) => json!(["function", s]),
+        Token::AtKeyword(s) => json!(["at-keyword", s]),
+        Token::Hash { value, id } => {
+            json!(["hash", value, if *id { "id" } else { "unrestricted" }])
+        }
+        Token::String(s) => json!(["string", s]),
+        Token::BadString => json!(["error", "bad-string"]),
+        Token::Url(s) => json!(["url", s]),
+        Token::BadUrl => json!(["error", "bad-url"]),
+        Token::Delim(c) => json!(c.to_string()),
+        Token::Number(n) => Value::Array(numeric("number", n)),
+        Token::Percentage(n) => Value::Array(numeric("percentage", n)),
+        Token::Dimension { number, unit } => {
+            let mut parts = numeric("dimension", number);
+            parts.push(json!(unit));
+            Value::Array(parts)
+        }
+        Token::Whitespace => json!(" "),
+        Token::Cdo => json!("<!--"),
+        Token::Cdc => json!("-->"),
+        Token::Colon => json!(":"),
+        Token::Semicolon => json!(";"),
+        Token::Comma => json!(","),
+        Token::OpenSquare => json!("["),
+        Token::OpenParen => json!("("),
+        Token::OpenCurly => json!("{"),
+        Token::CloseSquare => json!(["error", "]"]),
+        Token::CloseParen => json!(["error", ")"]),
+        Token::CloseCurly => json!(["error", "}"]),
+    }
+}
+
+fn component_values(values: &[ComponentValue]) -> Vec<Value> {
+    values.iter().map(component_value).collect()
+}
+
+fn component_value(value: &ComponentValue) -> Value {
+    match value {
+        ComponentValue::Token(t) => token(t),
+        ComponentValue::Block { kind, contents } => {
+            let name = match kind {
+                BlockKind::Curly => "{}",
+                BlockKind::Square => "[]",
+                BlockKind::Paren => "()",
+            };
+            let mut parts = vec![json!(name)];
+            parts.extend(component_values(contents));
+            Value::Array(parts)
+        }
+        ComponentValue::Function { name, arguments } => {
+            let mut parts = vec![json!("function"), json!(name)];
+            parts.extend(component_values(arguments));
+            Value::Array(parts)
+        }
+    }
+}
+
+fn error(e: SyntaxError) -> Value {
+    json!(["error", e.to_string()])
+}
+
+fn declaration(d: &Declaration) -> Value {
+    json!([
+        "declaration",
+        d.name,
+        component_values(&d.value),
+        d.important
+    ])
+}
+
+fn at_rule(rule: &AtRule) -> Value {
+    json!([
+        "at-rule",
+        rule.name,
+        component_values(&rule.prelude),
+        rule.block.as_deref().map(component_values)
+    ])
+}
+
+fn rule(rule: &Rule) -> Value {
+    match rule {
+        Rule::At(rule) => at_rule(rule),
+        Rule::Qualified(rule) => json!([
+            "qualified rule",
+            component_values(&rule.prelude),
+            component_values(&rule.block)
+        ]),
+    }
+}
+
+fn rules(rules: Vec<std::result::Result<Rule, SyntaxError>>) -> Value {
+    rules
+        .into_iter()
+        .map(|r| r.as_ref().map_or_else(|e| error(*e), rule))
+        .collect()
+}
+
+/// Parses `input` as `kind` and converts the result to the form used by the fixtures.
+pub fn parse(kind: Kind, input: &str) -> Value {
+    match kind {
+        Kind::ComponentValueList => {
+            Value::Array(component_values(&parser::parse_component_value_list(input)))
+        }
+        Kind::OneComponentValue => parser::parse_component_value(input)
+            .as_ref()
+            .map_or_else(|e| error(*e), component_value),
+        Kind::DeclarationList => parser::parse_declaration_list(input)
+            .iter()
+            .map(|item| match item {
+                Ok(DeclarationOrAtRule::Declaration(d)) => declaration(d),
+                Ok(DeclarationOrAtRule::AtRule(rule)) => at_rule(rule),
+                Err(e) => error(*e),
+            })
+            .collect(),
+        Kind::OneDeclaration => parser::parse_declaration(input)
+            .as_ref()
+            .map_or_else(|e| error(*e), declaration),
+        Kind::RuleList => rules(parser::parse_rule_list(input)),
+        Kind::OneRule => parser::parse_rule(input)
+            .as_ref()
+            .map_or_else(|e| error(*e), rule),
+        Kind::Stylesheet => rules(parser::parse_stylesheet(input)),
+    }
+}
+
+/// Whether two values in the fixture format are the same.  Numbers are compared as floats,
+/// since the fixtures write whole numbers without a fraction whatever their type.
+pub fn matches(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (
+                a.as_f64().unwrap_or(f64::NAN),
+                b.as_f64().unwrap_or(f64::NAN),
+            );
+            a == b || (a - b).abs() <= 1e-6 * a.abs().max(b.abs())
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| matches(a, b))
+        }
+        (a, b) => a == b,
+    }
+}
+
+#[derive(Debug)]
+pub struct Test {
+    pub input: String,
+    pub expected: Value,
+}
+
+impl Test {
+    /// Returns the actual and expected results.
+    pub fn run(&self, kind: Kind) -> (Value, Value) {
+        (parse(kind, &self.input), self.expected.clone())
+    }
+}
+
+pub struct Tests {
+    pub path: PathBuf,
+    pub kind: Kind,
+    pub tests: Vec<Test>,
+}
+
+impl Tests {
+    pub fn iter(&self) -> impl Iterator<Item = &Test> {
+        self.tests.iter()
+    }
+}
+
+/// Reads fixtures made of inputs alternating with their expected results.
+pub fn fixture_from_str(s: &str, kind: Kind, path: PathBuf) -> Result<Tests> {
+    let values: Vec<Value> = serde_json::from_str(s)?;
+    let mut tests = vec![];
+
+    for pair in values.chunks(2) {
+        let [Value::String(input), expected] = pair else {
+            return Err(Error::General(format!("invalid test: {pair:?}")));
+        };
+        tests.push(Test {
+            input: input.clone(),
+            expected: expected.clone(),
+        });
+    }
+
+    Ok(Tests { path, kind, tests })
+}
+
+pub fn fixture_from_path(path: &PathBuf) -> Result<Tests> {
+    let filename = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let kind = Kind::from_filename(filename)?;
+    let s = fs::read_to_string(path)?;
+    fixture_from_str(&s, kind, path.clone())
+}
+
+pub fn fixture_from_filename(filename: &str) -> Result<Tests> {
+    let path = PathBuf::from(FIXTURE_DIR).join(filename);
+    fixture_from_path(&path)
+}
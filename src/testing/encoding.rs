@@ -0,0 +1,91 @@
+// See https://github.com/html5lib/html5lib-tests/tree/master/encoding
+use crate::html5::Document;
+use crate::testing::FIXTURE_DIR;
+use crate::types::Result;
+use encoding_rs::Encoding;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub struct Test {
+    pub data: Vec<u8>,
+    pub encoding: String,
+}
+
+impl Test {
+    /// Parses the test input as bytes and returns the names of the actual and expected encodings.
+    pub fn run<'i, T>(&self) -> Result<(String, String)>
+    where
+        T: Document<'i, T>,
+    {
+        let (_, encoding) = T::parse_document_bytes(&self.data, None, false)?;
+        let actual = encoding.encoding.name().to_owned();
+        let expected = Encoding::for_label(self.encoding.as_bytes())
+            .map(|encoding| encoding.name().to_owned())
+            .unwrap_or_else(|| self.encoding.to_owned());
+        Ok((actual, expected))
+    }
+}
+
+pub struct Tests {
+    pub path: PathBuf,
+    pub tests: Vec<Test>,
+}
+
+impl Tests {
+    pub fn iter(&self) -> impl Iterator<Item = &Test> {
+        self.tests.iter()
+    }
+}
+
+// The fixtures are read as bytes, since some of them are not valid UTF-8
+fn parse_tests(s: &[u8]) -> Vec<Test> {
+    let mut tests = vec![];
+    let mut data: Option<Vec<&[u8]>> = None;
+    let mut encoding: Option<Vec<&[u8]>> = None;
+
+    macro_rules! finish_test ( () => (
+        if let (Some(data), Some(encoding)) = (data.take(), encoding.take()) {
+            let encoding = String::from_utf8_lossy(encoding.concat().trim_ascii()).into_owned();
+            tests.push(Test {
+                data: data.join(&b'\n'),
+                encoding,
+            });
+        }
+    ));
+
+    for line in s.split(|&b| b == b'\n') {
+        match line {
+            b"#data" => {
+                finish_test!();
+                data = Some(vec![]);
+            }
+            b"#encoding" => encoding = Some(vec![]),
+            line => {
+                if let Some(ref mut encoding) = encoding {
+                    encoding.push(line);
+                } else if let Some(ref mut data) = data {
+                    data.push(line);
+                }
+            }
+        }
+    }
+
+    finish_test!();
+    tests
+}
+
+pub fn fixture_from_path(path: &PathBuf) -> Result<Tests> {
+    let s = fs::read(path)?;
+    let tests = parse_tests(&s);
+
+    Ok(Tests {
+        path: path.into(),
+        tests,
+    })
+}
+
+pub fn fixture_from_filename(filename: &str) -> Result<Tests> {
+    let path = PathBuf::from(FIXTURE_DIR).join("encoding").join(filename);
+    fixture_from_path(&path)
+}
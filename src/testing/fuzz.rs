@@ -0,0 +1,492 @@
+// Differential fuzzing: documents generated at random or mutated from the tree construction
+// fixtures are parsed with every backend, and any input the backends build different trees
+// for, or panic on, is reduced to a minimal one
+use crate::testing::conformance::{backends, panic_message, Backend};
+use crate::testing::tree_construction::{fixture_filenames, fixture_from_filename};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+/// A xorshift generator, so that a seed always gives the same inputs.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..n`, which must not be empty.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn chance(&mut self, one_in: usize) -> bool {
+        self.below(one_in) == 0
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+// Names the tree builder treats specially, along with some it does not know
+const TAGS: &[&str] = &[
+    "html",
+    "head",
+    "body",
+    "title",
+    "meta",
+    "base",
+    "style",
+    "script",
+    "noscript",
+    "template",
+    "p",
+    "div",
+    "span",
+    "a",
+    "b",
+    "i",
+    "u",
+    "font",
+    "nobr",
+    "code",
+    "em",
+    "strong",
+    "big",
+    "s",
+    "h1",
+    "h2",
+    "pre",
+    "listing",
+    "ul",
+    "ol",
+    "li",
+    "dd",
+    "dt",
+    "form",
+    "button",
+    "input",
+    "select",
+    "option",
+    "optgroup",
+    "textarea",
+    "table",
+    "caption",
+    "colgroup",
+    "col",
+    "tbody",
+    "thead",
+    "tr",
+    "td",
+    "th",
+    "frameset",
+    "frame",
+    "iframe",
+    "xmp",
+    "plaintext",
+    "br",
+    "hr",
+    "img",
+    "image",
+    "applet",
+    "marquee",
+    "object",
+    "svg",
+    "math",
+    "foreignObject",
+    "desc",
+    "mi",
+    "mtext",
+    "annotation-xml",
+    "clipPath",
+    "custom-el",
+    "X",
+];
+
+const ATTRIBUTES: &[&str] = &[
+    "id",
+    "class",
+    "href",
+    "type",
+    "name",
+    "color",
+    "face",
+    "size",
+    "encoding",
+    "definitionURL",
+    "xlink:href",
+    "xml:lang",
+    "xmlns",
+    "xmlns:xlink",
+    "viewBox",
+    "CLASS",
+];
+
+const TEXT: &[&str] = &[
+    "x",
+    "Hi",
+    " ",
+    "\n",
+    "\t",
+    "\r\n",
+    "é",
+    "\u{0}",
+    "&",
+    "&amp;",
+    "&lt",
+    "&notin;",
+    "&#0;",
+    "&#x110000;",
+    "&#128;",
+    "<",
+    ">",
+    "=",
+    "'",
+    "\"",
+    "/",
+    "-->",
+    "]]>",
+];
+
+const MARKUP: &[&str] = &[
+    "<!DOCTYPE html>",
+    "<!doctype html public \"-//W3C//DTD HTML 4.01//EN\">",
+    "<!DOCTYPE>",
+    "<!--",
+    "<!-- c -->",
+    "<!--->",
+    "<!",
+    "</",
+    "<?x?>",
+    "<![CDATA[x]]>",
+    "</>",
+    "<a/b>",
+    "<p <p>",
+];
+
+/// Generates random documents and mutates existing ones.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    rng: Rng,
+    seeds: Vec<String>,
+}
+
+impl Generator {
+    pub fn new(seed: u64, seeds: Vec<String>) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            seeds,
+        }
+    }
+
+    fn start_tag(&mut self, s: &mut String) {
+        s.push('<');
+        s.push_str(self.rng.pick(TAGS));
+        for _ in 0..self.rng.below(3) {
+            s.push(' ');
+            s.push_str(self.rng.pick(ATTRIBUTES));
+            match self.rng.below(4) {
+                0 => {}
+                1 => s.push_str("=x"),
+                2 => {
+                    s.push_str("='");
+                    s.push_str(self.rng.pick(TEXT));
+                    s.push('\'');
+                }
+                _ => {
+                    s.push_str("=\"");
+                    s.push_str(self.rng.pick(TEXT));
+                    s.push('"');
+                }
+            }
+        }
+        if self.rng.chance(6) {
+            s.push('/');
+        }
+        s.push('>');
+    }
+
+    fn token(&mut self, s: &mut String) {
+        match self.rng.below(10) {
+            0..=3 => self.start_tag(s),
+            4..=6 => {
+                s.push_str("</");
+                s.push_str(self.rng.pick(TAGS));
+                s.push('>');
+            }
+            7 | 8 => s.push_str(self.rng.pick(TEXT)),
+            _ => s.push_str(self.rng.pick(MARKUP)),
+        }
+    }
+
+    /// A document made of `tokens` random tags, text and other markup.
+    pub fn generate(&mut self, tokens: usize) -> String {
+        let mut s = String::new();
+        for _ in 0..tokens {
+            self.token(&mut s);
+        }
+        s
+    }
+
+    fn boundary(&mut self, s: &str) -> usize {
+        let mut i = self.rng.below(s.len() + 1);
+        while !s.is_char_boundary(i) {
+            i -= 1;
+        }
+        i
+    }
+
+    /// Inserts, deletes, duplicates or splices in markup at random places in `s`.
+    pub fn mutate(&mut self, s: &str) -> String {
+        let mut s = s.to_owned();
+
+        for _ in 0..=self.rng.below(4) {
+            let (a, b) = (self.boundary(&s), self.boundary(&s));
+            let range = a.min(b)..a.max(b);
+
+            match self.rng.below(4) {
+                0 => {
+                    let mut token = String::new();
+                    self.token(&mut token);
+                    s.insert_str(a, &token);
+                }
+                1 => s.replace_range(range, ""),
+                2 => {
+                    let copy = s[range].to_owned();
+                    s.insert_str(self.boundary(&s), &copy);
+                }
+                _ if !self.seeds.is_empty() => {
+                    let other = self.seeds[self.rng.below(self.seeds.len())].clone();
+                    let (c, d) = (self.boundary(&other), self.boundary(&other));
+                    s.insert_str(a, &other[c.min(d)..c.max(d)]);
+                }
+                _ => {}
+            }
+        }
+
+        s
+    }
+
+    /// The next input: a mutated seed half the time when there are seeds, otherwise a new
+    /// document.
+    pub fn next_input(&mut self) -> String {
+        if !self.seeds.is_empty() && self.rng.chance(2) {
+            let seed = self.seeds[self.rng.below(self.seeds.len())].clone();
+            self.mutate(&seed)
+        } else {
+            let tokens = 1 + self.rng.below(16);
+            self.generate(tokens)
+        }
+    }
+}
+
+/// The inputs of the html5lib tree construction tests, or none if the fixtures are missing.
+pub fn fixture_seeds() -> Vec<String> {
+    let Ok(filenames) = fixture_filenames() else {
+        return vec![];
+    };
+
+    filenames
+        .iter()
+        .filter_map(|filename| fixture_from_filename(filename).ok())
+        .flat_map(|tests| tests.tests.into_iter().map(|test| test.data))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Tree(String),
+    Error(String),
+    Panic(String),
+}
+
+/// Parses `input` with `backend`, catching a panic.
+pub fn outcome(backend: &Backend, input: &str) -> Outcome {
+    match panic::catch_unwind(AssertUnwindSafe(|| (backend.parse)(input))) {
+        Ok(Ok(tree)) => Outcome::Tree(tree),
+        Ok(Err(e)) => Outcome::Error(e.to_string()),
+        Err(payload) => Outcome::Panic(panic_message(payload)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// The named backend panicked.
+    Panic(&'static str),
+    /// The backends built different trees, or one of them returned an error.
+    Mismatch,
+}
+
+/// How the backends disagree over `input`, if they do.
+pub fn divergence(backends: &[Backend], input: &str) -> Option<Divergence> {
+    let outcomes: Vec<_> = backends.iter().map(|b| outcome(b, input)).collect();
+
+    if let Some(i) = outcomes.iter().position(|o| matches!(o, Outcome::Panic(_))) {
+        return Some(Divergence::Panic(backends[i].name));
+    }
+    if outcomes.windows(2).any(|pair| pair[0] != pair[1]) {
+        return Some(Divergence::Mismatch);
+    }
+    None
+}
+
+/// Removes chunks of `input`, halving their size down to a character, as long as `keep` holds
+/// for what is left.
+pub fn minimize(input: &str, keep: impl Fn(&str) -> bool) -> String {
+    let mut chars: Vec<char> = input.chars().collect();
+    let mut chunk = chars.len() / 2;
+
+    while chunk > 0 {
+        let mut i = 0;
+        while i < chars.len() {
+            let end = (i + chunk).min(chars.len());
+            let candidate: String = chars[..i].iter().chain(&chars[end..]).collect();
+            if keep(&candidate) {
+                chars.drain(i..end);
+            } else {
+                i += chunk;
+            }
+        }
+        chunk /= 2;
+    }
+
+    chars.into_iter().collect()
+}
+
+// Minimized inputs that differ only in the letters of names are taken as the same finding
+fn shape(input: &str) -> String {
+    input
+        .chars()
+        .map(|c| if c.is_ascii_alphabetic() { 'a' } else { c })
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct Finding {
+    pub divergence: Divergence,
+    /// The number of the input, counting from 0, so it can be generated again from the seed.
+    pub iteration: usize,
+    pub input: String,
+    pub minimized: String,
+    /// The outcome for each backend on the minimized input.
+    pub outcomes: Vec<(&'static str, Outcome)>,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.divergence {
+            Divergence::Panic(name) => writeln!(f, "{name} panics on {:?}", self.minimized)?,
+            Divergence::Mismatch => writeln!(f, "backends differ on {:?}", self.minimized)?,
+        }
+        writeln!(f, "  from input {}: {:?}", self.iteration, self.input)?;
+
+        for (name, outcome) in &self.outcomes {
+            match outcome {
+                Outcome::Tree(tree) => writeln!(f, "  {name}:\n{tree}")?,
+                Outcome::Error(e) => writeln!(f, "  {name}: error: {e}")?,
+                Outcome::Panic(message) => writeln!(f, "  {name}: panicked: {message}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FuzzReport {
+    pub iterations: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl FuzzReport {
+    pub fn panics(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|finding| matches!(finding.divergence, Divergence::Panic(_)))
+    }
+
+    pub fn mismatches(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|finding| finding.divergence == Divergence::Mismatch)
+    }
+}
+
+pub struct Fuzzer {
+    pub seed: u64,
+    /// How long to generate inputs for.  Minimizing the last finding may run over.
+    pub budget: Duration,
+    pub max_iterations: usize,
+    /// Findings past this many are counted as iterations but not minimized or kept.
+    pub max_findings: usize,
+    pub seeds: Vec<String>,
+    pub backends: Vec<Backend>,
+}
+
+impl Fuzzer {
+    /// A fuzzer over every backend, seeded with the fixture inputs if they are there.
+    pub fn new(seed: u64, budget: Duration) -> Self {
+        Self {
+            seed,
+            budget,
+            max_iterations: usize::MAX,
+            max_findings: 20,
+            seeds: fixture_seeds(),
+            backends: backends(),
+        }
+    }
+
+    pub fn run(&self) -> FuzzReport {
+        let started = Instant::now();
+        let mut generator = Generator::new(self.seed, self.seeds.clone());
+        let mut report = FuzzReport::default();
+
+        while report.iterations < self.max_iterations && started.elapsed() < self.budget {
+            let iteration = report.iterations;
+            let input = generator.next_input();
+            report.iterations += 1;
+
+            if report.findings.len() >= self.max_findings {
+                continue;
+            }
+            let Some(kind) = divergence(&self.backends, &input) else {
+                continue;
+            };
+
+            let minimized = minimize(&input, |candidate| {
+                divergence(&self.backends, candidate).as_ref() == Some(&kind)
+            });
+            if report
+                .findings
+                .iter()
+                .any(|f| f.divergence == kind && shape(&f.minimized) == shape(&minimized))
+            {
+                continue;
+            }
+
+            let outcomes = self
+                .backends
+                .iter()
+                .map(|backend| (backend.name, outcome(backend, &minimized)))
+                .collect();
+            report.findings.push(Finding {
+                divergence: kind,
+                iteration,
+                input,
+                minimized,
+                outcomes,
+            });
+        }
+
+        report
+    }
+}
@@ -0,0 +1,25 @@
+//! Golden files: checked-in expected output that tests compare against.
+use crate::types::{Error, Result};
+use std::path::PathBuf;
+use std::{env, fs};
+
+pub const GOLDEN_DIR: &str = "./tests/golden";
+
+/// The contents of the golden file `name`, relative to the golden directory.  If
+/// `REGENERATE_GOLDEN` is set, the file is first rewritten with `actual`.
+pub fn expected(name: &str, actual: &str) -> Result<String> {
+    let path = PathBuf::from(GOLDEN_DIR).join(name);
+    if env::var_os("REGENERATE_GOLDEN").is_some() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, actual)?;
+    }
+
+    fs::read_to_string(&path).map_err(|err| {
+        Error::General(format!(
+            "{}: {err}; `make golden` writes it",
+            path.display()
+        ))
+    })
+}
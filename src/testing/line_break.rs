@@ -0,0 +1,87 @@
+use crate::layout::text::line_break;
+use crate::types::{Error, Result};
+use std::fs;
+use std::path::PathBuf;
+
+// LineBreakTest.txt is committed as published for Unicode 15.0.0, the version the line
+// breaking data comes from, at https://www.unicode.org/Public/15.0.0/ucd/auxiliary/.  It is
+// updated along with `line_break::UNICODE_VERSION`.
+pub const FIXTURE_DIR: &str = "./tests/data/unicode";
+
+/// The version of Unicode the committed fixture is from.
+pub const FIXTURE_VERSION: (u8, u8, u8) = (15, 0, 0);
+
+#[derive(Debug)]
+pub struct Test {
+    pub line: usize,
+    pub input: String,
+    /// The byte offsets after each break.
+    pub expected: Vec<usize>,
+}
+
+impl Test {
+    /// Returns the actual and expected breaks.
+    pub fn run(&self) -> (Vec<usize>, Vec<usize>) {
+        let actual = line_break::line_breaks(&self.input)
+            .into_iter()
+            .map(|(offset, _)| offset)
+            .collect();
+        (actual, self.expected.clone())
+    }
+}
+
+pub struct Tests {
+    pub path: PathBuf,
+    pub tests: Vec<Test>,
+}
+
+impl Tests {
+    pub fn iter(&self) -> impl Iterator<Item = &Test> {
+        self.tests.iter()
+    }
+}
+
+/// Reads lines of code points between `÷` where a line breaks and `×` where it does not,
+/// each with an optional comment after `#`.
+pub fn fixture_from_str(s: &str, path: PathBuf) -> Result<Tests> {
+    let mut tests = vec![];
+
+    for (i, line) in s.lines().enumerate() {
+        let line_number = i + 1;
+        let data = line.split('#').next().unwrap_or_default().trim();
+        if data.is_empty() {
+            continue;
+        }
+        let invalid = || Error::General(format!("invalid test on line {line_number}: {line:?}"));
+
+        let mut input = String::new();
+        let mut expected = vec![];
+        for (j, field) in data.split_whitespace().enumerate() {
+            match (j % 2, field) {
+                (0, "÷") if j > 0 => expected.push(input.len()),
+                (0, "×") => {}
+                (1, _) => {
+                    let c = u32::from_str_radix(field, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(invalid)?;
+                    input.push(c);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        tests.push(Test {
+            line: line_number,
+            input,
+            expected,
+        });
+    }
+
+    Ok(Tests { path, tests })
+}
+
+pub fn fixture_from_filename(filename: &str) -> Result<Tests> {
+    let path = PathBuf::from(FIXTURE_DIR).join(filename);
+    let s = fs::read_to_string(&path)?;
+    fixture_from_str(&s, path)
+}
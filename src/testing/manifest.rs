@@ -0,0 +1,138 @@
+use crate::types::Result;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+pub const MANIFEST_DIR: &str = "./tests/expected-failures";
+
+/// The tests of a fixture suite a backend is expected to fail, by id, one per line.  Ids start
+/// with the fixture file and "#".  Lines starting with "#" are comments.
+#[derive(Debug)]
+pub struct Manifest {
+    pub path: PathBuf,
+    suite: String,
+    name: String,
+    entries: BTreeSet<String>,
+}
+
+impl Manifest {
+    /// Loads the manifest of the `suite` tests for the backend called `name`.  A missing
+    /// manifest expects every test to pass.
+    pub fn load(suite: &str, name: &str) -> Result<Self> {
+        let path = PathBuf::from(MANIFEST_DIR)
+            .join(suite)
+            .join(format!("{name}.txt"));
+        let entries = match fs::read_to_string(&path) {
+            Ok(s) => s
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_owned)
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeSet::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path,
+            suite: suite.to_owned(),
+            name: name.to_owned(),
+            entries,
+        })
+    }
+
+    /// Compares the outcomes of every test in the given fixture files, by id and whether the
+    /// test passed, with the manifest.
+    pub fn check<I>(&self, outcomes: I, filenames: &[String]) -> ManifestReport
+    where
+        I: IntoIterator<Item = (String, bool)>,
+    {
+        let mut report = ManifestReport::default();
+        let mut seen = BTreeSet::new();
+
+        for (id, passed) in outcomes {
+            match (passed, self.entries.contains(&id)) {
+                (false, false) => report.unexpected_failures.push(id.clone()),
+                (true, true) => report.unexpected_passes.push(id.clone()),
+                _ => {}
+            }
+            if !passed {
+                report.failures.push(id.clone());
+            }
+            seen.insert(id);
+        }
+
+        // Entries for tests that no longer exist are as good as passes
+        let checked = |entry: &String| filenames.iter().any(|filename| filename == file(entry));
+        report.unexpected_passes.extend(
+            self.entries
+                .iter()
+                .filter(|entry| checked(entry) && !seen.contains(*entry))
+                .cloned(),
+        );
+
+        report
+    }
+
+    /// Replaces the manifest with the given failures.
+    pub fn write(&self, failures: &[String]) -> Result<()> {
+        let mut s = format!(
+            "# {} tests expected to fail with the {} backend\n\
+             # Regenerate with `make expected-failures`\n",
+            self.suite, self.name
+        );
+        for id in failures {
+            s.push_str(id);
+            s.push('\n');
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, s)?;
+        Ok(())
+    }
+}
+
+// The fixture file a test id or manifest entry belongs to
+fn file(id: &str) -> &str {
+    id.split('#').next().unwrap_or_default()
+}
+
+#[derive(Debug, Default)]
+pub struct ManifestReport {
+    /// Every test that failed, whether expected to or not.
+    pub failures: Vec<String>,
+    pub unexpected_failures: Vec<String>,
+    /// Tests the manifest expects to fail that passed, and entries for tests that no longer
+    /// exist.
+    pub unexpected_passes: Vec<String>,
+}
+
+impl ManifestReport {
+    pub fn is_ok(&self) -> bool {
+        self.unexpected_failures.is_empty() && self.unexpected_passes.is_empty()
+    }
+}
+
+impl fmt::Display for ManifestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} failures, {} unexpected, {} unexpected passes",
+            self.failures.len(),
+            self.unexpected_failures.len(),
+            self.unexpected_passes.len()
+        )?;
+
+        for id in &self.unexpected_failures {
+            writeln!(f, "  unexpected failure: {id}")?;
+        }
+        for id in &self.unexpected_passes {
+            writeln!(f, "  unexpected pass: {id}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+// Shared setup for the box tree, layout and paint tests, which run small documents through
+// each backend
+use crate::html5::Document;
+use crate::layout::{layout, BoxTree, FragmentTree};
+use crate::style::Viewport;
+
+/// A test helper for one backend, so that a test can take it as an argument and run with
+/// each, e.g. `#[test_case(dump::<html5ever::Dom>; "html5ever")]`.
+pub type WithBackend<R> = fn(&str) -> R;
+
+/// Text is in the Ahem font, so every character is a square one em wide.  This makes the em
+/// 10px with no page margin, so that positions are easy to work out.
+pub const STYLE: &str = "<style>body { margin: 0; font-size: 10px; line-height: 10px }</style>";
+
+/// The viewport of the paint tests, small enough to check pixel by pixel.
+pub const CANVAS: Viewport = Viewport {
+    width: 100.0,
+    height: 100.0,
+};
+
+/// Parses `html` with `STYLE` in front and lays it out in `viewport`, returning the document
+/// along with its fragments.
+pub fn lay_out<T: for<'i> Document<'i, T>>(html: &str, viewport: &Viewport) -> (T, FragmentTree) {
+    let dom = T::parse_document(&format!("{STYLE}{html}"), false).unwrap();
+    let styles = dom.compute_styles(viewport);
+    let boxes = BoxTree::new(dom.arena(), &styles);
+    let fragments = layout(&boxes, viewport);
+    (dom, fragments)
+}
@@ -0,0 +1,237 @@
+// See https://github.com/html5lib/html5lib-tests/tree/master/serializer
+use crate::dom::serialize::{serialize_tokens, AttrQuoting, SerializeOpts, Token};
+use crate::dom::Attribute;
+use crate::testing::FIXTURE_DIR;
+use crate::types::{Error, Result};
+use markup5ever::{namespace_url, ns, LocalName, Namespace, QualName};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub struct Test {
+    pub description: String,
+    pub input: Vec<Token>,
+    pub expected: Vec<String>,
+    pub opts: SerializeOpts,
+}
+
+impl Test {
+    /// Serializes the input tokens and returns the output along with the acceptable outputs.
+    pub fn run(&self) -> (String, &[String]) {
+        let actual = serialize_tokens(self.input.clone(), &self.opts);
+        (actual, &self.expected)
+    }
+}
+
+pub struct Tests {
+    pub path: PathBuf,
+    pub tests: Vec<Test>,
+}
+
+impl Tests {
+    pub fn iter(&self) -> impl Iterator<Item = &Test> {
+        self.tests.iter()
+    }
+}
+
+#[derive(Deserialize)]
+struct RawTests {
+    #[serde(default)]
+    tests: Vec<RawTest>,
+}
+
+#[derive(Deserialize)]
+struct RawTest {
+    description: String,
+    input: Vec<Value>,
+    expected: Vec<String>,
+    #[serde(default)]
+    options: Map<String, Value>,
+}
+
+// The options the html5lib test runner serializes with, unless a test overrides them
+fn default_opts() -> SerializeOpts {
+    SerializeOpts {
+        scripting_enabled: true,
+        quote_attr_values: AttrQuoting::Legacy,
+        use_best_quote_char: true,
+        minimize_boolean_attributes: true,
+        omit_optional_tags: true,
+        alphabetical_attributes: true,
+        doctype_ids: true,
+        ..SerializeOpts::default()
+    }
+}
+
+fn make_opts(options: &Map<String, Value>) -> Result<SerializeOpts> {
+    let mut opts = default_opts();
+
+    for (key, value) in options {
+        let invalid = || Error::General(format!("invalid option: {key}: {value}"));
+        let flag = || value.as_bool().ok_or_else(invalid);
+
+        match key.as_str() {
+            "quote_attr_values" => {
+                opts.quote_attr_values = match value {
+                    Value::Bool(true) => AttrQuoting::Always,
+                    Value::Bool(false) => AttrQuoting::Legacy,
+                    Value::String(s) if s == "always" => AttrQuoting::Always,
+                    Value::String(s) if s == "spec" => AttrQuoting::Spec,
+                    Value::String(s) if s == "legacy" => AttrQuoting::Legacy,
+                    _ => return Err(invalid()),
+                }
+            }
+            "quote_char" => {
+                opts.quote_char = value
+                    .as_str()
+                    .and_then(|s| s.chars().next())
+                    .ok_or_else(invalid)?
+            }
+            "use_best_quote_char" => opts.use_best_quote_char = flag()?,
+            "minimize_boolean_attributes" => opts.minimize_boolean_attributes = flag()?,
+            "use_trailing_solidus" => opts.use_trailing_solidus = flag()?,
+            "space_before_trailing_solidus" => opts.space_before_trailing_solidus = flag()?,
+            "escape_lt_in_attrs" => opts.escape_lt_in_attrs = flag()?,
+            "escape_rcdata" => opts.escape_rcdata = flag()?,
+            "omit_optional_tags" => opts.omit_optional_tags = flag()?,
+            "strip_whitespace" => opts.strip_whitespace = flag()?,
+            "alphabetical_attributes" => opts.alphabetical_attributes = flag()?,
+            "inject_meta_charset" => {
+                if !flag()? {
+                    opts.inject_meta_charset = None;
+                }
+            }
+            "encoding" => {
+                opts.inject_meta_charset = Some(value.as_str().ok_or_else(invalid)?.to_owned())
+            }
+            // Entity tokens do not occur in the fixtures
+            "resolve_entities" => {}
+            _ => return Err(Error::General(format!("unknown option: {key}"))),
+        }
+    }
+
+    // As in html5lib, an explicit quote character is always used
+    if options.contains_key("quote_char") {
+        opts.use_best_quote_char = false;
+    }
+
+    Ok(opts)
+}
+
+fn make_attrs(value: &Value) -> Result<Vec<Attribute>> {
+    let invalid = || Error::General(format!("invalid attributes: {value}"));
+    let mut attrs = vec![];
+
+    // Tags without attributes sometimes give them as an empty object
+    if value.as_object().is_some_and(Map::is_empty) {
+        return Ok(attrs);
+    }
+
+    for attr in value.as_array().ok_or_else(invalid)? {
+        let string = |key: &str| attr.get(key).and_then(Value::as_str);
+        let ns = string("namespace").map(Namespace::from).unwrap_or(ns!());
+        let name = string("name").ok_or_else(invalid)?;
+        let value = string("value").ok_or_else(invalid)?;
+
+        attrs.push(Attribute {
+            name: QualName::new(None, ns, LocalName::from(name)),
+            value: value.to_owned(),
+        });
+    }
+
+    Ok(attrs)
+}
+
+// Tags may leave out the namespace, in which case it is the HTML namespace
+fn make_token(value: &Value) -> Result<Token> {
+    let invalid = || Error::General(format!("invalid token: {value}"));
+    let parts = value.as_array().ok_or_else(invalid)?;
+    let kind = parts.first().and_then(Value::as_str).ok_or_else(invalid)?;
+    let string = |i: usize| parts.get(i).and_then(Value::as_str);
+
+    let tag = |with_attrs: bool| -> Result<(QualName, &Value)> {
+        let (ns, name, attrs) = match (parts.len(), with_attrs) {
+            (4, true) | (3, false) => (string(1), string(2), parts.last()),
+            (3, true) | (2, false) => (None, string(1), parts.last()),
+            _ => return Err(invalid()),
+        };
+        let ns = ns.map(Namespace::from).unwrap_or(ns!(html));
+        let name = name.ok_or_else(invalid)?;
+        Ok((
+            QualName::new(None, ns, LocalName::from(name)),
+            attrs.ok_or_else(invalid)?,
+        ))
+    };
+
+    let token = match kind {
+        "StartTag" => {
+            let (name, attrs) = tag(true)?;
+            Token::StartTag {
+                name,
+                attrs: make_attrs(attrs)?,
+            }
+        }
+
+        "EmptyTag" => {
+            let (name, attrs) = tag(true)?;
+            Token::EmptyTag {
+                name,
+                attrs: make_attrs(attrs)?,
+            }
+        }
+
+        "EndTag" => Token::EndTag {
+            name: tag(false)?.0,
+        },
+
+        "Characters" | "SpaceCharacters" => Token::Text(string(1).ok_or_else(invalid)?.into()),
+        "Comment" => Token::Comment(string(1).ok_or_else(invalid)?.into()),
+
+        "Doctype" => Token::Doctype {
+            name: string(1).unwrap_or_default().into(),
+            public_id: string(2).unwrap_or_default().into(),
+            system_id: string(3).unwrap_or_default().into(),
+        },
+
+        _ => return Err(invalid()),
+    };
+
+    Ok(token)
+}
+
+fn make_test(raw: RawTest) -> Result<Test> {
+    let input = raw
+        .input
+        .iter()
+        .map(make_token)
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Test {
+        description: raw.description,
+        input,
+        expected: raw.expected,
+        opts: make_opts(&raw.options)?,
+    })
+}
+
+pub fn fixture_from_path(path: &PathBuf) -> Result<Tests> {
+    let s = fs::read_to_string(path)?;
+    let raw: RawTests = serde_json::from_str(&s)?;
+    let tests = raw
+        .tests
+        .into_iter()
+        .map(make_test)
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Tests {
+        path: path.into(),
+        tests,
+    })
+}
+
+pub fn fixture_from_filename(filename: &str) -> Result<Tests> {
+    let path = PathBuf::from(FIXTURE_DIR).join("serializer").join(filename);
+    fixture_from_path(&path)
+}
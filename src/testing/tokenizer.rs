@@ -0,0 +1,484 @@
+// See https://github.com/html5lib/html5lib-tests/tree/master/tokenizer
+use crate::testing::FIXTURE_DIR;
+use crate::types::{Error, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::iter::Peekable;
+use std::path::PathBuf;
+use std::str::{Chars, FromStr};
+
+#[cfg(feature = "html5ever")]
+mod html5ever;
+#[cfg(feature = "quick-xml")]
+mod quick_xml;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InitialState {
+    Data,
+    Plaintext,
+    Rcdata,
+    Rawtext,
+    ScriptData,
+    CdataSection,
+}
+
+impl FromStr for InitialState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let state = match s {
+            "Data state" => Self::Data,
+            "PLAINTEXT state" => Self::Plaintext,
+            "RCDATA state" => Self::Rcdata,
+            "RAWTEXT state" => Self::Rawtext,
+            "Script data state" => Self::ScriptData,
+            "CDATA section state" => Self::CdataSection,
+            _ => return Err(Error::General(format!("unknown initial state: {s}"))),
+        };
+        Ok(state)
+    }
+}
+
+#[cfg(feature = "html5ever")]
+impl InitialState {
+    // The html5ever tokenizer state, which both backends start from
+    fn state(self) -> ::html5ever::tokenizer::states::State {
+        use ::html5ever::tokenizer::states::{RawKind, State};
+
+        match self {
+            Self::Data => State::Data,
+            Self::Plaintext => State::Plaintext,
+            Self::Rcdata => State::RawData(RawKind::Rcdata),
+            Self::Rawtext => State::RawData(RawKind::Rawtext),
+            Self::ScriptData => State::RawData(RawKind::ScriptData),
+            Self::CdataSection => State::CdataSection,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Doctype {
+        name: Option<String>,
+        public_id: Option<String>,
+        system_id: Option<String>,
+        correctness: bool,
+    },
+
+    StartTag {
+        name: String,
+        attrs: BTreeMap<String, String>,
+        self_closing: bool,
+    },
+
+    EndTag {
+        name: String,
+    },
+
+    Comment(String),
+
+    Character(String),
+}
+
+/// The tokens and the lines of the parse errors.  Errors are compared by line alone, since
+/// neither backend reports the fixtures' error codes: html5ever's messages are free text,
+/// without a column, and quick-xml's are for markup it cannot read, at the point it gave up,
+/// rather than for the errors the tokenizer states define.
+#[derive(Debug, PartialEq)]
+pub struct Output {
+    pub tokens: Vec<Token>,
+    pub errors: Vec<u64>,
+}
+
+impl Output {
+    // Adjacent character tokens are compared as a single token
+    fn new(tokens: impl IntoIterator<Item = Token>, errors: Vec<u64>) -> Self {
+        let mut merged: Vec<Token> = vec![];
+
+        for token in tokens {
+            match (merged.last_mut(), token) {
+                (Some(Token::Character(ref mut buf)), Token::Character(s)) => buf.push_str(&s),
+                (_, token) => merged.push(token),
+            }
+        }
+
+        Self {
+            tokens: merged,
+            errors,
+        }
+    }
+}
+
+/// Converts the tokens handed to a tree builder, with the lines they were seen on, into the form
+/// used by the fixtures.
+#[cfg(feature = "html5ever")]
+fn output(tokens: impl IntoIterator<Item = (::html5ever::tokenizer::Token, u64)>) -> Output {
+    use ::html5ever::tokenizer::{TagKind, Token as Html5Token};
+
+    let mut converted = vec![];
+    let mut errors = vec![];
+
+    for (token, line) in tokens {
+        let token = match token {
+            Html5Token::DoctypeToken(doctype) => Token::Doctype {
+                name: doctype.name.map(|s| s.to_string()),
+                public_id: doctype.public_id.map(|s| s.to_string()),
+                system_id: doctype.system_id.map(|s| s.to_string()),
+                correctness: !doctype.force_quirks,
+            },
+
+            Html5Token::TagToken(tag) => match tag.kind {
+                TagKind::StartTag => Token::StartTag {
+                    name: tag.name.to_string(),
+                    attrs: tag
+                        .attrs
+                        .into_iter()
+                        .map(|attr| (attr.name.local.to_string(), attr.value.to_string()))
+                        .collect(),
+                    self_closing: tag.self_closing,
+                },
+                TagKind::EndTag => Token::EndTag {
+                    name: tag.name.to_string(),
+                },
+            },
+
+            Html5Token::CommentToken(s) => Token::Comment(s.to_string()),
+            Html5Token::CharacterTokens(s) => Token::Character(s.to_string()),
+            Html5Token::NullCharacterToken => Token::Character("\0".into()),
+
+            Html5Token::ParseError(_) => {
+                errors.push(line);
+                continue;
+            }
+
+            Html5Token::EOFToken => continue,
+        };
+
+        converted.push(token);
+    }
+
+    Output::new(converted, errors)
+}
+
+pub trait TestTokenization {
+    fn tokenize(input: &str, state: InitialState, last_start_tag: Option<&str>) -> Result<Output>;
+}
+
+#[derive(Debug)]
+pub struct ExpectedError {
+    pub code: String,
+    pub line: usize,
+    pub col: usize,
+}
+
+#[derive(Debug)]
+pub struct Test {
+    pub file: String,
+    pub index: usize,
+    pub description: String,
+    pub input: String,
+    pub output: Vec<Token>,
+    pub initial_states: Vec<InitialState>,
+    pub last_start_tag: Option<String>,
+    pub errors: Vec<ExpectedError>,
+}
+
+impl Test {
+    /// Identifies the test run from the given initial state, e.g. "test1.test#4:Rawtext".
+    pub fn id(&self, state: InitialState) -> String {
+        format!("{}#{}:{state:?}", self.file, self.index)
+    }
+
+    pub fn results<T: TestTokenization>(&self) -> Result<Vec<TokenizerResult<'_>>> {
+        let mut results = vec![];
+
+        for &state in &self.initial_states {
+            let actual = T::tokenize(&self.input, state, self.last_start_tag.as_deref())?;
+            results.push(TokenizerResult {
+                test: self,
+                state,
+                actual,
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+pub struct TokenizerResult<'i> {
+    test: &'i Test,
+    pub state: InitialState,
+    actual: Output,
+}
+
+impl TokenizerResult<'_> {
+    pub fn run(self) -> (Output, Output) {
+        let errors = self.test.errors.iter().map(|e| e.line as u64).collect();
+        let expected = Output::new(self.test.output.clone(), errors);
+        (self.actual, expected)
+    }
+}
+
+pub struct Tests {
+    pub path: PathBuf,
+    pub tests: Vec<Test>,
+    /// Ids of the tests left out because they contain lone surrogates, e.g. "test1.test#6".
+    pub skipped: Vec<String>,
+}
+
+impl Tests {
+    pub fn iter(&self) -> impl Iterator<Item = &Test> {
+        self.tests.iter()
+    }
+}
+
+#[derive(Deserialize)]
+struct RawTests {
+    #[serde(default)]
+    tests: Vec<RawTest>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawTest {
+    description: String,
+    input: String,
+    output: Vec<Value>,
+    #[serde(default)]
+    initial_states: Vec<String>,
+    last_start_tag: Option<String>,
+    #[serde(default)]
+    double_escaped: bool,
+    #[serde(default)]
+    errors: Vec<RawError>,
+}
+
+#[derive(Deserialize)]
+struct RawError {
+    code: String,
+    line: usize,
+    col: usize,
+}
+
+fn hex(chars: &mut Peekable<Chars<'_>>) -> Option<u32> {
+    let digits: String = chars.by_ref().take(4).collect();
+    u32::from_str_radix(&digits, 16).ok()
+}
+
+// Double-escaped fixtures spell out code points as "\uXXXX" so that lone surrogates can be
+// expressed.  Those cannot be held in a Rust string, so None is returned for them.
+fn unescape(s: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' || chars.peek() != Some(&'u') {
+            out.push(c);
+            continue;
+        }
+
+        chars.next();
+        let high = hex(&mut chars)?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if chars.next() != Some('\\') || chars.next() != Some('u') {
+                return None;
+            }
+            let low = hex(&mut chars)?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return None;
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+
+        out.push(char::from_u32(code)?);
+    }
+
+    Some(out)
+}
+
+fn text(s: &str, double_escaped: bool) -> Option<String> {
+    if double_escaped {
+        unescape(s)
+    } else {
+        Some(s.to_owned())
+    }
+}
+
+fn string(value: &Value, double_escaped: bool) -> Option<Option<String>> {
+    match value {
+        Value::Null => Some(None),
+        Value::String(s) => text(s, double_escaped).map(Some),
+        _ => None,
+    }
+}
+
+fn make_token(value: &Value, double_escaped: bool) -> Result<Option<Token>> {
+    let invalid = || Error::General(format!("invalid token: {value}"));
+    let parts = value.as_array().ok_or_else(invalid)?;
+    let kind = parts.first().and_then(Value::as_str).ok_or_else(invalid)?;
+    let part = |i: usize| parts.get(i).unwrap_or(&Value::Null);
+
+    macro_rules! string {
+        ($i:expr) => {
+            match string(part($i), double_escaped) {
+                Some(s) => s,
+                None => return Ok(None),
+            }
+        };
+    }
+
+    let token = match kind {
+        "DOCTYPE" => Token::Doctype {
+            name: string!(1),
+            public_id: string!(2),
+            system_id: string!(3),
+            correctness: part(4).as_bool().ok_or_else(invalid)?,
+        },
+
+        "StartTag" => {
+            let mut attrs = BTreeMap::new();
+            for (name, value) in part(2).as_object().ok_or_else(invalid)? {
+                let (Some(name), Some(Some(value))) =
+                    (text(name, double_escaped), string(value, double_escaped))
+                else {
+                    return Ok(None);
+                };
+                attrs.insert(name, value);
+            }
+
+            Token::StartTag {
+                name: string!(1).ok_or_else(invalid)?,
+                attrs,
+                self_closing: part(3).as_bool().unwrap_or(false),
+            }
+        }
+
+        "EndTag" => Token::EndTag {
+            name: string!(1).ok_or_else(invalid)?,
+        },
+
+        "Comment" => Token::Comment(string!(1).ok_or_else(invalid)?),
+        "Character" => Token::Character(string!(1).ok_or_else(invalid)?),
+        _ => return Err(invalid()),
+    };
+
+    Ok(Some(token))
+}
+
+// Tests that cannot be represented, because they contain lone surrogates, are left out
+fn make_test(raw: RawTest, file: &str, index: usize) -> Result<Option<Test>> {
+    let Some(input) = text(&raw.input, raw.double_escaped) else {
+        return Ok(None);
+    };
+
+    let mut output = vec![];
+    for value in &raw.output {
+        match make_token(value, raw.double_escaped)? {
+            Some(token) => output.push(token),
+            None => return Ok(None),
+        }
+    }
+
+    let initial_states = if raw.initial_states.is_empty() {
+        vec![InitialState::Data]
+    } else {
+        raw.initial_states
+            .iter()
+            .map(|s| s.parse())
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let errors = raw
+        .errors
+        .into_iter()
+        .map(|e| ExpectedError {
+            code: e.code,
+            line: e.line,
+            col: e.col,
+        })
+        .collect();
+
+    Ok(Some(Test {
+        file: file.to_owned(),
+        index,
+        description: raw.description,
+        input,
+        output,
+        initial_states,
+        last_start_tag: raw.last_start_tag,
+        errors,
+    }))
+}
+
+pub fn fixture_from_path(path: &PathBuf) -> Result<Tests> {
+    let s = fs::read_to_string(path)?;
+    let raw: RawTests = serde_json::from_str(&s)?;
+    let file = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut tests = vec![];
+    let mut skipped = vec![];
+
+    for (index, raw) in raw.tests.into_iter().enumerate() {
+        match make_test(raw, &file, index)? {
+            Some(test) => tests.push(test),
+            None => skipped.push(format!("{file}#{index}")),
+        }
+    }
+
+    Ok(Tests {
+        path: path.into(),
+        tests,
+        skipped,
+    })
+}
+
+pub fn fixture_from_filename(filename: &str) -> Result<Tests> {
+    let path = PathBuf::from(FIXTURE_DIR).join("tokenizer").join(filename);
+    fixture_from_path(&path)
+}
+
+pub fn fixture_filenames() -> Result<Vec<String>> {
+    let dir = PathBuf::from(FIXTURE_DIR).join("tokenizer");
+    let mut filenames = vec![];
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "test") {
+            filenames.extend(
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map(str::to_owned),
+            );
+        }
+    }
+
+    filenames.sort();
+    Ok(filenames)
+}
+
+/// Runs every test in the given fixture files, from each initial state, for a manifest check,
+/// returning each `Test::id` and whether the tokens and errors matched.  Skipped tests are left
+/// out, since they are not run.
+pub fn outcomes<T: TestTokenization>(filenames: &[String]) -> Result<Vec<(String, bool)>> {
+    let mut outcomes = vec![];
+
+    for filename in filenames {
+        let tests = fixture_from_filename(filename)?;
+
+        for test in tests.iter() {
+            for result in test.results::<T>()? {
+                let id = test.id(result.state);
+                let (actual, expected) = result.run();
+                outcomes.push((id, actual == expected));
+            }
+        }
+    }
+
+    Ok(outcomes)
+}
@@ -0,0 +1,45 @@
+use super::{output, InitialState, Output, TestTokenization};
+use crate::html5::html5ever::Dom;
+use crate::types::Result;
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::{
+    BufferQueue, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+
+#[derive(Default)]
+struct Sink {
+    tokens: Vec<(Token, u64)>,
+}
+
+impl TokenSink for Sink {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, line_number: u64) -> TokenSinkResult<()> {
+        self.tokens.push((token, line_number));
+        TokenSinkResult::Continue
+    }
+}
+
+impl TestTokenization for Dom {
+    fn tokenize(
+        input: &str,
+        initial: InitialState,
+        last_start_tag: Option<&str>,
+    ) -> Result<Output> {
+        let opts = TokenizerOpts {
+            exact_errors: true,
+            discard_bom: false,
+            initial_state: Some(initial.state()),
+            last_start_tag_name: last_start_tag.map(str::to_owned),
+            ..TokenizerOpts::default()
+        };
+
+        let mut tokenizer = Tokenizer::new(Sink::default(), opts);
+        let mut queue = BufferQueue::default();
+        queue.push_back(StrTendril::from(input));
+        let _ = tokenizer.feed(&mut queue);
+        tokenizer.end();
+
+        Ok(output(tokenizer.sink.tokens))
+    }
+}
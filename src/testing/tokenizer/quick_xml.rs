@@ -0,0 +1,17 @@
+use super::{output, InitialState, Output, TestTokenization};
+use crate::html5::quick_xml::{self, Dom};
+use crate::types::Result;
+
+// The text of the initial state is read up to the end tag for the last start tag, as the
+// parser does for raw text elements.  quick-xml has no CDATA section state outside of markup,
+// so tests starting in it are tokenized from the data state.
+impl TestTokenization for Dom {
+    fn tokenize(
+        input: &str,
+        initial: InitialState,
+        last_start_tag: Option<&str>,
+    ) -> Result<Output> {
+        let tokens = quick_xml::tokenize(input.as_bytes(), initial.state(), last_start_tag)?;
+        Ok(output(tokens))
+    }
+}
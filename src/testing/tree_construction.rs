@@ -1,18 +1,25 @@
 // See https://github.com/html5lib/html5lib-tests/tree/master/tree-construction
-use crate::html5::Document;
+use crate::dom::serialize::SerializeOpts;
+use crate::html5;
+use crate::html5::{Document, Parser, ScriptHost, Serializable};
 use crate::types::{Error, Result};
 use regex::Regex;
+use std::fmt;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::LazyLock;
 
 #[cfg(feature = "html5ever")]
 mod html5ever;
-mod parser;
+mod manifest;
+pub(crate) mod parser;
 #[cfg(feature = "quick-xml")]
 mod quick_xml;
+mod scripted;
 
-pub use parser::fixture_from_filename;
+pub use manifest::{error_outcomes, outcomes, round_trip_outcomes, scripted_outcomes};
+pub use parser::{fixture_filenames, fixture_from_filename, scripted_fixture_filenames};
+pub use scripted::DocumentWrite;
 
 #[derive(Debug, PartialEq)]
 pub struct Position {
@@ -104,6 +111,69 @@ impl FromStr for ParseError {
     }
 }
 
+impl ParseError {
+    fn position(&self) -> Option<(usize, Option<usize>)> {
+        match self {
+            Self::Message(_) => None,
+            Self::Line { line, .. } => Some((*line, None)),
+            Self::Location { pos, .. } => Some((pos.line, Some(pos.col))),
+            Self::Span { start, .. } => Some((start.line, Some(start.col))),
+        }
+    }
+
+    // Columns are only compared when both sides have one
+    fn matches(&self, actual: &html5::ParseError) -> bool {
+        match self.position() {
+            None => true,
+            Some((line, col)) => {
+                line == actual.line
+                    && match (col, actual.col) {
+                        (Some(expected), Some(actual)) => expected == actual,
+                        _ => true,
+                    }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Message(message) => write!(f, "{message}"),
+            Self::Line { line, message } => write!(f, "({line}): {message}"),
+            Self::Location { pos, message } => write!(f, "({},{}): {message}", pos.line, pos.col),
+            Self::Span {
+                start,
+                end,
+                message,
+            } => write!(
+                f,
+                "({},{})-({},{}): {message}",
+                start.line, start.col, end.line, end.col
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ErrorMismatch {
+    Missing(String),
+    Unexpected(String),
+    Position { expected: String, actual: String },
+}
+
+impl fmt::Display for ErrorMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(expected) => write!(f, "missing {expected}"),
+            Self::Unexpected(actual) => write!(f, "unexpected {actual}"),
+            Self::Position { expected, actual } => {
+                write!(f, "expected {expected}, got {actual}")
+            }
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum ScriptMode {
     ScriptOn,
@@ -117,6 +187,9 @@ pub trait TestSerialization {
 
 #[derive(Debug)]
 pub struct Test {
+    /// The name of the fixture file the test is from, and its position there, counting from 0.
+    pub file: String,
+    pub index: usize,
     pub data: String,
     pub errors: Vec<ParseError>,
     pub new_errors: Vec<ParseError>,
@@ -133,7 +206,38 @@ impl<'i, S: TestSerialization> TreeConstructionResult<'i, S> {
     }
 }
 
+impl<'i, T: Document<'i, T>> TreeConstructionResult<'i, T> {
+    /// Compares the errors reported by the backend, in order, with the errors and new errors
+    /// expected by the fixture.
+    pub fn error_mismatches(&self) -> Vec<ErrorMismatch> {
+        let expected = self.test.errors.iter().chain(self.test.new_errors.iter());
+        let mut actual = self.dom.errors().iter();
+        let mut mismatches = vec![];
+
+        for expected in expected {
+            match actual.next() {
+                Some(actual) if expected.matches(actual) => {}
+                Some(actual) => mismatches.push(ErrorMismatch::Position {
+                    expected: expected.to_string(),
+                    actual: actual.to_string(),
+                }),
+                None => mismatches.push(ErrorMismatch::Missing(expected.to_string())),
+            }
+        }
+
+        mismatches.extend(actual.map(|actual| ErrorMismatch::Unexpected(actual.to_string())));
+        mismatches
+    }
+}
+
 impl Test {
+    /// Identifies the test run with the given scripting mode, e.g. "tests1.dat#12:script-off".
+    /// Ids stay the same as long as tests are not inserted or removed ahead of this one.
+    pub fn id(&self, scripting_enabled: bool) -> String {
+        let mode = if scripting_enabled { "on" } else { "off" };
+        format!("{}#{}:script-{mode}", self.file, self.index)
+    }
+
     pub fn results<'i, T>(&'i self) -> Result<Vec<TreeConstructionResult<'i, T>>>
     where
         T: Document<'i, T>,
@@ -168,6 +272,149 @@ impl Test {
             scripting_enabled,
         })
     }
+
+    /// Parses the input with scripting enabled, running its scripts with `host`, as for the
+    /// fixtures under tree-construction/scripted.
+    pub fn parse_scripted<'i, T>(
+        &'i self,
+        host: Box<dyn ScriptHost>,
+    ) -> Result<TreeConstructionResult<'i, T>>
+    where
+        T: Document<'i, T>,
+    {
+        let mut parser = if let Some(ref context) = self.document_fragment {
+            T::fragment_parser(true, context)?
+        } else {
+            T::document_parser(true)?
+        };
+
+        parser.set_script_host(host);
+        parser.feed(self.data.as_bytes())?;
+        let dom = parser.finish()?;
+
+        Ok(TreeConstructionResult {
+            dom,
+            test: self,
+            scripting_enabled: true,
+        })
+    }
+
+    /// Parses the input with an incremental parser, feeding it in two chunks split at byte
+    /// offset `split`.
+    pub fn parse_chunked<'i, T>(
+        &'i self,
+        scripting_enabled: bool,
+        split: usize,
+    ) -> Result<TreeConstructionResult<'i, T>>
+    where
+        T: Document<'i, T>,
+    {
+        let mut parser = if let Some(ref context) = self.document_fragment {
+            T::fragment_parser(scripting_enabled, context)?
+        } else {
+            T::document_parser(scripting_enabled)?
+        };
+
+        let (head, tail) = self.data.as_bytes().split_at(split);
+        parser.feed(head)?;
+        parser.feed(tail)?;
+        let dom = parser.finish()?;
+
+        Ok(TreeConstructionResult {
+            dom,
+            test: self,
+            scripting_enabled,
+        })
+    }
+
+    /// Re-parses the input split at every byte boundary and returns the splits for which the
+    /// serialized tree differs from the one produced by parsing the input in one go.
+    pub fn chunked_mismatches<'i, T>(
+        &'i self,
+        scripting_enabled: bool,
+    ) -> Result<Vec<ChunkedMismatch>>
+    where
+        T: Document<'i, T> + TestSerialization,
+    {
+        let expected = self.parse::<T>(scripting_enabled)?.dom.serialize();
+        let mut mismatches = vec![];
+
+        for split in 0..=self.data.len() {
+            let actual = self
+                .parse_chunked::<T>(scripting_enabled, split)?
+                .dom
+                .serialize();
+
+            if actual != expected {
+                mismatches.push(ChunkedMismatch {
+                    split,
+                    actual,
+                    expected: expected.clone(),
+                });
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    /// Serializes the parsed tree to HTML, keeping the identifiers of a DOCTYPE, and parses the
+    /// markup again in the same context.  Returns the html5lib serializations of both trees if
+    /// they differ.
+    pub fn round_trip_mismatch<T>(
+        &self,
+        scripting_enabled: bool,
+    ) -> Result<Option<RoundTripMismatch>>
+    where
+        T: for<'a> Document<'a, T> + Serializable + TestSerialization,
+    {
+        let mut dom = self.parse::<T>(scripting_enabled)?.dom;
+        let opts = SerializeOpts {
+            scripting_enabled,
+            doctype_ids: true,
+            ..SerializeOpts::default()
+        };
+        let html = dom.to_html_with(&opts);
+
+        let mut reparsed = if let Some(ref context) = self.document_fragment {
+            T::parse_fragment(&html, scripting_enabled, context)?
+        } else {
+            T::parse_document(&html, scripting_enabled)?
+        };
+
+        let expected = dom.serialize();
+        let actual = reparsed.serialize();
+        if actual == expected {
+            return Ok(None);
+        }
+
+        Ok(Some(RoundTripMismatch {
+            html,
+            actual,
+            expected,
+        }))
+    }
+
+    pub fn scripting_modes(&self) -> Vec<bool> {
+        match self.script_mode {
+            ScriptMode::ScriptOn => vec![true],
+            ScriptMode::ScriptOff => vec![false],
+            ScriptMode::Both => vec![true, false],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ChunkedMismatch {
+    pub split: usize,
+    pub actual: String,
+    pub expected: String,
+}
+
+#[derive(Debug)]
+pub struct RoundTripMismatch {
+    pub html: String,
+    pub actual: String,
+    pub expected: String,
 }
 
 pub struct TreeConstructionResult<'i, T> {
@@ -185,4 +432,72 @@ impl Tests {
     pub fn iter(&self) -> impl Iterator<Item = &Test> {
         self.tests.iter()
     }
+
+    /// Runs every test and collects those whose parse errors differ from the expected ones.
+    pub fn error_report<'i, T>(&'i self) -> Result<ErrorReport>
+    where
+        T: Document<'i, T>,
+    {
+        let mut report = ErrorReport {
+            path: self.path.clone(),
+            results: 0,
+            failures: vec![],
+        };
+
+        for test in self.iter() {
+            for result in test.results::<T>()? {
+                report.results += 1;
+                let mismatches = result.error_mismatches();
+
+                if !mismatches.is_empty() {
+                    report.failures.push(ErrorFailure {
+                        data: test.data.clone(),
+                        scripting_enabled: result.scripting_enabled,
+                        mismatches,
+                    });
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[derive(Debug)]
+pub struct ErrorFailure {
+    pub data: String,
+    pub scripting_enabled: bool,
+    pub mismatches: Vec<ErrorMismatch>,
+}
+
+#[derive(Debug)]
+pub struct ErrorReport {
+    pub path: PathBuf,
+    pub results: usize,
+    pub failures: Vec<ErrorFailure>,
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let passed = self.results - self.failures.len();
+        writeln!(
+            f,
+            "{}: errors match for {passed} of {} results",
+            self.path.display(),
+            self.results
+        )?;
+
+        for failure in &self.failures {
+            writeln!(
+                f,
+                "\n  data: {}\n  scripting enabled: {}",
+                failure.data, failure.scripting_enabled
+            )?;
+            for mismatch in &failure.mismatches {
+                writeln!(f, "    {mismatch}")?;
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -1,15 +1,16 @@
 use super::TestSerialization;
+use crate::dom::{Arena, NodeData, NodeId};
 use crate::html5::html5ever::Dom;
+use crate::html5::Document;
 use html5ever::{namespace_url, ns};
-use markup5ever_rcdom::{Handle, NodeData, RcDom};
 use std::iter;
 
 // Adapted from https://github.com/servo/html5ever/blob/8415d500150d3232036bd2fb9681e7820fd7ecea/rcdom/tests/html-tree-builder.rs#L77
-pub(crate) fn serialize(buf: &mut String, indent: usize, handle: Handle) {
+pub(crate) fn serialize(buf: &mut String, indent: usize, arena: &Arena, id: NodeId) {
     buf.push('|');
     buf.extend(iter::repeat(" ").take(indent));
 
-    let node = handle;
+    let node = arena.node(id);
     match node.data {
         NodeData::Document => panic!("should not reach Document"),
 
@@ -28,7 +29,7 @@ pub(crate) fn serialize(buf: &mut String, indent: usize, handle: Handle) {
 
         NodeData::Text { ref contents } => {
             buf.push('"');
-            buf.push_str(&contents.borrow());
+            buf.push_str(contents);
             buf.push_str("\"\n");
         }
 
@@ -52,7 +53,7 @@ pub(crate) fn serialize(buf: &mut String, indent: usize, handle: Handle) {
             buf.push_str(&name.local);
             buf.push_str(">\n");
 
-            let mut attrs = attrs.borrow().clone();
+            let mut attrs = attrs.clone();
             attrs.sort_by(|x, y| x.name.local.cmp(&y.name.local));
             // FIXME: sort by UTF-16 code unit
 
@@ -72,37 +73,25 @@ pub(crate) fn serialize(buf: &mut String, indent: usize, handle: Handle) {
         NodeData::ProcessingInstruction { .. } => unreachable!(),
     }
 
-    for child in node.children.borrow().iter() {
-        serialize(buf, indent + 2, child.clone());
+    for child in arena.children(id) {
+        serialize(buf, indent + 2, arena, child);
     }
 
-    if let NodeData::Element {
-        ref template_contents,
-        ..
-    } = node.data
-    {
-        if let Some(ref content) = &*template_contents.borrow() {
-            buf.push('|');
-            buf.extend(iter::repeat(" ").take(indent + 2));
-            buf.push_str("content\n");
-            for child in content.children.borrow().iter() {
-                serialize(buf, indent + 4, child.clone());
-            }
+    if let Some(content) = arena.template_contents(id) {
+        buf.push('|');
+        buf.extend(iter::repeat(" ").take(indent + 2));
+        buf.push_str("content\n");
+        for child in arena.children(content) {
+            serialize(buf, indent + 4, arena, child);
         }
     }
 }
 
-pub(crate) fn serialize_dom(dom: &RcDom, fragment: bool) -> String {
+pub(crate) fn serialize_dom(arena: &Arena, root: NodeId) -> String {
     let mut buf = String::new();
 
-    let root = if fragment {
-        &dom.document.children.borrow()[0]
-    } else {
-        &dom.document
-    };
-
-    for node in root.children.borrow().iter() {
-        serialize(&mut buf, 1, node.clone());
+    for node in arena.children(root) {
+        serialize(&mut buf, 1, arena, node);
     }
 
     buf.trim_end_matches("\n").into()
@@ -110,6 +99,6 @@ pub(crate) fn serialize_dom(dom: &RcDom, fragment: bool) -> String {
 
 impl TestSerialization for Dom {
     fn serialize(&mut self) -> String {
-        serialize_dom(&self.dom, self.fragment)
+        serialize_dom(self.arena(), self.root())
     }
 }
@@ -0,0 +1,91 @@
+use super::{fixture_from_filename, DocumentWrite, TestSerialization};
+use crate::html5::{Document, Serializable};
+use crate::types::Result;
+
+/// Runs every test in the given fixture files, in each scripting mode, for a manifest check,
+/// returning each `Test::id` and whether the test passed.
+pub fn outcomes<T>(filenames: &[String]) -> Result<Vec<(String, bool)>>
+where
+    T: for<'i> Document<'i, T> + TestSerialization,
+{
+    let mut outcomes = vec![];
+
+    for filename in filenames {
+        let tests = fixture_from_filename(filename)?;
+
+        for test in tests.iter() {
+            for scripting_enabled in test.scripting_modes() {
+                let (actual, expected) = test.parse::<T>(scripting_enabled)?.run();
+                outcomes.push((test.id(scripting_enabled), actual == expected));
+            }
+        }
+    }
+
+    Ok(outcomes)
+}
+
+/// Runs every test in the given fixture files under tree-construction/scripted, running their
+/// scripts with `DocumentWrite`, for a manifest check, returning each `Test::id` and whether the
+/// test passed.
+pub fn scripted_outcomes<T>(filenames: &[String]) -> Result<Vec<(String, bool)>>
+where
+    T: for<'i> Document<'i, T> + TestSerialization,
+{
+    let mut outcomes = vec![];
+
+    for filename in filenames {
+        let tests = fixture_from_filename(filename)?;
+
+        for test in tests.iter() {
+            let (actual, expected) = test.parse_scripted::<T>(Box::new(DocumentWrite))?.run();
+            outcomes.push((test.id(true), actual == expected));
+        }
+    }
+
+    Ok(outcomes)
+}
+
+/// Runs every test in the given fixture files, in each scripting mode, for a manifest check,
+/// returning each `Test::id` and whether the reported parse errors matched the expected ones.
+pub fn error_outcomes<T>(filenames: &[String]) -> Result<Vec<(String, bool)>>
+where
+    T: for<'i> Document<'i, T>,
+{
+    let mut outcomes = vec![];
+
+    for filename in filenames {
+        let tests = fixture_from_filename(filename)?;
+
+        for test in tests.iter() {
+            for scripting_enabled in test.scripting_modes() {
+                let result = test.parse::<T>(scripting_enabled)?;
+                let matched = result.error_mismatches().is_empty();
+                outcomes.push((test.id(scripting_enabled), matched));
+            }
+        }
+    }
+
+    Ok(outcomes)
+}
+
+/// Runs every test in the given fixture files, in each scripting mode, for a manifest check,
+/// returning each `Test::id` and whether the parsed tree survived serializing and reparsing.
+pub fn round_trip_outcomes<T>(filenames: &[String]) -> Result<Vec<(String, bool)>>
+where
+    T: for<'i> Document<'i, T> + Serializable + TestSerialization,
+{
+    let mut outcomes = vec![];
+
+    for filename in filenames {
+        let tests = fixture_from_filename(filename)?;
+
+        for test in tests.iter() {
+            for scripting_enabled in test.scripting_modes() {
+                let mismatch = test.round_trip_mismatch::<T>(scripting_enabled)?;
+                outcomes.push((test.id(scripting_enabled), mismatch.is_none()));
+            }
+        }
+    }
+
+    Ok(outcomes)
+}
@@ -14,7 +14,7 @@ fn parse_errors(s: &str) -> Vec<ParseError> {
 }
 
 // From https://github.com/servo/html5ever/blob/8415d500150d3232036bd2fb9681e7820fd7ecea/rcdom/tests/html-tree-builder.rs#L33
-fn parse_tests(s: &str) -> Vec<HashMap<String, String>> {
+pub(crate) fn parse_tests(s: &str) -> Vec<HashMap<String, String>> {
     let mut lines = s.lines();
     let mut tests = vec![];
     let mut test = HashMap::new();
@@ -59,7 +59,7 @@ fn parse_tests(s: &str) -> Vec<HashMap<String, String>> {
     tests
 }
 
-fn make_test(test: HashMap<String, String>) -> Test {
+fn make_test(test: HashMap<String, String>, file: &str, index: usize) -> Test {
     let data = test
         .get("data")
         .unwrap()
@@ -94,6 +94,8 @@ fn make_test(test: HashMap<String, String>) -> Test {
         .to_string();
 
     Test {
+        file: file.to_owned(),
+        index,
         data,
         errors,
         new_errors,
@@ -105,7 +107,15 @@ fn make_test(test: HashMap<String, String>) -> Test {
 
 pub fn fixture_from_path(path: &PathBuf) -> Result<Tests> {
     let s = fs::read_to_string(path)?;
-    let tests: Vec<_> = parse_tests(&s).into_iter().map(make_test).collect();
+    let file = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tests: Vec<_> = parse_tests(&s)
+        .into_iter()
+        .enumerate()
+        .map(|(index, test)| make_test(test, &file, index))
+        .collect();
 
     Ok(Tests {
         path: path.into(),
@@ -113,9 +123,48 @@ pub fn fixture_from_path(path: &PathBuf) -> Result<Tests> {
     })
 }
 
+/// Loads a fixture by its path under tree-construction, which its tests are identified by, so
+/// that "scripted/webkit01.dat" and "webkit01.dat" are told apart.
 pub fn fixture_from_filename(filename: &str) -> Result<Tests> {
     let path = PathBuf::from(FIXTURE_DIR)
         .join("tree-construction")
         .join(filename);
-    fixture_from_path(&path)
+    let mut tests = fixture_from_path(&path)?;
+    for test in &mut tests.tests {
+        test.file = filename.to_owned();
+    }
+    Ok(tests)
+}
+
+/// The names of the fixture files directly under tree-construction, in order.
+pub fn fixture_filenames() -> Result<Vec<String>> {
+    filenames_in("")
+}
+
+/// The names of the fixture files under tree-construction/scripted, in order, e.g.
+/// "scripted/webkit01.dat".
+pub fn scripted_fixture_filenames() -> Result<Vec<String>> {
+    filenames_in("scripted")
+}
+
+fn filenames_in(subdir: &str) -> Result<Vec<String>> {
+    let dir = PathBuf::from(FIXTURE_DIR)
+        .join("tree-construction")
+        .join(subdir);
+    let mut filenames = vec![];
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "dat") {
+            filenames.extend(path.file_name().and_then(|name| name.to_str()).map(
+                |name| match subdir {
+                    "" => name.to_owned(),
+                    _ => format!("{subdir}/{name}"),
+                },
+            ));
+        }
+    }
+
+    filenames.sort();
+    Ok(filenames)
 }
@@ -1,9 +1,10 @@
 use super::html5ever::serialize_dom;
 use super::TestSerialization;
 use crate::html5::quick_xml;
+use crate::html5::Document;
 
 impl TestSerialization for quick_xml::Dom {
     fn serialize(&mut self) -> String {
-        serialize_dom(&self.dom, self.fragment)
+        serialize_dom(self.arena(), self.root())
     }
 }
@@ -0,0 +1,127 @@
+// A stand-in for a script engine, enough to run the fixtures under tree-construction/scripted
+use crate::dom::{Arena, NodeId};
+use crate::html5::ScriptHost;
+
+/// A script host that understands calls to `document.write()` and `document.writeln()` whose
+/// arguments are string literals, possibly joined with "+".  Other statements are skipped.
+#[derive(Debug, Default)]
+pub struct DocumentWrite;
+
+impl ScriptHost for DocumentWrite {
+    fn run(&mut self, arena: &Arena, script: NodeId) -> Option<String> {
+        let source = arena.text_content(script);
+        let written = run(&source);
+        (!written.is_empty()).then_some(written)
+    }
+}
+
+struct Cursor<'a> {
+    rest: &'a str,
+}
+
+impl Cursor<'_> {
+    fn eat(&mut self, s: &str) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(s) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.rest = self.rest.trim_start();
+        let mut chars = self.rest.char_indices();
+        let quote = match chars.next()? {
+            (_, c @ ('"' | '\'')) => c,
+            _ => return None,
+        };
+
+        let mut s = String::new();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    let (_, c) = chars.next()?;
+                    s.push(match c {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        c => c,
+                    });
+                }
+                c if c == quote => {
+                    self.rest = &self.rest[i + 1..];
+                    return Some(s);
+                }
+                '\n' => return None,
+                c => s.push(c),
+            }
+        }
+
+        None
+    }
+
+    fn concatenation(&mut self) -> Option<String> {
+        let mut s = self.string()?;
+        while self.eat("+") {
+            s.push_str(&self.string()?);
+        }
+        Some(s)
+    }
+
+    // A call to document.write() or document.writeln(), returning what it writes
+    fn write(&mut self) -> Option<String> {
+        let newline = if self.eat("document.writeln(") {
+            true
+        } else if self.eat("document.write(") {
+            false
+        } else {
+            return None;
+        };
+
+        let mut written = String::new();
+        if !self.eat(")") {
+            loop {
+                written.push_str(&self.concatenation()?);
+                if self.eat(")") {
+                    break;
+                }
+                if !self.eat(",") {
+                    return None;
+                }
+            }
+        }
+
+        if newline {
+            written.push('\n');
+        }
+        Some(written)
+    }
+}
+
+fn run(source: &str) -> String {
+    let mut cursor = Cursor { rest: source };
+    let mut written = String::new();
+
+    loop {
+        cursor.rest = cursor
+            .rest
+            .trim_start_matches(|c: char| c.is_whitespace() || c == ';');
+        if cursor.rest.is_empty() {
+            break;
+        }
+
+        let statement = cursor.rest;
+        match cursor.write() {
+            Some(s) => written.push_str(&s),
+            None => match statement.find([';', '\n']) {
+                Some(end) => cursor.rest = &statement[end + 1..],
+                None => break,
+            },
+        }
+    }
+
+    written
+}
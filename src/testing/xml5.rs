@@ -0,0 +1,213 @@
+// See https://github.com/Ygg01/xml5lib-tests/tree/master/tree-construction
+use crate::dom::{Arena, NodeData, NodeId};
+use crate::html5::{xml, Document};
+use crate::testing::tree_construction::parser::parse_tests;
+use crate::types::{Error, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+pub const FIXTURE_DIR: &str = "./tests/data/xml5lib-tests";
+
+#[derive(Debug)]
+pub struct Test {
+    /// The name of the fixture file the test is from, and its position there, counting from 0.
+    pub file: String,
+    pub index: usize,
+    pub data: String,
+    pub errors: Vec<String>,
+    pub document: String,
+}
+
+impl Test {
+    /// Identifies the test, e.g. "tests1.dat#12".
+    pub fn id(&self) -> String {
+        format!("{}#{}", self.file, self.index)
+    }
+
+    /// Parses the input as an XML document and returns the serialized tree along with the
+    /// expected one.
+    pub fn run(&self) -> Result<(String, String)> {
+        let dom = xml::Dom::parse_document(&self.data, false)?;
+        let actual = serialize_dom(dom.arena(), dom.root());
+        Ok((actual, self.document.clone()))
+    }
+}
+
+pub struct Tests {
+    pub path: PathBuf,
+    pub tests: Vec<Test>,
+}
+
+impl Tests {
+    pub fn iter(&self) -> impl Iterator<Item = &Test> {
+        self.tests.iter()
+    }
+}
+
+// Adapted from https://github.com/servo/html5ever/blob/main/xml5ever/tests/tree_builder.rs
+fn serialize(buf: &mut String, indent: usize, arena: &Arena, id: NodeId) {
+    buf.push('|');
+    buf.push_str(&" ".repeat(indent));
+
+    match arena.node(id).data {
+        NodeData::Document => panic!("should not reach Document"),
+
+        NodeData::Doctype {
+            ref name,
+            ref public_id,
+            ref system_id,
+        } => {
+            buf.push_str("<!DOCTYPE ");
+            buf.push_str(name);
+            if !public_id.is_empty() || !system_id.is_empty() {
+                buf.push_str(&format!(" \"{public_id}\" \"{system_id}\""));
+            }
+            buf.push_str(">\n");
+        }
+
+        NodeData::Text { ref contents } => {
+            buf.push('"');
+            buf.push_str(contents);
+            buf.push_str("\"\n");
+        }
+
+        NodeData::Comment { ref contents } => {
+            buf.push_str("<!-- ");
+            buf.push_str(contents);
+            buf.push_str(" -->\n");
+        }
+
+        NodeData::ProcessingInstruction {
+            ref target,
+            ref contents,
+        } => {
+            buf.push_str(&format!("<?{target} {contents}?>\n"));
+        }
+
+        NodeData::Element {
+            ref name,
+            ref attrs,
+            ..
+        } => {
+            buf.push('<');
+            if !name.ns.is_empty() {
+                buf.push_str(&format!("{{{}}}", name.ns));
+            }
+            if let Some(ref prefix) = name.prefix {
+                buf.push_str(&format!("{prefix}:"));
+            }
+            buf.push_str(&name.local);
+            buf.push_str(">\n");
+
+            let mut attrs = attrs.clone();
+            attrs.sort_by(|x, y| x.name.local.cmp(&y.name.local));
+
+            for attr in attrs {
+                buf.push('|');
+                buf.push_str(&" ".repeat(indent + 2));
+                if !attr.name.ns.is_empty() {
+                    buf.push_str(&format!("{{{}}}", attr.name.ns));
+                }
+                if let Some(ref prefix) = attr.name.prefix {
+                    buf.push_str(&format!("{prefix}:"));
+                }
+                buf.push_str(&format!("{}=\"{}\"\n", attr.name.local, attr.value));
+            }
+        }
+    }
+
+    for child in arena.children(id) {
+        serialize(buf, indent + 2, arena, child);
+    }
+}
+
+/// Serializes the children of `root` in the xml5lib test format.
+pub fn serialize_dom(arena: &Arena, root: NodeId) -> String {
+    let mut buf = String::new();
+    for child in arena.children(root) {
+        serialize(&mut buf, 1, arena, child);
+    }
+    buf.trim_end_matches('\n').to_owned()
+}
+
+fn make_test(mut test: HashMap<String, String>, file: &str, index: usize) -> Result<Test> {
+    let mut field = |key: &str| {
+        test.remove(key)
+            .ok_or_else(|| Error::General(format!("missing #{key}")))
+    };
+
+    let data = field("data")?;
+    let errors = field("errors").unwrap_or_default();
+    let document = field("document")?;
+
+    Ok(Test {
+        file: file.to_owned(),
+        index,
+        data: data.strip_suffix('\n').unwrap_or(&data).to_owned(),
+        errors: errors.lines().map(str::to_owned).collect(),
+        document: document.trim_end_matches('\n').to_owned(),
+    })
+}
+
+pub fn fixture_from_path(path: &PathBuf) -> Result<Tests> {
+    let s = fs::read_to_string(path)?;
+    let file = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tests = parse_tests(&s)
+        .into_iter()
+        .enumerate()
+        .map(|(index, test)| make_test(test, &file, index))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Tests {
+        path: path.into(),
+        tests,
+    })
+}
+
+pub fn fixture_from_filename(filename: &str) -> Result<Tests> {
+    let path = PathBuf::from(FIXTURE_DIR)
+        .join("tree-construction")
+        .join(filename);
+    fixture_from_path(&path)
+}
+
+/// The names of the tree construction fixture files, in order.
+pub fn fixture_filenames() -> Result<Vec<String>> {
+    let dir = PathBuf::from(FIXTURE_DIR).join("tree-construction");
+    let mut filenames = vec![];
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "dat") {
+            filenames.extend(
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map(str::to_owned),
+            );
+        }
+    }
+
+    filenames.sort();
+    Ok(filenames)
+}
+
+/// Runs every test in the given fixture files for a manifest check, returning each `Test::id`
+/// and whether the tree matched.
+pub fn outcomes(filenames: &[String]) -> Result<Vec<(String, bool)>> {
+    let mut outcomes = vec![];
+
+    for filename in filenames {
+        let tests = fixture_from_filename(filename)?;
+
+        for test in tests.iter() {
+            let (actual, expected) = test.run()?;
+            outcomes.push((test.id(), actual == expected));
+        }
+    }
+
+    Ok(outcomes)
+}
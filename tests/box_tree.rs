@@ -0,0 +1,306 @@
+use rendering::html5::{html5ever, quick_xml, xml, Document};
+use rendering::layout::BoxTree;
+use rendering::style::Viewport;
+use rendering::testing::golden::{self, GOLDEN_DIR};
+use rendering::testing::render::WithBackend;
+use rendering::testing::tree_construction::fixture_from_filename;
+use std::{fs, path::Path};
+use test_case::test_case;
+
+fn dump<T: for<'i> Document<'i, T>>(html: &str) -> String {
+    let dom = T::parse_document(html, false).unwrap();
+    let styles = dom.compute_styles(&Viewport::default());
+    BoxTree::new(dom.arena(), &styles).dump(dom.arena())
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_blocks_and_inlines(dump: WithBackend<String>) {
+    assert_eq!(
+        dump("<title>x</title><p>One <em>two</em></p>\n<div>\n  <p>Three</p>\n</div>"),
+        "\
+| block <html>
+|   block <body>
+|     block <p>
+|       \"One \"
+|       inline <em>
+|         \"two\"
+|     block <div>
+|       block <p>
+|         \"Three\"
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_anonymous_blocks(dump: WithBackend<String>) {
+    // Inline content next to a block-level box goes in anonymous blocks, leaving out white space
+    assert_eq!(
+        dump("<div>Before <b>bold</b><p>Block</p>  \n  <i>after</i></div>"),
+        "\
+| block <html>
+|   block <body>
+|     block <div>
+|       block (anonymous)
+|         \"Before \"
+|         inline <b>
+|           \"bold\"
+|       block <p>
+|         \"Block\"
+|       block (anonymous)
+|         \"  \n  \"
+|         inline <i>
+|           \"after\"
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_block_in_inline(dump: WithBackend<String>) {
+    // The inline boxes are broken around the block, even where a piece is empty
+    assert_eq!(
+        dump("<span>a<em>b<div>c</div></em>d</span>"),
+        "\
+| block <html>
+|   block <body>
+|     block (anonymous)
+|       inline <span>
+|         \"a\"
+|         inline <em>
+|           \"b\"
+|     block <div>
+|       \"c\"
+|     block (anonymous)
+|       inline <span>
+|         inline <em>
+|         \"d\"
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_display(dump: WithBackend<String>) {
+    let html = "<style>.c { display: contents } .n { display: none } .ib { display: inline-block }\
+                .b { display: block }</style>\
+                <div><span class=c>contents <i>kept</i></span><span class=n>none <p>gone</p></span>\
+                <span class=ib>inline-block</span><em class=b>block</em></div>\
+                <p hidden>hidden</p><ul><li>item</li></ul>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <html>
+|   block <body>
+|     block <div>
+|       block (anonymous)
+|         \"contents \"
+|         inline <i>
+|           \"kept\"
+|         inline-block <span>
+|           \"inline-block\"
+|       block <em>
+|         \"block\"
+|     block <ul>
+|       block <li>
+|         \"item\"
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_replaced_and_line_breaks(dump: WithBackend<String>) {
+    let html = "<p>a<br>b<img width=20 height=10><img style='display: block'>\
+                <svg width=5><rect/></svg></p><math><mi>x</mi></math>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <html>
+|   block <body>
+|     block <p>
+|       block (anonymous)
+|         \"a\"
+|         line-break <br>
+|         \"b\"
+|         inline replaced <img> 20x10
+|       block replaced <img> autoxauto
+|       block (anonymous)
+|         inline replaced <svg svg> 5xauto
+|     block (anonymous)
+|       inline <math math>
+|         inline <math mi>
+|           \"x\"
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_tables(dump: WithBackend<String>) {
+    // Tables are block containers, and columns generate no boxes
+    let html = "<table><colgroup><col></colgroup><tr><td>a</td> <td>b</td></tr></table>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <html>
+|   block <body>
+|     block <table>
+|       block <tbody>
+|         block <tr>
+|           block <td>
+|             \"a\"
+|           block <td>
+|             \"b\"
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_root(dump: WithBackend<String>) {
+    assert_eq!(dump("<html style='display: none'>x"), "");
+    assert_eq!(
+        dump("<html style='display: contents'>x"),
+        "| block <html>\n|   block <body>\n|     \"x\"\n"
+    );
+    assert_eq!(
+        dump("<pre>\n\n  <b> </b>  </pre><div style='white-space: pre'>  </div>"),
+        "\
+| block <html>
+|   block <body>
+|     block <pre>
+|       \"\n  \"
+|       inline <b>
+|         \" \"
+|       \"  \"
+|     block <div>
+|       \"  \"
+"
+    );
+}
+
+#[test]
+fn test_xml_documents() {
+    let dom = xml::Dom::parse_document(
+        "<doc xmlns='http://www.w3.org/1999/xhtml'><p>a</p><b>c</b></doc>",
+        false,
+    )
+    .unwrap();
+    let styles = dom.compute_styles(&Viewport::default());
+    assert_eq!(
+        BoxTree::new(dom.arena(), &styles).dump(dom.arena()),
+        "\
+| block <doc>
+|   block <p>
+|     \"a\"
+|   block (anonymous)
+|     inline <b>
+|       \"c\"
+"
+    );
+}
+
+#[test_case("blocks")]
+#[test_case("display")]
+#[test_case("replaced")]
+#[test_case("tables")]
+#[test_case("whitespace")]
+fn test_golden(name: &str) {
+    let html =
+        fs::read_to_string(Path::new(GOLDEN_DIR).join(format!("boxes/documents/{name}.html")));
+    let dom = html5ever::Dom::parse_document(&html.unwrap(), false).unwrap();
+    let styles = dom.compute_styles(&Viewport::default());
+    let dump = BoxTree::new(dom.arena(), &styles).dump(dom.arena());
+
+    let expected = golden::expected(&format!("boxes/documents/{name}.txt"), &dump).unwrap();
+    assert_eq!(dump, expected);
+}
+
+// The box tree of every document in a tree construction fixture, against a golden file.  Set
+// REGENERATE_GOLDEN to rewrite them.
+macro_rules! matches_golden {
+    ($func:ident, [$($filename:expr),*,]) => {
+        $(
+            #[test_case($filename)]
+        )*
+        fn $func(filename: &str) {
+            let tests = fixture_from_filename(filename).expect("error loading fixture");
+            let mut actual = String::new();
+
+            for test in tests.iter().filter(|test| test.document_fragment.is_none()) {
+                let scripting_enabled = test.scripting_modes()[0];
+                let dom = html5ever::Dom::parse_document(&test.data, scripting_enabled).unwrap();
+                let styles = dom.compute_styles(&Viewport::default());
+                let boxes = BoxTree::new(dom.arena(), &styles).dump(dom.arena());
+                actual.push_str(&format!("#data\n{}\n#boxes\n{boxes}\n", test.data));
+            }
+
+            let name = format!("boxes/{}.txt", filename.trim_end_matches(".dat"));
+            let expected = golden::expected(&name, &actual).unwrap();
+            assert_eq!(actual, expected);
+        }
+    };
+}
+
+matches_golden!(
+    test_box_tree_matches_golden,
+    [
+        "adoption01.dat",
+        "adoption02.dat",
+        "blocks.dat",
+        "comments01.dat",
+        "doctype01.dat",
+        "domjs-unsafe.dat",
+        "entities01.dat",
+        "entities02.dat",
+        "html5test-com.dat",
+        "inbody01.dat",
+        "isindex.dat",
+        "main-element.dat",
+        "math.dat",
+        "menuitem-element.dat",
+        "namespace-sensitivity.dat",
+        "noscript01.dat",
+        "pending-spec-changes.dat",
+        "pending-spec-changes-plain-text-unsafe.dat",
+        "plain-text-unsafe.dat",
+        "quirks01.dat",
+        "ruby.dat",
+        "scriptdata01.dat",
+        "search-element.dat",
+        "svg.dat",
+        "tables01.dat",
+        "template.dat",
+        "tests10.dat",
+        "tests11.dat",
+        "tests12.dat",
+        "tests14.dat",
+        "tests15.dat",
+        "tests16.dat",
+        "tests17.dat",
+        "tests18.dat",
+        "tests19.dat",
+        "tests1.dat",
+        "tests20.dat",
+        "tests21.dat",
+        "tests22.dat",
+        "tests23.dat",
+        "tests24.dat",
+        "tests25.dat",
+        "tests26.dat",
+        "tests2.dat",
+        "tests3.dat",
+        "tests4.dat",
+        "tests5.dat",
+        "tests6.dat",
+        "tests7.dat",
+        "tests8.dat",
+        "tests9.dat",
+        "tests_innerHTML_1.dat",
+        "tricky01.dat",
+        "webkit01.dat",
+        "webkit02.dat",
+    ]
+);
@@ -0,0 +1,97 @@
+use rendering::testing::conformance::{backends, Backend, Report};
+use rendering::testing::tree_construction::Test;
+use rendering::types::Result;
+use std::fs;
+use std::path::PathBuf;
+
+const FIXTURE: &str = "\
+#data
+<p>One
+#errors
+(1,0): dummy
+#document
+| <html>
+|   <head>
+|   <body>
+|     <p>
+|       \"One\"
+
+#data
+<p>Two
+#errors
+(1,0): dummy
+#document
+| <html>
+|   <head>
+|   <body>
+|     <p>
+|       \"Three\"
+";
+
+fn fixture_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("conformance-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("report.dat"), FIXTURE).unwrap();
+    fs::write(dir.join("ignored.txt"), "not a fixture").unwrap();
+    dir
+}
+
+#[test]
+fn test_report_tallies_passes_and_mismatches() {
+    let dir = fixture_dir("tallies");
+    let report = Report::run_with(&dir, &backends()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(report.backends, vec!["html5ever", "quick_xml"]);
+    assert_eq!(report.files.len(), 1);
+    assert_eq!(report.files[0].file, "report.dat");
+
+    // Tests without a "#script-on" or "#script-off" line run in both modes, so count twice
+    for tally in &report.files[0].tallies {
+        assert_eq!((tally.passed, tally.failed, tally.panicked), (2, 2, 0));
+        let mismatch = tally.first_mismatch.as_ref().unwrap();
+        assert_eq!(mismatch.id, "report.dat#1:script-on");
+        assert_eq!(mismatch.data, "<p>Two");
+        assert!(mismatch
+            .diff
+            .contains("-|       \"Three\"\n+|       \"Two\"\n"));
+        assert!(mismatch.diff.contains(" |     <p>\n"));
+    }
+
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("| report.dat | 2/4, 2 failed | 2/4, 2 failed |"));
+    assert!(markdown.contains("## report.dat: quick_xml"));
+    assert!(markdown.contains("```diff\n"));
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["files"][0]["tallies"][1]["backend"], "quick_xml");
+    assert_eq!(json["files"][0]["tallies"][1]["failed"], 2);
+}
+
+fn panics(_test: &Test) -> Result<Vec<(bool, String)>> {
+    panic!("backend gave up");
+}
+
+fn parse_panics(_data: &str) -> Result<String> {
+    panic!("backend gave up");
+}
+
+#[test]
+fn test_report_catches_panics() {
+    let dir = fixture_dir("panics");
+    let backends = [Backend {
+        name: "panicky",
+        run: panics,
+        parse: parse_panics,
+    }];
+    let report = Report::run_with(&dir, &backends).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let tally = &report.files[0].tallies[0];
+    assert_eq!((tally.passed, tally.failed, tally.panicked), (0, 0, 4));
+    assert_eq!(tally.panics[0].id, "report.dat#0:script-on");
+    assert_eq!(tally.panics[0].message, "backend gave up");
+    assert!(report
+        .to_markdown()
+        .contains("- `report.dat#1:script-off`: backend gave up"));
+}
@@ -0,0 +1,163 @@
+use rendering::css::{self, CssRule, Stylesheet};
+use rendering::html5::{html5ever, Document};
+use rendering::testing::css_parsing::{self, fixture_from_filename, fixture_from_str, Kind};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use test_case::test_case;
+
+#[test_case("component_value_list.json")]
+#[test_case("one_component_value.json")]
+#[test_case("declaration_list.json")]
+#[test_case("one_declaration.json")]
+#[test_case("rule_list.json")]
+#[test_case("one_rule.json")]
+#[test_case("stylesheet.json")]
+fn test_css_parser_passes_fixtures(filename: &str) {
+    let tests = fixture_from_filename(filename).expect("error loading fixture");
+
+    for test in tests.iter() {
+        let (actual, expected) = test.run(tests.kind);
+        assert!(
+            css_parsing::matches(&actual, &expected),
+            "\n  input: {:?}\n  actual: {actual}\n  expected: {expected}\n",
+            test.input
+        );
+    }
+}
+
+// Tests in the fixture format, for inputs that exercise the error recovery
+const COMPONENT_VALUES: &str = r##"[
+"a:hover { color: red }", [
+    ["ident", "a"], ":", ["ident", "hover"], " ",
+    ["{}", " ", ["ident", "color"], ":", " ", ["ident", "red"], " "]
+],
+"#id #1x .5em -.5% +3e2 12", [
+    ["hash", "id", "id"], " ", ["hash", "1x", "unrestricted"], " ",
+    ["dimension", ".5", 0.5, "number", "em"], " ", ["percentage", "-.5", -0.5, "number"], " ",
+    ["number", "+3e2", 300, "number"], " ", ["number", "12", 12, "integer"]
+],
+"url( a\\)b ) url(\"c\") url(d e) 'f\ng", [
+    ["url", "a)b"], " ", ["function", "url", ["string", "c"]], " ", ["error", "bad-url"], " ",
+    ["error", "bad-string"], " ", ["ident", "g"]
+],
+"<!-- --> ) ] } /* comment */ \\41 x \\", [
+    "<!--", " ", "-->", " ", ["error", ")"], " ", ["error", "]"], " ", ["error", "}"], " ", " ",
+    ["ident", "Ax"], " ", ["ident", "\uFFFD"]
+],
+"f(a, [b) @media", [
+    ["function", "f", ["ident", "a"], ",", " ", ["[]", ["ident", "b"], ["error", ")"], " ",
+    ["at-keyword", "media"]]]
+]
+]"##;
+
+const DECLARATIONS: &str = r##"[
+"color: red !IMPORTANT; ;margin : 0 auto", [
+    ["declaration", "color", [["ident", "red"]], true],
+    ["declaration", "margin", [["number", "0", 0, "integer"], " ", ["ident", "auto"]], false]
+],
+"a b; c: d; 12: x; @page { x: y } e: f", [
+    ["error", "invalid"],
+    ["declaration", "c", [["ident", "d"]], false],
+    ["error", "invalid"],
+    ["at-rule", "page", [" "], [" ", ["ident", "x"], ":", " ", ["ident", "y"], " "]],
+    ["declaration", "e", [["ident", "f"]], false]
+]
+]"##;
+
+const STYLESHEETS: &str = r##"[
+"<!-- @import 'a.css' screen; p {} --> q", [
+    ["at-rule", "import", [" ", ["string", "a.css"], " ", ["ident", "screen"]], null],
+    ["qualified rule", [["ident", "p"], " "], []],
+    ["error", "invalid"]
+]
+]"##;
+
+#[test_case(COMPONENT_VALUES, Kind::ComponentValueList ; "component value lists")]
+#[test_case(DECLARATIONS, Kind::DeclarationList ; "declaration lists")]
+#[test_case(STYLESHEETS, Kind::Stylesheet ; "stylesheets")]
+fn test_css_parser_passes_inline_fixtures(json: &str, kind: Kind) {
+    let tests = fixture_from_str(json, kind, PathBuf::new()).unwrap();
+
+    for test in tests.iter() {
+        let (actual, expected) = test.run(kind);
+        assert!(
+            css_parsing::matches(&actual, &expected),
+            "\n  input: {:?}\n  actual: {actual}\n  expected: {expected}\n",
+            test.input
+        );
+    }
+}
+
+#[test_case(Kind::OneComponentValue, "  ", json!(["error", "empty"]) ; "empty component value")]
+#[test_case(Kind::OneComponentValue, " a b", json!(["error", "extra-input"]) ; "extra component value")]
+#[test_case(Kind::OneDeclaration, "a:b c", json!(["declaration", "a", [["ident", "b"], " ", ["ident", "c"]], false]) ; "one declaration")]
+#[test_case(Kind::OneDeclaration, "{a:b}", json!(["error", "invalid"]) ; "invalid declaration")]
+#[test_case(Kind::OneRule, "p {", json!(["qualified rule", [["ident", "p"], " "], []]) ; "unclosed rule")]
+#[test_case(Kind::OneRule, "p", json!(["error", "invalid"]) ; "rule without a block")]
+#[test_case(Kind::OneRule, "@x; y", json!(["error", "extra-input"]) ; "extra rule")]
+#[test_case(Kind::RuleList, "<!-- p{}", json!([["qualified rule", ["<!--", " ", ["ident", "p"]], []]]) ; "rule list keeps cdo")]
+fn test_css_parser_parses(kind: Kind, input: &str, expected: Value) {
+    let actual = css_parsing::parse(kind, input);
+    assert!(
+        css_parsing::matches(&actual, &expected),
+        "\n  actual: {actual}\n  expected: {expected}\n"
+    );
+}
+
+#[test]
+fn test_stylesheet_model() {
+    let stylesheet = Stylesheet::parse(
+        "@import url(\"a.css\") print;\n\
+         p, q { color: red; bogus; margin: 0 !important }\n\
+         @media screen { em { font-style: normal } }\n\
+         @font-face { font-family: x }\n\
+         dangling",
+    );
+
+    assert_eq!(stylesheet.rules.len(), 4);
+    let CssRule::Import(ref import) = stylesheet.rules[0] else {
+        panic!("not an import: {:?}", stylesheet.rules[0]);
+    };
+    assert_eq!(import.url, "a.css");
+    assert_eq!(import.conditions.len(), 1);
+    assert!(matches!(stylesheet.rules[2], CssRule::Media(_)));
+    assert!(matches!(stylesheet.rules[3], CssRule::Other(ref rule) if rule.name == "font-face"));
+
+    let style_rules: Vec<_> = stylesheet.style_rules().collect();
+    assert_eq!(style_rules.len(), 2);
+    let names: Vec<_> = style_rules[0]
+        .declarations
+        .iter()
+        .map(|d| (d.name.as_str(), d.important))
+        .collect();
+    assert_eq!(names, [("color", false), ("margin", true)]);
+    assert_eq!(style_rules[1].declarations[0].name, "font-style");
+
+    // The invalid declaration and the rule without a block
+    assert_eq!(stylesheet.errors.len(), 2);
+}
+
+#[test]
+fn test_css_is_collected_from_dom() {
+    let dom = html5ever::Dom::parse_document(
+        "<style>p { color: red }</style>\
+         <style type=text/plain>q {}</style>\
+         <template><style>r {}</style></template>\
+         <p style='color: blue; width: 1px; ;;'>x</p>\
+         <svg><style>circle { fill: red }</style></svg>",
+        false,
+    )
+    .unwrap();
+    let arena = dom.arena();
+
+    let stylesheets = css::stylesheets(arena);
+    assert_eq!(stylesheets.len(), 2);
+    assert_eq!(stylesheets[0].1.style_rules().count(), 1);
+    let svg = arena.node(stylesheets[1].0).element_name().unwrap();
+    assert_eq!(&*svg.ns, "http://www.w3.org/2000/svg");
+
+    let styles = css::style_attributes(arena);
+    assert_eq!(styles.len(), 1);
+    let names: Vec<_> = styles[0].1.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, ["color", "width"]);
+}
@@ -0,0 +1,241 @@
+use rendering::html5::{html5ever, quick_xml, Document};
+use rendering::layout::Rect;
+use rendering::paint::{DisplayItem, DisplayList};
+use rendering::testing::render::{lay_out, WithBackend, CANVAS};
+use serde_json::json;
+use test_case::test_case;
+
+fn build<T: for<'i> Document<'i, T>>(html: &str) -> DisplayList {
+    DisplayList::new(&lay_out::<T>(html, &CANVAS).1)
+}
+
+fn rect(rect: &Rect) -> String {
+    format!("{},{} {}x{}", rect.x, rect.y, rect.width, rect.height)
+}
+
+// A line for each item after the white of the canvas
+fn summary(list: &DisplayList) -> Vec<String> {
+    list.items[1..]
+        .iter()
+        .map(|item| match item {
+            DisplayItem::Rect { rect: r, color } => format!("rect {} {color}", rect(&r.rect)),
+            DisplayItem::Image { rect: r, .. } => format!("image {}", rect(&r.rect)),
+            DisplayItem::Border { rect: r, .. } => format!("border {}", rect(&r.rect)),
+            DisplayItem::Text { text, .. } => format!("text {text:?}"),
+            DisplayItem::PushClip { rect: r } => format!("push-clip {}", rect(&r.rect)),
+            DisplayItem::PopClip => "pop-clip".into(),
+            DisplayItem::PushLayer { opacity } => format!("push-layer {opacity}"),
+            DisplayItem::PopLayer => "pop-layer".into(),
+        })
+        .collect()
+}
+
+#[test_case(build::<html5ever::Dom>; "html5ever")]
+#[test_case(build::<quick_xml::Dom>; "quick_xml")]
+fn test_canvas(build: WithBackend<DisplayList>) {
+    let list = build("<style>html { background: blue }</style>");
+    assert_eq!(list.viewport, Rect::new(0.0, 0.0, 100.0, 100.0));
+    assert_eq!(
+        summary(&list),
+        ["rect 0,0 100x100 rgb(0, 0, 255)"],
+        "the root's background covers the canvas"
+    );
+}
+
+#[test_case(build::<html5ever::Dom>; "html5ever")]
+#[test_case(build::<quick_xml::Dom>; "quick_xml")]
+fn test_block_backgrounds_before_inline_content(build: WithBackend<DisplayList>) {
+    let list = build(
+        "<div style='background: red'>a</div>\
+         <div style='margin-top: -10px; background: blue'>b</div>",
+    );
+    assert_eq!(
+        summary(&list),
+        [
+            "rect 0,0 100x10 rgb(255, 0, 0)",
+            "rect 0,0 100x10 rgb(0, 0, 255)",
+            "text \"a\"",
+            "text \"b\"",
+        ]
+    );
+}
+
+#[test_case(build::<html5ever::Dom>; "html5ever")]
+#[test_case(build::<quick_xml::Dom>; "quick_xml")]
+fn test_inline_blocks(build: WithBackend<DisplayList>) {
+    // Inline blocks paint as a unit with the inline content, after all block backgrounds
+    let list = build(
+        "<span style='display: inline-block; background: red'>a</span>b\
+         <div style='margin-top: -10px; height: 10px; background: blue'></div>",
+    );
+    assert_eq!(
+        summary(&list),
+        [
+            "rect 0,0 100x10 rgb(0, 0, 255)",
+            "rect 0,0 10x10 rgb(255, 0, 0)",
+            "text \"a\"",
+            "text \"b\"",
+        ]
+    );
+}
+
+#[test_case(build::<html5ever::Dom>; "html5ever")]
+#[test_case(build::<quick_xml::Dom>; "quick_xml")]
+fn test_z_index(build: WithBackend<DisplayList>) {
+    let list = build(
+        "<div style='height: 10px; position: relative; z-index: 1; background: red'></div>\
+         <div style='height: 10px; position: relative; z-index: -1; background: blue'></div>\
+         <div style='height: 10px; position: relative; background: green'></div>\
+         <div style='height: 10px; background: yellow'></div>\
+         <div style='height: 10px; position: relative; z-index: 2; background: black'></div>\
+         <div style='height: 10px; position: relative; z-index: 1; background: gray'></div>\
+         <div style='height: 10px; position: relative; z-index: 0; background: white'></div>",
+    );
+    assert_eq!(
+        summary(&list),
+        [
+            "rect 0,10 100x10 rgb(0, 0, 255)",
+            "rect 0,30 100x10 rgb(255, 255, 0)",
+            "rect 0,20 100x10 rgb(0, 128, 0)",
+            "rect 0,60 100x10 rgb(255, 255, 255)",
+            "rect 0,0 100x10 rgb(255, 0, 0)",
+            "rect 0,50 100x10 rgb(128, 128, 128)",
+            "rect 0,40 100x10 rgb(0, 0, 0)",
+        ]
+    );
+}
+
+#[test_case(build::<html5ever::Dom>; "html5ever")]
+#[test_case(build::<quick_xml::Dom>; "quick_xml")]
+fn test_stacking_contexts(build: WithBackend<DisplayList>) {
+    // Descendants of a stacking context paint with it, while those of a positioned element
+    // without a z-index paint with the enclosing stacking context
+    let list = build(
+        "<div style='position: relative; z-index: 1'>\
+         <div style='height: 10px; position: relative; z-index: 5; background: red'></div>\
+         </div>\
+         <div style='position: relative'>\
+         <div style='height: 10px; position: relative; z-index: 3; background: blue'></div>\
+         </div>\
+         <div style='height: 10px; position: relative; z-index: 2; background: green'></div>",
+    );
+    assert_eq!(
+        summary(&list),
+        [
+            "rect 0,0 100x10 rgb(255, 0, 0)",
+            "rect 0,20 100x10 rgb(0, 128, 0)",
+            "rect 0,10 100x10 rgb(0, 0, 255)",
+        ]
+    );
+}
+
+#[test_case(build::<html5ever::Dom>; "html5ever")]
+#[test_case(build::<quick_xml::Dom>; "quick_xml")]
+fn test_opacity(build: WithBackend<DisplayList>) {
+    // Opacity makes a stacking context, painted after blocks in tree order
+    let list = build(
+        "<div style='height: 10px; opacity: 0.5; background: red'>a</div>\
+         <div style='height: 10px; background: blue'></div>\
+         <div style='height: 10px; opacity: 0'></div>",
+    );
+    assert_eq!(
+        summary(&list),
+        [
+            "rect 0,10 100x10 rgb(0, 0, 255)",
+            "push-layer 0.5",
+            "rect 0,0 100x10 rgb(255, 0, 0)",
+            "text \"a\"",
+            "pop-layer",
+            "push-layer 0",
+            "pop-layer",
+        ]
+    );
+}
+
+#[test_case(build::<html5ever::Dom>; "html5ever")]
+#[test_case(build::<quick_xml::Dom>; "quick_xml")]
+fn test_clips(build: WithBackend<DisplayList>) {
+    // Clips apply to descendants wherever they paint, and are only pushed around what they
+    // clip
+    let list = build(
+        "<div style='height: 10px; overflow: hidden; border: 1px solid'>\
+         <p style='margin: 0'>a</p>\
+         <div style='height: 20px; position: relative; background: red'></div>\
+         </div>\
+         <div style='height: 10px; background: blue'></div>\
+         <div style='height: 10px; opacity: 0.5'>\
+         <div style='height: 10px; overflow: hidden'>b</div>\
+         </div>",
+    );
+    assert_eq!(
+        summary(&list),
+        [
+            "border 0,0 100x12",
+            "rect 0,12 100x10 rgb(0, 0, 255)",
+            "push-clip 1,1 98x10",
+            "text \"a\"",
+            "rect 1,11 98x20 rgb(255, 0, 0)",
+            "pop-clip",
+            "push-layer 0.5",
+            "push-clip 0,22 100x10",
+            "text \"b\"",
+            "pop-clip",
+            "pop-layer",
+        ]
+    );
+}
+
+#[test_case(build::<html5ever::Dom>; "html5ever")]
+#[test_case(build::<quick_xml::Dom>; "quick_xml")]
+fn test_serialize(build: WithBackend<DisplayList>) {
+    let list = build(
+        "<div style='width: 10px; height: 5px; border: 1px dashed red; border-top-left-radius: 2px'>\
+         </div><p style='margin: 0; color: green'>a</p>\
+         <div style='height: 10px; background: linear-gradient(to right, black, white)'></div>",
+    );
+    let corners = |top_left| {
+        json!({
+            "top_left": top_left,
+            "top_right": [0.0, 0.0],
+            "bottom_right": [0.0, 0.0],
+            "bottom_left": [0.0, 0.0],
+        })
+    };
+    let sides = |value| json!({"top": value, "right": value, "bottom": value, "left": value});
+    assert_eq!(
+        serde_json::to_value(&list.items[1..]).unwrap(),
+        json!([
+            {
+                "type": "border",
+                "rect": {
+                    "rect": {"x": 0.0, "y": 0.0, "width": 12.0, "height": 7.0},
+                    "radii": corners([2.0, 2.0]),
+                },
+                "widths": sides(json!(1.0)),
+                "styles": sides(json!("dashed")),
+                "colors": sides(json!("rgb(255, 0, 0)")),
+            },
+            {
+                "type": "image",
+                "rect": {
+                    "rect": {"x": 0.0, "y": 17.0, "width": 100.0, "height": 10.0},
+                    "radii": corners([0.0, 0.0]),
+                },
+                "image": {
+                    "type": "linear-gradient",
+                    "start": [0.0, 22.0],
+                    "end": [100.0, 22.0],
+                    "stops": [[0.0, "rgb(0, 0, 0)"], [1.0, "rgb(255, 255, 255)"]],
+                },
+            },
+            {
+                "type": "text",
+                "rect": {"x": 0.0, "y": 7.0, "width": 10.0, "height": 10.0},
+                "baseline": 15.0,
+                "text": "a",
+                "font_size": 10.0,
+                "color": "rgb(0, 128, 0)",
+            },
+        ])
+    );
+}
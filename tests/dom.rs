@@ -0,0 +1,157 @@
+use rendering::dom::{Arena, NodeData, NodeId, SourceLocation};
+use rendering::html5::{html5ever, quick_xml, Document};
+use rendering::testing::tree_construction::DocumentWrite;
+use std::thread;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_trees_are_send_and_sync() {
+    assert_send_sync::<Arena>();
+    assert_send_sync::<html5ever::Dom>();
+    assert_send_sync::<quick_xml::Dom>();
+}
+
+#[test]
+fn test_tree_can_be_walked_on_another_thread() {
+    let dom = html5ever::Dom::parse_document("<p>One<p>Two", false).unwrap();
+
+    let texts = thread::spawn(move || {
+        let arena = dom.arena();
+        arena
+            .descendants(arena.document())
+            .filter_map(|id| match arena.node(id).data {
+                NodeData::Text { ref contents } => Some(contents.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(texts, vec!["One", "Two"]);
+}
+
+#[test]
+fn test_links_between_nodes() {
+    let dom =
+        quick_xml::Dom::parse_document("<ul><li>a</li><li>b</li><li>c</li></ul>", false).unwrap();
+    let arena = dom.arena();
+
+    let ul = arena
+        .descendants(arena.document())
+        .find(|&id| matches!(arena.node(id).element_name(), Some(name) if &*name.local == "ul"))
+        .unwrap();
+    let items: Vec<_> = arena.children(ul).collect();
+    assert_eq!(items.len(), 3);
+
+    for (i, &item) in items.iter().enumerate() {
+        let node = arena.node(item);
+        assert_eq!(node.parent, Some(ul));
+        assert_eq!(node.prev_sibling, i.checked_sub(1).map(|j| items[j]));
+        assert_eq!(node.next_sibling, items.get(i + 1).copied());
+    }
+
+    assert_eq!(arena.node(ul).first_child, items.first().copied());
+    assert_eq!(arena.node(ul).last_child, items.last().copied());
+}
+
+#[test]
+fn test_template_contents() {
+    let dom = html5ever::Dom::parse_document("<template><b>x</b></template>", false).unwrap();
+    let arena = dom.arena();
+
+    let template = arena
+        .descendants(arena.document())
+        .find(|&id| arena.template_contents(id).is_some())
+        .unwrap();
+    assert_eq!(arena.children(template).count(), 0);
+
+    let content = arena.template_contents(template).unwrap();
+    assert!(matches!(arena.node(content).data, NodeData::Document));
+    assert_eq!(arena.children(content).count(), 1);
+}
+
+fn find(arena: &Arena, local: &str) -> NodeId {
+    arena
+        .descendants(arena.document())
+        .find(|&id| matches!(arena.node(id).element_name(), Some(name) if &*name.local == local))
+        .unwrap()
+}
+
+#[test]
+fn test_quick_xml_nodes_have_source_locations() {
+    let html = "<!DOCTYPE html>\r\n<p class=a>one\r\ntwo</p>\n<!--c--><textarea>&amp;</textarea>";
+    let dom = quick_xml::Dom::parse_document(html, false).unwrap();
+    let arena = dom.arena();
+    let location = |id| dom.location(id).unwrap().clone();
+    let source = |id| &html[location(id).span.unwrap()];
+
+    let p = find(arena, "p");
+    assert_eq!(
+        location(p),
+        SourceLocation {
+            line: 2,
+            col: Some(1),
+            span: Some(17..28),
+        }
+    );
+
+    let text = arena.node(p).first_child.unwrap();
+    assert_eq!(source(text), "one\r\ntwo");
+    assert_eq!((location(text).line, location(text).col), (2, Some(12)));
+
+    let comment = arena
+        .node(arena.node(p).next_sibling.unwrap())
+        .next_sibling
+        .unwrap();
+    assert_eq!(source(comment), "<!--c-->");
+    assert_eq!(
+        (location(comment).line, location(comment).col),
+        (4, Some(1))
+    );
+
+    let textarea = find(arena, "textarea");
+    assert_eq!(source(textarea), "<textarea>");
+    assert_eq!(source(arena.node(textarea).first_child.unwrap()), "&amp;");
+    assert_eq!(dom.location(arena.document()), None);
+}
+
+#[test]
+fn test_html5ever_nodes_have_source_lines() {
+    let dom = html5ever::Dom::parse_document("<p>a\n<b>b</b>\n<i>c</i>", false).unwrap();
+    let arena = dom.arena();
+
+    for (local, line) in [("p", 1), ("b", 2), ("i", 3)] {
+        let location = dom.location(find(arena, local)).unwrap();
+        assert_eq!((location.line, location.col), (line, None), "{local}");
+    }
+}
+
+#[test]
+fn test_node_at_offset() {
+    let html = "<div><p id=x>text</p><!--c--></div>";
+    let dom = quick_xml::Dom::parse_document(html, false).unwrap();
+    let arena = dom.arena();
+    let p = find(arena, "p");
+
+    assert_eq!(dom.node_at(0), Some(find(arena, "div")));
+    assert_eq!(dom.node_at(8), Some(p));
+    assert_eq!(dom.node_at(14), arena.node(p).first_child);
+    assert_eq!(dom.node_at(24), arena.node(p).next_sibling);
+    assert_eq!(dom.node_at(html.len()), None);
+}
+
+#[test]
+fn test_written_markup_has_no_span() {
+    let html = "<script>document.write('<b>w</b>')</script><i>x</i>";
+    let dom =
+        quick_xml::Dom::parse_document_with_host(html, true, Box::new(DocumentWrite)).unwrap();
+    let arena = dom.arena();
+
+    let b = dom.location(find(arena, "b")).unwrap();
+    assert_eq!((b.line, b.col, b.span.clone()), (1, Some(44), None));
+
+    let i = dom.location(find(arena, "i")).unwrap();
+    assert_eq!((i.col, i.span.clone()), (Some(44), Some(43..46)));
+}
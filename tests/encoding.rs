@@ -0,0 +1,35 @@
+use rendering::html5::html5ever;
+use rendering::html5::quick_xml;
+use rendering::testing::encoding::fixture_from_filename;
+use test_case::test_case;
+
+macro_rules! passes {
+    ($type:ty, $func:ident, [$($filename:expr),*,]) => {
+        $(
+            #[test_case($filename)]
+        )*
+        fn $func(filename: &str) {
+            let tests = fixture_from_filename(filename).expect("error loading fixture");
+
+            for test in tests.iter() {
+                let data = String::from_utf8_lossy(&test.data);
+                println!("running {}", data);
+
+                let (actual, expected) = test.run::<$type>().unwrap();
+                assert_eq!(actual, expected, "\n  data: {data}\n");
+            }
+        }
+    };
+}
+
+passes!(
+    html5ever::Dom,
+    test_html5ever_dom_sniffs_encoding,
+    ["tests1.dat", "tests2.dat", "test-yahoo-jp.dat",]
+);
+
+passes!(
+    quick_xml::Dom,
+    test_quick_xml_dom_sniffs_encoding,
+    ["tests1.dat", "tests2.dat", "test-yahoo-jp.dat",]
+);
@@ -0,0 +1,114 @@
+use rendering::testing::conformance::{backends, Backend};
+use rendering::testing::fuzz::{divergence, minimize, Divergence, Fuzzer, Generator};
+use rendering::types::Result;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use std::{env, panic, thread};
+
+const SEED: u64 = 0x5eed;
+
+// Set FUZZ_SECONDS to fuzz for longer, and FUZZ_SEED to try other inputs
+fn fuzzer() -> Fuzzer {
+    let seed = env::var("FUZZ_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or(SEED);
+    let seconds = env::var("FUZZ_SECONDS")
+        .ok()
+        .and_then(|seconds| seconds.parse().ok())
+        .unwrap_or(3);
+    Fuzzer::new(seed, Duration::from_secs(seconds))
+}
+
+// Serializes swapping the panic hook, which is global, between tests running in parallel
+static HOOK: Mutex<()> = Mutex::new(());
+
+// Runs `f` without printing the panics the fuzzer catches on this thread, then puts the previous
+// panic hook back.  Panics on other threads are still reported.
+fn quietly<T>(f: impl FnOnce() -> T) -> T {
+    let _guard = HOOK.lock().unwrap_or_else(PoisonError::into_inner);
+    let hook = Arc::new(panic::take_hook());
+    let current = thread::current().id();
+    let previous = Arc::clone(&hook);
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().id() != current {
+            previous(info);
+        }
+    }));
+
+    let result = f();
+    let _ = panic::take_hook();
+    panic::set_hook(Box::new(move |info| hook(info)));
+    result
+}
+
+// The quick-xml backend does not build the same trees as html5ever yet, so differences are
+// only reported, but neither backend should panic
+#[test]
+fn test_backends_agree_on_fuzzed_input() {
+    let report = quietly(|| fuzzer().run());
+
+    println!(
+        "{} inputs, {} panics, {} mismatches",
+        report.iterations,
+        report.panics().count(),
+        report.mismatches().count()
+    );
+    for finding in report.mismatches() {
+        println!("{finding}");
+    }
+
+    let panics: Vec<_> = report.panics().map(ToString::to_string).collect();
+    assert!(panics.is_empty(), "{}", panics.join("\n"));
+}
+
+#[test]
+fn test_generator_is_deterministic() {
+    let seeds = vec!["<p>One<b>two</b>".to_owned()];
+    let mut a = Generator::new(SEED, seeds.clone());
+    let mut b = Generator::new(SEED, seeds);
+
+    for _ in 0..100 {
+        assert_eq!(a.next_input(), b.next_input());
+    }
+}
+
+#[test]
+fn test_minimize_keeps_what_the_predicate_needs() {
+    let minimized = minimize("<p>abc<table>def</table>ghi", |s| {
+        s.contains("<table>") && s.contains('f')
+    });
+    assert_eq!(minimized, "<table>f");
+}
+
+fn parse_panics_on_svg(data: &str) -> Result<String> {
+    if data.contains("<svg") {
+        panic!("no svg");
+    }
+    Ok(String::new())
+}
+
+#[test]
+fn test_fuzzer_minimizes_panics() {
+    let mut fuzzer = Fuzzer::new(SEED, Duration::from_secs(10));
+    fuzzer.max_iterations = 2000;
+    fuzzer.max_findings = 1;
+    fuzzer.seeds = vec![];
+    fuzzer.backends = vec![Backend {
+        name: "fussy",
+        run: |_| Ok(vec![]),
+        parse: parse_panics_on_svg,
+    }];
+
+    let report = quietly(|| fuzzer.run());
+
+    let finding = &report.findings[0];
+    assert_eq!(finding.divergence, Divergence::Panic("fussy"));
+    assert_eq!(finding.minimized, "<svg");
+    assert!(finding.input.contains("<svg"));
+}
+
+#[test]
+fn test_backends_agree_on_simple_input() {
+    assert_eq!(divergence(&backends(), "<p>One<b>two</b>"), None);
+}
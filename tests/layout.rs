@@ -0,0 +1,438 @@
+use rendering::html5::{html5ever, quick_xml, xml, Document};
+use rendering::layout::{layout, BoxTree, FragmentKind};
+use rendering::style::Viewport;
+use rendering::testing::golden::{self, GOLDEN_DIR};
+use rendering::testing::render::{lay_out, WithBackend};
+use std::{fs, path::Path};
+use test_case::test_case;
+
+fn dump<T: for<'i> Document<'i, T>>(html: &str) -> String {
+    let (dom, fragments) = lay_out::<T>(html, &Viewport::default());
+    let dump = fragments.dump(dom.arena());
+
+    // Leave out the html and body elements
+    dump.lines()
+        .skip(2)
+        .map(|line| format!("{}\n", line.replacen("|     ", "| ", 1)))
+        .collect()
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_margin_collapsing(dump: WithBackend<String>) {
+    // Siblings collapse, as do parents and their first and last children, and empty blocks
+    // collapse through, but not across padding or a new formatting context
+    let html = "<p style='margin: 10px 0'>a</p>\
+                <div style='margin-top: 30px'><p style='margin: 5px'>b</p></div>\
+                <div style='margin-bottom: 20px'></div><div style='margin-top: -5px'>c</div>\
+                <div style='padding-top: 1px'><p style='margin: 5px 0'>d</p></div>\
+                <div style='overflow: hidden'><p style='margin: 5px 0'>e</p></div>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <p> 0,10 800x10
+|   line 0,10 800x10
+|     \"a\" 0,10 10x10
+| block <div> 0,50 800x10
+|   block <p> 5,50 790x10
+|     line 5,50 790x10
+|       \"b\" 5,50 10x10
+| block <div> 0,65 800x0
+| block <div> 0,75 800x10
+|   line 0,75 800x10
+|     \"c\" 0,75 10x10
+| block <div> 0,85 800x16
+|   block <p> 0,91 800x10
+|     line 0,91 800x10
+|       \"d\" 0,91 10x10
+| block <div> 0,106 800x20
+|   block <p> 0,111 800x10
+|     line 0,111 800x10
+|       \"e\" 0,111 10x10
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_widths(dump: WithBackend<String>) {
+    let html = "<div style='width: 100px; margin: 0 auto; padding: 2px; border: 1px solid'>a</div>\
+                <div style='width: 50px; margin-left: auto'>b</div>\
+                <div style='width: 50px; padding: 5px; border: 2px solid; box-sizing: border-box'>c</div>\
+                <div style='width: 50%; min-width: 500px'>d</div>\
+                <div style='max-width: 100px; margin-left: 10px'>e</div>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <div> 347,0 106x16
+|   line 350,3 100x10
+|     \"a\" 350,3 10x10
+| block <div> 750,16 50x10
+|   line 750,16 50x10
+|     \"b\" 750,16 10x10
+| block <div> 0,26 50x24
+|   line 7,33 36x10
+|     \"c\" 7,33 10x10
+| block <div> 0,50 500x10
+|   line 0,50 500x10
+|     \"d\" 0,50 10x10
+| block <div> 10,60 100x10
+|   line 10,60 100x10
+|     \"e\" 10,60 10x10
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_heights(dump: WithBackend<String>) {
+    // Percentages of a containing block without a definite height are auto
+    let html = "<div style='height: 40px'><div style='height: 50%'></div></div>\
+                <div><div style='height: 50%'>a</div></div>\
+                <div style='height: 5px; min-height: 15px'></div>\
+                <div style='max-height: 10px'>b<br>c</div>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <div> 0,0 800x40
+|   block <div> 0,0 800x20
+| block <div> 0,40 800x10
+|   block <div> 0,40 800x10
+|     line 0,40 800x10
+|       \"a\" 0,40 10x10
+| block <div> 0,50 800x15
+| block <div> 0,65 800x10
+|   line 0,65 800x10
+|     \"b\" 0,65 10x10
+|   line 0,75 800x10
+|     \"c\" 0,75 10x10
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_line_wrapping(dump: WithBackend<String>) {
+    // Lines wrap after spaces, which hang at their ends, and inline boxes split across lines
+    // with their start and end edges only on the first and last
+    let html = "<p style='width: 100px'>aaa bbb ccc ddd \
+                <span style='padding: 0 2px; border: 1px solid'>eee fff ggg</span> hhh</p>\
+                <p style='width: 30px'>overflowing x</p>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <p> 0,10 100x40
+|   line 0,10 100x10
+|     \"aaa \" 0,10 40x10
+|     \"bbb\" 40,10 30x10
+|   line 0,20 100x10
+|     \"ccc \" 0,20 40x10
+|     \"ddd\" 40,20 30x10
+|   line 0,30 100x10
+|     inline <span> 0,29 73x12
+|       \"eee \" 3,30 40x10
+|       \"fff\" 43,30 30x10
+|   line 0,40 100x10
+|     inline <span> 0,39 33x12
+|       \"ggg\" 0,40 30x10
+|     \" \" 33,40 10x10
+|     \"hhh\" 43,40 30x10
+| block <p> 0,60 30x20
+|   line 0,60 30x10
+|     \"overflowing\" 0,60 110x10
+|   line 0,70 30x10
+|     \"x\" 0,70 10x10
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_white_space(dump: WithBackend<String>) {
+    let html = "<p style='width: 50px'>  a  \n  b  <br>  c  </p>\
+                <pre style='margin: 0; width: 50px'>a   b  c\n\n  d</pre>\
+                <p style='width: 50px; white-space: nowrap'>a b c d e f</p>\
+                <p style='width: 50px; white-space: pre-line'>a   b\nc</p>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <p> 0,10 50x20
+|   line 0,10 50x10
+|     \"a \" 0,10 20x10
+|     \"b\" 20,10 10x10
+|   line 0,20 50x10
+|     \"c\" 0,20 10x10
+| block <pre> 0,40 50x30
+|   line 0,40 50x10
+|     \"a   b  c\" 0,40 80x10
+|   line 0,50 50x10
+|   line 0,60 50x10
+|     \"  d\" 0,60 30x10
+| block <p> 0,80 50x10
+|   line 0,80 50x10
+|     \"a b c d e f\" 0,80 110x10
+| block <p> 0,100 50x20
+|   line 0,100 50x10
+|     \"a \" 0,100 20x10
+|     \"b\" 20,100 10x10
+|   line 0,110 50x10
+|     \"c\" 0,110 10x10
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_word_breaking(dump: WithBackend<String>) {
+    // Lines wrap between ideographs and before opening punctuation, but not before closing
+    // punctuation, and within words that overflow as `overflow-wrap` and `word-break` say
+    let html = "<p style='width: 30px'>中文字、中文</p>\
+                <p style='width: 30px; word-break: keep-all'>中文 字</p>\
+                <p style='width: 30px; word-break: break-all'>aaaa</p>\
+                <p style='width: 50px; overflow-wrap: break-word'>aaaaaaaa bb</p>\
+                <p style='width: 30px; white-space: break-spaces'>a    b</p>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <p> 0,10 30x30
+|   line 0,10 30x10
+|     \"中\" 0,10 10x10
+|     \"文\" 10,10 10x10
+|   line 0,20 30x10
+|     \"字、\" 0,20 20x10
+|     \"中\" 20,20 10x10
+|   line 0,30 30x10
+|     \"文\" 0,30 10x10
+| block <p> 0,50 30x20
+|   line 0,50 30x10
+|     \"中文\" 0,50 20x10
+|   line 0,60 30x10
+|     \"字\" 0,60 10x10
+| block <p> 0,80 30x20
+|   line 0,80 30x10
+|     \"a\" 0,80 10x10
+|     \"a\" 10,80 10x10
+|     \"a\" 20,80 10x10
+|   line 0,90 30x10
+|     \"a\" 0,90 10x10
+| block <p> 0,110 50x30
+|   line 0,110 50x10
+|     \"aaaaa\" 0,110 50x10
+|   line 0,120 50x10
+|     \"aaa\" 0,120 30x10
+|   line 0,130 50x10
+|     \"bb\" 0,130 20x10
+| block <p> 0,150 30x20
+|   line 0,150 30x10
+|     \"a \" 0,150 20x10
+|     \" \" 20,150 10x10
+|   line 0,160 30x10
+|     \" \" 0,160 10x10
+|     \" \" 10,160 10x10
+|     \"b\" 20,160 10x10
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_overflow_wrap_min_content(dump: WithBackend<String>) {
+    // Only breaks that `overflow-wrap: anywhere` allows make the min-content width narrower
+    let html = "<div style='width: 0'>\
+                <span style='display: inline-block; overflow-wrap: anywhere'>abc</span>\
+                <span style='display: inline-block; overflow-wrap: break-word'>abc</span>\
+                </div>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <div> 0,0 0x40
+|   line 0,0 0x30
+|     inline-block <span> 0,0 10x30
+|       line 0,0 10x10
+|         \"a\" 0,0 10x10
+|       line 0,10 10x10
+|         \"b\" 0,10 10x10
+|       line 0,20 10x10
+|         \"c\" 0,20 10x10
+|   line 0,30 0x10
+|     inline-block <span> 0,30 30x10
+|       line 0,30 30x10
+|         \"abc\" 0,30 30x10
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_text_align(dump: WithBackend<String>) {
+    let html = "<p style='width: 100px; text-align: center'>ab<br>c</p>\
+                <p style='width: 100px; text-align: right'>ab </p>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <p> 0,10 100x20
+|   line 0,10 100x10
+|     \"ab\" 40,10 20x10
+|   line 0,20 100x10
+|     \"c\" 45,20 10x10
+| block <p> 0,40 100x10
+|   line 0,40 100x10
+|     \"ab\" 80,40 20x10
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_baselines(dump: WithBackend<String>) {
+    // The line is tall enough for the struts and atomic inlines on it, aligned on their baselines
+    let html = "<div>a<span style='font-size: 20px'>b</span>\
+                <img width=20 height=30><span style='vertical-align: super'>c</span>\
+                <span style='vertical-align: -5px'>d</span>\
+                <small style='vertical-align: top; font-size: 5px'>e</small></div>\
+                <div style='line-height: 20px'>f</div>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <div> 0,0 800x37
+|   line 0,0 800x37
+|     \"a\" 0,22 10x10
+|     inline <span> 10,14 20x20
+|       \"b\" 10,14 20x20
+|     replaced <img> 30,0 20x30
+|     inline <span> 50,18.67 10x10
+|       \"c\" 50,18.67 10x10
+|     inline <span> 60,27 10x10
+|       \"d\" 60,27 10x10
+|     inline <small> 70,2.5 5x5
+|       \"e\" 70,2.5 5x5
+| block <div> 0,37 800x20
+|   line 0,37 800x20
+|     \"f\" 0,42 10x10
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_inline_blocks(dump: WithBackend<String>) {
+    // Inline-blocks shrink to fit and sit on the baseline of their last line, or on their
+    // bottom margin edge if they have none or clip their contents.  One wider than the line
+    // shrinks to the line
+    let html = "<div style='width: 100px'>a\
+                <span style='display: inline-block; border: 1px solid'>b c<div>d</div></span>\
+                <span style='display: inline-block; width: 20px; height: 5px'></span>\
+                <span style='display: inline-block; overflow: hidden; margin: 2px'>e</span>\
+                <span style='display: inline-block'>fff ggg hhh iii jjj</span></div>";
+    assert_eq!(
+        dump(html),
+        "\
+| block <div> 0,0 100x52
+|   line 0,0 100x22
+|     \"a\" 0,11 10x10
+|     inline-block <span> 10,0 32x22
+|       block (anonymous) 11,1 30x10
+|         line 11,1 30x10
+|           \"b \" 11,1 20x10
+|           \"c\" 31,1 10x10
+|       block <div> 11,11 30x10
+|         line 11,11 30x10
+|           \"d\" 11,11 10x10
+|     inline-block <span> 42,14 20x5
+|     inline-block <span> 64,7 10x10
+|       line 64,7 10x10
+|         \"e\" 64,7 10x10
+|   line 0,22 100x30
+|     inline-block <span> 0,22 100x30
+|       line 0,22 100x10
+|         \"fff \" 0,22 40x10
+|         \"ggg\" 40,22 30x10
+|       line 0,32 100x10
+|         \"hhh \" 0,32 40x10
+|         \"iii\" 40,32 30x10
+|       line 0,42 100x10
+|         \"jjj\" 0,42 30x10
+"
+    );
+}
+
+#[test_case(dump::<html5ever::Dom>; "html5ever")]
+#[test_case(dump::<quick_xml::Dom>; "quick_xml")]
+fn test_replaced(dump: WithBackend<String>) {
+    let html = "<img style='display: block; margin: 0 auto' width=100 height=50>\
+                <img style='display: block; width: 10px'><div><canvas></canvas></div>";
+    assert_eq!(
+        dump(html),
+        "\
+| replaced <img> 350,0 100x50
+| replaced <img> 0,50 10x150
+| block <div> 0,200 800x152
+|   line 0,200 800x152
+|     replaced <canvas> 0,200 300x150
+"
+    );
+}
+
+#[test]
+fn test_root_and_viewport() {
+    let dom = html5ever::Dom::parse_document(
+        "<html style='margin: 5px; padding: 10px'><body style='margin: 20px'>",
+        false,
+    )
+    .unwrap();
+    let viewport = Viewport {
+        width: 400.0,
+        height: 300.0,
+    };
+    let styles = dom.compute_styles(&viewport);
+    let tree = layout(&BoxTree::new(dom.arena(), &styles), &viewport);
+    assert_eq!(tree.viewport.width, 400.0);
+    assert_eq!(
+        tree.dump(dom.arena()),
+        "| block <html> 5,5 390x40\n|   block <body> 35,35 330x0\n"
+    );
+
+    // Fragments are in absolute coordinates, with their content inside their padding
+    let html = tree.root.unwrap();
+    assert_eq!(html.kind, FragmentKind::Block);
+    assert_eq!(html.content_rect().x, 15.0);
+    assert_eq!(html.content_rect().width, 370.0);
+}
+
+#[test]
+fn test_xml_documents() {
+    let dom = xml::Dom::parse_document(
+        "<doc xmlns='http://www.w3.org/1999/xhtml'><p>a</p><b>c</b></doc>",
+        false,
+    )
+    .unwrap();
+    let styles = dom.compute_styles(&Viewport::default());
+    let boxes = BoxTree::new(dom.arena(), &styles);
+    assert_eq!(
+        layout(&boxes, &Viewport::default()).dump(dom.arena()),
+        "\
+| block <doc> 0,0 800x70.4
+|   block <p> 0,16 800x19.2
+|     line 0,16 800x19.2
+|       \"a\" 0,17.6 16x16
+|   block (anonymous) 0,51.2 800x19.2
+|     line 0,51.2 800x19.2
+|       inline <b> 0,52.8 16x16
+|         \"c\" 0,52.8 16x16
+"
+    );
+}
+
+#[test_case("blocks")]
+#[test_case("inline")]
+#[test_case("margins")]
+fn test_golden(name: &str) {
+    let html = fs::read_to_string(Path::new(GOLDEN_DIR).join(format!("layout/{name}.html")));
+    let dom = html5ever::Dom::parse_document(&html.unwrap(), false).unwrap();
+    let styles = dom.compute_styles(&Viewport::default());
+    let boxes = BoxTree::new(dom.arena(), &styles);
+    let dump = layout(&boxes, &Viewport::default()).dump(dom.arena());
+
+    let expected = golden::expected(&format!("layout/{name}.txt"), &dump).unwrap();
+    assert_eq!(dump, expected);
+}
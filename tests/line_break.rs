@@ -0,0 +1,83 @@
+use rendering::layout::text::line_break::{self, Break};
+use rendering::style::values::WordBreak;
+use rendering::testing::line_break::{fixture_from_filename, fixture_from_str, FIXTURE_VERSION};
+use std::path::PathBuf;
+use test_case::test_case;
+
+#[test_case("LineBreakTest.txt")]
+fn test_line_breaking_passes_fixtures(filename: &str) {
+    let tests = fixture_from_filename(filename).expect("error loading fixture");
+
+    for test in tests.iter() {
+        let (actual, expected) = test.run();
+        assert_eq!(
+            actual, expected,
+            "\n  line {}: {:?}\n",
+            test.line, test.input
+        );
+    }
+}
+
+#[test]
+fn test_line_breaking_fixture_matches_unicode_version() {
+    assert_eq!(line_break::UNICODE_VERSION, FIXTURE_VERSION);
+}
+
+// Tests in the fixture format
+const LINE_BREAKS: &str = "\
+× 0061 × 0020 ÷ 0062 ÷	#  a b
+× 0061 × 0020 × 0020 ÷ 0062 ÷	#  a  b
+× 0061 × 002D ÷ 0062 ÷	#  a-b
+× 4E2D ÷ 6587 ÷	#  ideographs
+× 4E2D × 30A1 ÷	#  small kana are non-starters
+× 0024 × 0028 × 0031 × 0032 × 002E × 0033 × 0035 × 0029 ÷	#  $(12.35)
+× 0022 × 0061 × 0022 × 0020 ÷ 0062 ÷	#  quotation marks
+× 0061 × 0020 ÷ 0028 × 0062 ÷	#  opening punctuation
+× 0061 × 200B ÷ 0062 ÷	#  zero width space
+× 0061 × 2060 × 0062 ÷	#  word joiner
+× 0061 × 00A0 × 0062 ÷	#  no-break space
+× 0061 × 000A ÷ 0062 ÷	#  line feed
+× 0061 × 000D × 000A ÷ 0062 ÷	#  carriage return and line feed
+× 0061 × 0301 × 0020 ÷ 0062 ÷	#  combining mark
+× 1F1EB × 1F1F7 ÷ 1F1E9 × 1F1EA ÷	#  regional indicators
+× 1F44D × 1F3FD ÷	#  emoji modifier
+";
+
+#[test]
+fn test_line_breaking_passes_inline_fixtures() {
+    let tests = fixture_from_str(LINE_BREAKS, PathBuf::new()).unwrap();
+
+    for test in tests.iter() {
+        let (actual, expected) = test.run();
+        assert_eq!(
+            actual, expected,
+            "\n  line {}: {:?}\n",
+            test.line, test.input
+        );
+    }
+}
+
+#[test_case("a b\nc", &[(2, Break::Allowed), (4, Break::Mandatory), (5, Break::Mandatory)] ; "mandatory breaks")]
+#[test_case("", &[] ; "empty")]
+fn test_line_breaks(text: &str, expected: &[(usize, Break)]) {
+    assert_eq!(line_break::line_breaks(text), expected);
+}
+
+#[test_case("ab cd", WordBreak::Normal, &[3, 5] ; "normal")]
+#[test_case("ab cd", WordBreak::BreakAll, &[1, 3, 4, 5] ; "break all")]
+#[test_case("中文 字", WordBreak::Normal, &[3, 7, 10] ; "ideographs")]
+#[test_case("中文 字", WordBreak::KeepAll, &[7, 10] ; "keep all")]
+fn test_word_break(text: &str, word_break: WordBreak, expected: &[usize]) {
+    let breaks: Vec<usize> = line_break::line_breaks_by(text, |_| word_break)
+        .into_iter()
+        .map(|(offset, _)| offset)
+        .collect();
+    assert_eq!(breaks, expected);
+}
+
+#[test_case("abc", &[0, 1, 2] ; "letters")]
+#[test_case("a\u{0301}b", &[0, 3] ; "combining mark")]
+#[test_case("\u{1F44D}\u{200D}\u{1F44D}", &[0] ; "zero width joiner")]
+fn test_cluster_boundaries(text: &str, expected: &[usize]) {
+    assert_eq!(line_break::cluster_boundaries(text), expected);
+}
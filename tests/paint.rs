@@ -0,0 +1,302 @@
+use rendering::html5::{html5ever, quick_xml, Document};
+use rendering::paint::{paint, png, Canvas};
+use rendering::style::Color;
+use rendering::testing::render::{lay_out, WithBackend, CANVAS};
+use test_case::test_case;
+
+const RED: Color = Color::rgba(255, 0, 0, 255);
+const GREEN: Color = Color::rgba(0, 128, 0, 255);
+const BLUE: Color = Color::rgba(0, 0, 255, 255);
+const YELLOW: Color = Color::rgba(255, 255, 0, 255);
+
+fn render<T: for<'i> Document<'i, T>>(html: &str) -> Canvas {
+    paint(&lay_out::<T>(html, &CANVAS).1)
+}
+
+fn gray(canvas: &Canvas, x: u32, y: u32) -> u8 {
+    let Color { r, g, b, a } = canvas.pixel(x, y);
+    assert!(
+        r == g && g == b && a == 255,
+        "{} at {x},{y}",
+        canvas.pixel(x, y)
+    );
+    r
+}
+
+#[test_case(render::<html5ever::Dom>; "html5ever")]
+#[test_case(render::<quick_xml::Dom>; "quick_xml")]
+fn test_backgrounds(render: WithBackend<Canvas>) {
+    let canvas = render(
+        "<div style='width: 50px; height: 20px; background: red'>\
+         <div style='width: 10px; height: 10px; margin-left: 20px; background: blue'></div>\
+         </div>",
+    );
+    assert_eq!((canvas.width(), canvas.height()), (100, 100));
+    assert_eq!(canvas.pixel(0, 0), RED);
+    assert_eq!(canvas.pixel(49, 19), RED);
+    assert_eq!(canvas.pixel(25, 5), BLUE);
+    assert_eq!(canvas.pixel(50, 0), Color::WHITE);
+    assert_eq!(canvas.pixel(0, 20), Color::WHITE);
+}
+
+#[test_case(render::<html5ever::Dom>; "html5ever")]
+#[test_case(render::<quick_xml::Dom>; "quick_xml")]
+fn test_root_background(render: WithBackend<Canvas>) {
+    // The root's background covers the whole canvas, even beyond the root's box
+    let canvas = render("<style>html { height: 10px; background: blue }</style>");
+    assert_eq!(canvas.pixel(0, 0), BLUE);
+    assert_eq!(canvas.pixel(99, 99), BLUE);
+}
+
+#[test_case(render::<html5ever::Dom>; "html5ever")]
+#[test_case(render::<quick_xml::Dom>; "quick_xml")]
+fn test_borders(render: WithBackend<Canvas>) {
+    let canvas = render(
+        "<div style='width: 20px; height: 20px; border: 5px solid; \
+         border-color: red green blue yellow'></div>",
+    );
+    assert_eq!(canvas.pixel(15, 1), RED);
+    assert_eq!(canvas.pixel(28, 15), GREEN);
+    assert_eq!(canvas.pixel(15, 28), BLUE);
+    assert_eq!(canvas.pixel(1, 15), YELLOW);
+    assert_eq!(canvas.pixel(15, 15), Color::WHITE);
+    // Corners are split diagonally between the sides that meet there
+    assert_eq!(canvas.pixel(3, 1), RED);
+    assert_eq!(canvas.pixel(1, 3), YELLOW);
+    assert_eq!(canvas.pixel(28, 26), GREEN);
+    assert_eq!(canvas.pixel(26, 28), BLUE);
+}
+
+#[test_case(render::<html5ever::Dom>; "html5ever")]
+#[test_case(render::<quick_xml::Dom>; "quick_xml")]
+fn test_border_styles(render: WithBackend<Canvas>) {
+    let canvas = render(
+        "<div style='height: 9px; border-top: 9px double black'></div>\
+         <div style='height: 0; border-top: 4px dashed black'></div>\
+         <div style='height: 0; border-top: 4px dotted black'></div>\
+         <div style='height: 0; border-top: 4px inset red; border-bottom: 4px inset red'></div>\
+         <div style='height: 0; border-top: 4px hidden black'></div>",
+    );
+    // Two lines a third of the width each
+    assert_eq!(canvas.pixel(50, 1), Color::BLACK);
+    assert_eq!(canvas.pixel(50, 4), Color::WHITE);
+    assert_eq!(canvas.pixel(50, 7), Color::BLACK);
+    // Dashes three times as long as the border is wide, with gaps as long
+    assert_eq!(canvas.pixel(1, 19), Color::BLACK);
+    assert_eq!(canvas.pixel(10, 19), Color::BLACK);
+    assert_eq!(canvas.pixel(13, 19), Color::WHITE);
+    assert_eq!(canvas.pixel(25, 19), Color::BLACK);
+    // Round dots as wide as the border, with gaps as long
+    assert_eq!(canvas.pixel(3, 23), Color::BLACK);
+    assert_eq!(canvas.pixel(0, 22), Color::WHITE);
+    assert_eq!(canvas.pixel(7, 23), Color::WHITE);
+    assert_eq!(canvas.pixel(11, 23), Color::BLACK);
+    // The top of an inset border is darker than the bottom
+    assert_eq!(canvas.pixel(50, 26), Color::rgba(170, 0, 0, 255));
+    assert_eq!(canvas.pixel(50, 30), RED);
+    assert_eq!(canvas.pixel(50, 34), Color::WHITE);
+}
+
+#[test_case(render::<html5ever::Dom>; "html5ever")]
+#[test_case(render::<quick_xml::Dom>; "quick_xml")]
+fn test_border_radius(render: WithBackend<Canvas>) {
+    let canvas = render(
+        "<div style='width: 40px; height: 40px; background: black; border-radius: 50%'></div>\
+         <div style='width: 20px; height: 10px; border: 5px solid red; \
+         border-radius: 0 0 10px 10px'></div>",
+    );
+    // A circle, with its edge anti-aliased
+    assert_eq!(canvas.pixel(20, 20), Color::BLACK);
+    assert_eq!(canvas.pixel(0, 20), Color::BLACK);
+    assert_eq!(canvas.pixel(20, 0), Color::BLACK);
+    assert_eq!(canvas.pixel(2, 2), Color::WHITE);
+    assert_eq!(canvas.pixel(37, 37), Color::WHITE);
+    let edge = gray(&canvas, 5, 6);
+    assert!(0 < edge && edge < 255, "{edge}");
+    // The rounded inside of a border
+    assert_eq!(canvas.pixel(0, 41), RED);
+    assert_eq!(canvas.pixel(15, 50), Color::WHITE);
+    assert_eq!(canvas.pixel(1, 58), Color::WHITE);
+    assert_eq!(canvas.pixel(6, 56), RED);
+    assert_eq!(canvas.pixel(8, 52), Color::WHITE);
+    assert_eq!(canvas.pixel(15, 57), RED);
+}
+
+#[test_case(render::<html5ever::Dom>; "html5ever")]
+#[test_case(render::<quick_xml::Dom>; "quick_xml")]
+fn test_linear_gradients(render: WithBackend<Canvas>) {
+    let canvas = render(
+        "<div style='height: 10px; background: linear-gradient(to right, black, white)'></div>\
+         <div style='height: 10px; background: linear-gradient(90deg, red 50%, blue 50%)'></div>\
+         <div style='height: 20px; background-image: linear-gradient(black, white)'></div>\
+         <div style='height: 10px; background-image: linear-gradient(to left, red, green, blue)'>\
+         </div>",
+    );
+    assert!(gray(&canvas, 0, 5) < 3);
+    assert!(gray(&canvas, 50, 5).abs_diff(128) < 3);
+    assert!(gray(&canvas, 99, 5) > 252);
+
+    assert_eq!(canvas.pixel(49, 15), RED);
+    assert_eq!(canvas.pixel(50, 15), BLUE);
+
+    assert!(gray(&canvas, 50, 20) < 10);
+    assert!(gray(&canvas, 50, 30).abs_diff(128) < 10);
+    assert!(gray(&canvas, 50, 39) > 245);
+
+    // Stops without positions are spaced evenly
+    let Color { r, g, b, .. } = canvas.pixel(50, 45);
+    assert!(
+        r < 5 && g.abs_diff(128) < 5 && b < 5,
+        "{}",
+        canvas.pixel(50, 45)
+    );
+}
+
+#[test_case(render::<html5ever::Dom>; "html5ever")]
+#[test_case(render::<quick_xml::Dom>; "quick_xml")]
+fn test_opacity(render: WithBackend<Canvas>) {
+    // An element and its descendants are composited together, so overlapping children are no
+    // darker than either
+    let canvas = render(
+        "<div style='opacity: 0.5'>\
+         <div style='width: 20px; height: 20px; background: black'></div>\
+         <div style='width: 20px; height: 20px; margin: -10px 0 0 10px; background: black'></div>\
+         </div>",
+    );
+    assert_eq!(gray(&canvas, 5, 5), 128);
+    assert_eq!(gray(&canvas, 15, 15), 128);
+    assert_eq!(gray(&canvas, 25, 25), 128);
+    assert_eq!(canvas.pixel(5, 25), Color::WHITE);
+}
+
+#[test_case(render::<html5ever::Dom>; "html5ever")]
+#[test_case(render::<quick_xml::Dom>; "quick_xml")]
+fn test_overflow_clip(render: WithBackend<Canvas>) {
+    let canvas = render(
+        "<div style='height: 10px; width: 20px; border: 2px solid red; overflow: hidden'>\
+         <div style='height: 30px; width: 50px; background: blue'></div>\
+         </div>\
+         <div style='height: 10px; width: 20px'>\
+         <div style='height: 30px; width: 50px; background: green'></div>\
+         </div>",
+    );
+    assert_eq!(canvas.pixel(10, 10), BLUE);
+    assert_eq!(canvas.pixel(10, 13), RED);
+    assert_eq!(canvas.pixel(30, 10), Color::WHITE);
+    // Overflow is visible by default
+    assert_eq!(canvas.pixel(30, 30), GREEN);
+}
+
+#[test_case(render::<html5ever::Dom>; "html5ever")]
+#[test_case(render::<quick_xml::Dom>; "quick_xml")]
+fn test_painting_order(render: WithBackend<Canvas>) {
+    // Text paints over the backgrounds of later blocks, and positioned elements over both
+    let canvas = render(
+        "<div style='height: 10px; color: red'>a</div>\
+         <div style='height: 10px; margin-top: -10px; background: blue'></div>\
+         <div style='height: 10px; width: 5px; margin-top: -10px; position: relative; \
+         background: green'></div>",
+    );
+    assert_eq!(canvas.pixel(2, 5), GREEN);
+    assert_eq!(canvas.pixel(7, 5), RED);
+    assert_eq!(canvas.pixel(15, 5), BLUE);
+}
+
+#[test_case(render::<html5ever::Dom>; "html5ever")]
+#[test_case(render::<quick_xml::Dom>; "quick_xml")]
+fn test_text(render: WithBackend<Canvas>) {
+    let canvas = render(
+        "<p style='margin: 0; color: red'>a b</p>\
+         <p style='margin: 0; visibility: hidden'>c <span style='visibility: visible'>d</span></p>",
+    );
+    assert_eq!(canvas.pixel(0, 0), RED);
+    assert_eq!(canvas.pixel(9, 9), RED);
+    assert_eq!(canvas.pixel(15, 5), Color::WHITE);
+    assert_eq!(canvas.pixel(25, 5), RED);
+    assert_eq!(canvas.pixel(5, 15), Color::WHITE);
+    assert_eq!(canvas.pixel(25, 15), Color::BLACK);
+}
+
+// The chunks of a PNG image after its signature, each with its type and data, checking their
+// CRCs
+fn chunks(image: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut chunks = vec![];
+    let mut rest = &image[8..];
+    while !rest.is_empty() {
+        let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let (typed, after) = rest[4..].split_at(len + 4);
+        let crc = u32::from_be_bytes(after[..4].try_into().unwrap());
+        assert_eq!(png::crc32(typed), crc);
+        chunks.push((
+            String::from_utf8(typed[..4].to_vec()).unwrap(),
+            typed[4..].to_vec(),
+        ));
+        rest = &after[4..];
+    }
+    chunks
+}
+
+// Decompresses a zlib stream of stored deflate blocks
+fn inflate_stored(stream: &[u8]) -> Vec<u8> {
+    assert_eq!(stream[..2], [0x78, 0x01]);
+    let mut out = vec![];
+    let mut rest = &stream[2..];
+    loop {
+        let last = rest[0] & 1 == 1;
+        assert_eq!(rest[0] >> 1, 0, "not a stored block");
+        let len = u16::from_le_bytes([rest[1], rest[2]]);
+        assert_eq!(!len, u16::from_le_bytes([rest[3], rest[4]]));
+        out.extend(&rest[5..5 + len as usize]);
+        rest = &rest[5 + len as usize..];
+        if last {
+            break;
+        }
+    }
+    assert_eq!(rest.len(), 4);
+    out
+}
+
+#[test_case(b"", 0; "empty")]
+#[test_case(b"123456789", 0xcbf4_3926; "check")]
+#[test_case(b"IEND", 0xae42_6082; "iend")]
+fn test_crc32(bytes: &[u8], crc: u32) {
+    assert_eq!(png::crc32(bytes), crc);
+}
+
+#[test_case(2, 1; "small")]
+#[test_case(200, 100; "several blocks")]
+#[test_case(0, 0; "empty")]
+fn test_png(width: u32, height: u32) {
+    let rgba: Vec<u8> = (0..width * height * 4).map(|i| (i % 251) as u8).collect();
+    let image = png::encode(width, height, &rgba);
+    assert_eq!(image[..8], *b"\x89PNG\r\n\x1a\n");
+
+    let chunks = chunks(&image);
+    let types: Vec<&str> = chunks.iter().map(|(kind, _)| kind.as_str()).collect();
+    assert_eq!(types, ["IHDR", "IDAT", "IEND"]);
+
+    let header = &chunks[0].1;
+    assert_eq!(header[..4], width.to_be_bytes());
+    assert_eq!(header[4..8], height.to_be_bytes());
+    assert_eq!(header[8..], [8, 6, 0, 0, 0]);
+
+    let scanlines = inflate_stored(&chunks[1].1);
+    let stride = width as usize * 4;
+    assert_eq!(scanlines.len(), (stride + 1) * height as usize);
+    if stride > 0 {
+        for (y, row) in scanlines.chunks(stride + 1).enumerate() {
+            assert_eq!(row[0], 0);
+            assert_eq!(row[1..], rgba[y * stride..(y + 1) * stride]);
+        }
+    }
+}
+
+#[test]
+fn test_canvas_to_png() {
+    let canvas = render::<html5ever::Dom>("<div style='height: 1px; background: red'></div>");
+    let image = canvas.to_png();
+    let chunks = chunks(&image);
+    let scanlines = inflate_stored(&chunks[1].1);
+    assert_eq!(scanlines[..9], [0, 255, 0, 0, 255, 255, 0, 0, 255]);
+    assert_eq!(scanlines[402..406], [255, 255, 255, 255]);
+}
@@ -0,0 +1,81 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn parse(args: &[&str], input: impl AsRef<[u8]>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_parse"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run parse");
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_ref())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_prints_tree() {
+    let output = parse(&[], "<!DOCTYPE html><p>Hi");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "| <!DOCTYPE html>\n| <html>\n|   <head>\n|   <body>\n|     <p>\n|       \"Hi\"\n"
+    );
+}
+
+#[test]
+fn test_parses_fragment() {
+    let output = parse(&["--fragment", "tr", "--output", "html"], "<td>x");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "<td>x</td>\n");
+}
+
+#[test]
+fn test_decodes_fragment() {
+    let args = [
+        "--fragment",
+        "p",
+        "--encoding",
+        "windows-1252",
+        "--output",
+        "html",
+    ];
+    let output = parse(&args, b"caf\xe9");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "caf\u{e9}\n");
+}
+
+#[test]
+fn test_prints_json() {
+    let output = parse(
+        &["-b", "quick-xml", "-o", "json"],
+        "<!DOCTYPE html><p>Hi</p>",
+    );
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["type"], "document");
+    assert_eq!(json["children"][1]["name"], "html");
+}
+
+#[test]
+fn test_reports_errors() {
+    let output = parse(&["--errors"], "<p>Hi");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("<stdin>:1: "));
+
+    let output = parse(&["--errors"], "<!DOCTYPE html><p>Hi");
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_rejects_unknown_arguments() {
+    let output = parse(&["--bogus"], "");
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,55 @@
+use rendering::html5::html5ever;
+use rendering::html5::quick_xml;
+use rendering::testing::manifest::Manifest;
+use rendering::testing::tree_construction::{
+    error_outcomes, fixture_filenames, fixture_from_filename,
+};
+use std::env;
+use test_case::test_case;
+
+// Neither backend reports errors exactly as the fixtures expect them, so the tests whose
+// errors differ are listed in a manifest.  Set REGENERATE_EXPECTED_FAILURES to rewrite the
+// manifest with the current failures instead.
+macro_rules! matches_manifest {
+    ($type:ty, $func:ident, $name:expr) => {
+        #[test_case($name)]
+        fn $func(name: &str) {
+            let filenames = fixture_filenames().expect("error listing fixtures");
+            let manifest = Manifest::load("parse-errors", name).unwrap();
+            let outcomes = error_outcomes::<$type>(&filenames).unwrap();
+            let report = manifest.check(outcomes, &filenames);
+
+            if env::var_os("REGENERATE_EXPECTED_FAILURES").is_some() {
+                manifest.write(&report.failures).unwrap();
+                return;
+            }
+
+            // The mismatched errors of the files with unexpected failures, to see what changed
+            for filename in &filenames {
+                let prefix = format!("{filename}#");
+                if report
+                    .unexpected_failures
+                    .iter()
+                    .any(|id| id.starts_with(&prefix))
+                {
+                    let tests = fixture_from_filename(filename).expect("error loading fixture");
+                    println!("{}", tests.error_report::<$type>().unwrap());
+                }
+            }
+
+            assert!(report.is_ok(), "\n{}: {report}", manifest.path.display());
+        }
+    };
+}
+
+matches_manifest!(
+    html5ever::Dom,
+    test_html5ever_dom_matches_expected_failures,
+    "html5ever"
+);
+
+matches_manifest!(
+    quick_xml::Dom,
+    test_quick_xml_dom_matches_expected_failures,
+    "quick_xml"
+);
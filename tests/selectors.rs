@@ -0,0 +1,236 @@
+use rendering::css::matching::{self, MatchContext};
+use rendering::css::selector::{Namespaces, SelectorList};
+use rendering::html5::{html5ever, quick_xml, xml, Document};
+use test_case::test_case;
+
+type Select = fn(&str, &str) -> Vec<String>;
+
+// The ids of the matching elements, or their names if they have none
+fn select<T: for<'i> Document<'i, T>>(html: &str, selectors: &str) -> Vec<String> {
+    let dom = T::parse_document(html, false).unwrap();
+    let arena = dom.arena();
+    dom.query_selector_all(selectors)
+        .unwrap()
+        .into_iter()
+        .map(|id| {
+            let node = arena.node(id);
+            node.attr("id")
+                .map(str::to_owned)
+                .unwrap_or_else(|| node.element_name().unwrap().local.to_string())
+        })
+        .collect()
+}
+
+const LIST: &str = r#"<!DOCTYPE html>
+<ul id=list class="menu main">
+  <li id=a class=item lang=en-US>A</li>
+  <li id=b class="item selected" data-x=Foo>B</li>
+  <li id=c class=item title="x y z"><em id=c1></em></li>
+  <li id=d></li>
+</ul>
+<p id=p1>After <span id=s1>one</span></p>
+<p id=p2 lang=fr></p>"#;
+
+#[test_case(select::<html5ever::Dom>; "html5ever")]
+#[test_case(select::<quick_xml::Dom>; "quick_xml")]
+fn test_simple_selectors(select: Select) {
+    assert_eq!(select(LIST, "li"), vec!["a", "b", "c", "d"]);
+    assert_eq!(select(LIST, "LI"), vec!["a", "b", "c", "d"]);
+    assert_eq!(select(LIST, "#b"), vec!["b"]);
+    assert_eq!(select(LIST, ".item"), vec!["a", "b", "c"]);
+    assert_eq!(select(LIST, ".item.selected"), vec!["b"]);
+    assert_eq!(select(LIST, ".ITEM"), Vec::<String>::new());
+    assert_eq!(select(LIST, "p, #list"), vec!["list", "p1", "p2"]);
+    assert_eq!(select(LIST, "ul *"), vec!["a", "b", "c", "c1", "d"]);
+}
+
+#[test_case(select::<html5ever::Dom>; "html5ever")]
+#[test_case(select::<quick_xml::Dom>; "quick_xml")]
+fn test_attribute_selectors(select: Select) {
+    assert_eq!(select(LIST, "[lang]"), vec!["a", "p2"]);
+    assert_eq!(select(LIST, "[LANG]"), vec!["a", "p2"]);
+    assert_eq!(select(LIST, "[lang=fr]"), vec!["p2"]);
+    assert_eq!(select(LIST, "[lang|=en]"), vec!["a"]);
+    assert_eq!(select(LIST, "[title~=y]"), vec!["c"]);
+    assert_eq!(select(LIST, "[title~='x y']"), Vec::<String>::new());
+    assert_eq!(select(LIST, "[class^=it]"), vec!["a", "b", "c"]);
+    assert_eq!(select(LIST, "[class$=ted]"), vec!["b"]);
+    assert_eq!(select(LIST, "[class*=\"u m\"]"), vec!["list"]);
+    assert_eq!(select(LIST, "[class^='']"), Vec::<String>::new());
+    assert_eq!(select(LIST, "[data-x=foo]"), Vec::<String>::new());
+    assert_eq!(select(LIST, "[data-x=foo i]"), vec!["b"]);
+    assert_eq!(select(LIST, "[*|lang=fr]"), vec!["p2"]);
+}
+
+#[test_case(select::<html5ever::Dom>; "html5ever")]
+#[test_case(select::<quick_xml::Dom>; "quick_xml")]
+fn test_combinators(select: Select) {
+    assert_eq!(select(LIST, "ul > li"), vec!["a", "b", "c", "d"]);
+    assert_eq!(select(LIST, "ul > em"), Vec::<String>::new());
+    assert_eq!(select(LIST, "ul em"), vec!["c1"]);
+    assert_eq!(select(LIST, "#a + li"), vec!["b"]);
+    assert_eq!(select(LIST, "#b ~ li"), vec!["c", "d"]);
+    assert_eq!(select(LIST, "ul ~ p > span"), vec!["s1"]);
+    assert_eq!(select(LIST, "body > ul + p"), vec!["p1"]);
+    assert_eq!(select(LIST, "html li em"), vec!["c1"]);
+}
+
+#[test_case(select::<html5ever::Dom>; "html5ever")]
+#[test_case(select::<quick_xml::Dom>; "quick_xml")]
+fn test_structural_pseudo_classes(select: Select) {
+    assert_eq!(select(LIST, ":root"), vec!["html"]);
+    assert_eq!(select(LIST, "li:first-child"), vec!["a"]);
+    assert_eq!(select(LIST, "li:last-child"), vec!["d"]);
+    assert_eq!(select(LIST, ":only-child"), vec!["html", "c1", "s1"]);
+    assert_eq!(select(LIST, "p:first-of-type"), vec!["p1"]);
+    assert_eq!(select(LIST, "p:last-of-type"), vec!["p2"]);
+    assert_eq!(select(LIST, "li:empty, p:empty"), vec!["d", "p2"]);
+    assert_eq!(select(LIST, "li:nth-child(2n)"), vec!["b", "d"]);
+    assert_eq!(select(LIST, "li:nth-child(odd)"), vec!["a", "c"]);
+    assert_eq!(select(LIST, "li:nth-child(-n+2)"), vec!["a", "b"]);
+    assert_eq!(select(LIST, "li:nth-last-child(1)"), vec!["d"]);
+    assert_eq!(select(LIST, "li:nth-child(2 of .item)"), vec!["b"]);
+    assert_eq!(select(LIST, "li:nth-last-child(1 of .item)"), vec!["c"]);
+    assert_eq!(select(LIST, "p:nth-of-type(2)"), vec!["p2"]);
+    assert_eq!(select(LIST, "p:nth-last-of-type(2)"), vec!["p1"]);
+}
+
+#[test_case(select::<html5ever::Dom>; "html5ever")]
+#[test_case(select::<quick_xml::Dom>; "quick_xml")]
+fn test_logical_pseudo_classes(select: Select) {
+    assert_eq!(select(LIST, "li:not(.item)"), vec!["d"]);
+    assert_eq!(select(LIST, "li:not(#a, #b)"), vec!["c", "d"]);
+    assert_eq!(select(LIST, ":is(#a, #p1)"), vec!["a", "p1"]);
+    assert_eq!(select(LIST, ":where(ul, p) > span"), vec!["s1"]);
+    // Invalid selectors in :is() and :where() are dropped rather than failing the list
+    assert_eq!(select(LIST, ":is(#a, ::invalid)"), vec!["a"]);
+    assert_eq!(select(LIST, "li:has(em)"), vec!["c"]);
+    assert_eq!(select(LIST, "ul:has(> li > em)"), vec!["list"]);
+    assert_eq!(select(LIST, "ul:has(> em)"), Vec::<String>::new());
+    assert_eq!(select(LIST, "li:has(+ #d)"), vec!["c"]);
+    assert_eq!(
+        select(LIST, "li:has(~ li .selected, ~ #d)"),
+        vec!["a", "b", "c"]
+    );
+    assert_eq!(select(LIST, "p:has(span, em)"), vec!["p1"]);
+    assert_eq!(select(LIST, ":scope > body"), vec!["body"]);
+}
+
+#[test]
+fn test_query_selector_finds_the_first_match() {
+    let dom = quick_xml::Dom::parse_document(LIST, false).unwrap();
+    let first = dom.query_selector("li.item").unwrap().unwrap();
+    assert_eq!(dom.arena().node(first).attr("id"), Some("a"));
+    assert_eq!(dom.query_selector("table").unwrap(), None);
+    assert!(dom.query_selector("li,").is_err());
+}
+
+#[test_case(""; "empty")]
+#[test_case("li,"; "trailing comma")]
+#[test_case("> li"; "leading combinator")]
+#[test_case("li:nth-child(x)"; "bad nth")]
+#[test_case("[lang=]"; "missing value")]
+#[test_case("svg|rect"; "undeclared prefix")]
+#[test_case(":not(::before)"; "pseudo element")]
+#[test_case(":unknown"; "unknown pseudo class")]
+fn test_invalid_selectors_are_errors(selectors: &str) {
+    let dom = html5ever::Dom::parse_document(LIST, false).unwrap();
+    assert!(dom.query_selector_all(selectors).is_err());
+    assert!(SelectorList::parse(selectors).is_err());
+}
+
+const SVG: &str = r#"<!DOCTYPE html>
+<div id=d><svg id=s viewBox="0 0 1 1"><foreignObject id=f><p id=p></p></foreignObject>
+<rect id=r xlink:href=#x></rect></svg></div>"#;
+
+#[test]
+fn test_names_of_foreign_elements_are_case_sensitive() {
+    let select = select::<html5ever::Dom>;
+    assert_eq!(select(SVG, "foreignObject"), vec!["f"]);
+    assert_eq!(select(SVG, "foreignobject"), Vec::<String>::new());
+    assert_eq!(select(SVG, "[viewBox]"), vec!["s"]);
+    assert_eq!(select(SVG, "[viewbox]"), Vec::<String>::new());
+    assert_eq!(select(SVG, "foreignObject > P"), vec!["p"]);
+}
+
+#[test]
+fn test_namespaces() {
+    let dom = html5ever::Dom::parse_document(SVG, false).unwrap();
+    let arena = dom.arena();
+    let namespaces = Namespaces {
+        default: Some("http://www.w3.org/1999/xhtml".to_owned()),
+        prefixes: [
+            ("svg", "http://www.w3.org/2000/svg"),
+            ("xlink", "http://www.w3.org/1999/xlink"),
+        ]
+        .into_iter()
+        .map(|(prefix, url)| (prefix.to_owned(), url.to_owned()))
+        .collect(),
+    };
+    let cx = MatchContext::new(arena, true);
+    let select = |selectors| {
+        let list = SelectorList::parse_with_namespaces(selectors, &namespaces).unwrap();
+        matching::select(arena, arena.document(), &list, &cx)
+            .map(|id| arena.node(id).attr("id").unwrap_or_default().to_owned())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(select("svg|*"), vec!["s", "f", "r"]);
+    assert_eq!(select("*|p"), vec!["p"]);
+    assert_eq!(select("p"), vec!["p"]);
+    assert_eq!(select("rect"), Vec::<String>::new());
+    assert_eq!(select("|rect"), Vec::<String>::new());
+    assert_eq!(select("svg|*[xlink|href]"), vec!["r"]);
+    assert_eq!(select("svg|*[|id=r]"), vec!["r"]);
+    // Without a type selector, the default namespace still applies
+    assert_eq!(select("[id]"), vec!["d", "p"]);
+}
+
+#[test_case(select::<html5ever::Dom>; "html5ever")]
+#[test_case(select::<quick_xml::Dom>; "quick_xml")]
+fn test_classes_and_ids_ignore_case_in_quirks_mode(select: Select) {
+    let quirks = "<p id=Main class=Intro></p>";
+    assert_eq!(select(quirks, "#main.INTRO"), vec!["Main"]);
+
+    let standards = format!("<!DOCTYPE html>{quirks}");
+    assert_eq!(select(&standards, "#main"), Vec::<String>::new());
+    assert_eq!(select(&standards, "#Main.Intro"), vec!["Main"]);
+}
+
+#[test]
+fn test_xml_documents_are_case_sensitive() {
+    let select = select::<xml::Dom>;
+    let doc = r#"<Root><Item id="a" Kind="x"/><item id="b"/></Root>"#;
+    assert_eq!(select(doc, "Item"), vec!["a"]);
+    assert_eq!(select(doc, "item"), vec!["b"]);
+    assert_eq!(select(doc, "[Kind]"), vec!["a"]);
+    assert_eq!(select(doc, "[kind]"), Vec::<String>::new());
+    assert_eq!(select(doc, ":root > :last-child"), vec!["b"]);
+}
+
+#[test]
+fn test_template_contents_are_not_searched() {
+    let select = select::<html5ever::Dom>;
+    let doc = "<!DOCTYPE html><template id=t><p id=inside></p></template><p id=outside></p>";
+    assert_eq!(select(doc, "p"), vec!["outside"]);
+}
+
+#[test]
+fn test_matches_element() {
+    let dom = html5ever::Dom::parse_document(LIST, false).unwrap();
+    let arena = dom.arena();
+    let b = dom.query_selector("#b").unwrap().unwrap();
+    let cx = MatchContext::new(arena, true);
+    let list = SelectorList::parse("ul > .selected").unwrap();
+    assert!(matching::matches(arena, b, &list, &cx));
+
+    // :scope matches the element queried from
+    let cx = MatchContext {
+        scope: Some(b),
+        ..cx
+    };
+    let list = SelectorList::parse(":scope + li").unwrap();
+    let next: Vec<_> = matching::select(arena, arena.document(), &list, &cx).collect();
+    assert_eq!(next.len(), 1);
+    assert_eq!(arena.node(next[0]).attr("id"), Some("c"));
+}
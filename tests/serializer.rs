@@ -0,0 +1,144 @@
+use rendering::dom::serialize::{serialize_node, SerializeOpts};
+use rendering::html5::{html5ever, quick_xml, Document, Serializable};
+use rendering::testing::manifest::Manifest;
+use rendering::testing::serializer;
+use rendering::testing::tree_construction;
+use std::env;
+use test_case::test_case;
+
+macro_rules! passes {
+    ($func:ident, [$($filename:expr),*,]) => {
+        $(
+            #[test_case($filename)]
+        )*
+        fn $func(filename: &str) {
+            let tests = serializer::fixture_from_filename(filename).expect("error loading fixture");
+
+            for test in tests.iter() {
+                let description = &test.description;
+                println!("running {}", description);
+
+                let (actual, expected) = test.run();
+                assert!(expected.contains(&actual), "\n  description: {description}\n  actual: {actual:?}\n  expected: {expected:?}\n");
+            }
+        }
+    };
+}
+
+// Not every tree the parser builds survives serialization, e.g. the text of a <plaintext>
+// that contains "</plaintext>", so the tests that do not round-trip are listed in a manifest.  Set
+// REGENERATE_EXPECTED_FAILURES to rewrite the manifest with the current failures instead.
+macro_rules! matches_manifest {
+    ($type:ty, $func:ident, $name:expr) => {
+        #[test_case($name)]
+        fn $func(name: &str) {
+            let filenames = tree_construction::fixture_filenames().expect("error listing fixtures");
+            let manifest = Manifest::load("round-trip", name).unwrap();
+            let outcomes = tree_construction::round_trip_outcomes::<$type>(&filenames).unwrap();
+            let report = manifest.check(outcomes, &filenames);
+
+            if env::var_os("REGENERATE_EXPECTED_FAILURES").is_some() {
+                manifest.write(&report.failures).unwrap();
+                return;
+            }
+
+            assert!(report.is_ok(), "\n{}: {report}", manifest.path.display());
+        }
+    };
+}
+
+passes!(
+    test_serializer_passes_tests,
+    [
+        "core.test",
+        "injectmeta.test",
+        "optionaltags.test",
+        "options.test",
+        "whitespace.test",
+    ]
+);
+
+matches_manifest!(
+    html5ever::Dom,
+    test_html5ever_dom_round_trips_match_expected_failures,
+    "html5ever"
+);
+
+matches_manifest!(
+    quick_xml::Dom,
+    test_quick_xml_dom_round_trips_match_expected_failures,
+    "quick_xml"
+);
+
+#[test_case("<p>a &amp; b &lt; c</p>", "<p>a &amp; b &lt; c</p>" ; "escapes text")]
+#[test_case("<p title='a \"b\" &amp; c'>x</p>", "<p title=\"a &quot;b&quot; &amp; c\">x</p>" ; "escapes attributes")]
+#[test_case("<br><img src=a><input>", "<br><img src=\"a\"><input>" ; "void elements")]
+#[test_case("<script>a < b && c</script>", "<script>a < b && c</script>" ; "raw text")]
+#[test_case("<textarea>&lt;</textarea>", "<textarea>&lt;</textarea>" ; "escapable raw text")]
+#[test_case("<p>\u{a0}</p>", "<p>&nbsp;</p>" ; "no-break space")]
+#[test_case("<template><b>x</b></template>", "<template><b>x</b></template>" ; "template contents")]
+#[test_case("<svg viewBox='0 0 1 1'><a xlink:href=x /></svg>", "<svg viewBox=\"0 0 1 1\"><a xlink:href=\"x\"></a></svg>" ; "foreign attributes")]
+#[test_case("<math><mi>x</mi></math><!--c-->", "<math><mi>x</mi></math><!--c-->" ; "comments")]
+fn test_html5ever_dom_serializes_body(input: &str, expected: &str) {
+    let dom = html5ever::Dom::parse_fragment(input, false, "body").unwrap();
+    assert_eq!(dom.to_html(), expected);
+}
+
+#[test]
+fn test_document_serialization() {
+    let dom = html5ever::Dom::parse_document("<!DOCTYPE html><title>t</title>x", false).unwrap();
+    assert_eq!(
+        dom.to_html(),
+        "<!DOCTYPE html><html><head><title>t</title></head><body>x</body></html>"
+    );
+}
+
+#[test]
+fn test_noscript_contents_depend_on_scripting() {
+    // With scripting, the contents are raw text written out as is, and without, they are
+    // markup, so the paragraph gets its end tag
+    let html = "<noscript><p>a</noscript>";
+
+    let dom = html5ever::Dom::parse_fragment(html, true, "body").unwrap();
+    assert_eq!(dom.to_html(), html);
+
+    let dom = html5ever::Dom::parse_fragment(html, false, "body").unwrap();
+    assert_eq!(dom.to_html(), "<noscript><p>a</p></noscript>");
+}
+
+#[test]
+fn test_outer_html() {
+    let dom = quick_xml::Dom::parse_fragment("<ul><li>a</li></ul>", false, "body").unwrap();
+    let arena = dom.arena();
+    let ul = arena.children(dom.root()).next().unwrap();
+
+    assert_eq!(
+        serialize_node(arena, ul, &SerializeOpts::default()),
+        "<ul><li>a</li></ul>"
+    );
+}
+
+#[test]
+fn test_optional_tags_are_omitted() {
+    let dom = html5ever::Dom::parse_document("<ul><li>a<li>b</ul><p>c", false).unwrap();
+    let opts = SerializeOpts {
+        omit_optional_tags: true,
+        ..SerializeOpts::default()
+    };
+    assert_eq!(dom.to_html_with(&opts), "<ul><li>a<li>b</ul><p>c");
+}
+
+#[test_case("<p>a<b>b<i>c</b>d</i>e" ; "misnested formatting")]
+#[test_case("<table><tr><td>a<td>b</table>" ; "tables")]
+#[test_case("<select><option>a<option>b</select>" ; "select")]
+#[test_case("<svg><foreignObject><p>a</p></foreignObject></svg>" ; "foreign content")]
+#[test_case("<style>p > a { }</style><script>if (a < b) {}</script>" ; "raw text")]
+fn test_parsed_trees_round_trip(input: &str) {
+    let html = html5ever::Dom::parse_document(input, false)
+        .unwrap()
+        .to_html();
+    let reparsed = html5ever::Dom::parse_document(&html, false)
+        .unwrap()
+        .to_html();
+    assert_eq!(reparsed, html);
+}
@@ -0,0 +1,276 @@
+use rendering::html5::{html5ever, quick_xml, Document};
+use rendering::style::values::Display;
+use rendering::style::{Color, Longhand, Specified, Styles, Viewport};
+use rendering::testing::golden::{self, GOLDEN_DIR};
+use std::{fs, path::Path};
+use test_case::test_case;
+
+type Compute = fn(&str, &Viewport) -> (rendering::dom::Arena, Styles);
+
+fn compute<T: for<'i> Document<'i, T>>(
+    html: &str,
+    viewport: &Viewport,
+) -> (rendering::dom::Arena, Styles) {
+    let dom = T::parse_document(html, false).unwrap();
+    let styles = dom.compute_styles(viewport);
+    (dom.arena().clone(), styles)
+}
+
+// The value of a property of the element with an id, as serialized in dumps
+fn value(compute: Compute, html: &str, id: &str, longhand: &str) -> String {
+    value_in(compute, html, &Viewport::default(), id, longhand)
+}
+
+fn value_in(compute: Compute, html: &str, viewport: &Viewport, id: &str, longhand: &str) -> String {
+    let (arena, styles) = compute(html, viewport);
+    let element = arena
+        .descendants(arena.document())
+        .find(|&n| arena.node(n).attr("id") == Some(id))
+        .unwrap();
+    let longhand = Longhand::from_name(longhand).unwrap();
+    styles.get(element).unwrap().to_css(longhand)
+}
+
+#[test_case(compute::<html5ever::Dom>; "html5ever")]
+#[test_case(compute::<quick_xml::Dom>; "quick_xml")]
+fn test_user_agent_styles(compute: Compute) {
+    let html = "<!DOCTYPE html><head id=head></head><body id=body><h1 id=h1></h1><li id=li>\
+                <a id=a href=#></a><a id=no-href></a><b id=b></b></body>";
+    assert_eq!(value(compute, html, "head", "display"), "none");
+    assert_eq!(value(compute, html, "body", "display"), "block");
+    assert_eq!(value(compute, html, "body", "margin-left"), "8px");
+    assert_eq!(value(compute, html, "h1", "font-size"), "32px");
+    assert_eq!(value(compute, html, "h1", "margin-top"), "21.44px");
+    assert_eq!(value(compute, html, "h1", "font-weight"), "700");
+    assert_eq!(value(compute, html, "li", "display"), "list-item");
+    assert_eq!(value(compute, html, "a", "color"), "rgb(0, 0, 238)");
+    assert_eq!(
+        value(compute, html, "a", "text-decoration-line"),
+        "underline"
+    );
+    assert_eq!(value(compute, html, "no-href", "color"), "rgb(0, 0, 0)");
+    assert_eq!(value(compute, html, "b", "font-weight"), "700");
+}
+
+#[test_case(compute::<html5ever::Dom>; "html5ever")]
+#[test_case(compute::<quick_xml::Dom>; "quick_xml")]
+fn test_precedence(compute: Compute) {
+    let html = r#"<style>
+        p { color: red }
+        .c { color: green }
+        p { color: blue }
+        #i { color: olive }
+        .c#i { color: teal !important }
+        .x { color: navy !important }
+        .x { color: lime }
+        .y { display: none !important }
+      </style>
+      <p id=p></p>
+      <p id=c class=c></p>
+      <p id=i class=c style="color: orange"></p>
+      <p id=inline style="color: orange"></p>
+      <p id=important class=x style="color: orange !important"></p>
+      <input id=hidden type=hidden style="display: block !important">
+      <p id=ua class=y style="display: block"></p>"#;
+    // Source order
+    assert_eq!(value(compute, html, "p", "color"), "rgb(0, 0, 255)");
+    // Specificity
+    assert_eq!(value(compute, html, "c", "color"), "rgb(0, 128, 0)");
+    // Important declarations over the style attribute
+    assert_eq!(value(compute, html, "i", "color"), "rgb(0, 128, 128)");
+    assert_eq!(value(compute, html, "inline", "color"), "rgb(255, 165, 0)");
+    assert_eq!(
+        value(compute, html, "important", "color"),
+        "rgb(255, 165, 0)"
+    );
+    // Important user-agent declarations win over important author declarations
+    assert_eq!(value(compute, html, "hidden", "display"), "none");
+    assert_eq!(value(compute, html, "ua", "display"), "none");
+}
+
+#[test_case(compute::<html5ever::Dom>; "html5ever")]
+#[test_case(compute::<quick_xml::Dom>; "quick_xml")]
+fn test_inheritance(compute: Compute) {
+    let html = r#"<style>
+        div { color: red; padding: 5px; font-size: 20px; border-left: 3px solid }
+        .inherit { padding-top: inherit }
+        .initial { color: initial; display: initial }
+        .unset { color: unset; padding-left: unset }
+        .em { font-size: 1.5em; width: 2em }
+      </style>
+      <div><span id=span></span><p id=inherit class=inherit></p><p id=initial class=initial></p>
+      <p id=unset class=unset></p><p id=em class=em></p><hr id=hr></div>"#;
+    assert_eq!(value(compute, html, "span", "color"), "rgb(255, 0, 0)");
+    assert_eq!(value(compute, html, "span", "padding-top"), "0px");
+    assert_eq!(value(compute, html, "inherit", "padding-top"), "5px");
+    assert_eq!(value(compute, html, "initial", "color"), "rgb(0, 0, 0)");
+    assert_eq!(value(compute, html, "initial", "display"), "inline");
+    assert_eq!(value(compute, html, "unset", "color"), "rgb(255, 0, 0)");
+    assert_eq!(value(compute, html, "unset", "padding-left"), "0px");
+    assert_eq!(value(compute, html, "em", "font-size"), "30px");
+    assert_eq!(value(compute, html, "em", "width"), "60px");
+    // Border colors default to the color of the element, and its own color
+    assert_eq!(
+        value(compute, html, "hr", "border-top-color"),
+        "rgb(128, 128, 128)"
+    );
+}
+
+#[test_case(compute::<html5ever::Dom>; "html5ever")]
+#[test_case(compute::<quick_xml::Dom>; "quick_xml")]
+fn test_variables(compute: Compute) {
+    let html = r#"<style>
+        :root { --c: green; --size: 12px; --a: var(--b); --b: var(--a) }
+        p { color: var(--c); font-size: var(--size) }
+        .missing { color: var(--nope, var(--nope2, blue)) }
+        .cycle { color: var(--a, red); --x: var(--y, 1px); --y: var(--x, 2px); margin-top: var(--x, 3px) }
+        .invalid { --size: red }
+        .nested { --c: rgb(var(--r) 0 0); --r: 255 }
+      </style>
+      <p id=p></p><p id=missing class=missing></p><p id=cycle class=cycle></p>
+      <p id=invalid class=invalid></p><div class=nested><p id=nested></p></div>"#;
+    assert_eq!(value(compute, html, "p", "color"), "rgb(0, 128, 0)");
+    assert_eq!(value(compute, html, "p", "font-size"), "12px");
+    assert_eq!(value(compute, html, "missing", "color"), "rgb(0, 0, 255)");
+    // Variables in a cycle are invalid where it is declared, so descendants use fallbacks
+    assert_eq!(value(compute, html, "cycle", "color"), "rgb(255, 0, 0)");
+    // Custom properties in a cycle are invalid even with fallbacks
+    assert_eq!(value(compute, html, "cycle", "margin-top"), "3px");
+    assert_eq!(value(compute, html, "invalid", "font-size"), "16px");
+    assert_eq!(value(compute, html, "nested", "color"), "rgb(255, 0, 0)");
+}
+
+#[test_case(compute::<html5ever::Dom>; "html5ever")]
+#[test_case(compute::<quick_xml::Dom>; "quick_xml")]
+fn test_shorthands(compute: Compute) {
+    let html = r#"<style>
+        #m { margin: 1px 2px 3px; padding: 4px 5px }
+        #b { border: 4px dotted #abc; border-right: none }
+        #f { font: italic small-caps 700 24px/2 "Helvetica Neue", sans-serif }
+        #t { text-decoration: underline overline red; background: rgba(0, 0, 255, .5) }
+        #x { margin: 1px 2px 3px 4px 5px; padding: 1px; padding: bogus }
+      </style>
+      <p id=m></p><p id=b></p><p id=f></p><p id=t></p><p id=x></p>"#;
+    assert_eq!(value(compute, html, "m", "margin-left"), "2px");
+    assert_eq!(value(compute, html, "m", "margin-bottom"), "3px");
+    assert_eq!(value(compute, html, "m", "padding-bottom"), "4px");
+    assert_eq!(
+        value(compute, html, "b", "border-top-color"),
+        "rgb(170, 187, 204)"
+    );
+    assert_eq!(value(compute, html, "b", "border-top-style"), "dotted");
+    assert_eq!(value(compute, html, "b", "border-right-width"), "0px");
+    assert_eq!(value(compute, html, "f", "font-style"), "italic");
+    assert_eq!(value(compute, html, "f", "font-weight"), "700");
+    assert_eq!(value(compute, html, "f", "line-height"), "2");
+    assert_eq!(
+        value(compute, html, "f", "font-family"),
+        "\"Helvetica Neue\", sans-serif"
+    );
+    assert_eq!(
+        value(compute, html, "t", "text-decoration-line"),
+        "underline overline"
+    );
+    assert_eq!(
+        value(compute, html, "t", "background-color"),
+        "rgba(0, 0, 255, 0.5)"
+    );
+    // Invalid declarations are dropped
+    assert_eq!(value(compute, html, "x", "margin-top"), "16px");
+    assert_eq!(value(compute, html, "x", "padding-top"), "1px");
+}
+
+#[test_case(compute::<html5ever::Dom>; "html5ever")]
+#[test_case(compute::<quick_xml::Dom>; "quick_xml")]
+fn test_media_queries(compute: Compute) {
+    let html = r#"<style>
+        @media screen and (min-width: 600px) { p { color: green } }
+        @media print { p { color: red } }
+        @media (max-width: 599px), (orientation: portrait) { p { color: blue } }
+        @media not all and (min-width: 2000px) { p { margin-top: 1px } }
+      </style><p id=p></p>"#;
+    let wide = Viewport::default();
+    let narrow = Viewport {
+        width: 400.0,
+        height: 800.0,
+    };
+    assert_eq!(
+        value_in(compute, html, &wide, "p", "color"),
+        "rgb(0, 128, 0)"
+    );
+    assert_eq!(
+        value_in(compute, html, &narrow, "p", "color"),
+        "rgb(0, 0, 255)"
+    );
+    assert_eq!(value_in(compute, html, &wide, "p", "margin-top"), "1px");
+}
+
+#[test_case(compute::<html5ever::Dom>; "html5ever")]
+#[test_case(compute::<quick_xml::Dom>; "quick_xml")]
+fn test_computed_values(compute: Compute) {
+    let html = r#"<style>
+        html { font-size: 10px }
+        #root { width: 5rem; height: 10vh; max-width: 50% }
+        #abs { position: absolute; display: inline; z-index: 3 }
+        #none { border-top-style: none; border-top-width: 5px; border-left: 5px solid }
+        #lh { line-height: 150%; font-size: 20px }
+      </style>
+      <p id=root></p><span id=abs></span><p id=none></p><p id=lh></p>"#;
+    assert_eq!(value(compute, html, "root", "width"), "50px");
+    assert_eq!(value(compute, html, "root", "height"), "60px");
+    assert_eq!(value(compute, html, "root", "max-width"), "50%");
+    assert_eq!(value(compute, html, "abs", "display"), "block");
+    assert_eq!(value(compute, html, "abs", "z-index"), "3");
+    assert_eq!(value(compute, html, "none", "border-top-width"), "0px");
+    assert_eq!(value(compute, html, "none", "border-left-width"), "5px");
+    assert_eq!(value(compute, html, "lh", "line-height"), "30px");
+}
+
+#[test_case(compute::<html5ever::Dom>; "html5ever")]
+#[test_case(compute::<quick_xml::Dom>; "quick_xml")]
+fn test_specified_values(compute: Compute) {
+    let html = "<style>p { display: block; color: inherit }</style><p id=p></p><template><p></p></template>";
+    let (arena, styles) = compute(html, &Viewport::default());
+    let p = arena
+        .descendants(arena.document())
+        .find(|&n| arena.node(n).attr("id") == Some("p"))
+        .unwrap();
+    assert!(matches!(
+        styles.specified(p, Longhand::Display),
+        Some(Specified::Value(_))
+    ));
+    assert_eq!(
+        styles.specified(p, Longhand::Color),
+        Some(&Specified::Inherit)
+    );
+    assert_eq!(
+        styles.specified(p, Longhand::Width),
+        Some(&Specified::Initial)
+    );
+
+    let style = styles.get(p).unwrap();
+    assert_eq!(style.display, Display::Block);
+    assert_eq!(style.color, Color::BLACK);
+    // Elements in templates are not styled
+    let template = arena
+        .descendants(arena.document())
+        .find_map(|n| arena.template_contents(n))
+        .unwrap();
+    let inert = arena.children(template).next().unwrap();
+    assert!(styles.get(inert).is_none());
+}
+
+// Set REGENERATE_GOLDEN to rewrite the expected dumps with the current ones instead
+#[test_case("basic")]
+#[test_case("cascade")]
+#[test_case("display")]
+#[test_case("inheritance")]
+#[test_case("variables")]
+fn test_golden(name: &str) {
+    let html = fs::read_to_string(Path::new(GOLDEN_DIR).join(format!("style/{name}.html")));
+    let dom = html5ever::Dom::parse_document(&html.unwrap(), false).unwrap();
+    let dump = dom.compute_styles(&Viewport::default()).dump(dom.arena());
+
+    let expected = golden::expected(&format!("style/{name}.txt"), &dump).unwrap();
+    assert_eq!(dump, expected);
+}
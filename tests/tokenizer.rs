@@ -0,0 +1,80 @@
+use rendering::html5::html5ever;
+use rendering::html5::quick_xml;
+use rendering::testing::manifest::Manifest;
+use rendering::testing::tokenizer::{fixture_filenames, fixture_from_filename, outcomes};
+use std::env;
+use test_case::test_case;
+
+// Set REGENERATE_EXPECTED_FAILURES to rewrite the manifest with the current failures instead
+macro_rules! matches_manifest {
+    ($type:ty, $func:ident, $name:expr) => {
+        #[test_case($name)]
+        fn $func(name: &str) {
+            let filenames = fixture_filenames().expect("error listing fixtures");
+            let manifest = Manifest::load("tokenizer", name).unwrap();
+            let outcomes = outcomes::<$type>(&filenames).unwrap();
+            let report = manifest.check(outcomes, &filenames);
+
+            if env::var_os("REGENERATE_EXPECTED_FAILURES").is_some() {
+                manifest.write(&report.failures).unwrap();
+                return;
+            }
+
+            assert!(report.is_ok(), "\n{}: {report}", manifest.path.display());
+        }
+    };
+}
+
+macro_rules! passes {
+    ($type:ty, $func:ident, [$($filename:expr),*,]) => {
+        $(
+            #[test_case($filename)]
+        )*
+        fn $func(filename: &str) {
+            let tests = fixture_from_filename(filename).expect("error loading fixture");
+
+            for test in tests.iter() {
+                let description = &test.description;
+                println!("running {}", description);
+
+                for result in test.results::<$type>().unwrap() {
+                    let state = result.state;
+                    let (actual, expected) = result.run();
+                    assert_eq!(actual.tokens, expected.tokens, "\n  description: {description}\n  input: {:?}\n  initial state: {state:?}\n", test.input);
+                }
+            }
+        }
+    };
+}
+
+passes!(
+    html5ever::Dom,
+    test_html5ever_dom_passes_tests,
+    [
+        "contentModelFlags.test",
+        "domjs.test",
+        "entities.test",
+        "escapeFlag.test",
+        "namedEntities.test",
+        "numericEntities.test",
+        "pendingSpecChanges.test",
+        "test1.test",
+        "test2.test",
+        "test3.test",
+        "test4.test",
+        "unicodeChars.test",
+        "unicodeCharsProblematic.test",
+    ]
+);
+
+matches_manifest!(
+    html5ever::Dom,
+    test_html5ever_dom_matches_expected_failures,
+    "html5ever"
+);
+
+matches_manifest!(
+    quick_xml::Dom,
+    test_quick_xml_dom_matches_expected_failures,
+    "quick_xml"
+);
@@ -0,0 +1,80 @@
+use rendering::html5::{xml, Document, Parser};
+use rendering::testing::manifest::Manifest;
+use rendering::testing::xml5::{self, serialize_dom};
+use std::env;
+use test_case::test_case;
+
+// Recovery from malformed input follows quick-xml rather than the XML5 specification, so the
+// fixtures that fail are listed in a manifest.  Set REGENERATE_EXPECTED_FAILURES to rewrite
+// the manifest with the current failures instead.
+#[test_case("xml")]
+fn test_xml_dom_matches_expected_failures(name: &str) {
+    let filenames = xml5::fixture_filenames().expect("error listing fixtures");
+    let manifest = Manifest::load("xml5lib", name).unwrap();
+    let outcomes = xml5::outcomes(&filenames).unwrap();
+    let report = manifest.check(outcomes, &filenames);
+
+    if env::var_os("REGENERATE_EXPECTED_FAILURES").is_some() {
+        manifest.write(&report.failures).unwrap();
+        return;
+    }
+
+    assert!(report.is_ok(), "\n{}: {report}", manifest.path.display());
+}
+
+fn tree(input: &str) -> String {
+    let dom = xml::Dom::parse_document(input, false).unwrap();
+    serialize_dom(dom.arena(), dom.root())
+}
+
+#[test_case("<a><B>x</B></a>", "| <a>\n|   <B>\n|     \"x\"" ; "names keep their case")]
+#[test_case("<svg xmlns='http://www.w3.org/2000/svg'><g/></svg>", "| <{http://www.w3.org/2000/svg}svg>\n|   {http://www.w3.org/2000/xmlns/}xmlns=\"http://www.w3.org/2000/svg\"\n|   <{http://www.w3.org/2000/svg}g>" ; "default namespace")]
+#[test_case("<p:a xmlns:p='urn:p' p:b='1' c='2'/>", "| <{urn:p}p:a>\n|   {urn:p}p:b=\"1\"\n|   c=\"2\"\n|   {http://www.w3.org/2000/xmlns/}xmlns:p=\"urn:p\"" ; "prefixed names")]
+#[test_case("<a>x<![CDATA[<y>]]>&lt;</a>", "| <a>\n|   \"x<y><\"" ; "cdata is text")]
+#[test_case("<?xml version='1.0'?><?style href='a'?><a><?pi data?></a>", "| <?style href='a'?>\n| <a>\n|   <?pi data?>" ; "processing instructions")]
+#[test_case("<!DOCTYPE Svg SYSTEM 'a.dtd' [<!ENTITY e 'x'>]><Svg/>", "| <!DOCTYPE Svg \"\" \"a.dtd\">\n| <Svg>" ; "doctype")]
+#[test_case("<a><!--c--></a>", "| <a>\n|   <!-- c -->" ; "comments")]
+fn test_xml_dom_builds_tree(input: &str, expected: &str) {
+    assert_eq!(tree(input), expected);
+}
+
+#[test_case("<a><b></a>", "| <a>\n|   <b>", 1 ; "unclosed element")]
+#[test_case("<a></b></a>", "| <a>", 1 ; "unmatched end tag")]
+#[test_case("<a>&nbsp;</a>", "| <a>\n|   \"&nbsp;\"", 1 ; "unknown entity")]
+#[test_case("<a/><b/>", "| <a>\n| <b>", 1 ; "second root")]
+#[test_case("<p:a/>", "| <p:a>", 1 ; "unbound prefix")]
+fn test_xml_dom_recovers_from_errors(input: &str, expected: &str, errors: usize) {
+    let dom = xml::Dom::parse_document(input, false).unwrap();
+    assert_eq!(serialize_dom(dom.arena(), dom.root()), expected);
+    assert_eq!(dom.errors().len(), errors, "{:?}", dom.errors());
+}
+
+#[test]
+fn test_xml_dom_parses_split_input() {
+    let input = "<?xml version='1.0'?>\r\n<a xmlns='urn:a'>\r\n<![CDATA[x]]>&amp;</a>";
+    let expected = tree(input);
+
+    for split in 0..=input.len() {
+        let mut parser = xml::Dom::document_parser(false).unwrap();
+        let (head, tail) = input.as_bytes().split_at(split);
+        parser.feed(head).unwrap();
+        parser.feed(tail).unwrap();
+        let dom = parser.finish().unwrap();
+        assert_eq!(
+            serialize_dom(dom.arena(), dom.root()),
+            expected,
+            "split at {split}"
+        );
+    }
+}
+
+#[test]
+fn test_xml_dom_nodes_have_source_locations() {
+    let input = "<a>\r\n  <b>x</b>\r\n</a>";
+    let dom = xml::Dom::parse_document(input, false).unwrap();
+    let b = dom.node_at(8).unwrap();
+    let location = dom.location(b).unwrap();
+
+    assert_eq!(&input[location.span.clone().unwrap()], "<b>");
+    assert_eq!((location.line, location.col), (2, Some(3)));
+}